@@ -0,0 +1,60 @@
+//! 列指向バッチ符号化のベンチマーク — 18 バイト固定長との比較
+//!
+//! 実行: `cargo bench --bench batch`
+
+use alice_presence::{encode_event_batch, try_decode_event_batch, PresenceEvent};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// `parties` 人の間で約 1 秒間隔のイベントが続くアーカイブ。
+fn archive(n: usize, parties: u32) -> Vec<PresenceEvent> {
+    let mut state = 0x9E37_79B9_u64;
+    let mut ts = 1_700_000_000_000_000_000u64;
+    (0..n)
+        .map(|i| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            ts += 1_000_000_000 + (state >> 40) % 1_000_000;
+            let a = 1000 + (state >> 20) as u32 % parties;
+            let b = 1000 + (state >> 28) as u32 % parties;
+            match i % 3 {
+                0 => PresenceEvent::encounter_start(a, b, ts, true),
+                1 => PresenceEvent::heartbeat(a, b, ts),
+                _ => PresenceEvent::encounter_end(a, b, ts, true),
+            }
+        })
+        .collect()
+}
+
+fn bench_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("event_batch");
+    for (n, parties) in [(100, 4), (1_000, 16), (10_000, 256)] {
+        let events = archive(n, parties);
+        let encoded = encode_event_batch(&events);
+        println!(
+            "{n:>6} events / {parties:>3} parties: {:>7} bytes, {:.2} bytes/event (raw 18)",
+            encoded.len(),
+            encoded.len() as f64 / n as f64
+        );
+
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("encode", n), &events, |b, ev| {
+            b.iter(|| encode_event_batch(black_box(ev)));
+        });
+        group.bench_with_input(BenchmarkId::new("decode", n), &encoded, |b, data| {
+            b.iter(|| try_decode_event_batch(black_box(data)).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("raw_18", n), &events, |b, ev| {
+            b.iter(|| {
+                black_box(ev)
+                    .iter()
+                    .flat_map(PresenceEvent::to_bytes)
+                    .collect::<Vec<u8>>()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch);
+criterion_main!(benches);
@@ -3,7 +3,8 @@
 //! 実行: `cargo run --example presence_demo`
 
 use alice_presence::{
-    execute_presence_protocol, GroupConfig, PartyInfo, PresenceConfig, PresenceGroup, VivaldiCoord,
+    try_execute_presence_protocol, GroupConfig, PartyInfo, PresenceConfig, PresenceGroup,
+    VivaldiCoord,
 };
 
 fn banner(title: &str) {
@@ -17,15 +18,15 @@ fn main() {
         cfg.proximity_threshold, cfg.challenge_bits, cfg.require_mutual
     );
 
-    let alice = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 0xA11CE_5EC, 1);
+    let alice = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 0xA11C_E5EC, 1);
     let bob = PartyInfo::new(VivaldiCoord::new(3.0, 4.0), 0xB0B_5EC, 2);
     let charlie = PartyInfo::new(VivaldiCoord::new(80.0, 80.0), 0xC4A_5EC, 3);
 
     let ts_ns: u64 = 1_723_000_000_000_000_000;
 
     banner("Case 1: Alice ↔ Bob (近接 = 距離 5.0 ≤ 10.0)");
-    match execute_presence_protocol(&alice, &bob, ts_ns, &cfg) {
-        Some(rec) => {
+    match try_execute_presence_protocol(&alice, &bob, ts_ns, &cfg) {
+        Ok(rec) => {
             let ev_bytes = rec.event.to_bytes();
             println!(
                 "distance         = {:.4}  (threshold {})",
@@ -57,13 +58,13 @@ fn main() {
             println!("is_fully_verified = {}", rec.is_fully_verified());
             println!("status           = {:?}", rec.status());
         }
-        None => println!("(NOT proximate — 記録なし)"),
+        Err(e) => println!("(記録なし: {e})"),
     }
 
     banner("Case 2: Alice ↔ Charlie (遠い = 距離 ≈113 > 10.0)");
-    match execute_presence_protocol(&alice, &charlie, ts_ns, &cfg) {
-        Some(_) => println!("(想定外: 近接判定通過)"),
-        None => println!("proximity 未達 → CrossingRecord は None (= 出会いなし)"),
+    match try_execute_presence_protocol(&alice, &charlie, ts_ns, &cfg) {
+        Ok(_) => println!("(想定外: 近接判定通過)"),
+        Err(e) => println!("proximity 未達 → CrossingRecord なし (= 出会いなし): {e}"),
     }
 
     banner("Case 3: 3-party group proximity (Alice, Bob, Dora)");
@@ -71,9 +72,15 @@ fn main() {
         proximity_threshold: 10.0,
         min_members: 2,
     });
-    group.add_member(alice.id, alice.coord, ts_ns);
-    group.add_member(bob.id, bob.coord, ts_ns);
-    group.add_member(4, VivaldiCoord::new(6.0, 0.0), ts_ns);
+    for (id, coord) in [
+        (alice.id, alice.coord),
+        (bob.id, bob.coord),
+        (4, VivaldiCoord::new(6.0, 0.0)),
+    ] {
+        if let Err(e) = group.try_add_member(id, coord, ts_ns) {
+            println!("add_member({id}) failed: {e}");
+        }
+    }
 
     println!("member_count  = {}", group.member_count());
     println!("member_ids    = {:?}", group.member_ids());
@@ -88,7 +95,9 @@ fn main() {
     }
 
     banner("Case 4: 群に遠い Charlie を追加 → all_proximate 崩壊");
-    group.add_member(charlie.id, charlie.coord, ts_ns);
+    if let Err(e) = group.try_add_member(charlie.id, charlie.coord, ts_ns) {
+        println!("add_member({}) failed: {e}", charlie.id);
+    }
     println!("member_count  = {}", group.member_count());
     println!("max_pairwise  = {:.4}", group.max_pairwise_distance());
     println!("all_proximate = {}", group.is_all_proximate());
@@ -0,0 +1,252 @@
+/* ALICE-Presence C API. Generated by cbindgen from src/capi.rs — do not edit. */
+
+#ifndef ALICE_PRESENCE_H
+#define ALICE_PRESENCE_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <stdbool.h>
+
+// [`DistanceUnit`] の C 表現。
+typedef enum ApDistanceUnit {
+  // ネットワーク座標距離。
+  AP_DISTANCE_UNIT_NETWORK = 0,
+  // メートル (GPS)。
+  AP_DISTANCE_UNIT_METRES = 1,
+} ApDistanceUnit;
+
+// [`ProximityMode`] の C 表現。
+typedef enum ApProximityMode {
+  // Vivaldi ネットワーク距離のみ。
+  AP_PROXIMITY_MODE_NETWORK = 0,
+  // GPS fix 間の haversine 距離のみ。
+  AP_PROXIMITY_MODE_GEOGRAPHIC = 1,
+  // ネットワーク距離と地理的距離の両方。
+  AP_PROXIMITY_MODE_HYBRID = 2,
+} ApProximityMode;
+
+// 関数の結果コード。
+typedef enum ApStatus {
+  // 成功。
+  AP_STATUS_OK = 0,
+  // 必須のポインタ引数が `NULL`。
+  AP_STATUS_NULL_POINTER = 1,
+  // 出力バッファが小さすぎる (必要なサイズは `out_len` に書かれる)。
+  AP_STATUS_BUFFER_TOO_SMALL = 2,
+  // ネットワーク距離が閾値を超えた。
+  AP_STATUS_NOT_PROXIMATE = 3,
+  // 地理的距離が閾値を超えた。
+  AP_STATUS_GEO_NOT_PROXIMATE = 4,
+  // 近接確率が `min_probability` に届かない。
+  AP_STATUS_LOW_CONFIDENCE = 5,
+  // 地理モードで GPS fix のないパーティがある。
+  AP_STATUS_MISSING_GEO_FIX = 6,
+  // その他のプロトコルエラー。
+  AP_STATUS_PROTOCOL_ERROR = 7,
+  // バイト列が不正、または未対応の形式。
+  AP_STATUS_DECODE_ERROR = 8,
+  // 列挙型の引数が定義済みのどの値でもない。
+  AP_STATUS_INVALID_ARGUMENT = 9,
+} ApStatus;
+
+// [`VerifyResult`] の C 表現。
+typedef enum ApVerifyResult {
+  // content hash が一致。
+  AP_VERIFY_RESULT_VALID = 0,
+  // content hash 不一致 (改ざん疑い)。
+  AP_VERIFY_RESULT_HASH_MISMATCH = 1,
+  // ZKP 未検証。
+  AP_VERIFY_RESULT_ZKP_NOT_VERIFIED = 2,
+  // 近接未確認。
+  AP_VERIFY_RESULT_NOT_PROXIMATE = 3,
+  // セッション束縛の不一致。
+  AP_VERIFY_RESULT_SESSION_MISMATCH = 4,
+} ApVerifyResult;
+
+// プロトコル設定の不透明ハンドル。
+typedef struct ApConfig ApConfig;
+
+// パーティ情報 (座標・秘密・ID) の不透明ハンドル。
+typedef struct ApParty ApParty;
+
+// `CrossingRecord` の不透明ハンドル。
+typedef struct ApRecord ApRecord;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 結果コードの説明 (静的な NUL 終端 UTF-8 文字列)。
+//
+// C の列挙値は任意の `int` を取りうるため整数で受け取る。
+// 未定義の値には `"unknown status"` を返す。
+const char *ap_status_str(int status);
+
+// パーティを作成。`ap_party_free` で解放する。
+struct ApParty *ap_party_new(double x, double y, double height, uint64_t secret, uint32_t id);
+
+// Vivaldi 相対誤差を設定。
+//
+// # Safety
+//
+// `party` は `NULL` または `ap_party_new` が返した有効なハンドル。
+enum ApStatus ap_party_set_coord_error(struct ApParty *party, double error);
+
+// GPS fix (度) を設定。`Geographic` / `Hybrid` モードで必要。
+//
+// # Safety
+//
+// `party` は `NULL` または `ap_party_new` が返した有効なハンドル。
+enum ApStatus ap_party_set_geo(struct ApParty *party, double lat_deg, double lon_deg);
+
+// パーティを解放。
+//
+// # Safety
+//
+// `party` は `NULL` または `ap_party_new` が返した未解放のハンドル。
+void ap_party_free(struct ApParty *party);
+
+// 既定値の設定を作成。`ap_config_free` で解放する。
+struct ApConfig *ap_config_new(void);
+
+// Vivaldi 距離の閾値を設定。
+//
+// # Safety
+//
+// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+enum ApStatus ap_config_set_proximity_threshold(struct ApConfig *config, double threshold);
+
+// 地理的距離の閾値 (メートル) を設定。
+//
+// # Safety
+//
+// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+enum ApStatus ap_config_set_geo_threshold_m(struct ApConfig *config, double threshold_m);
+
+// 近接確率の下限を設定。負値・NaN は無効化 (固定閾値判定に戻す)。
+//
+// # Safety
+//
+// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+enum ApStatus ap_config_set_min_probability(struct ApConfig *config, double min_probability);
+
+// 近接判定モードを設定。
+//
+// `mode` は `ApProximityMode` の値を整数で渡す。未定義の値なら
+// `AP_STATUS_INVALID_ARGUMENT` を返し、設定は変更しない。
+//
+// # Safety
+//
+// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+enum ApStatus ap_config_set_mode(struct ApConfig *config, int mode);
+
+// 設定を解放。
+//
+// # Safety
+//
+// `config` は `NULL` または `ap_config_new` が返した未解放のハンドル。
+void ap_config_free(struct ApConfig *config);
+
+// プレゼンスプロトコルを実行し、成功すれば `*out` にレコードを書く。
+// `config` が `NULL` なら既定値を使う。
+//
+// # Safety
+//
+// `a` / `b` は有効な `ApParty`、`config` は `NULL` または有効な `ApConfig`、
+// `out` は書き込み可能な `ApRecord *` を指す。
+enum ApStatus ap_execute_protocol(const struct ApParty *a,
+                                  const struct ApParty *b,
+                                  uint64_t timestamp_ns,
+                                  const struct ApConfig *config,
+                                  struct ApRecord **out);
+
+// レコードをバイト列にシリアライズ。
+//
+// `*out_len` には常に必要なバイト数を書く。`buf` が `NULL` または `capacity`
+// が足りなければ `BufferTooSmall` を返し、バッファには書かない。
+//
+// # Safety
+//
+// `record` は有効な `ApRecord`、`out_len` は書き込み可能な `size_t`、
+// `buf` は `NULL` または `capacity` バイト書き込み可能な領域を指す。
+enum ApStatus ap_record_serialize(const struct ApRecord *record,
+                                  uint8_t *buf,
+                                  uintptr_t capacity,
+                                  uintptr_t *out_len);
+
+// バイト列からレコードを復元し、`*out` に書く。
+//
+// # Safety
+//
+// `data` は `len` バイト読み取り可能な領域 (`len == 0` なら `NULL` 可)、
+// `out` は書き込み可能な `ApRecord *` を指す。
+enum ApStatus ap_record_deserialize(const uint8_t *data, uintptr_t len, struct ApRecord **out);
+
+// レコードを検証し、結果を `*out` に書く。
+//
+// # Safety
+//
+// `record` は有効な `ApRecord`、`out` は書き込み可能な `ApVerifyResult` を指す。
+enum ApStatus ap_record_verify(const struct ApRecord *record, enum ApVerifyResult *out);
+
+// 18 バイトのプレゼンスイベントを `out` に書く。
+//
+// # Safety
+//
+// `record` は有効な `ApRecord`、`out` は 18 バイト書き込み可能な領域を指す。
+enum ApStatus ap_record_event_bytes(const struct ApRecord *record, uint8_t *out);
+
+// レコードの content hash (`NULL` なら 0)。
+//
+// # Safety
+//
+// `record` は `NULL` または有効な `ApRecord`。
+uint64_t ap_record_content_hash(const struct ApRecord *record);
+
+// パーティ A の ID (`NULL` なら 0)。
+//
+// # Safety
+//
+// `record` は `NULL` または有効な `ApRecord`。
+uint32_t ap_record_party_a_id(const struct ApRecord *record);
+
+// パーティ B の ID (`NULL` なら 0)。
+//
+// # Safety
+//
+// `record` は `NULL` または有効な `ApRecord`。
+uint32_t ap_record_party_b_id(const struct ApRecord *record);
+
+// エンカウンタのタイムスタンプ (ns, `NULL` なら 0)。
+//
+// # Safety
+//
+// `record` は `NULL` または有効な `ApRecord`。
+uint64_t ap_record_timestamp_ns(const struct ApRecord *record);
+
+// 近接証明の距離 (`NULL` なら NaN)。単位は [`ap_record_distance_unit`]。
+//
+// # Safety
+//
+// `record` は `NULL` または有効な `ApRecord`。
+double ap_record_distance(const struct ApRecord *record);
+
+// 近接証明の距離の単位 (`NULL` なら `AP_DISTANCE_UNIT_NETWORK`)。
+//
+// # Safety
+//
+// `record` は `NULL` または有効な `ApRecord`。
+enum ApDistanceUnit ap_record_distance_unit(const struct ApRecord *record);
+
+// レコードを解放。
+//
+// # Safety
+//
+// `record` は `NULL` または本 API が返した未解放のハンドル。
+void ap_record_free(struct ApRecord *record);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ALICE_PRESENCE_H */
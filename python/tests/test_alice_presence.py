@@ -0,0 +1,103 @@
+"""pytest tests for the `alice_presence` Python bindings.
+
+Build the module first, e.g. `maturin develop --features python`, or run
+`cargo test --features python --test python`, which builds it and runs this file.
+"""
+
+import pytest
+
+import alice_presence as ap
+
+
+def make_record(bx=3.0, by=4.0, ts=1_000):
+    alice = ap.Party(ap.VivaldiCoord(0.0, 0.0), secret=0xA11CE, id=1)
+    bob = ap.Party(ap.VivaldiCoord(bx, by), secret=0xB0B, id=2)
+    return ap.execute_presence_protocol(alice, bob, ts)
+
+
+def test_vivaldi_coord():
+    a = ap.VivaldiCoord(0.0, 0.0)
+    b = ap.VivaldiCoord(3.0, 4.0, height=1.0)
+    assert b.height == 1.0
+    assert a.distance(b) == pytest.approx(6.0)
+    assert "VivaldiCoord" in repr(a)
+
+
+def test_protocol_and_verify():
+    rec = make_record()
+    assert rec.distance == pytest.approx(5.0)
+    assert rec.distance_unit == "network"
+    assert rec.is_proximate
+    assert rec.is_fully_verified
+    assert rec.event.party_a_id == 1
+    assert rec.event.party_b_id == 2
+    assert rec.event.timestamp_ns == 1_000
+    assert rec.dwell is None
+    assert rec.geo_distance_m is None
+    assert ap.verify_record(rec) == ap.VerifyResult.Valid
+    assert rec.verify() == ap.VerifyResult.Valid
+
+
+def test_protocol_rejects_distant_parties():
+    with pytest.raises(ValueError, match="not proximate"):
+        make_record(bx=80.0, by=80.0)
+
+
+def test_crossing_serialization_roundtrip():
+    rec = make_record()
+    data = ap.serialize_crossing(rec)
+    assert isinstance(data, bytes)
+    assert data == rec.to_bytes()
+    back = ap.deserialize_crossing(data)
+    assert back.content_hash == rec.content_hash
+    assert ap.CrossingRecord.from_bytes(data).content_hash == rec.content_hash
+    assert back.verify() == ap.VerifyResult.Valid
+
+
+def test_deserialize_rejects_garbage():
+    with pytest.raises(ValueError):
+        ap.deserialize_crossing(b"\x00" * 8)
+
+
+def test_tampered_record_fails_verification():
+    data = bytearray(make_record().to_bytes())
+    data[-1] ^= 0xFF
+    try:
+        rec = ap.deserialize_crossing(bytes(data))
+    except ValueError:
+        return
+    assert rec.verify() == ap.VerifyResult.HashMismatch
+
+
+def test_presence_event_bytes():
+    ev = make_record().event
+    raw = ev.to_bytes()
+    assert len(raw) == 18
+    assert ap.PresenceEvent.from_bytes(raw) == ev
+    assert ev.is_mutual and ev.is_verified and ev.is_proximate
+    with pytest.raises(ValueError):
+        ap.PresenceEvent.from_bytes(raw[:17])
+    with pytest.raises(ValueError):
+        ap.PresenceEvent.from_bytes(b"\x01" + raw[1:])
+
+
+def test_event_batch_roundtrip():
+    events = [ap.PresenceEvent(i % 5, 7, 1_000_000_000 * i) for i in range(100)]
+    data = ap.encode_event_batch(events)
+    assert len(data) < 18 * len(events)
+    assert ap.decode_event_batch(data) == events
+    with pytest.raises(ValueError):
+        ap.decode_event_batch(data[:-1])
+
+
+def test_kdtree_queries():
+    coords = [(i, ap.VivaldiCoord(float(i), 0.0)) for i in range(10)]
+    tree = ap.KdTree(coords)
+    assert len(tree) == 10
+    query = ap.VivaldiCoord(4.2, 0.0)
+    ident, dist = tree.nearest(query)
+    assert ident == 4
+    assert dist == pytest.approx(0.2)
+    assert sorted(i for i, _ in tree.range_query(query, 1.5)) == [3, 4, 5]
+    assert [i for i, _ in tree.k_nearest(query, 2)] == [4, 5]
+    assert ap.KdTree([]).nearest(query) is None
@@ -0,0 +1,533 @@
+//! 列指向バッチ符号化 — `PresenceEvent` アーカイブの圧縮保存。
+//!
+//! アーカイブされたイベントは冗長 (単調増加するタイムスタンプ、繰り返し
+//! 現れるパーティ ID) なので、18 バイト固定長を並べる代わりに列ごとに
+//! 符号化する。
+//!
+//! ```text
+//! [magic "AEVB"][version: u8][count: varint]
+//! [type dict: varint n][n × u8, 昇順]
+//! [party dict: varint m][m × varint, 昇順の差分 (先頭は絶対値)]
+//! [flag width: u8 (0..=8)]
+//! [timestamps: count × zigzag varint, 直前との差分 (wrapping)]
+//! [packed rows: count × (type idx | flags | a idx | b idx), LSB-first, 0 パディング]
+//! ```
+//!
+//! 各行のビット幅は辞書サイズと flags の最上位ビットから決まる。
+//! 未登録の event type や任意の flags ビット、非単調なタイムスタンプも含めて
+//! 入力はビット単位で完全に復元される (検証なしの
+//! [`try_decode_event_batch_unchecked`] で読み戻した場合)。
+//!
+//! 外部依存ゼロ。
+
+use crate::error::DecodeError;
+use crate::event::PresenceEvent;
+
+/// バッチマジック。
+const MAGIC_BATCH: [u8; 4] = *b"AEVB";
+
+/// 現行のバッチ形式バージョン。
+pub const EVENT_BATCH_FORMAT_VERSION: u8 = 1;
+
+/// `[magic][version]` の長さ。
+const BATCH_HEADER_SIZE: usize = 5;
+
+// ============================================================================
+// Varint / bit packing
+// ============================================================================
+
+fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+const fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+const fn unzigzag(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// `n` 個の値を区別するのに必要なビット数。
+const fn index_width(n: usize) -> u32 {
+    if n <= 1 {
+        0
+    } else {
+        usize::BITS - (n - 1).leading_zeros()
+    }
+}
+
+/// LSB-first のビットライタ。
+struct BitWriter<'a> {
+    buf: &'a mut Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter<'_> {
+    fn put(&mut self, value: u64, width: u32) {
+        if width == 0 {
+            return;
+        }
+        self.acc |= value << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.buf.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(self) {
+        if self.bits > 0 {
+            self.buf.push(self.acc as u8);
+        }
+    }
+}
+
+/// 入力カーソル。
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        DecodeError::check_len(self.data, self.pos + 1)?;
+        self.pos += 1;
+        Ok(self.data[self.pos - 1])
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut v = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            let part = u64::from(b & 0x7F);
+            if shift == 63 && part > 1 {
+                return Err(DecodeError::InvalidField("varint"));
+            }
+            v |= part << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(DecodeError::InvalidField("varint"))
+    }
+
+    /// 要素数を読み、残りバイト数 (要素あたり最低 `min_size` バイト) で上限を検証する。
+    fn count(&mut self, field: &'static str, min_size: usize) -> Result<usize, DecodeError> {
+        let n = usize::try_from(self.varint()?).map_err(|_| DecodeError::InvalidField(field))?;
+        let need = n.saturating_mul(min_size);
+        if need > self.remaining() {
+            return Err(DecodeError::TooShort {
+                expected: self.pos.saturating_add(need),
+                actual: self.data.len(),
+            });
+        }
+        Ok(n)
+    }
+}
+
+/// LSB-first のビットリーダ。
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit: usize,
+}
+
+impl BitReader<'_> {
+    fn get(&mut self, width: u32) -> u64 {
+        let mut v = 0u64;
+        for i in 0..width {
+            let byte = self.data[self.bit / 8];
+            v |= u64::from((byte >> (self.bit % 8)) & 1) << i;
+            self.bit += 1;
+        }
+        v
+    }
+}
+
+// ============================================================================
+// Encode / decode
+// ============================================================================
+
+/// イベント列を列指向バッチにエンコード。
+#[must_use]
+pub fn encode_event_batch(events: &[PresenceEvent]) -> Vec<u8> {
+    let mut types: Vec<u8> = events.iter().map(|e| e.event_type).collect();
+    types.sort_unstable();
+    types.dedup();
+    let mut parties: Vec<u32> = events
+        .iter()
+        .flat_map(|e| [e.party_a_id, e.party_b_id])
+        .collect();
+    parties.sort_unstable();
+    parties.dedup();
+    let flag_width = 8 - events
+        .iter()
+        .fold(0u8, |acc, e| acc | e.flags)
+        .leading_zeros();
+    let type_width = index_width(types.len());
+    let party_width = index_width(parties.len());
+
+    let mut buf = Vec::with_capacity(BATCH_HEADER_SIZE + events.len() * 4);
+    buf.extend_from_slice(&MAGIC_BATCH);
+    buf.push(EVENT_BATCH_FORMAT_VERSION);
+    write_varint(&mut buf, events.len() as u64);
+
+    write_varint(&mut buf, types.len() as u64);
+    buf.extend_from_slice(&types);
+
+    write_varint(&mut buf, parties.len() as u64);
+    let mut prev = 0u32;
+    for &id in &parties {
+        write_varint(&mut buf, u64::from(id - prev));
+        prev = id;
+    }
+
+    buf.push(flag_width as u8);
+
+    let mut prev = 0u64;
+    for e in events {
+        write_varint(&mut buf, zigzag(e.timestamp_ns.wrapping_sub(prev) as i64));
+        prev = e.timestamp_ns;
+    }
+
+    // 辞書は重複排除済みでソートされているので二分探索は必ず成功する
+    let type_idx = |t: u8| types.binary_search(&t).unwrap_or_default() as u64;
+    let party_idx = |id: u32| parties.binary_search(&id).unwrap_or_default() as u64;
+    let mut bits = BitWriter {
+        buf: &mut buf,
+        acc: 0,
+        bits: 0,
+    };
+    for e in events {
+        bits.put(type_idx(e.event_type), type_width);
+        bits.put(u64::from(e.flags), flag_width);
+        bits.put(party_idx(e.party_a_id), party_width);
+        bits.put(party_idx(e.party_b_id), party_width);
+    }
+    bits.finish();
+    buf
+}
+
+/// 列指向バッチからイベント列を復元し、各イベントを検証する。
+///
+/// [`PresenceEvent::try_from_bytes`] と同じく、未登録の event type や
+/// その type で許されない flags を持つイベントは拒否する。
+///
+/// # Errors
+///
+/// 形式上の誤りは [`try_decode_event_batch_unchecked`] と同じ。
+/// 不正なイベントは [`PresenceEvent::validate`] のエラー
+/// (`UnknownType` / `InvalidField("flags")`)。
+pub fn try_decode_event_batch(data: &[u8]) -> Result<Vec<PresenceEvent>, DecodeError> {
+    let events = try_decode_event_batch_unchecked(data)?;
+    for e in &events {
+        e.validate()?;
+    }
+    Ok(events)
+}
+
+/// 列指向バッチからイベント列を復元 (イベント単位の検証なし)。
+///
+/// 復元されたイベントは type / flags の検証を行わない
+/// ([`PresenceEvent::from_bytes`] と同じく、エンコードされた値をそのまま返す)。
+/// 未知の type を含むアーカイブをビット単位で読み戻す用途向け。
+///
+/// # Errors
+///
+/// マジック不一致は `DecodeError::BadMagic`、未知のバージョンは
+/// `UnsupportedVersion`、途中切れは `TooShort`、余分なバイトは `TrailingBytes`、
+/// 辞書の順序違反・範囲外のインデックス・非 0 パディングなどは `InvalidField`。
+pub fn try_decode_event_batch_unchecked(data: &[u8]) -> Result<Vec<PresenceEvent>, DecodeError> {
+    DecodeError::check_len(data, BATCH_HEADER_SIZE)?;
+    if data[..4] != MAGIC_BATCH {
+        return Err(DecodeError::BadMagic);
+    }
+    if data[4] != EVENT_BATCH_FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(data[4]));
+    }
+    let mut cur = Cursor {
+        data,
+        pos: BATCH_HEADER_SIZE,
+    };
+    // タイムスタンプは 1 件あたり最低 1 バイト
+    let count = cur.count("event_count", 1)?;
+
+    let type_count = cur.count("type_dict", 1)?;
+    let types = &data[cur.pos..cur.pos + type_count];
+    cur.pos += type_count;
+    if types.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(DecodeError::InvalidField("type_dict"));
+    }
+
+    let party_count = cur.count("party_dict", 1)?;
+    let mut parties = Vec::with_capacity(party_count);
+    let mut prev = 0u64;
+    for i in 0..party_count {
+        let delta = cur.varint()?;
+        if i > 0 && delta == 0 {
+            return Err(DecodeError::InvalidField("party_dict"));
+        }
+        let id = prev
+            .checked_add(delta)
+            .and_then(|id| u32::try_from(id).ok())
+            .ok_or(DecodeError::InvalidField("party_dict"))?;
+        parties.push(id);
+        prev = u64::from(id);
+    }
+
+    let flag_width = u32::from(cur.byte()?);
+    if flag_width > 8 {
+        return Err(DecodeError::InvalidField("flag_width"));
+    }
+
+    let mut timestamps = Vec::with_capacity(count);
+    let mut prev = 0u64;
+    for _ in 0..count {
+        prev = prev.wrapping_add(unzigzag(cur.varint()?) as u64);
+        timestamps.push(prev);
+    }
+
+    let type_width = index_width(types.len());
+    let party_width = index_width(parties.len());
+    let row_bits = (type_width + flag_width + 2 * party_width) as usize;
+    let packed_len = (count * row_bits).div_ceil(8);
+    DecodeError::check_len(data, cur.pos + packed_len)?;
+    if data.len() > cur.pos + packed_len {
+        return Err(DecodeError::TrailingBytes {
+            extra: data.len() - cur.pos - packed_len,
+        });
+    }
+    if count > 0 && (types.is_empty() || parties.is_empty()) {
+        return Err(DecodeError::InvalidField("dictionary"));
+    }
+
+    let mut bits = BitReader {
+        data: &data[cur.pos..],
+        bit: 0,
+    };
+    let mut lookup = |dict_len: usize, width: u32, field: &'static str| {
+        let idx = bits.get(width) as usize;
+        if idx < dict_len {
+            Ok(idx)
+        } else {
+            Err(DecodeError::InvalidField(field))
+        }
+    };
+    let mut events = Vec::with_capacity(count);
+    for &timestamp_ns in &timestamps {
+        let event_type = types[lookup(types.len(), type_width, "type_index")?];
+        let flags = lookup(256, flag_width, "flags")? as u8;
+        let party_a_id = parties[lookup(parties.len(), party_width, "party_index")?];
+        let party_b_id = parties[lookup(parties.len(), party_width, "party_index")?];
+        events.push(PresenceEvent {
+            event_type,
+            flags,
+            party_a_id,
+            party_b_id,
+            timestamp_ns,
+        });
+    }
+    if bits.get((packed_len * 8 - count * row_bits) as u32) != 0 {
+        return Err(DecodeError::InvalidField("padding"));
+    }
+    Ok(events)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 少数のパーティ間で 1 秒間隔前後のイベントが続く典型的なアーカイブ。
+    fn archive(n: usize) -> Vec<PresenceEvent> {
+        let mut state = 0x9E37_79B9_u64;
+        let mut ts = 1_700_000_000_000_000_000u64;
+        (0..n)
+            .map(|i| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1);
+                ts += 1_000_000_000 + (state >> 40) % 1_000_000;
+                let a = 100 + (state >> 20) as u32 % 12;
+                let b = 100 + (state >> 28) as u32 % 12;
+                match i % 4 {
+                    0 => PresenceEvent::encounter_start(a, b, ts, true),
+                    1 => PresenceEvent::heartbeat(a, b, ts),
+                    2 => PresenceEvent::encounter_end(a, b, ts, i % 3 == 0),
+                    _ => {
+                        let mut ev = PresenceEvent::new(a, b, ts);
+                        ev.set_mutual();
+                        ev.set_proximate();
+                        ev
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn raw(events: &[PresenceEvent]) -> Vec<[u8; 18]> {
+        events.iter().map(PresenceEvent::to_bytes).collect()
+    }
+
+    #[test]
+    fn roundtrip_archive() {
+        let events = archive(1000);
+        let bytes = encode_event_batch(&events);
+        let back = try_decode_event_batch(&bytes).unwrap();
+        assert_eq!(raw(&back), raw(&events));
+        // 約 1 秒間隔のタイムスタンプ差分 (5 バイト) + 行 1 バイト前後 → 18 バイトの半分未満
+        assert!(bytes.len() * 2 < events.len() * 18, "{}", bytes.len());
+    }
+
+    #[test]
+    fn roundtrip_arbitrary_values() {
+        let events = [
+            PresenceEvent {
+                event_type: 0x01,
+                flags: 0xFF,
+                party_a_id: u32::MAX,
+                party_b_id: 0,
+                timestamp_ns: u64::MAX,
+            },
+            PresenceEvent {
+                event_type: 0xFE,
+                flags: 0,
+                party_a_id: 7,
+                party_b_id: 7,
+                timestamp_ns: 0,
+            },
+            PresenceEvent::new(1, u32::MAX, 5),
+        ];
+        let bytes = encode_event_batch(&events);
+        let back = try_decode_event_batch_unchecked(&bytes).unwrap();
+        assert_eq!(raw(&back), raw(&events));
+        // 検証付きのデコードは未登録の type を拒否する
+        assert_eq!(
+            try_decode_event_batch(&bytes).unwrap_err(),
+            DecodeError::UnknownType(0x01)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_events() {
+        // type は有効だが許されない flags ビットを持つ
+        let mut ev = PresenceEvent::heartbeat(1, 2, 3);
+        ev.flags = 0x80;
+        let bytes = encode_event_batch(&[PresenceEvent::new(1, 2, 0), ev]);
+        assert_eq!(
+            try_decode_event_batch(&bytes).unwrap_err(),
+            DecodeError::InvalidField("flags")
+        );
+        assert_eq!(
+            raw(&try_decode_event_batch_unchecked(&bytes).unwrap())[1],
+            ev.to_bytes()
+        );
+    }
+
+    #[test]
+    fn empty_and_single() {
+        let empty = encode_event_batch(&[]);
+        assert_eq!(empty.len(), BATCH_HEADER_SIZE + 4);
+        assert!(try_decode_event_batch(&empty).unwrap().is_empty());
+
+        let one = [PresenceEvent::new(3, 3, 42)];
+        let bytes = encode_event_batch(&one);
+        assert_eq!(raw(&try_decode_event_batch(&bytes).unwrap()), raw(&one));
+    }
+
+    #[test]
+    fn rejects_bad_header() {
+        let mut bytes = encode_event_batch(&archive(4));
+        bytes[4] = 9;
+        assert_eq!(
+            try_decode_event_batch(&bytes).unwrap_err(),
+            DecodeError::UnsupportedVersion(9)
+        );
+        bytes[0] = b'X';
+        assert_eq!(
+            try_decode_event_batch(&bytes).unwrap_err(),
+            DecodeError::BadMagic
+        );
+    }
+
+    #[test]
+    fn rejects_every_truncation_and_trailing_bytes() {
+        let mut bytes = encode_event_batch(&archive(16));
+        for len in 0..bytes.len() {
+            assert!(try_decode_event_batch(&bytes[..len]).is_err(), "{len}");
+        }
+        bytes.push(0);
+        assert_eq!(
+            try_decode_event_batch(&bytes).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_index() {
+        // party 辞書 3 件 → 幅 2 ビット、インデックス 3 は範囲外
+        let events = [PresenceEvent::new(1, 2, 0), PresenceEvent::new(3, 3, 0)];
+        let mut bytes = encode_event_batch(&events);
+        let last = bytes.len() - 1;
+        bytes[last] |= 0b1100_0000;
+        assert_eq!(
+            try_decode_event_batch(&bytes).unwrap_err(),
+            DecodeError::InvalidField("party_index")
+        );
+    }
+
+    #[test]
+    fn rejects_huge_count() {
+        let mut bytes = MAGIC_BATCH.to_vec();
+        bytes.push(EVENT_BATCH_FORMAT_VERSION);
+        write_varint(&mut bytes, u64::MAX);
+        assert!(matches!(
+            try_decode_event_batch(&bytes),
+            Err(DecodeError::TooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn decode_arbitrary_bytes_never_panics() {
+        let mut state = 0x1234_5678_u64;
+        for _ in 0..2000 {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            let len = (state >> 58) as usize + BATCH_HEADER_SIZE;
+            let mut buf: Vec<u8> = (0..len).map(|i| (state >> (i % 56)) as u8).collect();
+            buf[..4].copy_from_slice(&MAGIC_BATCH);
+            buf[4] = EVENT_BATCH_FORMAT_VERSION;
+            let _ = try_decode_event_batch(&buf);
+        }
+    }
+
+    #[test]
+    fn varint_zigzag_roundtrip() {
+        for v in [0i64, 1, -1, 63, -64, i64::MAX, i64::MIN] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, zigzag(v));
+            let mut cur = Cursor { data: &buf, pos: 0 };
+            assert_eq!(unzigzag(cur.varint().unwrap()), v);
+        }
+        let overlong = [0xFF; 11];
+        let mut cur = Cursor {
+            data: &overlong,
+            pos: 0,
+        };
+        assert_eq!(cur.varint(), Err(DecodeError::InvalidField("varint")));
+    }
+}
@@ -0,0 +1,735 @@
+//! C ABI — モバイルアプリ (iOS / Android) への組み込み用 (`capi` feature)。
+//!
+//! 不透明ハンドル (`ApParty` / `ApConfig` / `ApRecord`) と `extern "C"` 関数で
+//! プロトコル実行・レコードのシリアライズ/デシリアライズ・検証を公開する。
+//! ヘッダは cbindgen で `include/alice_presence.h` に生成する
+//! (`tests/capi.rs` が生成結果との一致を検査する)。
+//!
+//! 規約:
+//!
+//! - `ap_*_new` / 出力引数で返したハンドルは対応する `ap_*_free` で解放する
+//!   (`NULL` の解放は何もしない)
+//! - 失敗しうる関数は [`ApStatus`] を返し、結果は出力引数に書く
+//! - 出力引数は失敗時には書き換えない
+//! - ハンドルはスレッド間で共有してよいが、`free` との同時呼び出しは不可
+
+use core::ptr;
+use std::ffi::{c_char, c_int};
+
+use crate::error::{DecodeError, ProtocolError};
+use crate::event::{CrossingRecord, DistanceUnit};
+use crate::geo::GeoCoord;
+use crate::protocol::{try_execute_presence_protocol, PartyInfo, PresenceConfig, ProximityMode};
+use crate::serialize::{serialize_crossing, try_deserialize_crossing};
+use crate::verification::{verify_record, VerifyResult};
+use crate::vivaldi::VivaldiCoord;
+
+/// 関数の結果コード。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApStatus {
+    /// 成功。
+    Ok = 0,
+    /// 必須のポインタ引数が `NULL`。
+    NullPointer = 1,
+    /// 出力バッファが小さすぎる (必要なサイズは `out_len` に書かれる)。
+    BufferTooSmall = 2,
+    /// ネットワーク距離が閾値を超えた。
+    NotProximate = 3,
+    /// 地理的距離が閾値を超えた。
+    GeoNotProximate = 4,
+    /// 近接確率が `min_probability` に届かない。
+    LowConfidence = 5,
+    /// 地理モードで GPS fix のないパーティがある。
+    MissingGeoFix = 6,
+    /// その他のプロトコルエラー。
+    ProtocolError = 7,
+    /// バイト列が不正、または未対応の形式。
+    DecodeError = 8,
+    /// 列挙型の引数が定義済みのどの値でもない。
+    InvalidArgument = 9,
+}
+
+impl ApStatus {
+    /// C 側から渡された整数値を解釈する。未定義の値は `None`。
+    const fn from_raw(v: c_int) -> Option<Self> {
+        Some(match v {
+            0 => Self::Ok,
+            1 => Self::NullPointer,
+            2 => Self::BufferTooSmall,
+            3 => Self::NotProximate,
+            4 => Self::GeoNotProximate,
+            5 => Self::LowConfidence,
+            6 => Self::MissingGeoFix,
+            7 => Self::ProtocolError,
+            8 => Self::DecodeError,
+            9 => Self::InvalidArgument,
+            _ => return None,
+        })
+    }
+}
+
+impl From<ProtocolError> for ApStatus {
+    fn from(e: ProtocolError) -> Self {
+        match e {
+            ProtocolError::NotProximate { .. } => Self::NotProximate,
+            ProtocolError::GeoNotProximate { .. } => Self::GeoNotProximate,
+            ProtocolError::LowConfidence { .. } => Self::LowConfidence,
+            ProtocolError::MissingGeoFix => Self::MissingGeoFix,
+            _ => Self::ProtocolError,
+        }
+    }
+}
+
+impl From<DecodeError> for ApStatus {
+    fn from(_: DecodeError) -> Self {
+        Self::DecodeError
+    }
+}
+
+/// [`VerifyResult`] の C 表現。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApVerifyResult {
+    /// content hash が一致。
+    Valid = 0,
+    /// content hash 不一致 (改ざん疑い)。
+    HashMismatch = 1,
+    /// ZKP 未検証。
+    ZkpNotVerified = 2,
+    /// 近接未確認。
+    NotProximate = 3,
+    /// セッション束縛の不一致。
+    SessionMismatch = 4,
+}
+
+impl From<VerifyResult> for ApVerifyResult {
+    fn from(r: VerifyResult) -> Self {
+        match r {
+            VerifyResult::Valid => Self::Valid,
+            VerifyResult::HashMismatch => Self::HashMismatch,
+            VerifyResult::ZkpNotVerified => Self::ZkpNotVerified,
+            VerifyResult::NotProximate => Self::NotProximate,
+            VerifyResult::SessionMismatch => Self::SessionMismatch,
+        }
+    }
+}
+
+/// [`DistanceUnit`] の C 表現。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApDistanceUnit {
+    /// ネットワーク座標距離。
+    Network = 0,
+    /// メートル (GPS)。
+    Metres = 1,
+}
+
+impl From<DistanceUnit> for ApDistanceUnit {
+    fn from(u: DistanceUnit) -> Self {
+        match u {
+            DistanceUnit::Network => Self::Network,
+            DistanceUnit::Metres => Self::Metres,
+        }
+    }
+}
+
+/// [`ProximityMode`] の C 表現。
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApProximityMode {
+    /// Vivaldi ネットワーク距離のみ。
+    Network = 0,
+    /// GPS fix 間の haversine 距離のみ。
+    Geographic = 1,
+    /// ネットワーク距離と地理的距離の両方。
+    Hybrid = 2,
+}
+
+impl ApProximityMode {
+    /// C 側から渡された整数値を解釈する。未定義の値は `None`。
+    const fn from_raw(v: c_int) -> Option<Self> {
+        Some(match v {
+            0 => Self::Network,
+            1 => Self::Geographic,
+            2 => Self::Hybrid,
+            _ => return None,
+        })
+    }
+}
+
+impl From<ApProximityMode> for ProximityMode {
+    fn from(m: ApProximityMode) -> Self {
+        match m {
+            ApProximityMode::Network => Self::Network,
+            ApProximityMode::Geographic => Self::Geographic,
+            ApProximityMode::Hybrid => Self::Hybrid,
+        }
+    }
+}
+
+/// パーティ情報 (座標・秘密・ID) の不透明ハンドル。
+#[derive(Debug)]
+pub struct ApParty(PartyInfo);
+
+/// プロトコル設定の不透明ハンドル。
+#[derive(Debug)]
+pub struct ApConfig(PresenceConfig);
+
+/// `CrossingRecord` の不透明ハンドル。
+#[derive(Debug)]
+pub struct ApRecord(CrossingRecord);
+
+/// ハンドルを出力引数に書く。
+///
+/// # Safety
+///
+/// `out` は書き込み可能な `*mut T` を指す。
+unsafe fn put<T>(out: *mut *mut T, value: T) {
+    *out = Box::into_raw(Box::new(value));
+}
+
+// ============================================================================
+// Status
+// ============================================================================
+
+/// 結果コードの説明 (静的な NUL 終端 UTF-8 文字列)。
+///
+/// C の列挙値は任意の `int` を取りうるため整数で受け取る。
+/// 未定義の値には `"unknown status"` を返す。
+#[no_mangle]
+pub extern "C" fn ap_status_str(status: c_int) -> *const c_char {
+    let s: &'static [u8] = match ApStatus::from_raw(status) {
+        Some(ApStatus::Ok) => b"ok\0",
+        Some(ApStatus::NullPointer) => b"null pointer\0",
+        Some(ApStatus::BufferTooSmall) => b"buffer too small\0",
+        Some(ApStatus::NotProximate) => b"not proximate\0",
+        Some(ApStatus::GeoNotProximate) => b"not geographically proximate\0",
+        Some(ApStatus::LowConfidence) => b"proximity confidence too low\0",
+        Some(ApStatus::MissingGeoFix) => b"missing GPS fix\0",
+        Some(ApStatus::ProtocolError) => b"protocol error\0",
+        Some(ApStatus::DecodeError) => b"malformed or unsupported record\0",
+        Some(ApStatus::InvalidArgument) => b"invalid argument\0",
+        None => b"unknown status\0",
+    };
+    s.as_ptr().cast()
+}
+
+// ============================================================================
+// Party
+// ============================================================================
+
+/// パーティを作成。`ap_party_free` で解放する。
+#[no_mangle]
+pub extern "C" fn ap_party_new(x: f64, y: f64, height: f64, secret: u64, id: u32) -> *mut ApParty {
+    Box::into_raw(Box::new(ApParty(PartyInfo::new(
+        VivaldiCoord::with_height(x, y, height),
+        secret,
+        id,
+    ))))
+}
+
+/// Vivaldi 相対誤差を設定。
+///
+/// # Safety
+///
+/// `party` は `NULL` または `ap_party_new` が返した有効なハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_party_set_coord_error(party: *mut ApParty, error: f64) -> ApStatus {
+    let Some(party) = party.as_mut() else {
+        return ApStatus::NullPointer;
+    };
+    party.0.coord_error = error;
+    ApStatus::Ok
+}
+
+/// GPS fix (度) を設定。`Geographic` / `Hybrid` モードで必要。
+///
+/// # Safety
+///
+/// `party` は `NULL` または `ap_party_new` が返した有効なハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_party_set_geo(
+    party: *mut ApParty,
+    lat_deg: f64,
+    lon_deg: f64,
+) -> ApStatus {
+    let Some(party) = party.as_mut() else {
+        return ApStatus::NullPointer;
+    };
+    party.0.geo = Some(GeoCoord::new(lat_deg, lon_deg));
+    ApStatus::Ok
+}
+
+/// パーティを解放。
+///
+/// # Safety
+///
+/// `party` は `NULL` または `ap_party_new` が返した未解放のハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_party_free(party: *mut ApParty) {
+    if !party.is_null() {
+        drop(Box::from_raw(party));
+    }
+}
+
+// ============================================================================
+// Config
+// ============================================================================
+
+/// 既定値の設定を作成。`ap_config_free` で解放する。
+#[no_mangle]
+pub extern "C" fn ap_config_new() -> *mut ApConfig {
+    Box::into_raw(Box::new(ApConfig(PresenceConfig::default())))
+}
+
+/// Vivaldi 距離の閾値を設定。
+///
+/// # Safety
+///
+/// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_config_set_proximity_threshold(
+    config: *mut ApConfig,
+    threshold: f64,
+) -> ApStatus {
+    let Some(config) = config.as_mut() else {
+        return ApStatus::NullPointer;
+    };
+    config.0.proximity_threshold = threshold;
+    ApStatus::Ok
+}
+
+/// 地理的距離の閾値 (メートル) を設定。
+///
+/// # Safety
+///
+/// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_config_set_geo_threshold_m(
+    config: *mut ApConfig,
+    threshold_m: f64,
+) -> ApStatus {
+    let Some(config) = config.as_mut() else {
+        return ApStatus::NullPointer;
+    };
+    config.0.geo_threshold_m = threshold_m;
+    ApStatus::Ok
+}
+
+/// 近接確率の下限を設定。負値・NaN は無効化 (固定閾値判定に戻す)。
+///
+/// # Safety
+///
+/// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_config_set_min_probability(
+    config: *mut ApConfig,
+    min_probability: f64,
+) -> ApStatus {
+    let Some(config) = config.as_mut() else {
+        return ApStatus::NullPointer;
+    };
+    config.0.min_probability = (min_probability >= 0.0).then_some(min_probability);
+    ApStatus::Ok
+}
+
+/// 近接判定モードを設定。
+///
+/// `mode` は `ApProximityMode` の値を整数で渡す。未定義の値なら
+/// `AP_STATUS_INVALID_ARGUMENT` を返し、設定は変更しない。
+///
+/// # Safety
+///
+/// `config` は `NULL` または `ap_config_new` が返した有効なハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_config_set_mode(config: *mut ApConfig, mode: c_int) -> ApStatus {
+    let Some(config) = config.as_mut() else {
+        return ApStatus::NullPointer;
+    };
+    let Some(mode) = ApProximityMode::from_raw(mode) else {
+        return ApStatus::InvalidArgument;
+    };
+    config.0.mode = mode.into();
+    ApStatus::Ok
+}
+
+/// 設定を解放。
+///
+/// # Safety
+///
+/// `config` は `NULL` または `ap_config_new` が返した未解放のハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_config_free(config: *mut ApConfig) {
+    if !config.is_null() {
+        drop(Box::from_raw(config));
+    }
+}
+
+// ============================================================================
+// Protocol / records
+// ============================================================================
+
+/// プレゼンスプロトコルを実行し、成功すれば `*out` にレコードを書く。
+/// `config` が `NULL` なら既定値を使う。
+///
+/// # Safety
+///
+/// `a` / `b` は有効な `ApParty`、`config` は `NULL` または有効な `ApConfig`、
+/// `out` は書き込み可能な `ApRecord *` を指す。
+#[no_mangle]
+pub unsafe extern "C" fn ap_execute_protocol(
+    a: *const ApParty,
+    b: *const ApParty,
+    timestamp_ns: u64,
+    config: *const ApConfig,
+    out: *mut *mut ApRecord,
+) -> ApStatus {
+    let (Some(a), Some(b)) = (a.as_ref(), b.as_ref()) else {
+        return ApStatus::NullPointer;
+    };
+    if out.is_null() {
+        return ApStatus::NullPointer;
+    }
+    let default = PresenceConfig::default();
+    let config = config.as_ref().map_or(&default, |c| &c.0);
+    match try_execute_presence_protocol(&a.0, &b.0, timestamp_ns, config) {
+        Ok(record) => {
+            put(out, ApRecord(record));
+            ApStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// レコードをバイト列にシリアライズ。
+///
+/// `*out_len` には常に必要なバイト数を書く。`buf` が `NULL` または `capacity`
+/// が足りなければ `BufferTooSmall` を返し、バッファには書かない。
+///
+/// # Safety
+///
+/// `record` は有効な `ApRecord`、`out_len` は書き込み可能な `size_t`、
+/// `buf` は `NULL` または `capacity` バイト書き込み可能な領域を指す。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_serialize(
+    record: *const ApRecord,
+    buf: *mut u8,
+    capacity: usize,
+    out_len: *mut usize,
+) -> ApStatus {
+    let Some(record) = record.as_ref() else {
+        return ApStatus::NullPointer;
+    };
+    if out_len.is_null() {
+        return ApStatus::NullPointer;
+    }
+    let bytes = serialize_crossing(&record.0);
+    *out_len = bytes.len();
+    if buf.is_null() || capacity < bytes.len() {
+        return ApStatus::BufferTooSmall;
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len());
+    ApStatus::Ok
+}
+
+/// バイト列からレコードを復元し、`*out` に書く。
+///
+/// # Safety
+///
+/// `data` は `len` バイト読み取り可能な領域 (`len == 0` なら `NULL` 可)、
+/// `out` は書き込み可能な `ApRecord *` を指す。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_deserialize(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut ApRecord,
+) -> ApStatus {
+    if out.is_null() || (data.is_null() && len > 0) {
+        return ApStatus::NullPointer;
+    }
+    let data = if len == 0 {
+        &[]
+    } else {
+        core::slice::from_raw_parts(data, len)
+    };
+    match try_deserialize_crossing(data) {
+        Ok(record) => {
+            put(out, ApRecord(record));
+            ApStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// レコードを検証し、結果を `*out` に書く。
+///
+/// # Safety
+///
+/// `record` は有効な `ApRecord`、`out` は書き込み可能な `ApVerifyResult` を指す。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_verify(
+    record: *const ApRecord,
+    out: *mut ApVerifyResult,
+) -> ApStatus {
+    let Some(record) = record.as_ref() else {
+        return ApStatus::NullPointer;
+    };
+    if out.is_null() {
+        return ApStatus::NullPointer;
+    }
+    *out = verify_record(&record.0).into();
+    ApStatus::Ok
+}
+
+/// 18 バイトのプレゼンスイベントを `out` に書く。
+///
+/// # Safety
+///
+/// `record` は有効な `ApRecord`、`out` は 18 バイト書き込み可能な領域を指す。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_event_bytes(record: *const ApRecord, out: *mut u8) -> ApStatus {
+    let Some(record) = record.as_ref() else {
+        return ApStatus::NullPointer;
+    };
+    if out.is_null() {
+        return ApStatus::NullPointer;
+    }
+    let bytes = record.0.event.to_bytes();
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    ApStatus::Ok
+}
+
+/// レコードの content hash (`NULL` なら 0)。
+///
+/// # Safety
+///
+/// `record` は `NULL` または有効な `ApRecord`。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_content_hash(record: *const ApRecord) -> u64 {
+    record.as_ref().map_or(0, |r| r.0.content_hash)
+}
+
+/// パーティ A の ID (`NULL` なら 0)。
+///
+/// # Safety
+///
+/// `record` は `NULL` または有効な `ApRecord`。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_party_a_id(record: *const ApRecord) -> u32 {
+    record.as_ref().map_or(0, |r| r.0.event.party_a_id)
+}
+
+/// パーティ B の ID (`NULL` なら 0)。
+///
+/// # Safety
+///
+/// `record` は `NULL` または有効な `ApRecord`。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_party_b_id(record: *const ApRecord) -> u32 {
+    record.as_ref().map_or(0, |r| r.0.event.party_b_id)
+}
+
+/// エンカウンタのタイムスタンプ (ns, `NULL` なら 0)。
+///
+/// # Safety
+///
+/// `record` は `NULL` または有効な `ApRecord`。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_timestamp_ns(record: *const ApRecord) -> u64 {
+    record.as_ref().map_or(0, |r| r.0.event.timestamp_ns)
+}
+
+/// 近接証明の距離 (`NULL` なら NaN)。単位は [`ap_record_distance_unit`]。
+///
+/// # Safety
+///
+/// `record` は `NULL` または有効な `ApRecord`。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_distance(record: *const ApRecord) -> f64 {
+    record.as_ref().map_or(f64::NAN, |r| r.0.proximity.distance)
+}
+
+/// 近接証明の距離の単位 (`NULL` なら `AP_DISTANCE_UNIT_NETWORK`)。
+///
+/// # Safety
+///
+/// `record` は `NULL` または有効な `ApRecord`。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_distance_unit(record: *const ApRecord) -> ApDistanceUnit {
+    record
+        .as_ref()
+        .map_or(ApDistanceUnit::Network, |r| r.0.proximity.unit.into())
+}
+
+/// レコードを解放。
+///
+/// # Safety
+///
+/// `record` は `NULL` または本 API が返した未解放のハンドル。
+#[no_mangle]
+pub unsafe extern "C" fn ap_record_free(record: *mut ApRecord) {
+    if !record.is_null() {
+        drop(Box::from_raw(record));
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    struct Pair {
+        a: *mut ApParty,
+        b: *mut ApParty,
+    }
+
+    impl Pair {
+        fn new(bx: f64) -> Self {
+            Self {
+                a: ap_party_new(0.0, 0.0, 0.0, 42, 1),
+                b: ap_party_new(bx, 0.0, 0.0, 99, 2),
+            }
+        }
+
+        fn run(&self, config: *const ApConfig) -> (ApStatus, *mut ApRecord) {
+            let mut rec = ptr::null_mut();
+            let status = unsafe { ap_execute_protocol(self.a, self.b, 1000, config, &raw mut rec) };
+            (status, rec)
+        }
+    }
+
+    impl Drop for Pair {
+        fn drop(&mut self) {
+            unsafe {
+                ap_party_free(self.a);
+                ap_party_free(self.b);
+            }
+        }
+    }
+
+    #[test]
+    fn protocol_serialize_verify_roundtrip() {
+        let pair = Pair::new(3.0);
+        let (status, rec) = pair.run(ptr::null());
+        assert_eq!(status, ApStatus::Ok);
+        unsafe {
+            assert_eq!(ap_record_party_a_id(rec), 1);
+            assert_eq!(ap_record_party_b_id(rec), 2);
+            assert_eq!(ap_record_timestamp_ns(rec), 1000);
+            assert!((ap_record_distance(rec) - 3.0).abs() < 1e-9);
+
+            let mut len = 0;
+            assert_eq!(
+                ap_record_serialize(rec, ptr::null_mut(), 0, &raw mut len),
+                ApStatus::BufferTooSmall
+            );
+            let mut buf = vec![0u8; len];
+            assert_eq!(
+                ap_record_serialize(rec, buf.as_mut_ptr(), buf.len(), &raw mut len),
+                ApStatus::Ok
+            );
+
+            let mut back = ptr::null_mut();
+            assert_eq!(
+                ap_record_deserialize(buf.as_ptr(), buf.len(), &raw mut back),
+                ApStatus::Ok
+            );
+            assert_eq!(ap_record_content_hash(back), ap_record_content_hash(rec));
+            let mut verdict = ApVerifyResult::HashMismatch;
+            assert_eq!(ap_record_verify(back, &raw mut verdict), ApStatus::Ok);
+            assert_eq!(verdict, ApVerifyResult::Valid);
+
+            let mut event = [0u8; 18];
+            assert_eq!(
+                ap_record_event_bytes(back, event.as_mut_ptr()),
+                ApStatus::Ok
+            );
+            assert_eq!(event[0], b'P');
+
+            ap_record_free(back);
+            ap_record_free(rec);
+        }
+    }
+
+    #[test]
+    fn protocol_errors_map_to_status() {
+        let far = Pair::new(50.0);
+        let (status, rec) = far.run(ptr::null());
+        assert_eq!(status, ApStatus::NotProximate);
+        assert!(rec.is_null());
+
+        let config = ap_config_new();
+        unsafe {
+            assert_eq!(
+                ap_config_set_proximity_threshold(config, 100.0),
+                ApStatus::Ok
+            );
+            let (status, rec) = far.run(config);
+            assert_eq!(status, ApStatus::Ok);
+            ap_record_free(rec);
+
+            assert_eq!(
+                ap_config_set_mode(config, ApProximityMode::Geographic as c_int),
+                ApStatus::Ok
+            );
+            assert_eq!(far.run(config).0, ApStatus::MissingGeoFix);
+            // 未定義のモード値は拒否し、設定を変えない
+            assert_eq!(ap_config_set_mode(config, 3), ApStatus::InvalidArgument);
+            assert_eq!(far.run(config).0, ApStatus::MissingGeoFix);
+
+            assert_eq!(ap_party_set_geo(far.a, 35.0, 139.0), ApStatus::Ok);
+            assert_eq!(ap_party_set_geo(far.b, 35.0, 139.0), ApStatus::Ok);
+            let (status, rec) = far.run(config);
+            assert_eq!(status, ApStatus::Ok);
+            ap_record_free(rec);
+            ap_config_free(config);
+        }
+    }
+
+    #[test]
+    fn null_and_malformed_inputs() {
+        unsafe {
+            let mut rec = ptr::null_mut();
+            assert_eq!(
+                ap_execute_protocol(ptr::null(), ptr::null(), 0, ptr::null(), &raw mut rec),
+                ApStatus::NullPointer
+            );
+            assert_eq!(
+                ap_record_deserialize(ptr::null(), 0, &raw mut rec),
+                ApStatus::DecodeError
+            );
+            assert_eq!(
+                ap_record_deserialize(ptr::null(), 4, &raw mut rec),
+                ApStatus::NullPointer
+            );
+            let junk = [0xFFu8; 64];
+            assert_eq!(
+                ap_record_deserialize(junk.as_ptr(), junk.len(), &raw mut rec),
+                ApStatus::DecodeError
+            );
+            assert!(rec.is_null());
+            assert_eq!(ap_record_content_hash(ptr::null()), 0);
+            assert!(ap_record_distance(ptr::null()).is_nan());
+            assert_eq!(
+                ap_party_set_geo(ptr::null_mut(), 0.0, 0.0),
+                ApStatus::NullPointer
+            );
+            ap_record_free(ptr::null_mut());
+            ap_party_free(ptr::null_mut());
+            ap_config_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn status_strings_are_nul_terminated() {
+        let s = unsafe { CStr::from_ptr(ap_status_str(ApStatus::BufferTooSmall as c_int)) };
+        assert_eq!(s.to_str().unwrap(), "buffer too small");
+        let s = unsafe { CStr::from_ptr(ap_status_str(-1)) };
+        assert_eq!(s.to_str().unwrap(), "unknown status");
+    }
+}
@@ -0,0 +1,946 @@
+//! 正規 CBOR 符号化 — 組み込み機器との相互運用 (`cbor` feature)。
+//!
+//! `PresenceEvent` / `ProximityProof` / `CrossingRecord` / `GroupProximityProof`
+//! を整数キーの CBOR map として、RFC 8949 §4.2.1 の決定的 (core deterministic)
+//! 符号化で読み書きする。
+//!
+//! - 整数・長さは最短形式、不定長は使わない
+//! - map キーは昇順の小さな非負整数 (0..=23)
+//! - 浮動小数点は値を保つ最短の幅 (f16 / f32 / f64)、NaN は `0xf97e00`
+//!
+//! 同じ値は常に同じバイト列になるため、符号化結果に対して
+//! [`cbor_hash`] で content hash を計算できる。デコーダは非正規な入力
+//! (冗長な長さ、キー順序違反、未知のキーなど) を拒否する。
+//!
+//! 外部依存ゼロ。
+//!
+//! | 型 | キー |
+//! |----|------|
+//! | `PresenceEvent` | 0 `event_type`, 1 flags, 2 `party_a_id`, 3 `party_b_id`, 4 `timestamp_ns` |
+//! | `ProximityProof` | 0 distance, 1 threshold, 2 `is_proximate`, 3 `coord_hash_a`, 4 `coord_hash_b`, 5 `content_hash`, 6 confidence?, 7 unit? (`DistanceUnit` tag, omitted for `Network`) |
+//! | `ZkProof` | 0 challenge, 1 response, 2 commitment, 3 verified |
+//! | `CrossingRecord` | 0 event, 1 `proof_a`, 2 `proof_b`, 3 proximity, 4 `geo_proximity`?, 5 dwell `[start, end]`?, 6 `content_hash`, 7 session `[a, b]`? |
+//! | `GroupProximityProof` | 0 `group_id`, 1 `member_ids` `[...]`, 2 `max_distance`, 3 threshold, 4 `all_proximate`, 5 `content_hash` |
+//!
+//! `?` 付きのキーは値がある場合のみ書き出す。
+
+use crate::error::DecodeError;
+use crate::event::{
+    CrossingRecord, DistanceUnit, DwellInterval, PresenceEvent, ProximityProof, SessionBinding,
+};
+use crate::fnv1a;
+use crate::group::{GroupProximityProof, MAX_GROUP_SIZE};
+use crate::identity::ZkProof;
+use crate::serialize::check_crossing_event;
+
+/// 構造的に不正、または正規形でない CBOR。
+const NON_CANONICAL: DecodeError = DecodeError::InvalidField("cbor");
+
+/// CBOR major type。
+const MAJOR_UINT: u8 = 0;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_SIMPLE: u8 = 7;
+
+/// 正規 CBOR で読み書きできる型。
+pub trait Cbor: Sized {
+    /// 正規 CBOR にエンコード。
+    #[must_use]
+    fn to_cbor(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        self.write(&mut w);
+        w.buf
+    }
+
+    /// 正規 CBOR からデコード。入力全体がちょうど 1 つの値でなければならない。
+    ///
+    /// # Errors
+    ///
+    /// 途中切れは `DecodeError::TooShort`、余分なバイトは `TrailingBytes`、
+    /// 非正規・構造不正は `InvalidField("cbor")`、必須キーの欠落や型違いは
+    /// `InvalidField(<フィールド名>)`。
+    fn from_cbor(data: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader { data, pos: 0 };
+        let value = Self::read(&mut r)?;
+        if r.pos < data.len() {
+            return Err(DecodeError::TrailingBytes {
+                extra: data.len() - r.pos,
+            });
+        }
+        Ok(value)
+    }
+
+    #[doc(hidden)]
+    fn write(&self, w: &mut Writer);
+
+    #[doc(hidden)]
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError>;
+}
+
+/// 正規 CBOR 符号化に対する FNV-1a ハッシュ。
+#[must_use]
+pub fn cbor_hash<T: Cbor>(value: &T) -> u64 {
+    fnv1a(&value.to_cbor())
+}
+
+// ============================================================================
+// Half-precision helpers
+// ============================================================================
+
+/// `v` を正確に表せる場合の IEEE 754 binary16 ビット列。
+fn f16_exact(v: f64) -> Option<u16> {
+    let sign = if v.is_sign_negative() { 0x8000 } else { 0 };
+    if v.is_nan() {
+        return Some(0x7E00);
+    }
+    if v.is_infinite() {
+        return Some(sign | 0x7C00);
+    }
+    let a = v.abs();
+    if a == 0.0 {
+        return Some(sign);
+    }
+    let bits = a.to_bits();
+    let exp = ((bits >> 52) & 0x7FF) as i32 - 1023;
+    let mantissa = bits & ((1u64 << 52) - 1);
+    let h = if (-14..=15).contains(&exp) {
+        if mantissa & ((1u64 << 42) - 1) != 0 {
+            return None;
+        }
+        (((exp + 15) as u16) << 10) | (mantissa >> 42) as u16
+    } else if (-24..-14).contains(&exp) {
+        // 非正規化数: a = m * 2^-24
+        let m = a * f64::from(1u32 << 24);
+        if m.fract() != 0.0 {
+            return None;
+        }
+        m as u16
+    } else {
+        return None;
+    };
+    Some(sign | h)
+}
+
+/// binary16 ビット列を `f64` に。
+fn f16_to_f64(h: u16) -> f64 {
+    let sign = if h & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = i32::from((h >> 10) & 0x1F);
+    let mant = f64::from(h & 0x3FF);
+    let magnitude = match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mant / 1024.0) * 2f64.powi(exp - 15),
+    };
+    sign * magnitude
+}
+
+/// `v` が `f32` で正確に表せるか (NaN は f16 で扱うため除外)。
+fn f32_exact(v: f64) -> Option<f32> {
+    let f = v as f32;
+    (f64::from(f).to_bits() == v.to_bits()).then_some(f)
+}
+
+// ============================================================================
+// Writer
+// ============================================================================
+
+/// 正規 CBOR ライタ。
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn head(&mut self, major: u8, arg: u64) {
+        let m = major << 5;
+        match arg {
+            0..=23 => self.buf.push(m | arg as u8),
+            24..=0xFF => self.buf.extend_from_slice(&[m | 24, arg as u8]),
+            0x100..=0xFFFF => {
+                self.buf.push(m | 25);
+                self.buf.extend_from_slice(&(arg as u16).to_be_bytes());
+            }
+            0x1_0000..=0xFFFF_FFFF => {
+                self.buf.push(m | 26);
+                self.buf.extend_from_slice(&(arg as u32).to_be_bytes());
+            }
+            _ => {
+                self.buf.push(m | 27);
+                self.buf.extend_from_slice(&arg.to_be_bytes());
+            }
+        }
+    }
+
+    fn uint(&mut self, v: u64) {
+        self.head(MAJOR_UINT, v);
+    }
+
+    fn bool(&mut self, v: bool) {
+        self.buf.push((MAJOR_SIMPLE << 5) | if v { 21 } else { 20 });
+    }
+
+    fn float(&mut self, v: f64) {
+        let m = MAJOR_SIMPLE << 5;
+        if let Some(h) = f16_exact(v) {
+            self.buf.push(m | 25);
+            self.buf.extend_from_slice(&h.to_be_bytes());
+        } else if let Some(f) = f32_exact(v) {
+            self.buf.push(m | 26);
+            self.buf.extend_from_slice(&f.to_bits().to_be_bytes());
+        } else {
+            self.buf.push(m | 27);
+            self.buf.extend_from_slice(&v.to_bits().to_be_bytes());
+        }
+    }
+
+    fn map(&mut self, len: usize) {
+        self.head(MAJOR_MAP, len as u64);
+    }
+
+    fn array(&mut self, len: usize) {
+        self.head(MAJOR_ARRAY, len as u64);
+    }
+
+    fn key(&mut self, k: u8) {
+        self.uint(u64::from(k));
+    }
+}
+
+// ============================================================================
+// Reader
+// ============================================================================
+
+/// 正規形を検証する CBOR リーダ。
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], DecodeError> {
+        DecodeError::check_len(self.data, self.pos + n)?;
+        let s = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// 初期バイトと引数を読む。引数が最短形式でなければ拒否。
+    fn head(&mut self) -> Result<(u8, u8, u64), DecodeError> {
+        let ib = self.byte()?;
+        let (major, info) = (ib >> 5, ib & 0x1F);
+        if major == MAJOR_SIMPLE {
+            // 浮動小数点・simple value は呼び出し側で解釈する
+            return Ok((major, info, 0));
+        }
+        let arg = match info {
+            0..=23 => u64::from(info),
+            24 => {
+                let v = u64::from(self.byte()?);
+                if v < 24 {
+                    return Err(NON_CANONICAL);
+                }
+                v
+            }
+            25 => {
+                let b = self.take(2)?;
+                let v = u64::from(u16::from_be_bytes([b[0], b[1]]));
+                if v <= 0xFF {
+                    return Err(NON_CANONICAL);
+                }
+                v
+            }
+            26 => {
+                let b = self.take(4)?;
+                let v = u64::from(u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+                if v <= 0xFFFF {
+                    return Err(NON_CANONICAL);
+                }
+                v
+            }
+            27 => {
+                let b = self.take(8)?;
+                let mut a = [0u8; 8];
+                a.copy_from_slice(b);
+                let v = u64::from_be_bytes(a);
+                if v <= 0xFFFF_FFFF {
+                    return Err(NON_CANONICAL);
+                }
+                v
+            }
+            // 不定長 (31) と予約値
+            _ => return Err(NON_CANONICAL),
+        };
+        Ok((major, info, arg))
+    }
+
+    fn uint(&mut self, field: &'static str) -> Result<u64, DecodeError> {
+        match self.head()? {
+            (MAJOR_UINT, _, v) => Ok(v),
+            _ => Err(DecodeError::InvalidField(field)),
+        }
+    }
+
+    fn uint_as<T: TryFrom<u64>>(&mut self, field: &'static str) -> Result<T, DecodeError> {
+        T::try_from(self.uint(field)?).map_err(|_| DecodeError::InvalidField(field))
+    }
+
+    fn bool(&mut self, field: &'static str) -> Result<bool, DecodeError> {
+        match self.head()? {
+            (MAJOR_SIMPLE, 20, _) => Ok(false),
+            (MAJOR_SIMPLE, 21, _) => Ok(true),
+            _ => Err(DecodeError::InvalidField(field)),
+        }
+    }
+
+    fn float(&mut self, field: &'static str) -> Result<f64, DecodeError> {
+        let v = match self.head()? {
+            (MAJOR_SIMPLE, 25, _) => {
+                let b = self.take(2)?;
+                let h = u16::from_be_bytes([b[0], b[1]]);
+                let v = f16_to_f64(h);
+                if v.is_nan() && h != 0x7E00 {
+                    return Err(NON_CANONICAL);
+                }
+                return Ok(v);
+            }
+            (MAJOR_SIMPLE, 26, _) => {
+                let b = self.take(4)?;
+                f64::from(f32::from_bits(u32::from_be_bytes([b[0], b[1], b[2], b[3]])))
+            }
+            (MAJOR_SIMPLE, 27, _) => {
+                let b = self.take(8)?;
+                let mut a = [0u8; 8];
+                a.copy_from_slice(b);
+                let v = f64::from_bits(u64::from_be_bytes(a));
+                if f32_exact(v).is_some() {
+                    return Err(NON_CANONICAL);
+                }
+                v
+            }
+            _ => return Err(DecodeError::InvalidField(field)),
+        };
+        // より短い幅で表せる値は非正規
+        if f16_exact(v).is_some() {
+            return Err(NON_CANONICAL);
+        }
+        Ok(v)
+    }
+
+    fn array(&mut self, field: &'static str) -> Result<usize, DecodeError> {
+        match self.head()? {
+            (MAJOR_ARRAY, _, n) => usize::try_from(n).map_err(|_| NON_CANONICAL),
+            _ => Err(DecodeError::InvalidField(field)),
+        }
+    }
+
+    /// map を開き、昇順キーを順に検証するためのカーソルを返す。
+    fn map(&mut self, field: &'static str) -> Result<MapKeys, DecodeError> {
+        match self.head()? {
+            (MAJOR_MAP, _, n) if n <= 24 => Ok(MapKeys {
+                remaining: n as usize,
+                last: None,
+            }),
+            (MAJOR_MAP, _, _) => Err(NON_CANONICAL),
+            _ => Err(DecodeError::InvalidField(field)),
+        }
+    }
+}
+
+/// map キーの読み取り状態。
+struct MapKeys {
+    remaining: usize,
+    last: Option<u64>,
+}
+
+impl MapKeys {
+    /// 次のキーを読む。残りがなければ `None`。キーは厳密に昇順。
+    fn next(&mut self, r: &mut Reader<'_>) -> Result<Option<u64>, DecodeError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let k = r.uint("cbor")?;
+        if self.last.is_some_and(|last| k <= last) {
+            return Err(NON_CANONICAL);
+        }
+        self.last = Some(k);
+        self.remaining -= 1;
+        Ok(Some(k))
+    }
+
+    /// 必須キー `expected` を読む。
+    fn expect(&mut self, r: &mut Reader<'_>, expected: u64) -> Result<(), DecodeError> {
+        match self.next(r)? {
+            Some(k) if k == expected => Ok(()),
+            _ => Err(NON_CANONICAL),
+        }
+    }
+
+    /// すべてのキーを読み終えたことを確認する。
+    fn finish(&self) -> Result<(), DecodeError> {
+        if self.remaining == 0 {
+            Ok(())
+        } else {
+            Err(NON_CANONICAL)
+        }
+    }
+}
+
+// ============================================================================
+// Type impls
+// ============================================================================
+
+impl Cbor for PresenceEvent {
+    fn write(&self, w: &mut Writer) {
+        w.map(5);
+        w.key(0);
+        w.uint(u64::from(self.event_type));
+        w.key(1);
+        w.uint(u64::from(self.flags));
+        w.key(2);
+        w.uint(u64::from(self.party_a_id));
+        w.key(3);
+        w.uint(u64::from(self.party_b_id));
+        w.key(4);
+        w.uint(self.timestamp_ns);
+    }
+
+    /// type / flags は [`PresenceEvent::validate`] で検証する。
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let mut keys = r.map("event")?;
+        keys.expect(r, 0)?;
+        let event_type = r.uint_as("event_type")?;
+        keys.expect(r, 1)?;
+        let flags = r.uint_as("flags")?;
+        keys.expect(r, 2)?;
+        let party_a_id = r.uint_as("party_a_id")?;
+        keys.expect(r, 3)?;
+        let party_b_id = r.uint_as("party_b_id")?;
+        keys.expect(r, 4)?;
+        let timestamp_ns = r.uint("timestamp_ns")?;
+        keys.finish()?;
+        let event = Self {
+            event_type,
+            flags,
+            party_a_id,
+            party_b_id,
+            timestamp_ns,
+        };
+        event.validate()?;
+        Ok(event)
+    }
+}
+
+impl Cbor for ProximityProof {
+    fn write(&self, w: &mut Writer) {
+        let metric = self.unit != DistanceUnit::Network;
+        w.map(6 + usize::from(self.confidence.is_some()) + usize::from(metric));
+        w.key(0);
+        w.float(self.distance);
+        w.key(1);
+        w.float(self.threshold);
+        w.key(2);
+        w.bool(self.is_proximate);
+        w.key(3);
+        w.uint(self.coord_hash_a);
+        w.key(4);
+        w.uint(self.coord_hash_b);
+        w.key(5);
+        w.uint(self.content_hash);
+        if let Some(c) = self.confidence {
+            w.key(6);
+            w.float(c);
+        }
+        if metric {
+            w.key(7);
+            w.uint(self.unit as u64);
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let mut keys = r.map("proximity")?;
+        keys.expect(r, 0)?;
+        let distance = r.float("distance")?;
+        keys.expect(r, 1)?;
+        let threshold = r.float("threshold")?;
+        keys.expect(r, 2)?;
+        let is_proximate = r.bool("is_proximate")?;
+        keys.expect(r, 3)?;
+        let coord_hash_a = r.uint("coord_hash_a")?;
+        keys.expect(r, 4)?;
+        let coord_hash_b = r.uint("coord_hash_b")?;
+        keys.expect(r, 5)?;
+        let content_hash = r.uint("content_hash")?;
+        let mut key = keys.next(r)?;
+        let mut confidence = None;
+        if key == Some(6) {
+            confidence = Some(r.float("confidence")?);
+            key = keys.next(r)?;
+        }
+        let unit = match key {
+            None => DistanceUnit::Network,
+            Some(7) => u8::try_from(r.uint("unit")?)
+                .ok()
+                .and_then(DistanceUnit::from_u8)
+                .filter(|u| *u != DistanceUnit::Network)
+                .ok_or(DecodeError::InvalidField("unit"))?,
+            Some(_) => return Err(NON_CANONICAL),
+        };
+        keys.finish()?;
+        Ok(Self {
+            distance,
+            threshold,
+            is_proximate,
+            coord_hash_a,
+            coord_hash_b,
+            confidence,
+            unit,
+            content_hash,
+        })
+    }
+}
+
+impl Cbor for ZkProof {
+    fn write(&self, w: &mut Writer) {
+        w.map(4);
+        w.key(0);
+        w.uint(self.challenge);
+        w.key(1);
+        w.uint(self.response);
+        w.key(2);
+        w.uint(self.commitment);
+        w.key(3);
+        w.bool(self.verified);
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let mut keys = r.map("zk_proof")?;
+        keys.expect(r, 0)?;
+        let challenge = r.uint("challenge")?;
+        keys.expect(r, 1)?;
+        let response = r.uint("response")?;
+        keys.expect(r, 2)?;
+        let commitment = r.uint("commitment")?;
+        keys.expect(r, 3)?;
+        let verified = r.bool("verified")?;
+        keys.finish()?;
+        Ok(Self {
+            challenge,
+            response,
+            commitment,
+            verified,
+        })
+    }
+}
+
+impl Cbor for CrossingRecord {
+    fn write(&self, w: &mut Writer) {
+        let optional = usize::from(self.geo_proximity.is_some())
+            + usize::from(self.dwell.is_some())
+            + usize::from(self.session.is_some());
+        w.map(5 + optional);
+        w.key(0);
+        self.event.write(w);
+        w.key(1);
+        self.proof_a.write(w);
+        w.key(2);
+        self.proof_b.write(w);
+        w.key(3);
+        self.proximity.write(w);
+        if let Some(geo) = &self.geo_proximity {
+            w.key(4);
+            geo.write(w);
+        }
+        if let Some(dwell) = &self.dwell {
+            w.key(5);
+            w.array(2);
+            w.uint(dwell.start_ns);
+            w.uint(dwell.end_ns);
+        }
+        w.key(6);
+        w.uint(self.content_hash);
+        if let Some(session) = &self.session {
+            w.key(7);
+            w.array(2);
+            w.uint(session.session_a);
+            w.uint(session.session_b);
+        }
+    }
+
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let mut keys = r.map("record")?;
+        keys.expect(r, 0)?;
+        let event = PresenceEvent::read(r)?;
+        check_crossing_event(&event)?;
+        keys.expect(r, 1)?;
+        let proof_a = ZkProof::read(r)?;
+        keys.expect(r, 2)?;
+        let proof_b = ZkProof::read(r)?;
+        keys.expect(r, 3)?;
+        let proximity = ProximityProof::read(r)?;
+
+        let mut geo_proximity = None;
+        let mut dwell = None;
+        let mut key = keys.next(r)?;
+        if key == Some(4) {
+            geo_proximity = Some(ProximityProof::read(r)?);
+            key = keys.next(r)?;
+        }
+        if key == Some(5) {
+            if r.array("dwell")? != 2 {
+                return Err(DecodeError::InvalidField("dwell"));
+            }
+            dwell = Some(DwellInterval {
+                start_ns: r.uint("dwell")?,
+                end_ns: r.uint("dwell")?,
+            });
+            key = keys.next(r)?;
+        }
+        if key != Some(6) {
+            return Err(NON_CANONICAL);
+        }
+        let content_hash = r.uint("content_hash")?;
+        let session = match keys.next(r)? {
+            None => None,
+            Some(7) => {
+                if r.array("session")? != 2 {
+                    return Err(DecodeError::InvalidField("session"));
+                }
+                Some(SessionBinding::new(r.uint("session")?, r.uint("session")?))
+            }
+            Some(_) => return Err(NON_CANONICAL),
+        };
+        keys.finish()?;
+        Ok(Self {
+            event,
+            proof_a,
+            proof_b,
+            proximity,
+            geo_proximity,
+            dwell,
+            session,
+            content_hash,
+        })
+    }
+}
+
+impl Cbor for GroupProximityProof {
+    fn write(&self, w: &mut Writer) {
+        w.map(6);
+        w.key(0);
+        w.uint(self.group_id);
+        w.key(1);
+        w.array(self.member_ids.len());
+        for &id in &self.member_ids {
+            w.uint(u64::from(id));
+        }
+        w.key(2);
+        w.float(self.max_distance);
+        w.key(3);
+        w.float(self.threshold);
+        w.key(4);
+        w.bool(self.all_proximate);
+        w.key(5);
+        w.uint(self.content_hash);
+    }
+
+    /// `member_count` は `member_ids` の長さから復元する。
+    fn read(r: &mut Reader<'_>) -> Result<Self, DecodeError> {
+        let mut keys = r.map("group_proof")?;
+        keys.expect(r, 0)?;
+        let group_id = r.uint("group_id")?;
+        keys.expect(r, 1)?;
+        let n = r.array("member_ids")?;
+        if n > MAX_GROUP_SIZE {
+            return Err(DecodeError::InvalidField("member_count"));
+        }
+        let member_ids = (0..n)
+            .map(|_| r.uint_as("member_ids"))
+            .collect::<Result<Vec<u32>, _>>()?;
+        if member_ids.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(DecodeError::InvalidField("member_ids"));
+        }
+        keys.expect(r, 2)?;
+        let max_distance = r.float("max_distance")?;
+        keys.expect(r, 3)?;
+        let threshold = r.float("threshold")?;
+        keys.expect(r, 4)?;
+        let all_proximate = r.bool("all_proximate")?;
+        keys.expect(r, 5)?;
+        let content_hash = r.uint("content_hash")?;
+        keys.finish()?;
+        Ok(Self {
+            group_id,
+            member_count: member_ids.len(),
+            member_ids,
+            max_distance,
+            threshold,
+            all_proximate,
+            content_hash,
+        })
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group::{GroupConfig, PresenceGroup};
+    use crate::protocol::{try_execute_presence_protocol, PartyInfo, PresenceConfig};
+    use crate::verification::{verify_group_proof, verify_record, VerifyResult};
+    use crate::vivaldi::VivaldiCoord;
+
+    fn encode_float(v: f64) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.float(v);
+        w.buf
+    }
+
+    fn encode_uint(v: u64) -> Vec<u8> {
+        let mut w = Writer::default();
+        w.uint(v);
+        w.buf
+    }
+
+    fn make_record() -> CrossingRecord {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(1.0, 0.3), 99, 2);
+        try_execute_presence_protocol(&a, &b, 100, &PresenceConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn rfc8949_integer_vectors() {
+        assert_eq!(encode_uint(0), [0x00]);
+        assert_eq!(encode_uint(23), [0x17]);
+        assert_eq!(encode_uint(24), [0x18, 0x18]);
+        assert_eq!(encode_uint(1000), [0x19, 0x03, 0xE8]);
+        assert_eq!(encode_uint(1_000_000), [0x1A, 0x00, 0x0F, 0x42, 0x40]);
+        assert_eq!(
+            encode_uint(u64::MAX),
+            [0x1B, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn rfc8949_float_vectors() {
+        assert_eq!(encode_float(0.0), [0xF9, 0x00, 0x00]);
+        assert_eq!(encode_float(-0.0), [0xF9, 0x80, 0x00]);
+        assert_eq!(encode_float(1.5), [0xF9, 0x3E, 0x00]);
+        assert_eq!(encode_float(65504.0), [0xF9, 0x7B, 0xFF]);
+        assert_eq!(encode_float(5.960_464_477_539_063e-8), [0xF9, 0x00, 0x01]);
+        assert_eq!(encode_float(100_000.0), [0xFA, 0x47, 0xC3, 0x50, 0x00]);
+        assert_eq!(
+            encode_float(1.1),
+            [0xFB, 0x3F, 0xF1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9A]
+        );
+        assert_eq!(encode_float(f64::INFINITY), [0xF9, 0x7C, 0x00]);
+        assert_eq!(encode_float(f64::NAN), [0xF9, 0x7E, 0x00]);
+    }
+
+    #[test]
+    fn event_roundtrip_and_layout() {
+        let ev = PresenceEvent::heartbeat(1, 300, 5);
+        let bytes = ev.to_cbor();
+        // {0: 0x48, 1: 4, 2: 1, 3: 300, 4: 5}
+        assert_eq!(
+            bytes,
+            [0xA5, 0x00, 0x18, 0x48, 0x01, 0x04, 0x02, 0x01, 0x03, 0x19, 0x01, 0x2C, 0x04, 0x05]
+        );
+        let back = PresenceEvent::from_cbor(&bytes).unwrap();
+        assert_eq!(back.to_bytes(), ev.to_bytes());
+    }
+
+    #[test]
+    fn record_roundtrip_is_deterministic() {
+        let record = make_record().with_dwell(DwellInterval {
+            start_ns: 100,
+            end_ns: 400,
+        });
+        let bytes = record.to_cbor();
+        let back = CrossingRecord::from_cbor(&bytes).unwrap();
+        assert_eq!(verify_record(&back), VerifyResult::Valid);
+        assert_eq!(back.dwell, record.dwell);
+        assert_eq!(back.to_cbor(), bytes);
+        assert_eq!(cbor_hash(&back), cbor_hash(&record));
+        assert_ne!(cbor_hash(&back), cbor_hash(&make_record()));
+
+        let geo = make_record().with_geo_proximity(record.proximity);
+        let back = CrossingRecord::from_cbor(&geo.to_cbor()).unwrap();
+        assert!(back.geo_proximity.is_some() && back.dwell.is_none());
+
+        let bound = make_record().with_session(SessionBinding::new(7, 8));
+        let bytes = bound.to_cbor();
+        let back = CrossingRecord::from_cbor(&bytes).unwrap();
+        assert_eq!(back.session, bound.session);
+        assert_eq!(back.content_hash, bound.content_hash);
+        assert_eq!(back.to_cbor(), bytes);
+    }
+
+    #[test]
+    fn proximity_confidence_roundtrip() {
+        let proof = make_record().proximity;
+        assert_eq!(proof.to_cbor()[0], 0xA6);
+        let with = proof.with_confidence(0.75);
+        let bytes = with.to_cbor();
+        assert_eq!(bytes[0], 0xA7);
+        let back = ProximityProof::from_cbor(&bytes).unwrap();
+        assert_eq!(back.confidence, Some(0.75));
+        assert_eq!(back.content_hash, with.content_hash);
+        assert_eq!(back.to_cbor(), bytes);
+    }
+
+    #[test]
+    fn proximity_unit_roundtrip() {
+        use crate::geo::GeoCoord;
+        let proof = ProximityProof::prove(
+            &GeoCoord::new(35.6812, 139.7671),
+            &GeoCoord::new(35.6813, 139.7672),
+            50.0,
+        );
+        let bytes = proof.to_cbor();
+        assert_eq!(bytes[0], 0xA7);
+        assert_eq!(bytes[bytes.len() - 2..], [0x07, 0x01]);
+        let back = ProximityProof::from_cbor(&bytes).unwrap();
+        assert_eq!(back.unit, DistanceUnit::Metres);
+        assert_eq!(back.content_hash, proof.content_hash);
+        assert_eq!(back.to_cbor(), bytes);
+
+        // Network は省略するので明示的な 0 と未定義の値は拒否
+        for tag in [0x00, 0x09] {
+            let mut bad = bytes.clone();
+            *bad.last_mut().unwrap() = tag;
+            assert_eq!(
+                ProximityProof::from_cbor(&bad).unwrap_err(),
+                DecodeError::InvalidField("unit")
+            );
+        }
+    }
+
+    #[test]
+    fn group_proof_roundtrip() {
+        let mut g = PresenceGroup::new(GroupConfig::default());
+        for id in [9, 4, 1000] {
+            g.try_add_member(id, VivaldiCoord::new(f64::from(id % 3), 0.0), 0)
+                .unwrap();
+        }
+        let proof = g.prove_proximity().unwrap();
+        let back = GroupProximityProof::from_cbor(&proof.to_cbor()).unwrap();
+        assert_eq!(back.member_ids, vec![4, 9, 1000]);
+        assert_eq!(back.member_count, 3);
+        assert_eq!(verify_group_proof(&back), VerifyResult::Valid);
+    }
+
+    #[test]
+    fn record_requires_presence_event() {
+        let mut record = make_record();
+        record.event = PresenceEvent::heartbeat(1, 2, 100);
+        assert_eq!(
+            CrossingRecord::from_cbor(&record.to_cbor()).unwrap_err(),
+            DecodeError::InvalidField("event_type")
+        );
+    }
+
+    #[test]
+    fn record_rejects_unknown_trailing_key() {
+        let bytes = make_record().to_cbor();
+        assert_eq!(bytes[0], 0xA5);
+
+        // content_hash の後に未知のキー 8 (値なし = 宙に浮いたキー)
+        let mut dangling = bytes.clone();
+        dangling[0] = 0xA6;
+        dangling.push(0x08);
+        assert_eq!(
+            CrossingRecord::from_cbor(&dangling).unwrap_err(),
+            NON_CANONICAL
+        );
+
+        // 値付きの未知キー 8
+        let mut unknown = dangling.clone();
+        unknown.push(0x01);
+        assert_eq!(
+            CrossingRecord::from_cbor(&unknown).unwrap_err(),
+            NON_CANONICAL
+        );
+
+        // キー 7 の後にさらにキーが続く
+        let bound = make_record().with_session(SessionBinding::new(7, 8));
+        let mut extra = bound.to_cbor();
+        extra[0] += 1;
+        extra.extend_from_slice(&[0x08, 0x01]);
+        assert_eq!(
+            CrossingRecord::from_cbor(&extra).unwrap_err(),
+            NON_CANONICAL
+        );
+    }
+
+    #[test]
+    fn rejects_non_canonical_input() {
+        let bytes = PresenceEvent::new(1, 2, 3).to_cbor();
+
+        // 冗長な整数 (1 を 0x18 0x01 で表現)
+        let mut long_int = bytes.clone();
+        long_int.splice(6..7, [0x18, 0x01]);
+        assert_eq!(
+            PresenceEvent::from_cbor(&long_int).unwrap_err(),
+            NON_CANONICAL
+        );
+
+        // キー順序の入れ替え
+        let mut swapped = bytes.clone();
+        swapped.swap(1, 3);
+        swapped.swap(2, 4);
+        assert!(PresenceEvent::from_cbor(&swapped).is_err());
+
+        // 不定長 map
+        let mut indefinite = bytes.clone();
+        indefinite[0] = 0xBF;
+        assert_eq!(
+            PresenceEvent::from_cbor(&indefinite).unwrap_err(),
+            NON_CANONICAL
+        );
+
+        // 余分なバイト・途中切れ
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            PresenceEvent::from_cbor(&trailing).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+        for len in 0..bytes.len() {
+            assert!(PresenceEvent::from_cbor(&bytes[..len]).is_err(), "{len}");
+        }
+
+        // 短く表せる float を f64 で書いたもの
+        let proof = make_record().proximity;
+        let mut w = Writer::default();
+        w.map(6);
+        w.key(0);
+        w.buf.push(0xFB);
+        w.buf.extend_from_slice(&1.0f64.to_bits().to_be_bytes());
+        assert_eq!(
+            ProximityProof::from_cbor(&w.buf).unwrap_err(),
+            NON_CANONICAL
+        );
+        assert!(ProximityProof::from_cbor(&proof.to_cbor()).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_event_type() {
+        let mut ev = PresenceEvent::new(1, 2, 3);
+        ev.event_type = 0x01;
+        assert_eq!(
+            PresenceEvent::from_cbor(&ev.to_cbor()).unwrap_err(),
+            DecodeError::UnknownType(0x01)
+        );
+    }
+}
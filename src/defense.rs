@@ -0,0 +1,477 @@
+//! Malicious-node defenses for Vivaldi updates
+//!
+//! Any peer can lie about its coordinate or delay probes to inflate RTT,
+//! poisoning `VivaldiNode::observe`. `UpdateGuard` screens each update
+//! before it is applied, in the spirit of Veracity / `RVivaldi`:
+//!
+//! 1. **Sanity** — the claimed coordinate and error must be finite and in
+//!    range.
+//! 2. **Verifier quorum** — independent neighbours measure their own RTT
+//!    to the peer; the claimed coordinate must predict those RTTs within
+//!    a relative tolerance (widened by each verifier's own error) for a
+//!    quorum of them.
+//! 3. **Implausible error** — a peer may not claim to be much more
+//!    accurate than the verifiers observe (low error buys update weight).
+//! 4. **Triangle inequality** — the RTT we measured to the peer may not
+//!    exceed the detour through a verifier by more than a slack factor
+//!    (probe delaying only ever inflates RTT).
+//!
+//! Author: Moroya Sakamoto
+
+use crate::vivaldi::{VivaldiNode, MAX_ERROR};
+
+/// Guard configuration.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DefenseConfig {
+    /// Max relative residual `|d(V, P) - rtt(V, P)| / rtt(V, P)` for a
+    /// verifier to vouch for the claimed coordinate, on top of the
+    /// verifier's own error (default 0.35). Unconverged verifiers are thus
+    /// lenient and converged ones strict, which lets the network bootstrap.
+    pub verification_tolerance: f64,
+    /// Fraction of eligible verifiers that must vouch (default 0.5).
+    pub quorum: f64,
+    /// Minimum eligible verifiers required to run the checks (default 3).
+    pub min_verifiers: usize,
+    /// Verifiers whose own error exceeds this are ignored (default 1.0).
+    pub max_verifier_error: f64,
+    /// Allowed gap between observed verifier residual and claimed error
+    /// (default 0.25).
+    pub error_slack: f64,
+    /// Multiplicative slack on the triangle inequality (default 0.5, i.e.
+    /// `rtt(L,P) <= 1.5 * (rtt(L,V) + rtt(V,P))`).
+    pub triangle_slack: f64,
+    /// Apply updates when too few verifiers are eligible (bootstrap phase,
+    /// default true). When false such updates are rejected.
+    pub accept_unverified: bool,
+}
+
+impl Default for DefenseConfig {
+    fn default() -> Self {
+        Self {
+            verification_tolerance: 0.35,
+            quorum: 0.5,
+            min_verifiers: 3,
+            max_verifier_error: 1.0,
+            error_slack: 0.25,
+            triangle_slack: 0.5,
+            accept_unverified: true,
+        }
+    }
+}
+
+/// What a verifier neighbour reports about the peer.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VerifierReport {
+    /// Verifier's own coordinate and error.
+    pub verifier: VivaldiNode,
+    /// RTT measured by the verifier to the peer.
+    pub rtt_to_peer: f64,
+    /// RTT measured by us to the verifier.
+    pub rtt_to_local: f64,
+}
+
+/// Outcome of screening one update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UpdateVerdict {
+    /// Checks passed; update applied.
+    Accepted,
+    /// Too few eligible verifiers; applied or rejected per
+    /// `DefenseConfig::accept_unverified`.
+    Unverified,
+    /// Claimed coordinate/error is non-finite or out of range, or the RTT
+    /// is unusable.
+    Malformed,
+    /// Fewer than `quorum` verifiers vouched for the claimed coordinate.
+    VerificationFailed { vouched: usize, eligible: usize },
+    /// Claimed error is far below the residual verifiers observe.
+    ImplausibleError { claimed: f64, observed: f64 },
+    /// Measured RTT breaks the triangle inequality through the verifiers.
+    TriangleViolation,
+}
+
+impl UpdateVerdict {
+    /// Was the update applied (given the config that produced it)?
+    #[must_use]
+    pub const fn is_accepted(&self, config: &DefenseConfig) -> bool {
+        match self {
+            Self::Accepted => true,
+            Self::Unverified => config.accept_unverified,
+            _ => false,
+        }
+    }
+}
+
+/// Screens and applies Vivaldi updates.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpdateGuard {
+    config: DefenseConfig,
+    accepted: u64,
+    rejected: u64,
+}
+
+impl UpdateGuard {
+    /// Create a guard.
+    #[must_use]
+    pub const fn new(config: DefenseConfig) -> Self {
+        Self {
+            config,
+            accepted: 0,
+            rejected: 0,
+        }
+    }
+
+    /// Create a guard with default configuration.
+    #[must_use]
+    pub fn with_defaults() -> Self {
+        Self::new(DefenseConfig::default())
+    }
+
+    /// Guard configuration.
+    #[must_use]
+    pub const fn config(&self) -> &DefenseConfig {
+        &self.config
+    }
+
+    /// Updates applied so far.
+    #[must_use]
+    pub const fn accepted(&self) -> u64 {
+        self.accepted
+    }
+
+    /// Updates rejected so far.
+    #[must_use]
+    pub const fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Screen an update from `peer` (claimed node) with measured `rtt`.
+    #[must_use]
+    pub fn check(
+        &self,
+        peer: &VivaldiNode,
+        rtt: f64,
+        verifiers: &[VerifierReport],
+    ) -> UpdateVerdict {
+        let c = &peer.coord;
+        let finite = c.x.is_finite() && c.y.is_finite() && c.height.is_finite();
+        if !finite
+            || c.height < 0.0
+            || !(0.0..=MAX_ERROR).contains(&peer.error)
+            || !rtt.is_finite()
+            || rtt <= 0.0
+        {
+            return UpdateVerdict::Malformed;
+        }
+
+        let mut eligible = 0usize;
+        let mut vouched = 0usize;
+        let mut violations = 0usize;
+        let mut residuals = Vec::with_capacity(verifiers.len());
+        let mut measured = 0usize;
+        for v in verifiers {
+            if !v.rtt_to_peer.is_finite() || v.rtt_to_peer <= 0.0 || !v.rtt_to_local.is_finite() {
+                continue;
+            }
+            // 三角不等式は RTT のみで判定できるので収束前の検証者も使う
+            measured += 1;
+            if rtt > (1.0 + self.config.triangle_slack) * (v.rtt_to_local + v.rtt_to_peer) {
+                violations += 1;
+            }
+            if v.verifier.error > self.config.max_verifier_error {
+                continue;
+            }
+            eligible += 1;
+            let residual = (v.verifier.coord.distance(c) - v.rtt_to_peer).abs() / v.rtt_to_peer;
+            // 検証者自身の誤差分だけ許容幅を広げる (収束前は寛容、収束後は厳格)
+            if residual <= self.config.verification_tolerance + v.verifier.error {
+                vouched += 1;
+            }
+            residuals.push(residual);
+        }
+
+        if measured > 0 && (violations as f64) > (1.0 - self.config.quorum) * measured as f64 {
+            return UpdateVerdict::TriangleViolation;
+        }
+        if eligible < self.config.min_verifiers {
+            return UpdateVerdict::Unverified;
+        }
+        if (vouched as f64) < self.config.quorum * eligible as f64 {
+            return UpdateVerdict::VerificationFailed { vouched, eligible };
+        }
+        residuals.sort_unstable_by(f64::total_cmp);
+        let observed = residuals[residuals.len() / 2];
+        if observed > peer.error + self.config.error_slack {
+            return UpdateVerdict::ImplausibleError {
+                claimed: peer.error,
+                observed,
+            };
+        }
+        UpdateVerdict::Accepted
+    }
+
+    /// Screen the update and, if accepted, apply it to `local`.
+    ///
+    /// Unverified updates (bootstrap) are applied as if the peer reported
+    /// `MAX_ERROR`, so an unchecked claim of high accuracy buys no weight.
+    pub fn observe(
+        &mut self,
+        local: &mut VivaldiNode,
+        peer: &VivaldiNode,
+        rtt: f64,
+        verifiers: &[VerifierReport],
+        cc: f64,
+        ce: f64,
+    ) -> UpdateVerdict {
+        let verdict = self.check(peer, rtt, verifiers);
+        if verdict.is_accepted(&self.config) {
+            let mut peer = *peer;
+            if verdict == UpdateVerdict::Unverified {
+                peer.error = MAX_ERROR;
+            }
+            local.observe(&peer, rtt, cc, ce);
+            self.accepted += 1;
+        } else {
+            self.rejected += 1;
+        }
+        verdict
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vivaldi::VivaldiCoord;
+
+    fn node(x: f64, y: f64, error: f64) -> VivaldiNode {
+        VivaldiNode::with_error(VivaldiCoord::new(x, y), error)
+    }
+
+    /// Three converged verifiers around the origin measuring a peer at (10, 0).
+    fn verifiers() -> Vec<VerifierReport> {
+        [(0.0, 5.0), (0.0, -5.0), (5.0, 5.0)]
+            .iter()
+            .map(|&(x, y)| {
+                let v = node(x, y, 0.1);
+                VerifierReport {
+                    verifier: v,
+                    rtt_to_peer: v.coord.distance(&VivaldiCoord::new(10.0, 0.0)),
+                    rtt_to_local: v.coord.distance(&VivaldiCoord::new(0.0, 0.0)),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn honest_update_accepted() {
+        let g = UpdateGuard::with_defaults();
+        let v = g.check(&node(10.0, 0.0, 0.1), 10.0, &verifiers());
+        assert_eq!(v, UpdateVerdict::Accepted);
+    }
+
+    #[test]
+    fn malformed_rejected() {
+        let g = UpdateGuard::with_defaults();
+        assert_eq!(
+            g.check(&node(f64::NAN, 0.0, 0.1), 10.0, &verifiers()),
+            UpdateVerdict::Malformed
+        );
+        let mut bad = node(10.0, 0.0, 0.1);
+        bad.error = -1.0;
+        assert_eq!(g.check(&bad, 10.0, &verifiers()), UpdateVerdict::Malformed);
+        assert_eq!(
+            g.check(&node(10.0, 0.0, 0.1), 0.0, &verifiers()),
+            UpdateVerdict::Malformed
+        );
+    }
+
+    #[test]
+    fn fake_coordinate_fails_verification() {
+        let g = UpdateGuard::with_defaults();
+        let v = g.check(&node(-80.0, 60.0, 0.1), 10.0, &verifiers());
+        assert!(matches!(v, UpdateVerdict::VerificationFailed { .. }));
+    }
+
+    #[test]
+    fn understated_error_rejected() {
+        // 座標は少しずれている (残差 ~0.33) のに誤差 0 と主張
+        let g = UpdateGuard::with_defaults();
+        let v = g.check(&node(14.0, 0.0, 0.0), 10.0, &verifiers());
+        assert!(matches!(v, UpdateVerdict::ImplausibleError { .. }), "{v:?}");
+        // 誤差を正直に申告すれば通る
+        let v = g.check(&node(14.0, 0.0, 0.3), 10.0, &verifiers());
+        assert_eq!(v, UpdateVerdict::Accepted);
+    }
+
+    #[test]
+    fn inflated_rtt_breaks_triangle() {
+        let g = UpdateGuard::with_defaults();
+        let v = g.check(&node(10.0, 0.0, 0.1), 60.0, &verifiers());
+        assert_eq!(v, UpdateVerdict::TriangleViolation);
+    }
+
+    #[test]
+    fn unconverged_verifiers_ignored() {
+        let g = UpdateGuard::with_defaults();
+        let mut vs = verifiers();
+        for v in &mut vs {
+            v.verifier.error = 1.2;
+        }
+        assert_eq!(
+            g.check(&node(-80.0, 60.0, 0.1), 10.0, &vs),
+            UpdateVerdict::Unverified
+        );
+        let strict = UpdateGuard::new(DefenseConfig {
+            accept_unverified: false,
+            ..Default::default()
+        });
+        let mut local = node(0.0, 0.0, 1.0);
+        let mut g = strict;
+        g.observe(&mut local, &node(-80.0, 60.0, 0.1), 10.0, &vs, 0.25, 0.25);
+        assert_eq!(g.rejected(), 1);
+        assert_eq!(local.coord.x, 0.0);
+    }
+
+    #[test]
+    fn observe_counts() {
+        let mut g = UpdateGuard::with_defaults();
+        let mut local = node(0.0, 0.0, 0.5);
+        g.observe(
+            &mut local,
+            &node(10.0, 0.0, 0.1),
+            10.0,
+            &verifiers(),
+            0.25,
+            0.25,
+        );
+        g.observe(
+            &mut local,
+            &node(-80.0, 60.0, 0.1),
+            10.0,
+            &verifiers(),
+            0.25,
+            0.25,
+        );
+        assert_eq!(g.accepted(), 1);
+        assert_eq!(g.rejected(), 1);
+    }
+
+    // ── Simulation ──
+
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            self.0 >> 33
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+
+        fn unit(&mut self) -> f64 {
+            self.next() as f64 / (1u64 << 31) as f64
+        }
+    }
+
+    /// Run a Vivaldi network where the first `liars` nodes claim random
+    /// far-away coordinates with near-zero error and delay probes.
+    /// Returns the median relative error over honest pairs.
+    fn simulate(n: usize, liars: usize, defended: bool, warmup: usize, rounds: usize) -> f64 {
+        let mut rng = Lcg(0x5EED);
+        let truth: Vec<(f64, f64)> = (0..n)
+            .map(|_| (rng.unit() * 100.0, rng.unit() * 100.0))
+            .collect();
+        let rtt = |i: usize, j: usize| -> f64 {
+            let (a, b) = (truth[i], truth[j]);
+            (a.0 - b.0).hypot(a.1 - b.1) + 1.0
+        };
+        let mut nodes: Vec<VivaldiNode> = (0..n)
+            .map(|_| VivaldiNode::new(VivaldiCoord::new(rng.unit(), rng.unit())))
+            .collect();
+        let mut guards: Vec<UpdateGuard> = (0..n).map(|_| UpdateGuard::with_defaults()).collect();
+
+        for round in 0..warmup + rounds {
+            for i in liars..n {
+                let mut j = rng.below(n);
+                while j == i {
+                    j = rng.below(n);
+                }
+                let is_liar = j < liars && round >= warmup;
+                let (claimed, measured) = if is_liar {
+                    let fake = node(
+                        rng.unit() * 1000.0 - 500.0,
+                        rng.unit() * 1000.0 - 500.0,
+                        0.01,
+                    );
+                    (fake, rtt(i, j) + rng.unit() * 200.0)
+                } else {
+                    (nodes[j], rtt(i, j))
+                };
+                if defended {
+                    let reports: Vec<VerifierReport> = (0..6)
+                        .map(|_| rng.below(n))
+                        .filter(|&v| v != i && v != j)
+                        .map(|v| VerifierReport {
+                            verifier: nodes[v],
+                            rtt_to_peer: rtt(v, j),
+                            rtt_to_local: rtt(i, v),
+                        })
+                        .collect();
+                    let mut local = nodes[i];
+                    guards[i].observe(&mut local, &claimed, measured, &reports, 0.25, 0.25);
+                    nodes[i] = local;
+                } else {
+                    nodes[i].observe(&claimed, measured, 0.25, 0.25);
+                }
+            }
+        }
+
+        let mut errs = Vec::new();
+        for i in liars..n {
+            for j in (i + 1)..n {
+                let t = rtt(i, j);
+                errs.push((nodes[i].coord.distance(&nodes[j].coord) - t).abs() / t);
+            }
+        }
+        errs.sort_unstable_by(f64::total_cmp);
+        errs[errs.len() / 2]
+    }
+
+    #[test]
+    fn simulation_liars_after_warmup_rejected() {
+        // 収束後に攻撃開始しても収束状態が維持される
+        let clean = simulate(40, 0, false, 100, 300);
+        let defended = simulate(40, 8, true, 100, 300);
+        assert!(
+            defended < 2.0 * clean,
+            "defended {defended} vs clean {clean}"
+        );
+    }
+
+    #[test]
+    fn simulation_convergence_preserved_with_liars() {
+        // 20% の嘘つきノードが最初から参加
+        let clean = simulate(40, 0, false, 0, 300);
+        let attacked = simulate(40, 8, false, 0, 300);
+        let defended = simulate(40, 8, true, 0, 300);
+        assert!(clean < 0.15, "clean {clean}");
+        assert!(
+            attacked > 2.0 * clean,
+            "attack ineffective: {attacked} vs {clean}"
+        );
+        assert!(
+            defended < 2.0 * clean,
+            "defended {defended} vs clean {clean}"
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Error types
+//!
+//! `PresenceError` is the crate-wide error. Each subsystem has its own
+//! error enum so callers can match precisely, and every one converts into
+//! `PresenceError` with `?`.
+//!
+//! Author: Moroya Sakamoto
+
+use std::fmt;
+
+use crate::protocol::ProximityMode;
+use crate::session::{SessionInput, SessionState};
+
+/// Crate-wide error. `Display` names the failing subsystem; the
+/// underlying error is available through `source()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PresenceError {
+    /// The presence protocol did not produce a record.
+    Protocol(ProtocolError),
+    /// Malformed or unsupported binary input.
+    Decode(DecodeError),
+    /// A value cannot be represented in its binary format.
+    Encode(EncodeError),
+    /// A session input was rejected in the current state.
+    Transition(TransitionError),
+    /// A session manager operation failed.
+    SessionManager(SessionManagerError),
+    /// A group operation failed.
+    Group(GroupError),
+}
+
+impl fmt::Display for PresenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Protocol(_) => "protocol error",
+            Self::Decode(_) => "decode error",
+            Self::Encode(_) => "encode error",
+            Self::Transition(_) => "session transition error",
+            Self::SessionManager(_) => "session manager error",
+            Self::Group(_) => "group error",
+        })
+    }
+}
+
+impl std::error::Error for PresenceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Protocol(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            Self::Encode(e) => Some(e),
+            Self::Transition(e) => Some(e),
+            Self::SessionManager(e) => Some(e),
+            Self::Group(e) => Some(e),
+        }
+    }
+}
+
+// ── Protocol ───────────────────────────────────────────────────────────
+
+/// Why the presence protocol refused to build a record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProtocolError {
+    /// Network (Vivaldi) distance exceeds the threshold.
+    NotProximate { distance: f64, threshold: f64 },
+    /// Geographic distance (metres) exceeds the threshold.
+    GeoNotProximate { distance_m: f64, threshold_m: f64 },
+    /// Probability of being within the threshold is below the minimum.
+    LowConfidence { probability: f64, min: f64 },
+    /// A geographic mode was selected but a party has no GPS fix.
+    MissingGeoFix,
+    /// The trajectories never stayed proximate for the minimum dwell time.
+    NoEncounter,
+    /// An encounter response answers a different offer.
+    ResponseMismatch,
+    /// Only one party is bound to a session.
+    MissingSessionId,
+    /// The operation does not support this proximity mode.
+    UnsupportedMode(ProximityMode),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotProximate {
+                distance,
+                threshold,
+            } => write!(f, "not proximate: distance {distance} > {threshold}"),
+            Self::GeoNotProximate {
+                distance_m,
+                threshold_m,
+            } => write!(
+                f,
+                "not geographically proximate: {distance_m} m > {threshold_m} m"
+            ),
+            Self::LowConfidence { probability, min } => {
+                write!(f, "proximity probability {probability} < {min}")
+            }
+            Self::MissingGeoFix => f.write_str("party has no GPS fix"),
+            Self::NoEncounter => f.write_str("no proximity interval met the minimum dwell time"),
+            Self::ResponseMismatch => f.write_str("response does not answer this offer"),
+            Self::MissingSessionId => f.write_str("only one party is bound to a session"),
+            Self::UnsupportedMode(mode) => write!(f, "proximity mode {mode:?} is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl From<ProtocolError> for PresenceError {
+    fn from(e: ProtocolError) -> Self {
+        Self::Protocol(e)
+    }
+}
+
+// ── Decode ─────────────────────────────────────────────────────────────
+
+/// Malformed or unsupported binary input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Input is shorter than the layout requires.
+    TooShort { expected: usize, actual: usize },
+    /// Input has bytes left over after a fixed-size message.
+    TrailingBytes { extra: usize },
+    /// Magic number does not match.
+    BadMagic,
+    /// Format version is not supported by this reader.
+    UnsupportedVersion(u8),
+    /// Message or record type tag is unknown.
+    UnknownType(u8),
+    /// A field holds a value outside its valid range (e.g. non-finite).
+    InvalidField(&'static str),
+    /// The embedded checksum does not match the content.
+    Checksum,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { expected, actual } => {
+                write!(f, "input too short: need {expected} bytes, got {actual}")
+            }
+            Self::TrailingBytes { extra } => write!(f, "{extra} trailing bytes"),
+            Self::BadMagic => f.write_str("bad magic"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            Self::UnknownType(t) => write!(f, "unknown type tag 0x{t:02x}"),
+            Self::InvalidField(name) => write!(f, "invalid field `{name}`"),
+            Self::Checksum => f.write_str("checksum mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<DecodeError> for PresenceError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+impl DecodeError {
+    /// `Err(TooShort)` unless `data` holds at least `expected` bytes.
+    pub(crate) const fn check_len(data: &[u8], expected: usize) -> Result<(), Self> {
+        if data.len() < expected {
+            return Err(Self::TooShort {
+                expected,
+                actual: data.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+// ── Encode ─────────────────────────────────────────────────────────────
+
+/// A value cannot be represented in its binary format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// A group proof lists more members than the format allows.
+    TooManyMembers { count: usize, max: usize },
+    /// A frame payload is longer than the stream format allows.
+    PayloadTooLarge { len: usize, max: usize },
+    /// A field holds a value its decoder would reject (e.g. non-finite).
+    InvalidField(&'static str),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyMembers { count, max } => {
+                write!(f, "{count} members exceed the maximum of {max}")
+            }
+            Self::PayloadTooLarge { len, max } => {
+                write!(f, "payload of {len} bytes exceeds the maximum of {max}")
+            }
+            Self::InvalidField(name) => write!(f, "invalid field `{name}`"),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+impl From<EncodeError> for PresenceError {
+    fn from(e: EncodeError) -> Self {
+        Self::Encode(e)
+    }
+}
+
+// ── Session transitions ────────────────────────────────────────────────
+
+/// A session input rejected in the current state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionError {
+    /// The transition table rejects `input` in state `from`.
+    InvalidState {
+        from: SessionState,
+        input: SessionInput,
+    },
+    /// The retry budget is exhausted.
+    RetriesExhausted { max_retries: u32 },
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidState { from, input } => match input.target() {
+                Some(to) => write!(f, "cannot transition from {from:?} to {to:?} on {input:?}"),
+                None => write!(f, "cannot apply {input:?} in {from:?}"),
+            },
+            Self::RetriesExhausted { max_retries } => {
+                write!(f, "retries exhausted (max {max_retries})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+impl From<TransitionError> for PresenceError {
+    fn from(e: TransitionError) -> Self {
+        Self::Transition(e)
+    }
+}
+
+// ── Session manager ────────────────────────────────────────────────────
+
+/// A `SessionManager` operation failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionManagerError {
+    /// No session with this ID is managed.
+    UnknownSession { session_id: u64 },
+    /// A session with this ID is already managed.
+    DuplicateSession { session_id: u64 },
+    /// `remote_id` is already bound to another active session.
+    RemoteInUse { remote_id: u32, session_id: u64 },
+    /// The session rejected the input.
+    Transition(TransitionError),
+}
+
+impl fmt::Display for SessionManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSession { session_id } => {
+                write!(f, "unknown session {session_id:016x}")
+            }
+            Self::DuplicateSession { session_id } => {
+                write!(f, "session {session_id:016x} already exists")
+            }
+            Self::RemoteInUse {
+                remote_id,
+                session_id,
+            } => write!(
+                f,
+                "remote {remote_id} already bound to session {session_id:016x}"
+            ),
+            Self::Transition(_) => f.write_str("session transition failed"),
+        }
+    }
+}
+
+impl std::error::Error for SessionManagerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transition(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TransitionError> for SessionManagerError {
+    fn from(e: TransitionError) -> Self {
+        Self::Transition(e)
+    }
+}
+
+impl From<SessionManagerError> for PresenceError {
+    fn from(e: SessionManagerError) -> Self {
+        Self::SessionManager(e)
+    }
+}
+
+// ── Group ──────────────────────────────────────────────────────────────
+
+/// A group operation failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupError {
+    /// The group already holds `capacity` members.
+    Full { capacity: usize },
+    /// A member with this ID is already present.
+    DuplicateMember(u32),
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full { capacity } => write!(f, "group full ({capacity} members)"),
+            Self::DuplicateMember(id) => write!(f, "member {id} already in group"),
+        }
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+impl From<GroupError> for PresenceError {
+    fn from(e: GroupError) -> Self {
+        Self::Group(e)
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn conversions_wrap_subsystem_errors() {
+        let e: PresenceError = DecodeError::BadMagic.into();
+        assert_eq!(e, PresenceError::Decode(DecodeError::BadMagic));
+        let e: PresenceError = GroupError::DuplicateMember(3).into();
+        assert!(matches!(
+            e,
+            PresenceError::Group(GroupError::DuplicateMember(3))
+        ));
+        let e: PresenceError = ProtocolError::MissingGeoFix.into();
+        assert!(e.source().is_some());
+        let e: PresenceError = EncodeError::TooManyMembers { count: 65, max: 64 }.into();
+        assert_eq!(e.to_string(), "encode error");
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "65 members exceed the maximum of 64"
+        );
+    }
+
+    #[test]
+    fn display_is_descriptive() {
+        let e = PresenceError::from(DecodeError::TooShort {
+            expected: 45,
+            actual: 3,
+        });
+        assert_eq!(e.to_string(), "decode error");
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "input too short: need 45 bytes, got 3"
+        );
+        let t = TransitionError::InvalidState {
+            from: SessionState::Idle,
+            input: SessionInput::Verify,
+        };
+        assert_eq!(
+            t.to_string(),
+            "cannot transition from Idle to Verified on Verify"
+        );
+        let r = TransitionError::InvalidState {
+            from: SessionState::Closed,
+            input: SessionInput::Retry,
+        };
+        assert_eq!(r.to_string(), "cannot apply Retry in Closed");
+        let p = ProtocolError::NotProximate {
+            distance: 12.5,
+            threshold: 10.0,
+        };
+        assert_eq!(p.to_string(), "not proximate: distance 12.5 > 10");
+        assert_eq!(
+            ProtocolError::UnsupportedMode(ProximityMode::Hybrid).to_string(),
+            "proximity mode Hybrid is not supported"
+        );
+        let m = SessionManagerError::RemoteInUse {
+            remote_id: 7,
+            session_id: 0xab,
+        };
+        let e = PresenceError::from(m);
+        assert_eq!(e.to_string(), "session manager error");
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "remote 7 already bound to session 00000000000000ab"
+        );
+        let m = SessionManagerError::from(t);
+        assert_eq!(m.to_string(), "session transition failed");
+        assert_eq!(m.source().unwrap().to_string(), t.to_string());
+    }
+
+    #[test]
+    fn check_len() {
+        assert!(DecodeError::check_len(&[0; 4], 4).is_ok());
+        assert_eq!(
+            DecodeError::check_len(&[0; 3], 4),
+            Err(DecodeError::TooShort {
+                expected: 4,
+                actual: 3
+            })
+        );
+    }
+}
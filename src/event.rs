@@ -5,54 +5,245 @@
 //!
 //! Author: Moroya Sakamoto
 
+use crate::error::DecodeError;
 use crate::fnv1a;
 use crate::identity::ZkProof;
-use crate::vivaldi::VivaldiCoord;
+use crate::space::CoordinateSpace;
 
 // ── Proximity Proof ────────────────────────────────────────────────────
 
-/// Evidence that two Vivaldi coordinates are within threshold distance.
+/// What a `ProximityProof`'s `distance` and `threshold` are measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum DistanceUnit {
+    /// Network coordinate distance (Vivaldi, hyperbolic).
+    #[default]
+    Network = 0,
+    /// Metres (GPS haversine).
+    Metres = 1,
+}
+
+impl DistanceUnit {
+    /// Unit for a wire tag, `None` if unknown.
+    #[must_use]
+    pub const fn from_u8(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(Self::Network),
+            1 => Some(Self::Metres),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name (`"network"`, `"metres"`) for the language bindings.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Network => "network",
+            Self::Metres => "metres",
+        }
+    }
+}
+
+/// Evidence that two coordinates are within threshold distance.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProximityProof {
-    /// Vivaldi distance between the two parties.
+    /// Coordinate-space distance between the two parties.
     pub distance: f64,
     /// Maximum distance for "proximity".
     pub threshold: f64,
     /// `distance <= threshold`
     pub is_proximate: bool,
     /// Hash of party A's coordinate (privacy).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub coord_hash_a: u64,
     /// Hash of party B's coordinate (privacy).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub coord_hash_b: u64,
+    /// P(true distance <= threshold) when the verdict came from an
+    /// uncertain estimate (probabilistic network mode, radio ranging).
+    pub confidence: Option<f64>,
+    /// Unit of `distance` and `threshold`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unit: DistanceUnit,
     /// Hash of the entire proof payload.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub content_hash: u64,
 }
 
 impl ProximityProof {
-    /// Generate a proximity proof between two coordinates.
+    /// Generate a proximity proof between two coordinates of the same space,
+    /// in that space's [`UNIT`](CoordinateSpace::UNIT).
     #[must_use]
-    pub fn prove(coord_a: &VivaldiCoord, coord_b: &VivaldiCoord, threshold: f64) -> Self {
+    pub fn prove<C: CoordinateSpace>(coord_a: &C, coord_b: &C, threshold: f64) -> Self {
         let distance = coord_a.distance(coord_b);
-        let is_proximate = distance <= threshold;
-        let coord_hash_a = coord_a.hash();
-        let coord_hash_b = coord_b.hash();
-
-        let mut buf = [0u8; 40];
-        buf[..8].copy_from_slice(&distance.to_le_bytes());
-        buf[8..16].copy_from_slice(&threshold.to_le_bytes());
-        buf[16..24].copy_from_slice(&coord_hash_a.to_le_bytes());
-        buf[24..32].copy_from_slice(&coord_hash_b.to_le_bytes());
-        buf[32..40].copy_from_slice(&(is_proximate as u64).to_le_bytes());
-        let content_hash = fnv1a(&buf);
+        Self::from_parts(
+            distance,
+            threshold,
+            distance <= threshold,
+            coord_a.commit(),
+            coord_b.commit(),
+        )
+        .with_unit(C::UNIT)
+    }
 
-        Self {
+    /// Assemble a proof from an externally decided verdict (e.g. radio
+    /// ranging) and compute its content hash. `coord_hash_a`/`coord_hash_b`
+    /// carry whatever commitments the evidence source binds.
+    #[must_use]
+    pub fn from_parts(
+        distance: f64,
+        threshold: f64,
+        is_proximate: bool,
+        coord_hash_a: u64,
+        coord_hash_b: u64,
+    ) -> Self {
+        let mut proof = Self {
             distance,
             threshold,
             is_proximate,
             coord_hash_a,
             coord_hash_b,
-            content_hash,
+            confidence: None,
+            unit: DistanceUnit::Network,
+            content_hash: 0,
+        };
+        proof.content_hash = proof.compute_hash();
+        proof
+    }
+
+    /// Set the unit of `distance` / `threshold` and rehash.
+    #[must_use]
+    pub fn with_unit(mut self, unit: DistanceUnit) -> Self {
+        self.unit = unit;
+        self.content_hash = self.compute_hash();
+        self
+    }
+
+    /// Record the probability behind the verdict and rehash.
+    #[must_use]
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = Some(confidence);
+        self.content_hash = self.compute_hash();
+        self
+    }
+
+    /// Hash over distance, threshold, both coordinate hashes and the
+    /// verdict. The confidence only contributes when present and the unit
+    /// only when it is not `Network`, so network proofs without a
+    /// confidence hash exactly as before.
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let mut buf = [0u8; 49];
+        buf[..8].copy_from_slice(&self.distance.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.threshold.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.coord_hash_a.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.coord_hash_b.to_le_bytes());
+        buf[32..40].copy_from_slice(&u64::from(self.is_proximate).to_le_bytes());
+        let mut len = 40;
+        if let Some(c) = self.confidence {
+            buf[len..len + 8].copy_from_slice(&c.to_le_bytes());
+            len += 8;
         }
+        if self.unit != DistanceUnit::Network {
+            buf[len] = self.unit as u8;
+            len += 1;
+        }
+        fnv1a(&buf[..len])
+    }
+}
+
+// ── Event Types ────────────────────────────────────────────────────────
+
+/// Flag bit: both parties confirmed.
+pub const FLAG_MUTUAL: u8 = 0b0000_0001;
+/// Flag bit: both ZKPs verified (or, for revocation / group events, the
+/// emitting party authenticated the event).
+pub const FLAG_VERIFIED: u8 = 0b0000_0010;
+/// Flag bit: the parties were proximate when the event was emitted.
+pub const FLAG_PROXIMATE: u8 = 0b0000_0100;
+/// Flag bit: the encounter met the minimum dwell time.
+pub const FLAG_DWELL: u8 = 0b0000_1000;
+
+/// Registry of presence event types (the first wire byte).
+///
+/// Tags are ASCII letters so raw dumps stay readable. Each type fixes the
+/// meaning of `party_b_id` and the set of flags it may carry; decoding
+/// rejects unknown tags and flags outside that set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum EventType {
+    /// `'P'` — completed crossing between A and B (the record event).
+    Presence = 0x50,
+    /// `'S'` — A and B came into proximity.
+    EncounterStart = 0x53,
+    /// `'E'` — A and B left proximity (departure).
+    EncounterEnd = 0x45,
+    /// `'H'` — A and B are still in proximity.
+    Heartbeat = 0x48,
+    /// `'R'` — A withdraws its crossing with B at `timestamp_ns`.
+    Revocation = 0x52,
+    /// `'J'` — A joined group `party_b_id`.
+    GroupJoin = 0x4A,
+    /// `'L'` — A left group `party_b_id`.
+    GroupLeave = 0x4C,
+}
+
+impl EventType {
+    /// All registered types.
+    pub const ALL: [Self; 7] = [
+        Self::Presence,
+        Self::EncounterStart,
+        Self::EncounterEnd,
+        Self::Heartbeat,
+        Self::Revocation,
+        Self::GroupJoin,
+        Self::GroupLeave,
+    ];
+
+    /// Look up a wire tag.
+    ///
+    /// # Errors
+    ///
+    /// `DecodeError::UnknownType` if the tag is not registered.
+    pub const fn from_u8(tag: u8) -> Result<Self, DecodeError> {
+        match tag {
+            0x50 => Ok(Self::Presence),
+            0x53 => Ok(Self::EncounterStart),
+            0x45 => Ok(Self::EncounterEnd),
+            0x48 => Ok(Self::Heartbeat),
+            0x52 => Ok(Self::Revocation),
+            0x4A => Ok(Self::GroupJoin),
+            0x4C => Ok(Self::GroupLeave),
+            t => Err(DecodeError::UnknownType(t)),
+        }
+    }
+
+    /// Flags this type may carry.
+    ///
+    /// | Type | Flags |
+    /// |------|-------|
+    /// | `Presence` | mutual, verified, proximate, dwell |
+    /// | `EncounterStart` | mutual, proximate |
+    /// | `EncounterEnd` | mutual, dwell |
+    /// | `Heartbeat` | proximate |
+    /// | `Revocation`, `GroupJoin`, `GroupLeave` | verified |
+    #[must_use]
+    pub const fn allowed_flags(self) -> u8 {
+        match self {
+            Self::Presence => FLAG_MUTUAL | FLAG_VERIFIED | FLAG_PROXIMATE | FLAG_DWELL,
+            Self::EncounterStart => FLAG_MUTUAL | FLAG_PROXIMATE,
+            Self::EncounterEnd => FLAG_MUTUAL | FLAG_DWELL,
+            Self::Heartbeat => FLAG_PROXIMATE,
+            Self::Revocation | Self::GroupJoin | Self::GroupLeave => FLAG_VERIFIED,
+        }
+    }
+
+    /// True if `party_b_id` is a compact group identifier rather than a party.
+    #[must_use]
+    pub const fn is_group_event(self) -> bool {
+        matches!(self, Self::GroupJoin | Self::GroupLeave)
     }
 }
 
@@ -61,26 +252,69 @@ impl ProximityProof {
 /// Minimal 18-byte presence event for P2P sync (ALICE-Sync compatible).
 ///
 /// Wire layout: `[event_type: u8][flags: u8][party_a_id: u32 LE][party_b_id: u32 LE][timestamp: u64 LE]`
+///
+/// Deserialization runs [`PresenceEvent::validate`].
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "PresenceEventRepr"))]
 pub struct PresenceEvent {
-    /// 0x50 = 'P' for Presence.
+    /// Wire tag of an [`EventType`] (0x50 = 'P' for Presence).
     pub event_type: u8,
-    /// bit 0: mutual, bit 1: verified, bit 2: proximate.
+    /// bit 0: mutual, bit 1: verified, bit 2: proximate, bit 3: dwell.
     pub flags: u8,
     /// Compact ID for party A.
     pub party_a_id: u32,
-    /// Compact ID for party B.
+    /// Compact ID for party B (or the group for group events).
     pub party_b_id: u32,
     /// Nanosecond timestamp of the crossing.
     pub timestamp_ns: u64,
 }
 
+/// Unvalidated serde form of `PresenceEvent`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct PresenceEventRepr {
+    event_type: u8,
+    flags: u8,
+    party_a_id: u32,
+    party_b_id: u32,
+    timestamp_ns: u64,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PresenceEventRepr> for PresenceEvent {
+    type Error = DecodeError;
+
+    fn try_from(r: PresenceEventRepr) -> Result<Self, DecodeError> {
+        let event = Self {
+            event_type: r.event_type,
+            flags: r.flags,
+            party_a_id: r.party_a_id,
+            party_b_id: r.party_b_id,
+            timestamp_ns: r.timestamp_ns,
+        };
+        event.validate()?;
+        Ok(event)
+    }
+}
+
 impl PresenceEvent {
     /// Create a new presence event with default flags.
     #[must_use]
     pub const fn new(party_a_id: u32, party_b_id: u32, timestamp_ns: u64) -> Self {
+        Self::of_type(EventType::Presence, party_a_id, party_b_id, timestamp_ns)
+    }
+
+    /// Create an event of the given type with no flags set.
+    #[must_use]
+    pub const fn of_type(
+        kind: EventType,
+        party_a_id: u32,
+        party_b_id: u32,
+        timestamp_ns: u64,
+    ) -> Self {
         Self {
-            event_type: 0x50,
+            event_type: kind as u8,
             flags: 0,
             party_a_id,
             party_b_id,
@@ -88,31 +322,138 @@ impl PresenceEvent {
         }
     }
 
+    /// A and B came into proximity (flagged proximate; mutual if `mutual`).
+    #[must_use]
+    pub const fn encounter_start(
+        party_a_id: u32,
+        party_b_id: u32,
+        timestamp_ns: u64,
+        mutual: bool,
+    ) -> Self {
+        let mut e = Self::of_type(
+            EventType::EncounterStart,
+            party_a_id,
+            party_b_id,
+            timestamp_ns,
+        );
+        e.flags = FLAG_PROXIMATE | if mutual { FLAG_MUTUAL } else { 0 };
+        e
+    }
+
+    /// A and B left proximity; `dwell_met` marks an encounter that lasted
+    /// the minimum dwell time.
+    #[must_use]
+    pub const fn encounter_end(
+        party_a_id: u32,
+        party_b_id: u32,
+        timestamp_ns: u64,
+        dwell_met: bool,
+    ) -> Self {
+        let mut e = Self::of_type(
+            EventType::EncounterEnd,
+            party_a_id,
+            party_b_id,
+            timestamp_ns,
+        );
+        if dwell_met {
+            e.flags = FLAG_DWELL;
+        }
+        e
+    }
+
+    /// A and B are still proximate.
+    #[must_use]
+    pub const fn heartbeat(party_a_id: u32, party_b_id: u32, timestamp_ns: u64) -> Self {
+        let mut e = Self::of_type(EventType::Heartbeat, party_a_id, party_b_id, timestamp_ns);
+        e.flags = FLAG_PROXIMATE;
+        e
+    }
+
+    /// A revokes its crossing with B that happened at `crossing_ns`.
+    #[must_use]
+    pub const fn revocation(party_a_id: u32, party_b_id: u32, crossing_ns: u64) -> Self {
+        let mut e = Self::of_type(EventType::Revocation, party_a_id, party_b_id, crossing_ns);
+        e.flags = FLAG_VERIFIED;
+        e
+    }
+
+    /// `member_id` joined the group with compact id `group` (e.g. the low
+    /// 32 bits of `GroupProximityProof::group_id`).
+    #[must_use]
+    pub const fn group_join(member_id: u32, group: u32, timestamp_ns: u64) -> Self {
+        let mut e = Self::of_type(EventType::GroupJoin, member_id, group, timestamp_ns);
+        e.flags = FLAG_VERIFIED;
+        e
+    }
+
+    /// `member_id` left the group with compact id `group`.
+    #[must_use]
+    pub const fn group_leave(member_id: u32, group: u32, timestamp_ns: u64) -> Self {
+        let mut e = Self::of_type(EventType::GroupLeave, member_id, group, timestamp_ns);
+        e.flags = FLAG_VERIFIED;
+        e
+    }
+
+    /// Registered type of this event.
+    ///
+    /// # Errors
+    ///
+    /// `DecodeError::UnknownType` if `event_type` is not registered.
+    pub const fn kind(&self) -> Result<EventType, DecodeError> {
+        EventType::from_u8(self.event_type)
+    }
+
+    /// Check the type tag and that `flags` only uses bits allowed for it.
+    ///
+    /// # Errors
+    ///
+    /// `DecodeError::UnknownType` for an unregistered tag,
+    /// `DecodeError::InvalidField("flags")` for disallowed flag bits.
+    pub const fn validate(&self) -> Result<(), DecodeError> {
+        match self.kind() {
+            Ok(kind) if self.flags & !kind.allowed_flags() != 0 => {
+                Err(DecodeError::InvalidField("flags"))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
     pub const fn set_mutual(&mut self) {
-        self.flags |= 0b0000_0001;
+        self.flags |= FLAG_MUTUAL;
     }
 
     pub const fn set_verified(&mut self) {
-        self.flags |= 0b0000_0010;
+        self.flags |= FLAG_VERIFIED;
     }
 
     pub const fn set_proximate(&mut self) {
-        self.flags |= 0b0000_0100;
+        self.flags |= FLAG_PROXIMATE;
+    }
+
+    /// Mark that the record carries a dwell interval.
+    pub const fn set_dwell(&mut self) {
+        self.flags |= FLAG_DWELL;
     }
 
     #[must_use]
     pub const fn is_mutual(&self) -> bool {
-        self.flags & 0b0000_0001 != 0
+        self.flags & FLAG_MUTUAL != 0
     }
 
     #[must_use]
     pub const fn is_verified(&self) -> bool {
-        self.flags & 0b0000_0010 != 0
+        self.flags & FLAG_VERIFIED != 0
     }
 
     #[must_use]
     pub const fn is_proximate(&self) -> bool {
-        self.flags & 0b0000_0100 != 0
+        self.flags & FLAG_PROXIMATE != 0
+    }
+
+    #[must_use]
+    pub const fn has_dwell(&self) -> bool {
+        self.flags & FLAG_DWELL != 0
     }
 
     /// Serialize to exactly 18 bytes.
@@ -127,7 +468,22 @@ impl PresenceEvent {
         out
     }
 
-    /// Deserialize from exactly 18 bytes.
+    /// Deserialize from exactly 18 bytes, validating type and flags.
+    ///
+    /// # Errors
+    ///
+    /// See [`PresenceEvent::validate`].
+    pub const fn try_from_bytes(bytes: &[u8; 18]) -> Result<Self, DecodeError> {
+        let e = Self::from_bytes(bytes);
+        match e.validate() {
+            Ok(()) => Ok(e),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Deserialize from exactly 18 bytes without validation.
+    ///
+    /// Use [`PresenceEvent::try_from_bytes`] for untrusted input.
     #[must_use]
     pub const fn from_bytes(bytes: &[u8; 18]) -> Self {
         let event_type = bytes[0];
@@ -157,6 +513,7 @@ impl PresenceEvent {
 
 /// Crossing status state machine.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CrossingStatus {
     /// One party started.
     Initiated,
@@ -170,10 +527,68 @@ pub enum CrossingStatus {
     Revoked,
 }
 
+// ── Dwell Interval ─────────────────────────────────────────────────────
+
+/// Start/end of the continuous proximity interval behind a dwell-time
+/// encounter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DwellInterval {
+    pub start_ns: u64,
+    pub end_ns: u64,
+}
+
+impl DwellInterval {
+    /// Dwell time (ns); 0 if the interval is inverted.
+    #[must_use]
+    pub const fn duration_ns(&self) -> u64 {
+        self.end_ns.saturating_sub(self.start_ns)
+    }
+}
+
+// ── Session Binding ────────────────────────────────────────────────────
+
+/// Session IDs of both parties for the exchange that produced a record.
+///
+/// Bound records derive their ZKP challenges from these IDs, so proofs
+/// from one session cannot be replayed into another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionBinding {
+    /// Party A's `Session::session_id`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+    pub session_a: u64,
+    /// Party B's `Session::session_id`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+    pub session_b: u64,
+}
+
+impl SessionBinding {
+    #[must_use]
+    pub const fn new(session_a: u64, session_b: u64) -> Self {
+        Self {
+            session_a,
+            session_b,
+        }
+    }
+
+    /// Is `session_id` either side of this binding?
+    #[must_use]
+    pub const fn contains(&self, session_id: u64) -> bool {
+        self.session_a == session_id || self.session_b == session_id
+    }
+}
+
 // ── Crossing Record ────────────────────────────────────────────────────
 
+/// Tag bytes preceding the optional trailers in the record hash.
+const HASH_TAG_GEO: u8 = 0x01;
+const HASH_TAG_DWELL: u8 = 0x02;
+const HASH_TAG_SESSION: u8 = 0x03;
+
 /// Permanent crossing record — the full record stored in DB.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CrossingRecord {
     pub event: PresenceEvent,
     /// Party A's identity proof.
@@ -181,7 +596,14 @@ pub struct CrossingRecord {
     /// Party B's identity proof.
     pub proof_b: ZkProof,
     pub proximity: ProximityProof,
+    /// Geographic proof (metres) when the record was produced in hybrid mode.
+    pub geo_proximity: Option<ProximityProof>,
+    /// Proximity interval for dwell-time encounters.
+    pub dwell: Option<DwellInterval>,
+    /// Session IDs the exchange was bound to, if any.
+    pub session: Option<SessionBinding>,
     /// Hash of the entire record.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub content_hash: u64,
 }
 
@@ -194,28 +616,88 @@ impl CrossingRecord {
         proof_b: ZkProof,
         proximity: ProximityProof,
     ) -> Self {
-        let ev_bytes = event.to_bytes();
-        let mut buf = Vec::with_capacity(18 + 8 * 4);
-        buf.extend_from_slice(&ev_bytes);
-        buf.extend_from_slice(&proof_a.response.to_le_bytes());
-        buf.extend_from_slice(&proof_b.response.to_le_bytes());
-        buf.extend_from_slice(&proximity.content_hash.to_le_bytes());
-        buf.extend_from_slice(&proximity.distance.to_le_bytes());
-        let content_hash = fnv1a(&buf);
-
-        Self {
+        let mut record = Self {
             event,
             proof_a,
             proof_b,
             proximity,
-            content_hash,
+            geo_proximity: None,
+            dwell: None,
+            session: None,
+            content_hash: 0,
+        };
+        record.content_hash = record.compute_hash();
+        record
+    }
+
+    /// Attach a geographic proximity proof (hybrid mode) and rehash.
+    #[must_use]
+    pub fn with_geo_proximity(mut self, geo: ProximityProof) -> Self {
+        self.geo_proximity = Some(geo);
+        self.content_hash = self.compute_hash();
+        self
+    }
+
+    /// Record the dwell interval, set the event's dwell flag and rehash.
+    #[must_use]
+    pub fn with_dwell(mut self, dwell: DwellInterval) -> Self {
+        self.dwell = Some(dwell);
+        self.event.set_dwell();
+        self.content_hash = self.compute_hash();
+        self
+    }
+
+    /// Record the session binding and rehash.
+    ///
+    /// The binding does not change the proofs; use it only for records
+    /// whose challenges were derived from the same sessions.
+    #[must_use]
+    pub fn with_session(mut self, session: SessionBinding) -> Self {
+        self.session = Some(session);
+        self.content_hash = self.compute_hash();
+        self
+    }
+
+    /// Hash over event, both responses and the proximity proof(s).
+    /// The geographic proof, dwell interval and session binding only
+    /// contribute when present, so records without them hash exactly as
+    /// before. Each is preceded by its own tag byte (the TLV extension type)
+    /// so that equal-sized trailers cannot stand in for one another.
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let ev_bytes = self.event.to_bytes();
+        let mut buf = Vec::with_capacity(18 + 8 * 10 + 3);
+        buf.extend_from_slice(&ev_bytes);
+        buf.extend_from_slice(&self.proof_a.response.to_le_bytes());
+        buf.extend_from_slice(&self.proof_b.response.to_le_bytes());
+        buf.extend_from_slice(&self.proximity.content_hash.to_le_bytes());
+        buf.extend_from_slice(&self.proximity.distance.to_le_bytes());
+        if let Some(geo) = &self.geo_proximity {
+            buf.push(HASH_TAG_GEO);
+            buf.extend_from_slice(&geo.content_hash.to_le_bytes());
+            buf.extend_from_slice(&geo.distance.to_le_bytes());
+        }
+        if let Some(dwell) = &self.dwell {
+            buf.push(HASH_TAG_DWELL);
+            buf.extend_from_slice(&dwell.start_ns.to_le_bytes());
+            buf.extend_from_slice(&dwell.end_ns.to_le_bytes());
         }
+        if let Some(session) = &self.session {
+            buf.push(HASH_TAG_SESSION);
+            buf.extend_from_slice(&session.session_a.to_le_bytes());
+            buf.extend_from_slice(&session.session_b.to_le_bytes());
+        }
+        fnv1a(&buf)
     }
 
-    /// Fully verified: both ZKPs verified + proximity confirmed.
+    /// Fully verified: both ZKPs verified + proximity confirmed
+    /// (including the geographic proof, if any).
     #[must_use]
     pub const fn is_fully_verified(&self) -> bool {
-        self.proof_a.verified && self.proof_b.verified && self.proximity.is_proximate
+        let geo_ok = match &self.geo_proximity {
+            Some(geo) => geo.is_proximate,
+            None => true,
+        };
+        self.proof_a.verified && self.proof_b.verified && self.proximity.is_proximate && geo_ok
     }
 
     /// Derive the crossing status from the current state of the record.
@@ -239,7 +721,9 @@ impl CrossingRecord {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hyperbolic::HyperbolicCoord;
     use crate::identity::IdentityCommitment;
+    use crate::vivaldi::VivaldiCoord;
 
     #[test]
     fn proximity_within_threshold() {
@@ -275,6 +759,44 @@ mod tests {
         assert_eq!(p1.content_hash, p2.content_hash);
     }
 
+    #[test]
+    fn proximity_hyperbolic_space() {
+        let a = HyperbolicCoord::new(0.0, 0.0);
+        let b = HyperbolicCoord::new(1.0, 0.0);
+        let proof = ProximityProof::prove(&a, &b, 1.0);
+        assert!(proof.is_proximate);
+        assert!((proof.distance - 1.0_f64.asinh()).abs() < 1e-12);
+        assert_eq!(proof.coord_hash_a, a.hash());
+    }
+
+    #[test]
+    fn proximity_unit_in_hash() {
+        let a = VivaldiCoord::new(1.0, 2.0);
+        let b = VivaldiCoord::new(3.0, 4.0);
+        let network = ProximityProof::prove(&a, &b, 10.0);
+        assert_eq!(network.unit, DistanceUnit::Network);
+        // The default unit leaves the hash of existing proofs unchanged
+        let same = ProximityProof::from_parts(
+            network.distance,
+            network.threshold,
+            network.is_proximate,
+            network.coord_hash_a,
+            network.coord_hash_b,
+        );
+        assert_eq!(same.content_hash, network.content_hash);
+        let metres = network.with_unit(DistanceUnit::Metres);
+        assert_ne!(metres.content_hash, network.content_hash);
+
+        let geo = ProximityProof::prove(
+            &crate::geo::GeoCoord::new(35.0, 139.0),
+            &crate::geo::GeoCoord::new(35.0, 139.0),
+            50.0,
+        );
+        assert_eq!(geo.unit, DistanceUnit::Metres);
+        assert_eq!(DistanceUnit::from_u8(1), Some(DistanceUnit::Metres));
+        assert_eq!(DistanceUnit::from_u8(2), None);
+    }
+
     #[test]
     fn event_byte_size() {
         assert_eq!(PresenceEvent::byte_size(), 18);
@@ -361,6 +883,63 @@ mod tests {
         assert_eq!(e2.party_a_id, 0);
     }
 
+    #[test]
+    fn event_type_registry_roundtrip() {
+        for kind in EventType::ALL {
+            assert_eq!(EventType::from_u8(kind as u8), Ok(kind));
+        }
+        assert_eq!(EventType::from_u8(0x00), Err(DecodeError::UnknownType(0)));
+        assert_eq!(PresenceEvent::new(1, 2, 3).kind(), Ok(EventType::Presence));
+    }
+
+    #[test]
+    fn typed_constructors_validate() {
+        let events = [
+            PresenceEvent::encounter_start(1, 2, 10, true),
+            PresenceEvent::encounter_end(1, 2, 20, true),
+            PresenceEvent::heartbeat(1, 2, 15),
+            PresenceEvent::revocation(1, 2, 10),
+            PresenceEvent::group_join(1, 0xABCD, 30),
+            PresenceEvent::group_leave(1, 0xABCD, 40),
+        ];
+        for e in events {
+            assert_eq!(e.validate(), Ok(()), "{e:?}");
+            let back = PresenceEvent::try_from_bytes(&e.to_bytes()).unwrap();
+            assert_eq!(back.kind(), e.kind());
+            assert_eq!(back.flags, e.flags);
+        }
+        assert!(events[0].is_mutual() && events[0].is_proximate());
+        assert!(events[1].has_dwell() && !events[1].is_mutual());
+        assert!(events[4].kind().unwrap().is_group_event());
+        assert!(!events[3].kind().unwrap().is_group_event());
+    }
+
+    #[test]
+    fn decode_rejects_unknown_type_and_flags() {
+        let mut bytes = PresenceEvent::new(1, 2, 3).to_bytes();
+        bytes[0] = 0x51;
+        assert_eq!(
+            PresenceEvent::try_from_bytes(&bytes).unwrap_err(),
+            DecodeError::UnknownType(0x51)
+        );
+
+        // A heartbeat cannot carry the verified flag
+        let mut hb = PresenceEvent::heartbeat(1, 2, 3);
+        hb.set_verified();
+        assert_eq!(
+            PresenceEvent::try_from_bytes(&hb.to_bytes()).unwrap_err(),
+            DecodeError::InvalidField("flags")
+        );
+
+        // Undefined flag bit
+        let mut bytes = PresenceEvent::new(1, 2, 3).to_bytes();
+        bytes[1] = 0b1_0000;
+        assert_eq!(
+            PresenceEvent::try_from_bytes(&bytes).unwrap_err(),
+            DecodeError::InvalidField("flags")
+        );
+    }
+
     #[test]
     fn crossing_fully_verified() {
         let a = VivaldiCoord::new(0.0, 0.0);
@@ -421,4 +1000,83 @@ mod tests {
         let record = CrossingRecord::new(event, pa, pb, prox);
         assert_ne!(record.content_hash, 0);
     }
+
+    #[test]
+    fn crossing_geo_proximity_changes_hash() {
+        use crate::geo::GeoCoord;
+        let a = VivaldiCoord::new(0.0, 0.0);
+        let b = VivaldiCoord::new(1.0, 0.0);
+        let prox = ProximityProof::prove(&a, &b, 10.0);
+        let ca = IdentityCommitment::new(42, 1, 100);
+        let cb = IdentityCommitment::new(99, 2, 100);
+        let pa = ZkProof::prove(42, &ca, 0xAA);
+        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let record = CrossingRecord::new(PresenceEvent::new(1, 2, 100), pa, pb, prox);
+        let far = ProximityProof::prove(
+            &GeoCoord::new(35.0, 139.0),
+            &GeoCoord::new(36.0, 139.0),
+            50.0,
+        );
+        let hybrid = record.with_geo_proximity(far);
+        assert_ne!(hybrid.content_hash, record.content_hash);
+        assert!(record.is_fully_verified());
+        assert!(!hybrid.is_fully_verified());
+    }
+
+    #[test]
+    fn crossing_dwell_sets_flag_and_rehashes() {
+        let a = VivaldiCoord::new(0.0, 0.0);
+        let prox = ProximityProof::prove(&a, &a, 10.0);
+        let ca = IdentityCommitment::new(42, 1, 100);
+        let cb = IdentityCommitment::new(99, 2, 100);
+        let pa = ZkProof::prove(42, &ca, 0xAA);
+        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let record = CrossingRecord::new(PresenceEvent::new(1, 2, 100), pa, pb, prox);
+        let dwell = DwellInterval {
+            start_ns: 100,
+            end_ns: 700,
+        };
+        let with = record.with_dwell(dwell);
+        assert!(with.event.has_dwell());
+        assert!(!record.event.has_dwell());
+        assert_eq!(with.dwell.unwrap().duration_ns(), 600);
+        assert_ne!(with.content_hash, record.content_hash);
+    }
+
+    #[test]
+    fn crossing_session_rehashes() {
+        let a = VivaldiCoord::new(0.0, 0.0);
+        let prox = ProximityProof::prove(&a, &a, 10.0);
+        let ca = IdentityCommitment::new(42, 1, 100);
+        let cb = IdentityCommitment::new(99, 2, 100);
+        let pa = ZkProof::prove(42, &ca, 0xAA);
+        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let record = CrossingRecord::new(PresenceEvent::new(1, 2, 100), pa, pb, prox);
+        let one = record.with_session(SessionBinding::new(7, 8));
+        let other = record.with_session(SessionBinding::new(7, 9));
+        assert_ne!(one.content_hash, record.content_hash);
+        assert_ne!(one.content_hash, other.content_hash);
+        assert!(one.session.unwrap().contains(8));
+        assert!(!one.session.unwrap().contains(9));
+    }
+
+    #[test]
+    fn crossing_trailers_do_not_collide() {
+        let a = VivaldiCoord::new(0.0, 0.0);
+        let prox = ProximityProof::prove(&a, &a, 10.0);
+        let ca = IdentityCommitment::new(42, 1, 100);
+        let cb = IdentityCommitment::new(99, 2, 100);
+        let pa = ZkProof::prove(42, &ca, 0xAA);
+        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let record = CrossingRecord::new(PresenceEvent::new(1, 2, 100), pa, pb, prox);
+
+        // A session binding with the same 16 bytes as the geo trailer.
+        let geo = ProximityProof::prove(&a, &VivaldiCoord::new(3.0, 4.0), 10.0);
+        let with_geo = record.with_geo_proximity(geo);
+        let with_session = record.with_session(SessionBinding::new(
+            geo.content_hash,
+            geo.distance.to_bits(),
+        ));
+        assert_ne!(with_geo.content_hash, with_session.content_hash);
+    }
 }
@@ -0,0 +1,246 @@
+//! Geographic coordinates
+//!
+//! WGS-84 latitude/longitude with great-circle distance in metres, for
+//! encounters where GPS is available but RTT is not meaningful.
+//! Haversine is used for proofs and indexing; Vincenty's inverse formula
+//! is available when ellipsoidal accuracy matters.
+//!
+//! Author: Moroya Sakamoto
+
+use crate::event::DistanceUnit;
+use crate::fnv1a;
+use crate::space::{read_finite_f64, CoordinateSpace};
+
+/// Mean Earth radius (IUGG), metres.
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+/// WGS-84 semi-major axis, metres.
+const WGS84_A: f64 = 6_378_137.0;
+/// WGS-84 flattening.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// Geographic coordinate in degrees.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoCoord {
+    /// Latitude in degrees, `[-90, 90]`.
+    pub lat_deg: f64,
+    /// Longitude in degrees, `[-180, 180)`.
+    pub lon_deg: f64,
+}
+
+impl GeoCoord {
+    /// Create a coordinate. Latitude is clamped to `[-90, 90]` and
+    /// longitude wrapped into `[-180, 180)`.
+    #[must_use]
+    pub fn new(lat_deg: f64, lon_deg: f64) -> Self {
+        Self {
+            lat_deg: lat_deg.clamp(-90.0, 90.0),
+            lon_deg: (lon_deg + 180.0).rem_euclid(360.0) - 180.0,
+        }
+    }
+
+    /// Great-circle distance in metres on a sphere of radius
+    /// [`EARTH_RADIUS_M`].
+    #[must_use]
+    pub fn haversine_m(&self, other: &Self) -> f64 {
+        let phi1 = self.lat_deg.to_radians();
+        let phi2 = other.lat_deg.to_radians();
+        let dphi = phi2 - phi1;
+        let dlambda = (other.lon_deg - self.lon_deg).to_radians();
+        let s1 = (dphi * 0.5).sin();
+        let s2 = (dlambda * 0.5).sin();
+        let h = (phi1.cos() * phi2.cos()).mul_add(s2 * s2, s1 * s1);
+        2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+    }
+
+    /// Ellipsoidal (WGS-84) distance in metres via Vincenty's inverse
+    /// formula. Returns `None` if the iteration does not converge
+    /// (nearly antipodal points).
+    #[must_use]
+    pub fn vincenty_m(&self, other: &Self) -> Option<f64> {
+        let b = WGS84_A * (1.0 - WGS84_F);
+        let l = (other.lon_deg - self.lon_deg).to_radians();
+        let u1 = ((1.0 - WGS84_F) * self.lat_deg.to_radians().tan()).atan();
+        let u2 = ((1.0 - WGS84_F) * other.lat_deg.to_radians().tan()).atan();
+        let (sin_u1, cos_u1) = u1.sin_cos();
+        let (sin_u2, cos_u2) = u2.sin_cos();
+
+        let mut lambda = l;
+        for _ in 0..200 {
+            let (sin_l, cos_l) = lambda.sin_cos();
+            let t1 = cos_u2 * sin_l;
+            let t2 = cos_u1.mul_add(sin_u2, -(sin_u1 * cos_u2 * cos_l));
+            let sin_sigma = t1.hypot(t2);
+            if sin_sigma < 1e-15 {
+                return Some(0.0); // coincident points
+            }
+            let cos_sigma = sin_u1.mul_add(sin_u2, cos_u1 * cos_u2 * cos_l);
+            let sigma = sin_sigma.atan2(cos_sigma);
+            let sin_alpha = cos_u1 * cos_u2 * sin_l / sin_sigma;
+            let cos2_alpha = sin_alpha.mul_add(-sin_alpha, 1.0);
+            // Equatorial line: cos2_alpha == 0
+            let cos_2sm = if cos2_alpha == 0.0 {
+                0.0
+            } else {
+                cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha
+            };
+            let c = WGS84_F / 16.0 * cos2_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos2_alpha));
+            let prev = lambda;
+            lambda = l
+                + (1.0 - c)
+                    * WGS84_F
+                    * sin_alpha
+                    * (sigma
+                        + c * sin_sigma
+                            * (cos_2sm + c * cos_sigma * (2.0 * cos_2sm).mul_add(cos_2sm, -1.0)));
+            if (lambda - prev).abs() < 1e-12 {
+                let u_sq = cos2_alpha * (WGS84_A * WGS84_A - b * b) / (b * b);
+                let big_a = 1.0
+                    + u_sq / 16_384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+                let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+                let delta_sigma = big_b
+                    * sin_sigma
+                    * (cos_2sm
+                        + big_b / 4.0
+                            * (cos_sigma * (2.0 * cos_2sm).mul_add(cos_2sm, -1.0)
+                                - big_b / 6.0
+                                    * cos_2sm
+                                    * (4.0 * sin_sigma).mul_add(sin_sigma, -3.0)
+                                    * (4.0 * cos_2sm).mul_add(cos_2sm, -3.0)));
+                return Some(b * big_a * (sigma - delta_sigma));
+            }
+        }
+        None
+    }
+
+    /// Hash the coordinate for privacy-preserving proofs.
+    #[must_use]
+    pub fn hash(&self) -> u64 {
+        let mut buf = [0u8; 16];
+        buf[..8].copy_from_slice(&self.lat_deg.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.lon_deg.to_le_bytes());
+        fnv1a(&buf)
+    }
+}
+
+/// Distance is haversine metres. Positions come from the receiver, not
+/// from RTT measurements, so `update` is a no-op.
+impl CoordinateSpace for GeoCoord {
+    /// Only latitude gives a cheap admissible bound (meridian arc length).
+    const AXES: usize = 1;
+    const ENCODED_LEN: usize = 16;
+    const UNIT: DistanceUnit = DistanceUnit::Metres;
+
+    fn distance(&self, other: &Self) -> f64 {
+        self.haversine_m(other)
+    }
+
+    fn update(&mut self, _other: &Self, _rtt: f64, _cc: f64) {}
+
+    fn commit(&self) -> u64 {
+        self.hash()
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.lat_deg.to_le_bytes());
+        out.extend_from_slice(&self.lon_deg.to_le_bytes());
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let lat_deg = read_finite_f64(data, 0)?;
+        let lon_deg = read_finite_f64(data, 8)?;
+        if !(-90.0..=90.0).contains(&lat_deg) || !(-180.0..180.0).contains(&lon_deg) {
+            return None;
+        }
+        Some(Self { lat_deg, lon_deg })
+    }
+
+    fn axis(&self, _axis: usize) -> f64 {
+        EARTH_RADIUS_M * self.lat_deg.to_radians()
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_point_zero() {
+        let a = GeoCoord::new(35.68, 139.76);
+        assert!(a.haversine_m(&a).abs() < 1e-9);
+        assert_eq!(a.vincenty_m(&a), Some(0.0));
+    }
+
+    #[test]
+    fn one_degree_latitude() {
+        // 1° of latitude on the mean sphere ≈ 111.195 km
+        let a = GeoCoord::new(0.0, 0.0);
+        let b = GeoCoord::new(1.0, 0.0);
+        assert!((a.haversine_m(&b) - 111_195.08).abs() < 1.0);
+    }
+
+    #[test]
+    fn tokyo_osaka() {
+        // 東京駅 → 大阪駅 ≈ 403 km
+        let tokyo = GeoCoord::new(35.6812, 139.7671);
+        let osaka = GeoCoord::new(34.7025, 135.4959);
+        let h = tokyo.haversine_m(&osaka);
+        let v = tokyo.vincenty_m(&osaka).unwrap();
+        assert!((h - 403_000.0).abs() < 3_000.0);
+        // Ellipsoid vs sphere differ by well under 1%
+        assert!((h - v).abs() / v < 0.01);
+    }
+
+    #[test]
+    fn vincenty_known_equator() {
+        // 1° of longitude on the WGS-84 equator = 111 319.49 m
+        let a = GeoCoord::new(0.0, 0.0);
+        let b = GeoCoord::new(0.0, 1.0);
+        assert!((a.vincenty_m(&b).unwrap() - 111_319.49).abs() < 0.01);
+    }
+
+    #[test]
+    fn vincenty_antipodal_may_fail() {
+        let a = GeoCoord::new(0.0, 0.0);
+        let b = GeoCoord::new(0.5, 179.7);
+        assert!(a.vincenty_m(&b).is_none());
+    }
+
+    #[test]
+    fn longitude_wraps() {
+        let a = GeoCoord::new(10.0, 190.0);
+        assert!((a.lon_deg + 170.0).abs() < 1e-12);
+        let b = GeoCoord::new(10.0, -170.0);
+        assert!(a.haversine_m(&b) < 1e-6);
+    }
+
+    #[test]
+    fn dateline_short_distance() {
+        let a = GeoCoord::new(0.0, 179.9999);
+        let b = GeoCoord::new(0.0, -179.9999);
+        assert!(a.haversine_m(&b) < 30.0);
+    }
+
+    #[test]
+    fn latitude_clamped() {
+        assert_eq!(GeoCoord::new(95.0, 0.0).lat_deg, 90.0);
+    }
+
+    #[test]
+    fn decode_rejects_out_of_range() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&91.0_f64.to_le_bytes());
+        buf.extend_from_slice(&0.0_f64.to_le_bytes());
+        assert!(GeoCoord::decode(&buf).is_none());
+    }
+
+    #[test]
+    fn latitude_axis_is_lower_bound() {
+        let a = GeoCoord::new(35.0, 139.0);
+        let b = GeoCoord::new(35.01, 139.02);
+        assert!(a.axis_lower_bound(&b, 0) <= a.haversine_m(&b));
+    }
+}
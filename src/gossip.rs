@@ -0,0 +1,468 @@
+//! 座標ゴシップ — Vivaldi 座標アナウンスとプローブメッセージのワイヤ形式。
+//!
+//! proof には座標のハッシュしか載らないため、ピアへ座標そのもの
+//! (誤差推定・タイムスタンプ付き) を送るための固定長エンコーディングと、
+//! トランスポート非依存のメッセージセット (probe / probe-reply / push) を提供する。
+//!
+//! 外部依存ゼロ。すべてリトルエンディアン。
+
+use crate::error::{DecodeError, EncodeError};
+use crate::serialize::le_u64;
+use crate::space::{read_finite_f64, CoordinateSpace};
+use crate::vivaldi::{VivaldiCoord, VivaldiNode, MAX_ERROR};
+
+/// 座標アナウンスのエンコーディングバージョン。
+pub const COORD_WIRE_VERSION: u8 = 1;
+
+/// 座標アナウンスのバイトサイズ: `version(1) + coord(24) + error(8) + timestamp(8)`。
+pub const COORD_ANNOUNCEMENT_SIZE: usize = 1 + 24 + 8 + 8;
+
+/// ゴシップメッセージのマジック。
+const MAGIC_GOSSIP: [u8; 4] = *b"AGSP";
+
+/// ゴシップメッセージのフォーマットバージョン。
+pub const GOSSIP_VERSION: u8 = 1;
+
+/// メッセージヘッダサイズ: `magic(4) + version(1) + type(1) + sender_id(4)`。
+const HEADER_SIZE: usize = 4 + 1 + 1 + 4;
+
+/// メッセージ種別タグ。
+const TYPE_PROBE: u8 = 0x01;
+const TYPE_PROBE_REPLY: u8 = 0x02;
+const TYPE_PUSH: u8 = 0x03;
+
+// ============================================================================
+// CoordAnnouncement
+// ============================================================================
+
+/// ピアへ送る座標アナウンス。
+///
+/// Wire layout (41 bytes):
+/// `[version: u8][x: f64][y: f64][height: f64][error: f64][timestamp_ns: u64]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoordAnnouncement {
+    /// 座標と相対誤差。
+    pub node: VivaldiNode,
+    /// 座標を計測した時刻 (ナノ秒)。
+    pub timestamp_ns: u64,
+}
+
+impl CoordAnnouncement {
+    /// アナウンスを作成。
+    #[must_use]
+    pub const fn new(node: VivaldiNode, timestamp_ns: u64) -> Self {
+        Self { node, timestamp_ns }
+    }
+
+    /// 固定長バイト列へエンコード。
+    ///
+    /// # Errors
+    ///
+    /// [`from_bytes`](Self::from_bytes) が拒否する値、つまり非有限の座標や
+    /// `0.0..=MAX_ERROR` 外の誤差は `EncodeError::InvalidField`。
+    pub fn to_bytes(&self) -> Result<[u8; COORD_ANNOUNCEMENT_SIZE], EncodeError> {
+        let c = &self.node.coord;
+        if ![c.x, c.y, c.height].iter().all(|v| v.is_finite()) {
+            return Err(EncodeError::InvalidField("coord"));
+        }
+        if !(0.0..=MAX_ERROR).contains(&self.node.error) {
+            return Err(EncodeError::InvalidField("error"));
+        }
+        Ok(self.encode_unchecked())
+    }
+
+    /// 値を検証せずにエンコード (ダイジェスト計算用)。
+    pub(crate) fn encode_unchecked(&self) -> [u8; COORD_ANNOUNCEMENT_SIZE] {
+        let c = &self.node.coord;
+        let mut out = [0u8; COORD_ANNOUNCEMENT_SIZE];
+        out[0] = COORD_WIRE_VERSION;
+        out[1..9].copy_from_slice(&c.x.to_le_bytes());
+        out[9..17].copy_from_slice(&c.y.to_le_bytes());
+        out[17..25].copy_from_slice(&c.height.to_le_bytes());
+        out[25..33].copy_from_slice(&self.node.error.to_le_bytes());
+        out[33..].copy_from_slice(&self.timestamp_ns.to_le_bytes());
+        out
+    }
+
+    /// バイト列からデコード。
+    ///
+    /// # Errors
+    ///
+    /// 長さ不足は `TooShort`、余分なバイトは `TrailingBytes`、未知バージョンは
+    /// `UnsupportedVersion`、非有限の座標や範囲外の誤差は `InvalidField`。
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        DecodeError::check_len(data, COORD_ANNOUNCEMENT_SIZE)?;
+        if data.len() > COORD_ANNOUNCEMENT_SIZE {
+            return Err(DecodeError::TrailingBytes {
+                extra: data.len() - COORD_ANNOUNCEMENT_SIZE,
+            });
+        }
+        if data[0] != COORD_WIRE_VERSION {
+            return Err(DecodeError::UnsupportedVersion(data[0]));
+        }
+        let coord = VivaldiCoord::decode(&data[1..25]).ok_or(DecodeError::InvalidField("coord"))?;
+        let error = read_finite_f64(data, 25)
+            .filter(|e| (0.0..=MAX_ERROR).contains(e))
+            .ok_or(DecodeError::InvalidField("error"))?;
+        let timestamp_ns = le_u64(data, 33);
+        Ok(Self {
+            node: VivaldiNode { coord, error },
+            timestamp_ns,
+        })
+    }
+}
+
+// ============================================================================
+// GossipMessage
+// ============================================================================
+
+/// トランスポート非依存のゴシップメッセージ。
+///
+/// 共通ヘッダ: `[magic "AGSP"][version: u8][type: u8][sender_id: u32]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GossipMessage {
+    /// RTT 計測プローブ。`nonce` は返信で照合する。
+    Probe { sender_id: u32, nonce: u64 },
+    /// プローブへの返信。送信者の現在座標を添える。
+    ProbeReply {
+        sender_id: u32,
+        nonce: u64,
+        announcement: CoordAnnouncement,
+    },
+    /// 座標の一方的な通知。
+    CoordinatePush {
+        sender_id: u32,
+        announcement: CoordAnnouncement,
+    },
+}
+
+impl GossipMessage {
+    /// 送信者 ID。
+    #[must_use]
+    pub const fn sender_id(&self) -> u32 {
+        match self {
+            Self::Probe { sender_id, .. }
+            | Self::ProbeReply { sender_id, .. }
+            | Self::CoordinatePush { sender_id, .. } => *sender_id,
+        }
+    }
+
+    /// 添付された座標アナウンス (probe には無い)。
+    #[must_use]
+    pub const fn announcement(&self) -> Option<&CoordAnnouncement> {
+        match self {
+            Self::Probe { .. } => None,
+            Self::ProbeReply { announcement, .. } | Self::CoordinatePush { announcement, .. } => {
+                Some(announcement)
+            }
+        }
+    }
+
+    const fn type_tag(&self) -> u8 {
+        match self {
+            Self::Probe { .. } => TYPE_PROBE,
+            Self::ProbeReply { .. } => TYPE_PROBE_REPLY,
+            Self::CoordinatePush { .. } => TYPE_PUSH,
+        }
+    }
+
+    /// バイト列へエンコード。
+    ///
+    /// # Errors
+    ///
+    /// アナウンスが不正なら [`CoordAnnouncement::to_bytes`] と同じ。
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::with_capacity(HEADER_SIZE + 8 + COORD_ANNOUNCEMENT_SIZE);
+        buf.extend_from_slice(&MAGIC_GOSSIP);
+        buf.push(GOSSIP_VERSION);
+        buf.push(self.type_tag());
+        buf.extend_from_slice(&self.sender_id().to_le_bytes());
+        match self {
+            Self::Probe { nonce, .. } => buf.extend_from_slice(&nonce.to_le_bytes()),
+            Self::ProbeReply {
+                nonce,
+                announcement,
+                ..
+            } => {
+                buf.extend_from_slice(&nonce.to_le_bytes());
+                buf.extend_from_slice(&announcement.to_bytes()?);
+            }
+            Self::CoordinatePush { announcement, .. } => {
+                buf.extend_from_slice(&announcement.to_bytes()?);
+            }
+        }
+        Ok(buf)
+    }
+
+    /// バイト列からデコード。
+    ///
+    /// # Errors
+    ///
+    /// マジック不一致は `BadMagic`、バージョン・種別が未知なら
+    /// `UnsupportedVersion` / `UnknownType`、長さが種別と一致しなければ
+    /// `TooShort` / `TrailingBytes`、アナウンスが不正ならその理由を返す。
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        DecodeError::check_len(data, HEADER_SIZE)?;
+        if data[..4] != MAGIC_GOSSIP {
+            return Err(DecodeError::BadMagic);
+        }
+        if data[4] != GOSSIP_VERSION {
+            return Err(DecodeError::UnsupportedVersion(data[4]));
+        }
+        let tag = data[5];
+        let sender_id = u32::from_le_bytes([data[6], data[7], data[8], data[9]]);
+        let body_len = match tag {
+            TYPE_PROBE => 8,
+            TYPE_PROBE_REPLY => 8 + COORD_ANNOUNCEMENT_SIZE,
+            TYPE_PUSH => COORD_ANNOUNCEMENT_SIZE,
+            other => return Err(DecodeError::UnknownType(other)),
+        };
+        DecodeError::check_len(data, HEADER_SIZE + body_len)?;
+        if data.len() > HEADER_SIZE + body_len {
+            return Err(DecodeError::TrailingBytes {
+                extra: data.len() - HEADER_SIZE - body_len,
+            });
+        }
+        let body = &data[HEADER_SIZE..];
+        Ok(match tag {
+            TYPE_PROBE => Self::Probe {
+                sender_id,
+                nonce: le_u64(body, 0),
+            },
+            TYPE_PROBE_REPLY => Self::ProbeReply {
+                sender_id,
+                nonce: le_u64(body, 0),
+                announcement: CoordAnnouncement::from_bytes(&body[8..])?,
+            },
+            _ => Self::CoordinatePush {
+                sender_id,
+                announcement: CoordAnnouncement::from_bytes(body)?,
+            },
+        })
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn announcement() -> CoordAnnouncement {
+        CoordAnnouncement::new(
+            VivaldiNode::with_error(VivaldiCoord::with_height(1.5, -2.25, 0.75), 0.4),
+            1_700_000_000_000_000_000,
+        )
+    }
+
+    fn all_messages() -> [GossipMessage; 3] {
+        [
+            GossipMessage::Probe {
+                sender_id: 7,
+                nonce: 0xDEAD_BEEF,
+            },
+            GossipMessage::ProbeReply {
+                sender_id: 8,
+                nonce: 0xDEAD_BEEF,
+                announcement: announcement(),
+            },
+            GossipMessage::CoordinatePush {
+                sender_id: 9,
+                announcement: announcement(),
+            },
+        ]
+    }
+
+    #[test]
+    fn announcement_roundtrip() {
+        let a = announcement();
+        let bytes = a.to_bytes().unwrap();
+        assert_eq!(bytes.len(), 41);
+        assert_eq!(bytes[0], COORD_WIRE_VERSION);
+        assert_eq!(CoordAnnouncement::from_bytes(&bytes), Ok(a));
+    }
+
+    #[test]
+    fn announcement_rejects_unknown_version() {
+        let mut bytes = announcement().to_bytes().unwrap();
+        bytes[0] = 2;
+        assert_eq!(
+            CoordAnnouncement::from_bytes(&bytes),
+            Err(DecodeError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn announcement_rejects_non_finite() {
+        let mut bytes = announcement().to_bytes().unwrap();
+        bytes[1..9].copy_from_slice(&f64::NAN.to_le_bytes());
+        assert_eq!(
+            CoordAnnouncement::from_bytes(&bytes),
+            Err(DecodeError::InvalidField("coord"))
+        );
+    }
+
+    #[test]
+    fn announcement_rejects_error_out_of_range() {
+        for bad in [-0.1, MAX_ERROR + 0.1, f64::INFINITY] {
+            let mut bytes = announcement().to_bytes().unwrap();
+            bytes[25..33].copy_from_slice(&bad.to_le_bytes());
+            assert_eq!(
+                CoordAnnouncement::from_bytes(&bytes),
+                Err(DecodeError::InvalidField("error")),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn announcement_encode_rejects_invalid_values() {
+        let mut nan = announcement();
+        nan.node.coord.height = f64::NAN;
+        assert_eq!(nan.to_bytes(), Err(EncodeError::InvalidField("coord")));
+        for bad in [-0.1, MAX_ERROR + 0.1, f64::NAN] {
+            let mut a = announcement();
+            a.node.error = bad;
+            assert_eq!(
+                a.to_bytes(),
+                Err(EncodeError::InvalidField("error")),
+                "{bad}"
+            );
+            let push = GossipMessage::CoordinatePush {
+                sender_id: 9,
+                announcement: a,
+            };
+            assert_eq!(push.encode(), Err(EncodeError::InvalidField("error")));
+        }
+    }
+
+    #[test]
+    fn announcement_rejects_trailing_bytes() {
+        let mut bytes = announcement().to_bytes().unwrap().to_vec();
+        bytes.push(0);
+        assert_eq!(
+            CoordAnnouncement::from_bytes(&bytes),
+            Err(DecodeError::TrailingBytes { extra: 1 })
+        );
+    }
+
+    #[test]
+    fn announcement_too_short() {
+        let bytes = announcement().to_bytes().unwrap();
+        assert_eq!(
+            CoordAnnouncement::from_bytes(&bytes[..40]),
+            Err(DecodeError::TooShort {
+                expected: 41,
+                actual: 40
+            })
+        );
+        assert!(CoordAnnouncement::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn message_roundtrip() {
+        for msg in all_messages() {
+            let bytes = msg.encode().unwrap();
+            assert_eq!(GossipMessage::decode(&bytes), Ok(msg));
+        }
+    }
+
+    #[test]
+    fn message_sizes() {
+        let [probe, reply, push] = all_messages();
+        assert_eq!(probe.encode().unwrap().len(), 18);
+        assert_eq!(reply.encode().unwrap().len(), 18 + 41);
+        assert_eq!(push.encode().unwrap().len(), 10 + 41);
+    }
+
+    #[test]
+    fn message_accessors() {
+        let [probe, reply, push] = all_messages();
+        assert_eq!(probe.sender_id(), 7);
+        assert!(probe.announcement().is_none());
+        assert_eq!(reply.announcement(), Some(&announcement()));
+        assert_eq!(push.sender_id(), 9);
+    }
+
+    #[test]
+    fn message_rejects_bad_header() {
+        let bytes = all_messages()[0].encode().unwrap();
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            GossipMessage::decode(&bad_magic),
+            Err(DecodeError::BadMagic)
+        );
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 0;
+        assert_eq!(
+            GossipMessage::decode(&bad_version),
+            Err(DecodeError::UnsupportedVersion(0))
+        );
+        let mut bad_type = bytes;
+        bad_type[5] = 0x7F;
+        assert_eq!(
+            GossipMessage::decode(&bad_type),
+            Err(DecodeError::UnknownType(0x7F))
+        );
+    }
+
+    #[test]
+    fn message_rejects_every_truncation() {
+        for msg in all_messages() {
+            let bytes = msg.encode().unwrap();
+            for len in 0..bytes.len() {
+                assert!(
+                    matches!(
+                        GossipMessage::decode(&bytes[..len]),
+                        Err(DecodeError::TooShort { .. })
+                    ),
+                    "{len}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn message_rejects_trailing_bytes() {
+        for msg in all_messages() {
+            let mut bytes = msg.encode().unwrap();
+            bytes.push(0);
+            assert_eq!(
+                GossipMessage::decode(&bytes),
+                Err(DecodeError::TrailingBytes { extra: 1 })
+            );
+        }
+    }
+
+    #[test]
+    fn message_rejects_corrupt_announcement() {
+        let mut bytes = all_messages()[2].encode().unwrap();
+        // アナウンス内のバージョンバイトを壊す
+        bytes[HEADER_SIZE] = 0xFF;
+        assert_eq!(
+            GossipMessage::decode(&bytes),
+            Err(DecodeError::UnsupportedVersion(0xFF))
+        );
+    }
+
+    #[test]
+    fn decode_arbitrary_bytes_never_panics() {
+        // 簡易 LCG による疑似ランダム入力
+        let mut state = 0x1234_5678_u64;
+        for _ in 0..2000 {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1);
+            let len = (state >> 58) as usize + HEADER_SIZE;
+            let mut buf: Vec<u8> = (0..len).map(|i| (state >> (i % 56)) as u8).collect();
+            buf[..4].copy_from_slice(&MAGIC_GOSSIP);
+            buf[4] = GOSSIP_VERSION;
+            let _ = GossipMessage::decode(&buf);
+        }
+    }
+}
@@ -5,7 +5,9 @@
 //!
 //! Author: Moroya Sakamoto
 
+use crate::error::GroupError;
 use crate::fnv1a;
+use crate::space::CoordinateSpace;
 use crate::vivaldi::VivaldiCoord;
 
 /// Maximum group size (prevents O(n^2) blowup in proximity checks).
@@ -13,22 +15,27 @@ pub const MAX_GROUP_SIZE: usize = 64;
 
 /// A member in a presence group.
 #[derive(Debug, Clone, Copy)]
-pub struct GroupMember {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupMember<C = VivaldiCoord> {
     /// Compact party identifier.
     pub id: u32,
-    /// Vivaldi coordinate.
-    pub coord: VivaldiCoord,
+    /// Network coordinate.
+    pub coord: C,
     /// Timestamp (ns) when member joined the group.
     pub joined_ns: u64,
 }
 
 /// Group proximity proof — evidence that all members are within threshold.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupProximityProof {
     /// Group identifier (hash of sorted member IDs).
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub group_id: u64,
-    /// Number of members in the group.
+    /// Number of members in the group (always `member_ids.len()`).
     pub member_count: usize,
+    /// Sorted member IDs the `group_id` was derived from.
+    pub member_ids: Vec<u32>,
     /// Maximum pairwise distance among all members.
     pub max_distance: f64,
     /// Proximity threshold used.
@@ -36,13 +43,15 @@ pub struct GroupProximityProof {
     /// True if `max_distance` <= threshold (all pairs proximate).
     pub all_proximate: bool,
     /// Deterministic content hash.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub content_hash: u64,
 }
 
 /// Group configuration.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupConfig {
-    /// Maximum Vivaldi distance for "group proximity".
+    /// Maximum coordinate distance for "group proximity".
     pub proximity_threshold: f64,
     /// Minimum members for a valid group proof.
     pub min_members: usize,
@@ -58,36 +67,109 @@ impl Default for GroupConfig {
 }
 
 /// Presence group — manages members and proximity checks.
+///
+/// Generic over the coordinate space; defaults to [`VivaldiCoord`].
+/// Deserialization goes through [`PresenceGroup::try_with_members`], so
+/// the size limit and duplicate check also apply to stored groups.
 #[derive(Debug, Clone)]
-pub struct PresenceGroup {
-    members: Vec<GroupMember>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "PresenceGroupRepr<C>",
+        bound(deserialize = "C: CoordinateSpace + serde::Deserialize<'de>")
+    )
+)]
+pub struct PresenceGroup<C = VivaldiCoord> {
+    members: Vec<GroupMember<C>>,
     config: GroupConfig,
 }
 
+/// Unvalidated serde form of `PresenceGroup`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct PresenceGroupRepr<C> {
+    members: Vec<GroupMember<C>>,
+    config: GroupConfig,
+}
+
+#[cfg(feature = "serde")]
+impl<C: CoordinateSpace> TryFrom<PresenceGroupRepr<C>> for PresenceGroup<C> {
+    type Error = GroupError;
+
+    fn try_from(repr: PresenceGroupRepr<C>) -> Result<Self, GroupError> {
+        Self::try_with_members(repr.config, repr.members)
+    }
+}
+
 impl PresenceGroup {
-    /// Create a new empty group.
+    /// Create a new empty group over Vivaldi coordinates.
     #[must_use]
     pub const fn new(config: GroupConfig) -> Self {
+        Self::in_space(config)
+    }
+}
+
+impl<C: CoordinateSpace> PresenceGroup<C> {
+    /// Create a new empty group over coordinate space `C`.
+    #[must_use]
+    pub const fn in_space(config: GroupConfig) -> Self {
         Self {
             members: Vec::new(),
             config,
         }
     }
 
-    /// Add a member. Returns false if group is full or ID already present.
-    pub fn add_member(&mut self, id: u32, coord: VivaldiCoord, timestamp_ns: u64) -> bool {
+    /// Create a group over coordinate space `C` holding `members`, checked
+    /// exactly as if each were added with [`Self::try_add_member`].
+    ///
+    /// # Errors
+    ///
+    /// `GroupError::Full` beyond `MAX_GROUP_SIZE` members,
+    /// `GroupError::DuplicateMember` if an ID appears twice.
+    pub fn try_with_members(
+        config: GroupConfig,
+        members: impl IntoIterator<Item = GroupMember<C>>,
+    ) -> Result<Self, GroupError> {
+        let mut group = Self::in_space(config);
+        for m in members {
+            group.try_add_member(m.id, m.coord, m.joined_ns)?;
+        }
+        Ok(group)
+    }
+
+    /// Add a member.
+    ///
+    /// # Errors
+    ///
+    /// `GroupError::Full` if the group already holds `MAX_GROUP_SIZE`
+    /// members, `GroupError::DuplicateMember` if `id` is already present.
+    pub fn try_add_member(
+        &mut self,
+        id: u32,
+        coord: C,
+        timestamp_ns: u64,
+    ) -> Result<(), GroupError> {
         if self.members.len() >= MAX_GROUP_SIZE {
-            return false;
+            return Err(GroupError::Full {
+                capacity: MAX_GROUP_SIZE,
+            });
         }
-        if self.members.iter().any(|m| m.id == id) {
-            return false;
+        if self.contains(id) {
+            return Err(GroupError::DuplicateMember(id));
         }
         self.members.push(GroupMember {
             id,
             coord,
             joined_ns: timestamp_ns,
         });
-        true
+        Ok(())
+    }
+
+    /// Add a member. Returns false if group is full or ID already present.
+    #[deprecated(since = "0.2.0", note = "use `try_add_member`")]
+    pub fn add_member(&mut self, id: u32, coord: C, timestamp_ns: u64) -> bool {
+        self.try_add_member(id, coord, timestamp_ns).is_ok()
     }
 
     /// Remove a member by ID. Returns true if found and removed.
@@ -97,6 +179,18 @@ impl PresenceGroup {
         self.members.len() < before
     }
 
+    /// Members in insertion order.
+    #[must_use]
+    pub fn members(&self) -> &[GroupMember<C>] {
+        &self.members
+    }
+
+    /// Group configuration.
+    #[must_use]
+    pub const fn config(&self) -> &GroupConfig {
+        &self.config
+    }
+
     /// Current member count.
     #[must_use]
     pub const fn member_count(&self) -> usize {
@@ -153,43 +247,47 @@ impl PresenceGroup {
 
         let max_distance = self.max_pairwise_distance();
         let all_proximate = max_distance <= self.config.proximity_threshold;
-        let group_id = self.compute_group_id();
+        let member_ids = self.member_ids();
+        let group_id = group_id_of(&member_ids);
 
-        // Content hash
-        let mut buf = [0u8; 33];
+        // Content hash — member count is hashed as a full u64 so it can
+        // never disagree with the `usize` field.
+        let mut buf = [0u8; 40];
         buf[..8].copy_from_slice(&group_id.to_le_bytes());
         buf[8..16].copy_from_slice(&max_distance.to_le_bytes());
         buf[16..24].copy_from_slice(&self.config.proximity_threshold.to_le_bytes());
-        buf[24] = self.members.len() as u8;
-        buf[25..33].copy_from_slice(&(all_proximate as u64).to_le_bytes());
+        buf[24..32].copy_from_slice(&(member_ids.len() as u64).to_le_bytes());
+        buf[32..40].copy_from_slice(&(all_proximate as u64).to_le_bytes());
         let content_hash = fnv1a(&buf);
 
         Some(GroupProximityProof {
             group_id,
-            member_count: self.members.len(),
+            member_count: member_ids.len(),
+            member_ids,
             max_distance,
             threshold: self.config.proximity_threshold,
             all_proximate,
             content_hash,
         })
     }
+}
 
-    /// Compute group ID as hash of sorted member IDs.
-    fn compute_group_id(&self) -> u64 {
-        let ids = self.member_ids();
-        let mut buf = Vec::with_capacity(ids.len() * 4);
-        for id in &ids {
-            buf.extend_from_slice(&id.to_le_bytes());
-        }
-        fnv1a(&buf)
+/// Compute group ID as hash of sorted member IDs.
+pub(crate) fn group_id_of(ids: &[u32]) -> u64 {
+    let mut buf = Vec::with_capacity(ids.len() * 4);
+    for id in ids {
+        buf.extend_from_slice(&id.to_le_bytes());
     }
+    fnv1a(&buf)
 }
 
 // ── Tests ──────────────────────────────────────────────────────────────
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
+    use crate::hyperbolic::HyperbolicCoord;
 
     fn make_coord(x: f64, y: f64) -> VivaldiCoord {
         VivaldiCoord::new(x, y)
@@ -233,6 +331,33 @@ mod tests {
         assert!(!g.add_member(999, make_coord(0.0, 0.0), 0));
     }
 
+    #[test]
+    fn try_with_members_validates() {
+        let member = |id| GroupMember {
+            id,
+            coord: make_coord(0.0, 0.0),
+            joined_ns: 0,
+        };
+        let g = PresenceGroup::try_with_members(GroupConfig::default(), [member(3), member(1)])
+            .unwrap();
+        assert_eq!(g.member_ids(), vec![1, 3]);
+        assert_eq!(
+            PresenceGroup::try_with_members(GroupConfig::default(), [member(1), member(1)])
+                .unwrap_err(),
+            GroupError::DuplicateMember(1)
+        );
+        assert_eq!(
+            PresenceGroup::try_with_members(
+                GroupConfig::default(),
+                (0..=MAX_GROUP_SIZE as u32).map(member)
+            )
+            .unwrap_err(),
+            GroupError::Full {
+                capacity: MAX_GROUP_SIZE
+            }
+        );
+    }
+
     #[test]
     fn remove_nonexistent_returns_false() {
         let mut g = PresenceGroup::new(GroupConfig::default());
@@ -396,4 +521,37 @@ mod tests {
         let proof = g.prove_proximity().unwrap();
         assert!(proof.all_proximate);
     }
+
+    #[test]
+    fn hyperbolic_group_proof() {
+        let mut g: PresenceGroup<HyperbolicCoord> = PresenceGroup::in_space(GroupConfig {
+            proximity_threshold: 2.0,
+            min_members: 2,
+        });
+        g.add_member(1, HyperbolicCoord::new(0.0, 0.0), 0);
+        g.add_member(2, HyperbolicCoord::new(1.0, 0.0), 0);
+        let proof = g.prove_proximity().unwrap();
+        assert!(proof.all_proximate);
+        assert!((proof.max_distance - 1.0_f64.asinh()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn try_add_member_reports_reason() {
+        let mut g = PresenceGroup::new(GroupConfig::default());
+        assert_eq!(g.try_add_member(1, VivaldiCoord::new(0.0, 0.0), 0), Ok(()));
+        assert_eq!(
+            g.try_add_member(1, VivaldiCoord::new(1.0, 0.0), 0),
+            Err(GroupError::DuplicateMember(1))
+        );
+        for id in 2..=MAX_GROUP_SIZE as u32 {
+            g.try_add_member(id, VivaldiCoord::new(0.0, 0.0), 0)
+                .unwrap();
+        }
+        assert_eq!(
+            g.try_add_member(999, VivaldiCoord::new(0.0, 0.0), 0),
+            Err(GroupError::Full {
+                capacity: MAX_GROUP_SIZE
+            })
+        );
+    }
 }
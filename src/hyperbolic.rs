@@ -0,0 +1,261 @@
+//! Hyperbolic network coordinates
+//!
+//! Vivaldi played on the hyperboloid ("Loid") model of the hyperbolic
+//! plane, which embeds Internet-like topologies with a dense core and
+//! long-latency periphery better than a flat plane.
+//! Distance model: c * acosh(sqrt((1+|p|^2)(1+|q|^2)) - p·q) + h1 + h2
+//!
+//! Author: Moroya Sakamoto
+
+use crate::fnv1a;
+use crate::space::{read_finite_f64, CoordinateSpace};
+
+/// Default curvature scale (`c` in the distance model).
+pub const DEFAULT_CURVATURE: f64 = 1.0;
+
+/// Hyperbolic network coordinate (Loid model + height).
+///
+/// All coordinates compared with each other must share the same
+/// `curvature`; distance uses the receiver's value.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HyperbolicCoord {
+    pub x: f64,
+    pub y: f64,
+    /// Error term (always >= 0).
+    pub height: f64,
+    /// Curvature scale (always > 0).
+    pub curvature: f64,
+}
+
+impl HyperbolicCoord {
+    /// Create a coordinate with height = 0 and the default curvature.
+    #[must_use]
+    pub const fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+            height: 0.0,
+            curvature: DEFAULT_CURVATURE,
+        }
+    }
+
+    /// Create a coordinate with an explicit height (error) term.
+    #[must_use]
+    pub fn with_height(x: f64, y: f64, height: f64) -> Self {
+        Self {
+            height: if height < 0.0 { 0.0 } else { height },
+            ..Self::new(x, y)
+        }
+    }
+
+    /// Return a copy using curvature scale `curvature`.
+    /// Non-positive values fall back to [`DEFAULT_CURVATURE`].
+    #[must_use]
+    pub fn with_curvature(self, curvature: f64) -> Self {
+        Self {
+            curvature: if curvature > 0.0 {
+                curvature
+            } else {
+                DEFAULT_CURVATURE
+            },
+            ..self
+        }
+    }
+
+    /// `cosh` of the unscaled hyperbolic distance (always >= 1).
+    fn cosh_dist(&self, other: &Self) -> f64 {
+        let n1 = self.x.mul_add(self.x, self.y * self.y);
+        let n2 = other.x.mul_add(other.x, other.y * other.y);
+        let dot = self.x.mul_add(other.x, self.y * other.y);
+        let a = ((1.0 + n1) * (1.0 + n2)).sqrt() - dot;
+        a.max(1.0)
+    }
+
+    /// Hyperbolic distance plus both height terms.
+    #[must_use]
+    pub fn distance(&self, other: &Self) -> f64 {
+        self.curvature * self.cosh_dist(other).acosh() + self.height + other.height
+    }
+
+    /// Hash the coordinate for privacy-preserving proofs.
+    #[must_use]
+    pub fn hash(&self) -> u64 {
+        let mut buf = [0u8; 32];
+        buf[..8].copy_from_slice(&self.x.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.y.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.height.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.curvature.to_le_bytes());
+        fnv1a(&buf)
+    }
+
+    /// Gradient step toward measured RTT.
+    ///
+    /// The step is `error * grad / |grad|^2`, so to first order the
+    /// predicted distance moves by `cc * error` regardless of how far
+    /// from the origin the coordinate sits.
+    pub fn update(&mut self, other: &Self, rtt: f64, cc: f64) {
+        let error = rtt - self.distance(other);
+        let a = self.cosh_dist(other);
+        let sinh = a.mul_add(a, -1.0).sqrt();
+        if sinh < 1e-12 {
+            // Coincident points: no defined direction, push diagonally
+            self.x += cc * error * 0.5;
+            self.y += cc * error * 0.5;
+        } else {
+            let n1 = self.x.mul_add(self.x, self.y * self.y);
+            let n2 = other.x.mul_add(other.x, other.y * other.y);
+            let ratio = ((1.0 + n2) / (1.0 + n1)).sqrt();
+            let scale = self.curvature / sinh;
+            let gx = scale * self.x.mul_add(ratio, -other.x);
+            let gy = scale * self.y.mul_add(ratio, -other.y);
+            let g2 = gx.mul_add(gx, gy * gy);
+            if g2 < 1e-24 {
+                self.x += cc * error * 0.5;
+                self.y += cc * error * 0.5;
+            } else {
+                self.x += cc * error * gx / g2;
+                self.y += cc * error * gy / g2;
+            }
+        }
+        self.height += cc * (error - self.height);
+        if self.height < 0.0 {
+            self.height = 0.0;
+        }
+    }
+}
+
+impl CoordinateSpace for HyperbolicCoord {
+    const AXES: usize = 2;
+    const ENCODED_LEN: usize = 32;
+
+    fn distance(&self, other: &Self) -> f64 {
+        Self::distance(self, other)
+    }
+
+    fn update(&mut self, other: &Self, rtt: f64, cc: f64) {
+        Self::update(self, other, rtt, cc);
+    }
+
+    fn commit(&self) -> u64 {
+        self.hash()
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.x.to_le_bytes());
+        out.extend_from_slice(&self.y.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+        out.extend_from_slice(&self.curvature.to_le_bytes());
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let x = read_finite_f64(data, 0)?;
+        let y = read_finite_f64(data, 8)?;
+        let height = read_finite_f64(data, 16)?;
+        let curvature = read_finite_f64(data, 24)?;
+        if curvature <= 0.0 {
+            return None;
+        }
+        Some(Self::with_height(x, y, height).with_curvature(curvature))
+    }
+
+    /// `c * asinh(x_i)` is the signed distance to the geodesic `x_i = 0`,
+    /// which is 1-Lipschitz, so axis differences bound the true distance.
+    fn axis(&self, axis: usize) -> f64 {
+        let v = if axis == 0 { self.x } else { self.y };
+        self.curvature * v.asinh()
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_point_zero_distance() {
+        let a = HyperbolicCoord::new(1.0, -2.0);
+        assert!(a.distance(&a).abs() < 1e-6);
+    }
+
+    #[test]
+    fn distance_from_origin_on_axis() {
+        // From the origin, Loid distance along an axis is asinh(|x|)
+        let o = HyperbolicCoord::new(0.0, 0.0);
+        let p = HyperbolicCoord::new(3.0, 0.0);
+        assert!((o.distance(&p) - 3.0_f64.asinh()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn distance_symmetry() {
+        let a = HyperbolicCoord::with_height(0.4, 1.2, 0.5);
+        let b = HyperbolicCoord::with_height(-2.0, 0.3, 1.0);
+        assert!((a.distance(&b) - b.distance(&a)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn heights_add() {
+        let a = HyperbolicCoord::with_height(0.0, 0.0, 1.5);
+        let b = HyperbolicCoord::with_height(0.0, 0.0, 2.5);
+        assert!((a.distance(&b) - 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn curvature_scales_distance() {
+        let a = HyperbolicCoord::new(0.0, 0.0).with_curvature(10.0);
+        let b = HyperbolicCoord::new(1.0, 0.0).with_curvature(10.0);
+        assert!((a.distance(&b) - 10.0 * 1.0_f64.asinh()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn non_positive_curvature_falls_back() {
+        let a = HyperbolicCoord::new(0.0, 0.0).with_curvature(-1.0);
+        assert_eq!(a.curvature, DEFAULT_CURVATURE);
+    }
+
+    #[test]
+    fn triangle_inequality() {
+        let a = HyperbolicCoord::new(0.0, 0.0);
+        let b = HyperbolicCoord::new(2.0, 1.0);
+        let c = HyperbolicCoord::new(-1.0, 3.0);
+        assert!(a.distance(&c) <= a.distance(&b) + b.distance(&c) + 1e-12);
+    }
+
+    #[test]
+    fn update_moves_toward_rtt() {
+        let mut a = HyperbolicCoord::new(0.0, 0.0);
+        let b = HyperbolicCoord::new(2.0, 0.0);
+        let before = (a.distance(&b) - 5.0).abs();
+        a.update(&b, 5.0, 0.1);
+        let after = (a.distance(&b) - 5.0).abs();
+        assert!(after < before);
+    }
+
+    #[test]
+    fn update_converges() {
+        let mut a = HyperbolicCoord::new(0.5, 0.5);
+        let b = HyperbolicCoord::new(3.0, -1.0);
+        for _ in 0..300 {
+            a.update(&b, 1.5, 0.05);
+        }
+        assert!((a.distance(&b) - 1.5).abs() < 0.5);
+    }
+
+    #[test]
+    fn update_same_point() {
+        let mut a = HyperbolicCoord::new(0.0, 0.0);
+        let b = HyperbolicCoord::new(0.0, 0.0);
+        a.update(&b, 5.0, 0.1);
+        assert!(a.x.is_finite());
+        assert!(a.y.is_finite());
+        assert!(a.height >= 0.0);
+    }
+
+    #[test]
+    fn hash_includes_curvature() {
+        let a = HyperbolicCoord::new(1.0, 2.0);
+        let b = a.with_curvature(2.0);
+        assert_ne!(a.hash(), b.hash());
+    }
+}
@@ -13,9 +13,12 @@ use crate::fnv1a;
 ///
 /// `commitment_hash = H(secret_bytes || nonce_bytes)` where H is FNV-1a.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IdentityCommitment {
     /// H(secret || nonce)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub commitment_hash: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub nonce: u64,
     pub timestamp_ns: u64,
 }
@@ -52,12 +55,16 @@ impl IdentityCommitment {
 /// Prover generates `response = H(secret || challenge)`.
 /// Verifier holds the original commitment and checks structural consistency.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ZkProof {
     /// Verifier's challenge.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub challenge: u64,
     /// Prover's response: `H(secret || challenge)`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub response: u64,
     /// Original commitment hash.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub commitment: u64,
     /// Whether the proof checks out.
     pub verified: bool,
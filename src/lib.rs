@@ -23,45 +23,125 @@
 //!
 //! | Module | Description |
 //! |--------|-------------|
+//! | [`batch`] | Columnar, dictionary- and delta-coded `PresenceEvent` archive batches |
+//! | `capi` | C ABI with opaque handles (`capi` feature, header in `include/alice_presence.h`) |
+//! | `cbor` | Canonical CBOR maps with integer keys (`cbor` feature) |
+//! | [`defense`] | Verifier-quorum and triangle-inequality screening of Vivaldi updates |
+//! | [`error`] | `PresenceError` and per-subsystem error types |
 //! | [`event`] | Proximity events, crossing records, presence proofs |
+//! | [`gossip`] | Coordinate announcement wire format and probe/push gossip messages |
+//! | [`geo`] | Geographic (lat/lon) coordinates with haversine/Vincenty distance |
 //! | [`group`] | Group proximity detection and multi-party proofs |
+//! | [`hyperbolic`] | Hyperbolic (Loid model) network coordinates |
 //! | [`identity`] | Identity commitments and ZKP structures |
 //! | [`protocol`] | End-to-end presence protocol execution |
+//! | `python` | `PyO3` `alice_presence` extension module (`python` feature) |
+//! | [`qr`] | Base45 text / QR encoding of encounter offers, responses and crossing records |
+//! | [`radio`] | BLE RSSI / UWB ranging evidence with Kalman smoothing |
 //! | [`session`] | Session FSM (Idle → Discovered → Exchanging → Verified → Closed) |
+//! | [`session_manager`] | Many sessions keyed by session / remote ID, with timeout `tick` and transition events |
+//! | [`space`] | `CoordinateSpace` trait shared by proofs, groups and the k-d tree |
 //! | [`spatial`] | KD-tree spatial index for range queries |
+//! | [`stream`] | CRC32-checked, self-synchronizing stream framing with `Read`/`Write` adapters |
+//! | [`trajectory`] | Coordinate trajectories and dwell-time encounter detection |
+//! | [`view`] | Zero-copy `CrossingRecordRef` / `PresenceEventRef` over serialized bytes |
 //! | [`vivaldi`] | Vivaldi network coordinate system |
+//! | `wasm` | `wasm-bindgen` exports for in-browser record, proximity and group proof verification (`wasm` feature) |
+//!
+//! # Features
+//!
+//! | Feature | Description |
+//! |---------|-------------|
+//! | `serde` | `Serialize`/`Deserialize` for public data and config types; `u64` hashes are hex strings in human-readable formats |
+//! | `capi` | `extern "C"` API for mobile embedding; build the `staticlib` / `cdylib` with `cargo rustc --crate-type` |
+//! | `python` | `PyO3` bindings (`VivaldiCoord`, `PresenceEvent`, `CrossingRecord`, `verify_record`, `KdTree`, serializers); build with `maturin` |
+//! | `wasm` | `wasm-bindgen` exports for `wasm32-unknown-unknown` (decode/verify records and proximity proofs, compute group proofs) |
+//! | `cbor` | Canonical (deterministic) CBOR encoding of events, proofs, crossing records and group proofs |
 //!
 //! # Quick Start
 //!
 //! ```rust
-//! use alice_presence::{VivaldiCoord, PartyInfo, PresenceConfig, execute_presence_protocol};
+//! use alice_presence::{VivaldiCoord, PartyInfo, PresenceConfig, try_execute_presence_protocol};
 //!
 //! let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
 //! let b = PartyInfo::new(VivaldiCoord::new(1.0, 1.0), 99, 2);
 //! let cfg = PresenceConfig::default();
 //!
-//! let record = execute_presence_protocol(&a, &b, 1000, &cfg).unwrap();
+//! let record = try_execute_presence_protocol(&a, &b, 1000, &cfg).unwrap();
 //! assert!(record.is_fully_verified());
 //! ```
 
+pub mod batch;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod defense;
+pub mod error;
 pub mod event;
+pub mod geo;
+pub mod gossip;
 pub mod group;
+pub mod hyperbolic;
 pub mod identity;
 pub mod protocol;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod qr;
+pub mod radio;
 pub mod replay_guard;
 pub mod serialize;
 pub mod session;
+pub mod session_manager;
+pub mod space;
 pub mod spatial;
+pub mod stream;
+pub mod trajectory;
 pub mod verification;
+pub mod view;
 pub mod vivaldi;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-pub use event::{CrossingRecord, CrossingStatus, PresenceEvent, ProximityProof};
+#[cfg(feature = "serde")]
+mod serde_hex;
+
+pub use batch::{encode_event_batch, try_decode_event_batch, try_decode_event_batch_unchecked};
+#[cfg(feature = "cbor")]
+pub use cbor::{cbor_hash, Cbor};
+pub use defense::{DefenseConfig, UpdateGuard, UpdateVerdict, VerifierReport};
+pub use error::{
+    DecodeError, EncodeError, GroupError, PresenceError, ProtocolError, SessionManagerError,
+    TransitionError,
+};
+pub use event::{
+    CrossingRecord, CrossingStatus, DistanceUnit, DwellInterval, EventType, PresenceEvent,
+    ProximityProof, SessionBinding,
+};
+pub use geo::GeoCoord;
+pub use gossip::{CoordAnnouncement, GossipMessage};
 pub use group::{GroupConfig, GroupProximityProof, PresenceGroup};
+pub use hyperbolic::HyperbolicCoord;
 pub use identity::{IdentityCommitment, ZkProof};
-pub use protocol::{execute_presence_protocol, PartyInfo, PresenceConfig};
-pub use session::{CloseReason, Session, SessionConfig, SessionState};
+#[allow(deprecated)]
+pub use protocol::execute_presence_protocol;
+pub use protocol::{
+    exchange_challenges, execute_dwell_protocol, proximity_probability,
+    try_execute_presence_protocol, PartyInfo, PresenceConfig, ProximityMode,
+};
+pub use qr::{EncounterOffer, EncounterResponse};
+pub use radio::{ProximityEvidence, ProximityVerdict, RangingEstimator, RssiEstimator};
+pub use session::{
+    BackoffPolicy, CloseReason, Session, SessionConfig, SessionInput, SessionState,
+    TransitionHistory, TransitionRecord,
+};
+pub use session_manager::{SessionEvent, SessionManager};
+pub use space::CoordinateSpace;
 pub use spatial::{KdTree, SpatialEntry};
-pub use vivaldi::VivaldiCoord;
+pub use stream::{Frame, FrameDecoder, FrameReader, FrameType, FrameWriter, StreamRecord};
+pub use trajectory::{DwellConfig, ProximityInterval, Trajectory};
+pub use view::{CrossingRecordRef, PresenceEventRef};
+pub use vivaldi::{VivaldiCoord, VivaldiNode};
 
 // ── Shared hash primitive ──────────────────────────────────────────────
 
@@ -76,9 +156,27 @@ pub(crate) fn fnv1a(data: &[u8]) -> u64 {
     hash
 }
 
+/// Standard normal CDF Φ(z) (Abramowitz–Stegun 7.1.26, |ε| < 1.5e-7).
+#[must_use]
+pub(crate) fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / core::f64::consts::SQRT_2;
+    let t = 1.0 / 0.327_591_1f64.mul_add(x, 1.0);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
 // ── Integration tests ──────────────────────────────────────────────────
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
 
@@ -93,6 +191,14 @@ mod tests {
         assert_eq!(fnv1a(b"hello"), fnv1a(b"hello"));
     }
 
+    #[test]
+    fn normal_cdf_reference_values() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.0) - 0.841_344_7).abs() < 1e-6);
+        assert!((normal_cdf(-1.959_964) - 0.025).abs() < 1e-6);
+        assert!(normal_cdf(10.0) > 0.999_999);
+    }
+
     #[test]
     fn end_to_end_protocol_with_session() {
         // Full flow: session FSM + protocol execution
@@ -155,4 +261,222 @@ mod tests {
         assert!(proof.all_proximate);
         assert_eq!(proof.member_count, 3);
     }
+
+    #[test]
+    fn end_to_end_result_apis() -> Result<(), PresenceError> {
+        // Every subsystem error converts into PresenceError through `?`
+        let mut sess = Session::new(1, 1000, SessionConfig::default());
+        sess.try_discover(2, 2000)?;
+        sess.try_begin_exchange(3000)?;
+
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(1.0, 1.0), 99, 2);
+        let record = try_execute_presence_protocol(&a, &b, 3000, &PresenceConfig::default())?;
+        let bytes = serialize::serialize_crossing(&record);
+        let restored = serialize::try_deserialize_crossing(&bytes)?;
+        assert_eq!(restored.content_hash, record.content_hash);
+
+        sess.try_verify(4000)?;
+        sess.try_close(CloseReason::Success, 5000)?;
+        assert_eq!(
+            sess.try_verify(6000).map_err(PresenceError::from),
+            Err(PresenceError::Transition(TransitionError::InvalidState {
+                from: SessionState::Closed,
+                input: SessionInput::Verify,
+            }))
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    fn sample_record() -> CrossingRecord {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::with_height(1.0, 1.0, 0.5), 99, 2);
+        try_execute_presence_protocol(&a, &b, 1_000, &PresenceConfig::default())
+            .unwrap()
+            .with_dwell(DwellInterval {
+                start_ns: 1_000,
+                end_ns: 2_000,
+            })
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json_roundtrip_record() {
+        let record = sample_record();
+        let json = serde_json::to_string(&record).unwrap();
+        // Hashes are 16-digit hex strings
+        assert!(json.contains(&format!(
+            "\"content_hash\":\"{:016x}\"",
+            record.content_hash
+        )));
+        let back: CrossingRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.content_hash, record.content_hash);
+        assert_eq!(back.dwell, record.dwell);
+        assert_eq!(
+            verification::verify_record(&back),
+            verification::VerifyResult::Valid
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_json_roundtrip_distance_unit() {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1)
+            .with_geo(GeoCoord::new(35.6812, 139.7671));
+        let b = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 99, 2)
+            .with_geo(GeoCoord::new(35.6813, 139.7671));
+        let config = PresenceConfig {
+            mode: ProximityMode::Geographic,
+            ..PresenceConfig::default()
+        };
+        let record = try_execute_presence_protocol(&a, &b, 1_000, &config).unwrap();
+        let json = serde_json::to_string(&record).unwrap();
+        let back: CrossingRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.proximity.unit, DistanceUnit::Metres);
+        assert_eq!(
+            verification::verify_record(&back),
+            verification::VerifyResult::Valid
+        );
+
+        // Older JSON without `unit` reads as a network proof
+        let legacy = serde_json::to_string(&sample_record())
+            .unwrap()
+            .replace(",\"unit\":\"Network\"", "");
+        assert!(!legacy.contains("unit"));
+        let back: CrossingRecord = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(back.proximity.unit, DistanceUnit::Network);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_validates_events() {
+        let ev = PresenceEvent::heartbeat(1, 2, 3);
+        let json = serde_json::to_string(&ev).unwrap();
+        let back: PresenceEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.to_bytes(), ev.to_bytes());
+        // A heartbeat cannot carry the verified flag
+        let bad = json.replace(r#""flags":4"#, r#""flags":6"#);
+        assert!(serde_json::from_str::<PresenceEvent>(&bad).is_err());
+        let unknown = json.replace(r#""event_type":72"#, r#""event_type":0"#);
+        assert!(serde_json::from_str::<PresenceEvent>(&unknown)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown type tag"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_bincode_roundtrip_record() {
+        let record = sample_record();
+        let bytes = bincode::serialize(&record).unwrap();
+        let back: CrossingRecord = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(
+            serialize::serialize_crossing(&back),
+            serialize::serialize_crossing(&record)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_validates_history_bound() {
+        let mut sess = Session::new(1, 1000, SessionConfig::default()).with_history(2);
+        sess.try_discover(2, 2000).unwrap();
+        sess.try_begin_exchange(3000).unwrap();
+        let history = sess.history.as_ref().unwrap();
+        let json = serde_json::to_string(history).unwrap();
+        let back: TransitionHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(&back, history);
+
+        // A history holding more records than its capacity is rejected
+        let shrunk = json.replace("\"capacity\":2", "\"capacity\":1");
+        assert!(serde_json::from_str::<TransitionHistory>(&shrunk).is_err());
+        let json = serde_json::to_string(&sess).unwrap();
+        let shrunk = json.replace("\"capacity\":2", "\"capacity\":1");
+        assert!(serde_json::from_str::<Session>(&shrunk).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_validates_trajectory_order() {
+        let mut t = Trajectory::new();
+        t.push(50, VivaldiCoord::new(0.0, 0.0));
+        t.push(100, VivaldiCoord::new(1.0, 0.0));
+        let json = serde_json::to_string(&t).unwrap();
+        let back: Trajectory = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.time_span(), Some((50, 100)));
+
+        // Out-of-order and duplicate timestamps are rejected
+        let unsorted = json.replace("100", "40");
+        assert!(serde_json::from_str::<Trajectory>(&unsorted).is_err());
+        let duplicate = json.replace("100", "50");
+        assert!(serde_json::from_str::<Trajectory>(&duplicate).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip_session_group_configs() {
+        let mut sess = Session::new(1, 1000, SessionConfig::default());
+        sess.try_discover(2, 2000).unwrap();
+        let json = serde_json::to_string(&sess).unwrap();
+        let back: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.session_id, sess.session_id);
+        assert_eq!(back.state, SessionState::Discovering);
+        assert_eq!(back.content_hash, sess.content_hash);
+        let back: Session = bincode::deserialize(&bincode::serialize(&sess).unwrap()).unwrap();
+        assert_eq!(back.remote_id, Some(2));
+
+        let mut group = PresenceGroup::new(GroupConfig::default());
+        group
+            .try_add_member(1, VivaldiCoord::new(0.0, 0.0), 0)
+            .unwrap();
+        group
+            .try_add_member(2, VivaldiCoord::new(1.0, 0.0), 0)
+            .unwrap();
+        let proof = group.prove_proximity().unwrap();
+        let back: GroupProximityProof =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(back.group_id, proof.group_id);
+        assert_eq!(back.content_hash, proof.content_hash);
+        let back: PresenceGroup =
+            bincode::deserialize(&bincode::serialize(&group).unwrap()).unwrap();
+        assert_eq!(back.member_ids(), vec![1, 2]);
+        // Duplicate members and overflow are rejected as in try_add_member
+        let json = serde_json::to_string(&group).unwrap();
+        let member =
+            |id| format!(r#"{{"id":{id},"coord":{{"x":0.0,"y":0.0,"height":0.0}},"joined_ns":0}}"#);
+        let dup = json.replacen(
+            r#""members":["#,
+            &format!(r#""members":[{},"#, member(1)),
+            1,
+        );
+        assert!(serde_json::from_str::<PresenceGroup>(&dup)
+            .unwrap_err()
+            .to_string()
+            .contains("already in group"));
+        let many: Vec<String> = (0..=group::MAX_GROUP_SIZE).map(member).collect();
+        let many = many.join(",");
+        let full = format!(
+            r#"{{"members":[{many}],"config":{{"proximity_threshold":10.0,"min_members":2}}}}"#
+        );
+        assert!(serde_json::from_str::<PresenceGroup>(&full)
+            .unwrap_err()
+            .to_string()
+            .contains("full"));
+
+        let cfg = PresenceConfig {
+            mode: ProximityMode::Hybrid,
+            min_probability: Some(0.9),
+            ..Default::default()
+        };
+        let back: PresenceConfig =
+            serde_json::from_str(&serde_json::to_string(&cfg).unwrap()).unwrap();
+        assert_eq!(back.mode, ProximityMode::Hybrid);
+        assert_eq!(back.min_probability, Some(0.9));
+
+        let node = VivaldiNode::with_error(VivaldiCoord::new(3.0, 4.0), 0.2);
+        let back: VivaldiNode =
+            serde_json::from_str(&serde_json::to_string(&node).unwrap()).unwrap();
+        assert_eq!(back, node);
+    }
 }
@@ -5,18 +5,43 @@
 //!
 //! Author: Moroya Sakamoto
 
-use crate::event::{CrossingRecord, PresenceEvent, ProximityProof};
+use crate::error::ProtocolError;
+use crate::event::{CrossingRecord, DwellInterval, PresenceEvent, ProximityProof, SessionBinding};
 use crate::fnv1a;
+use crate::geo::GeoCoord;
 use crate::identity::{IdentityCommitment, ZkProof};
-use crate::vivaldi::VivaldiCoord;
+use crate::trajectory::{detect_encounter, DwellConfig, Trajectory};
+use crate::vivaldi::{VivaldiCoord, VivaldiNode};
 
 // ── Configuration ──────────────────────────────────────────────────────
 
+/// Which proximity evidence the protocol requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProximityMode {
+    /// Vivaldi network distance only.
+    #[default]
+    Network,
+    /// Haversine distance between GPS fixes only.
+    Geographic,
+    /// Both network and geographic proximity, each within its own threshold.
+    Hybrid,
+}
+
 /// Protocol configuration.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PresenceConfig {
     /// Vivaldi distance threshold (default 10.0).
     pub proximity_threshold: f64,
+    /// When set, network proximity additionally requires
+    /// P(true distance <= threshold) to reach this value, and the proof
+    /// records that probability as its `confidence` (default `None`).
+    pub min_probability: Option<f64>,
+    /// Geographic distance threshold in metres (default 50.0).
+    pub geo_threshold_m: f64,
+    /// Proximity evidence to require (default `Network`).
+    pub mode: ProximityMode,
     /// ZKP challenge size in bits (default 64).
     pub challenge_bits: u32,
     /// Both parties must confirm (default true).
@@ -27,6 +52,9 @@ impl Default for PresenceConfig {
     fn default() -> Self {
         Self {
             proximity_threshold: 10.0,
+            min_probability: None,
+            geo_threshold_m: 50.0,
+            mode: ProximityMode::Network,
             challenge_bits: 64,
             require_mutual: true,
         }
@@ -40,55 +68,159 @@ impl Default for PresenceConfig {
 pub struct PartyInfo {
     /// Vivaldi network coordinate.
     pub coord: VivaldiCoord,
+    /// Relative error of `coord` (0.0 = exact, see `VivaldiNode::error`).
+    pub coord_error: f64,
+    /// GPS fix, required for `Geographic` and `Hybrid` modes.
+    pub geo: Option<GeoCoord>,
     /// Secret used for ZKP identity commitment.
     pub secret: u64,
     /// Compact 32-bit party identifier.
     pub id: u32,
+    /// This party's `Session::session_id` for the exchange, if bound.
+    pub session_id: Option<u64>,
 }
 
 impl PartyInfo {
     /// Create a new `PartyInfo`.
     #[must_use]
     pub const fn new(coord: VivaldiCoord, secret: u64, id: u32) -> Self {
-        Self { coord, secret, id }
+        Self {
+            coord,
+            coord_error: 0.0,
+            geo: None,
+            secret,
+            id,
+            session_id: None,
+        }
+    }
+
+    /// Attach the Vivaldi relative error estimate.
+    #[must_use]
+    pub const fn with_coord_error(mut self, error: f64) -> Self {
+        self.coord_error = error;
+        self
+    }
+
+    /// The coordinate and its error as a `VivaldiNode`.
+    #[must_use]
+    pub fn node(&self) -> VivaldiNode {
+        VivaldiNode::with_error(self.coord, self.coord_error)
+    }
+
+    /// Attach a GPS fix.
+    #[must_use]
+    pub const fn with_geo(mut self, geo: GeoCoord) -> Self {
+        self.geo = Some(geo);
+        self
+    }
+
+    /// Bind the exchange to this party's session.
+    ///
+    /// When both parties are bound, the ZKP challenges and the record hash
+    /// cover both session IDs.
+    #[must_use]
+    pub const fn with_session(mut self, session_id: u64) -> Self {
+        self.session_id = Some(session_id);
+        self
     }
 }
 
 // ── Protocol Execution ─────────────────────────────────────────────────
 
-/// Execute the full presence protocol between two parties.
-///
-/// 1. Check proximity via Vivaldi coordinates.
-/// 2. Exchange ZKP identity proofs.
-/// 3. Create minimal `PresenceEvent`.
-/// 4. Build full `CrossingRecord`.
-///
-/// Returns `None` if the parties are not within `config.proximity_threshold`.
+/// P(true Vivaldi distance <= `threshold`) given both parties' errors.
 #[must_use]
-pub fn execute_presence_protocol(
+pub fn proximity_probability(party_a: &PartyInfo, party_b: &PartyInfo, threshold: f64) -> f64 {
+    party_a
+        .node()
+        .proximity_probability(&party_b.node(), threshold)
+}
+
+/// Network proximity proof, honouring `config.min_probability`.
+///
+/// `is_proximate` is always the hard `distance <= threshold` decision;
+/// with `min_probability` set, P(true distance <= threshold) must also
+/// reach the minimum and is recorded as the proof's `confidence`.
+pub(crate) fn network_proof(
     party_a: &PartyInfo,
     party_b: &PartyInfo,
-    timestamp_ns: u64,
     config: &PresenceConfig,
-) -> Option<CrossingRecord> {
-    let proximity =
-        ProximityProof::prove(&party_a.coord, &party_b.coord, config.proximity_threshold);
-    if !proximity.is_proximate {
-        return None;
+) -> Result<ProximityProof, ProtocolError> {
+    let threshold = config.proximity_threshold;
+    let mut proof = ProximityProof::prove(&party_a.coord, &party_b.coord, threshold);
+    if let Some(min) = config.min_probability {
+        let probability = proximity_probability(party_a, party_b, threshold);
+        if probability < min {
+            return Err(ProtocolError::LowConfidence { probability, min });
+        }
+        proof = proof.with_confidence(probability);
+    }
+    if !proof.is_proximate {
+        return Err(ProtocolError::NotProximate {
+            distance: proof.distance,
+            threshold,
+        });
     }
+    Ok(proof)
+}
 
-    let nonce_a = fnv1a(&party_a.id.to_le_bytes());
-    let nonce_b = fnv1a(&party_b.id.to_le_bytes());
-    let commitment_a = IdentityCommitment::new(party_a.secret, nonce_a, timestamp_ns);
-    let commitment_b = IdentityCommitment::new(party_b.secret, nonce_b, timestamp_ns);
+/// ZKP challenges `(challenge_a, challenge_b)` for an exchange at
+/// `timestamp_ns`, additionally derived from both session IDs when bound.
+///
+/// `verify_record` expects exactly these challenges, so records assembled
+/// by hand with `CrossingRecord::new` must prove against them.
+#[must_use]
+pub fn exchange_challenges(timestamp_ns: u64, session: Option<&SessionBinding>) -> (u64, u64) {
+    let h = match session {
+        None => fnv1a(&timestamp_ns.to_le_bytes()),
+        Some(s) => {
+            let mut buf = [0u8; 24];
+            buf[..8].copy_from_slice(&timestamp_ns.to_le_bytes());
+            buf[8..16].copy_from_slice(&s.session_a.to_le_bytes());
+            buf[16..].copy_from_slice(&s.session_b.to_le_bytes());
+            fnv1a(&buf)
+        }
+    };
+    (h ^ 0xAAAA_AAAA_AAAA_AAAA, h ^ 0x5555_5555_5555_5555)
+}
+
+/// Session binding of an exchange: both parties bound, or neither.
+pub(crate) const fn session_binding(
+    session_a: Option<u64>,
+    session_b: Option<u64>,
+) -> Result<Option<SessionBinding>, ProtocolError> {
+    match (session_a, session_b) {
+        (Some(a), Some(b)) => Ok(Some(SessionBinding::new(a, b))),
+        (None, None) => Ok(None),
+        _ => Err(ProtocolError::MissingSessionId),
+    }
+}
 
-    let challenge_a = fnv1a(&timestamp_ns.to_le_bytes()) ^ 0xAAAA_AAAA_AAAA_AAAA;
-    let challenge_b = fnv1a(&timestamp_ns.to_le_bytes()) ^ 0x5555_5555_5555_5555;
+/// Commitment of `party` for an exchange at `timestamp_ns`.
+pub(crate) fn party_commitment(party: &PartyInfo, timestamp_ns: u64) -> IdentityCommitment {
+    let nonce = fnv1a(&party.id.to_le_bytes());
+    IdentityCommitment::new(party.secret, nonce, timestamp_ns)
+}
 
-    let proof_a = ZkProof::prove(party_a.secret, &commitment_a, challenge_a);
-    let proof_b = ZkProof::prove(party_b.secret, &commitment_b, challenge_b);
+/// One party's half of the ZKP exchange.
+pub(crate) fn party_proof(party: &PartyInfo, timestamp_ns: u64, challenge: u64) -> ZkProof {
+    ZkProof::prove(
+        party.secret,
+        &party_commitment(party, timestamp_ns),
+        challenge,
+    )
+}
 
-    let mut event = PresenceEvent::new(party_a.id, party_b.id, timestamp_ns);
+/// Event and record assembly from both halves of the exchange.
+pub(crate) fn assemble_record(
+    party_a_id: u32,
+    party_b_id: u32,
+    timestamp_ns: u64,
+    config: &PresenceConfig,
+    proximity: ProximityProof,
+    proof_a: ZkProof,
+    proof_b: ZkProof,
+) -> CrossingRecord {
+    let mut event = PresenceEvent::new(party_a_id, party_b_id, timestamp_ns);
     if config.require_mutual {
         event.set_mutual();
     }
@@ -99,14 +231,175 @@ pub fn execute_presence_protocol(
         event.set_proximate();
     }
 
-    Some(CrossingRecord::new(event, proof_a, proof_b, proximity))
+    CrossingRecord::new(event, proof_a, proof_b, proximity)
+}
+
+/// ZKP exchange and record assembly shared by all protocol entry points.
+fn build_record(
+    party_a: &PartyInfo,
+    party_b: &PartyInfo,
+    timestamp_ns: u64,
+    config: &PresenceConfig,
+    proximity: ProximityProof,
+    session: Option<SessionBinding>,
+) -> CrossingRecord {
+    let (challenge_a, challenge_b) = exchange_challenges(timestamp_ns, session.as_ref());
+    let proof_a = party_proof(party_a, timestamp_ns, challenge_a);
+    let proof_b = party_proof(party_b, timestamp_ns, challenge_b);
+    let record = assemble_record(
+        party_a.id,
+        party_b.id,
+        timestamp_ns,
+        config,
+        proximity,
+        proof_a,
+        proof_b,
+    );
+    match session {
+        Some(session) => record.with_session(session),
+        None => record,
+    }
+}
+
+/// Execute the full presence protocol between two parties.
+///
+/// 1. Check proximity (Vivaldi, GPS or both, per `config.mode`).
+/// 2. Exchange ZKP identity proofs.
+/// 3. Create minimal `PresenceEvent`.
+/// 4. Build full `CrossingRecord`.
+///
+/// In `Geographic` mode the record's `proximity` is the GPS proof, marked
+/// `DistanceUnit::Metres`; in `Hybrid` mode it is the Vivaldi proof and the
+/// GPS proof is stored in `geo_proximity`.
+///
+/// If both parties carry a session ID (`PartyInfo::with_session`), the
+/// challenges are derived from both IDs and the record stores them in
+/// `CrossingRecord::session`.
+///
+/// # Errors
+///
+/// Returns the `ProtocolError` describing which check failed: network or
+/// geographic distance, insufficient confidence (`min_probability`), a
+/// missing GPS fix in a geographic mode, or only one party bound to a
+/// session.
+pub fn try_execute_presence_protocol(
+    party_a: &PartyInfo,
+    party_b: &PartyInfo,
+    timestamp_ns: u64,
+    config: &PresenceConfig,
+) -> Result<CrossingRecord, ProtocolError> {
+    let session = session_binding(party_a.session_id, party_b.session_id)?;
+    let geo_proof = match config.mode {
+        ProximityMode::Network => None,
+        ProximityMode::Geographic | ProximityMode::Hybrid => {
+            let (Some(geo_a), Some(geo_b)) = (party_a.geo, party_b.geo) else {
+                return Err(ProtocolError::MissingGeoFix);
+            };
+            let geo = ProximityProof::prove(&geo_a, &geo_b, config.geo_threshold_m);
+            if !geo.is_proximate {
+                return Err(ProtocolError::GeoNotProximate {
+                    distance_m: geo.distance,
+                    threshold_m: config.geo_threshold_m,
+                });
+            }
+            Some(geo)
+        }
+    };
+    let proximity = match (config.mode, geo_proof) {
+        (ProximityMode::Geographic, Some(geo)) => geo,
+        _ => network_proof(party_a, party_b, config)?,
+    };
+
+    let record = build_record(party_a, party_b, timestamp_ns, config, proximity, session);
+    Ok(match (config.mode, geo_proof) {
+        (ProximityMode::Hybrid, Some(geo)) => record.with_geo_proximity(geo),
+        _ => record,
+    })
+}
+
+/// Execute the full presence protocol between two parties.
+///
+/// Returns `None` if the parties are not within the required threshold(s),
+/// or if a geographic mode is selected and either party lacks a GPS fix.
+#[deprecated(since = "0.2.0", note = "use `try_execute_presence_protocol`")]
+#[must_use]
+pub fn execute_presence_protocol(
+    party_a: &PartyInfo,
+    party_b: &PartyInfo,
+    timestamp_ns: u64,
+    config: &PresenceConfig,
+) -> Option<CrossingRecord> {
+    try_execute_presence_protocol(party_a, party_b, timestamp_ns, config).ok()
+}
+
+/// Execute the presence protocol for a dwell-time encounter.
+///
+/// Finds the longest continuous interval during which the two trajectories
+/// stay within `config.proximity_threshold` and, if it lasts at least
+/// `dwell.min_dwell_ns`, builds a record whose proximity proof uses the
+/// positions at the closest approach. The event timestamp is the interval
+/// start and the interval itself is recorded in `CrossingRecord::dwell`.
+///
+/// Only Vivaldi trajectories are supported; `config.mode` and the parties'
+/// static coordinates are ignored.
+///
+/// # Errors
+///
+/// `ProtocolError::NoEncounter` if no interval lasts long enough,
+/// `ProtocolError::MissingSessionId` if only one party is bound to a
+/// session.
+pub fn execute_dwell_protocol(
+    party_a: &PartyInfo,
+    party_b: &PartyInfo,
+    trajectory_a: &Trajectory,
+    trajectory_b: &Trajectory,
+    config: &PresenceConfig,
+    dwell: &DwellConfig,
+) -> Result<CrossingRecord, ProtocolError> {
+    let session = session_binding(party_a.session_id, party_b.session_id)?;
+    let interval = detect_encounter(
+        trajectory_a,
+        trajectory_b,
+        config.proximity_threshold,
+        dwell,
+    )
+    .ok_or(ProtocolError::NoEncounter)?;
+    let (Some(coord_a), Some(coord_b)) = (
+        trajectory_a.position_at(interval.closest_ns),
+        trajectory_b.position_at(interval.closest_ns),
+    ) else {
+        return Err(ProtocolError::NoEncounter);
+    };
+    // `<=` as in `ProximityProof::prove`: a closest approach exactly at the
+    // threshold is proximate
+    let proximity = ProximityProof::from_parts(
+        interval.min_distance,
+        config.proximity_threshold,
+        interval.min_distance <= config.proximity_threshold,
+        coord_a.hash(),
+        coord_b.hash(),
+    );
+    let record = build_record(
+        party_a,
+        party_b,
+        interval.start_ns,
+        config,
+        proximity,
+        session,
+    );
+    Ok(record.with_dwell(DwellInterval {
+        start_ns: interval.start_ns,
+        end_ns: interval.end_ns,
+    }))
 }
 
 // ── Tests ──────────────────────────────────────────────────────────────
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
+    use crate::event::DistanceUnit;
 
     #[test]
     fn config_defaults() {
@@ -208,4 +501,242 @@ mod tests {
         };
         assert!(execute_presence_protocol(&a, &b, 0, &cfg_tight).is_none());
     }
+
+    fn geo_parties(lat_b: f64, x_b: f64) -> (PartyInfo, PartyInfo) {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1)
+            .with_geo(GeoCoord::new(35.6812, 139.7671));
+        let b = PartyInfo::new(VivaldiCoord::new(x_b, 0.0), 99, 2)
+            .with_geo(GeoCoord::new(lat_b, 139.7671));
+        (a, b)
+    }
+
+    #[test]
+    fn protocol_geographic_mode() {
+        // 0.0002° of latitude ≈ 22 m; the Vivaldi distance is irrelevant
+        let (a, b) = geo_parties(35.6814, 1000.0);
+        let cfg = PresenceConfig {
+            mode: ProximityMode::Geographic,
+            ..Default::default()
+        };
+        let record = execute_presence_protocol(&a, &b, 0, &cfg).unwrap();
+        assert!(record.is_fully_verified());
+        assert!((record.proximity.distance - 22.2).abs() < 1.0);
+        assert_eq!(record.proximity.unit, DistanceUnit::Metres);
+        assert!(record.geo_proximity.is_none());
+
+        let (a, b) = geo_parties(35.6830, 0.0); // ≈ 200 m
+        assert!(execute_presence_protocol(&a, &b, 0, &cfg).is_none());
+    }
+
+    #[test]
+    fn protocol_geographic_requires_fix() {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 99, 2);
+        let cfg = PresenceConfig {
+            mode: ProximityMode::Hybrid,
+            ..Default::default()
+        };
+        assert!(execute_presence_protocol(&a, &b, 0, &cfg).is_none());
+    }
+
+    #[test]
+    fn protocol_hybrid_requires_both() {
+        let cfg = PresenceConfig {
+            mode: ProximityMode::Hybrid,
+            ..Default::default()
+        };
+        // Both proximate
+        let (a, b) = geo_parties(35.6814, 1.0);
+        let record = execute_presence_protocol(&a, &b, 0, &cfg).unwrap();
+        assert!((record.proximity.distance - 1.0).abs() < 1e-12);
+        assert_eq!(record.proximity.unit, DistanceUnit::Network);
+        let geo = record.geo_proximity.unwrap();
+        assert!(geo.is_proximate);
+        assert_eq!(geo.unit, DistanceUnit::Metres);
+        assert!(record.is_fully_verified());
+
+        // Close by GPS but far in the network
+        let (a, b) = geo_parties(35.6814, 1000.0);
+        assert!(execute_presence_protocol(&a, &b, 0, &cfg).is_none());
+
+        // Close in the network but far by GPS
+        let (a, b) = geo_parties(35.7000, 1.0);
+        assert!(execute_presence_protocol(&a, &b, 0, &cfg).is_none());
+    }
+
+    #[test]
+    fn protocol_min_probability() {
+        // Predicted distance 8.0 < 10.0, but the error is large
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1).with_coord_error(0.3);
+        let b = PartyInfo::new(VivaldiCoord::new(8.0, 0.0), 99, 2).with_coord_error(0.3);
+        let p = proximity_probability(&a, &b, 10.0);
+        assert!(p > 0.7 && p < 0.9, "p = {p}");
+
+        assert!(execute_presence_protocol(&a, &b, 0, &PresenceConfig::default()).is_some());
+        let strict = PresenceConfig {
+            min_probability: Some(0.95),
+            ..Default::default()
+        };
+        assert!(execute_presence_protocol(&a, &b, 0, &strict).is_none());
+        let lenient = PresenceConfig {
+            min_probability: Some(0.5),
+            ..Default::default()
+        };
+        let record = execute_presence_protocol(&a, &b, 0, &lenient).unwrap();
+        assert!(crate::verification::verify_proximity(&record.proximity));
+    }
+
+    #[test]
+    fn protocol_min_probability_exact_coords() {
+        // With zero error this matches the hard decision
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(10.0, 0.0), 99, 2);
+        let cfg = PresenceConfig {
+            min_probability: Some(0.99),
+            ..Default::default()
+        };
+        let r1 = execute_presence_protocol(&a, &b, 0, &cfg).unwrap();
+        let r2 = execute_presence_protocol(&a, &b, 0, &PresenceConfig::default()).unwrap();
+        assert_eq!(r1.proximity.confidence, Some(1.0));
+        assert_eq!(r2.proximity.confidence, None);
+        let hard = ProximityProof {
+            confidence: None,
+            ..r1.proximity
+        };
+        assert_eq!(hard.compute_hash(), r2.proximity.content_hash);
+    }
+
+    #[test]
+    fn protocol_min_probability_keeps_hard_verdict() {
+        // Predicted distance 11.0 > 10.0, but the large error gives P ≈ 0.4
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1).with_coord_error(0.5);
+        let b = PartyInfo::new(VivaldiCoord::new(11.0, 0.0), 99, 2).with_coord_error(0.5);
+        let p = proximity_probability(&a, &b, 10.0);
+        assert!(p > 0.2 && p < 0.5, "p = {p}");
+        let cfg = PresenceConfig {
+            min_probability: Some(0.2),
+            ..Default::default()
+        };
+        assert!(matches!(
+            try_execute_presence_protocol(&a, &b, 0, &cfg),
+            Err(ProtocolError::NotProximate { .. })
+        ));
+
+        // An accepted proof carries both the probability and the hard verdict
+        let b = PartyInfo::new(VivaldiCoord::new(8.0, 0.0), 99, 2).with_coord_error(0.5);
+        let record = try_execute_presence_protocol(&a, &b, 0, &cfg).unwrap();
+        let prox = record.proximity;
+        assert!(prox.is_proximate && prox.distance <= prox.threshold);
+        assert_eq!(prox.confidence, Some(proximity_probability(&a, &b, 10.0)));
+    }
+
+    #[test]
+    fn dwell_protocol_records_interval() {
+        const MIN: u64 = 60_000_000_000;
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 99, 2);
+        let mut ta = Trajectory::new();
+        let mut tb = Trajectory::new();
+        // b stays near a only between minutes 2 and 12
+        for i in 0..=14 {
+            let t = i * MIN;
+            let x = if (2..=12).contains(&i) { 3.0 } else { 50.0 };
+            ta.push(t, VivaldiCoord::new(0.0, 0.0));
+            tb.push(t, VivaldiCoord::new(x, 0.0));
+        }
+        let cfg = PresenceConfig::default();
+        let dwell = DwellConfig {
+            min_dwell_ns: 5 * MIN,
+            max_gap_ns: 2 * MIN,
+        };
+        let record = execute_dwell_protocol(&a, &b, &ta, &tb, &cfg, &dwell).unwrap();
+        let iv = record.dwell.unwrap();
+        assert!(iv.start_ns > MIN && iv.start_ns < 2 * MIN);
+        assert!(iv.end_ns > 12 * MIN && iv.end_ns < 13 * MIN);
+        assert_eq!(record.event.timestamp_ns, iv.start_ns);
+        assert!(record.event.has_dwell());
+        assert!(record.is_fully_verified());
+        assert_eq!(
+            crate::verification::verify_record(&record),
+            crate::verification::VerifyResult::Valid
+        );
+
+        let long = DwellConfig {
+            min_dwell_ns: 15 * MIN,
+            ..dwell
+        };
+        assert!(matches!(
+            execute_dwell_protocol(&a, &b, &ta, &tb, &cfg, &long),
+            Err(ProtocolError::NoEncounter)
+        ));
+    }
+
+    #[test]
+    fn try_protocol_reports_reason() {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let far = PartyInfo::new(VivaldiCoord::new(100.0, 0.0), 99, 2);
+        let cfg = PresenceConfig::default();
+        assert!(matches!(
+            try_execute_presence_protocol(&a, &far, 0, &cfg),
+            Err(ProtocolError::NotProximate { distance, threshold })
+                if (distance - 100.0).abs() < 1e-9 && threshold == cfg.proximity_threshold
+        ));
+
+        let geo = PresenceConfig {
+            mode: ProximityMode::Geographic,
+            ..Default::default()
+        };
+        assert!(matches!(
+            try_execute_presence_protocol(&a, &far, 0, &geo),
+            Err(ProtocolError::MissingGeoFix)
+        ));
+        let (ga, gb) = geo_parties(1.0, 0.0);
+        assert!(matches!(
+            try_execute_presence_protocol(&ga, &gb, 0, &geo),
+            Err(ProtocolError::GeoNotProximate { .. })
+        ));
+
+        let near = PartyInfo::new(VivaldiCoord::new(8.0, 0.0), 99, 2).with_coord_error(0.5);
+        let strict = PresenceConfig {
+            min_probability: Some(0.99),
+            ..Default::default()
+        };
+        assert!(matches!(
+            try_execute_presence_protocol(&a, &near, 0, &strict),
+            Err(ProtocolError::LowConfidence { min, .. }) if min == 0.99
+        ));
+    }
+
+    #[test]
+    fn deprecated_wrapper_matches_try() {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(1.0, 1.0), 99, 2);
+        let cfg = PresenceConfig::default();
+        let old = execute_presence_protocol(&a, &b, 7, &cfg).unwrap();
+        let new = try_execute_presence_protocol(&a, &b, 7, &cfg).unwrap();
+        assert_eq!(old.content_hash, new.content_hash);
+    }
+
+    #[test]
+    fn session_ids_bind_challenges_and_record() {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(1.0, 1.0), 99, 2);
+        let cfg = PresenceConfig::default();
+        let unbound = try_execute_presence_protocol(&a, &b, 7, &cfg).unwrap();
+        assert!(unbound.session.is_none());
+
+        let bound =
+            try_execute_presence_protocol(&a.with_session(10), &b.with_session(20), 7, &cfg)
+                .unwrap();
+        assert_eq!(bound.session, Some(SessionBinding::new(10, 20)));
+        assert_ne!(bound.proof_a.challenge, unbound.proof_a.challenge);
+        assert_ne!(bound.proof_b.response, unbound.proof_b.response);
+        assert_ne!(bound.content_hash, unbound.content_hash);
+        assert!(bound.is_fully_verified());
+
+        assert!(matches!(
+            try_execute_presence_protocol(&a.with_session(10), &b, 7, &cfg),
+            Err(ProtocolError::MissingSessionId)
+        ));
+    }
 }
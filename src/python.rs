@@ -0,0 +1,496 @@
+//! Python バインディング — 分析パイプライン向け (`python` feature)。
+//!
+//! `PyO3` で `alice_presence` 拡張モジュールを公開する。`maturin` でビルドする:
+//!
+//! ```text
+//! maturin develop --features python
+//! pytest python/tests
+//! ```
+//!
+//! | Python | Rust |
+//! |--------|------|
+//! | `VivaldiCoord` | [`VivaldiCoord`] |
+//! | `Party` | [`PartyInfo`] (秘密は Python 側から読めない) |
+//! | `PresenceEvent` | [`PresenceEvent`] |
+//! | `CrossingRecord` | [`CrossingRecord`] |
+//! | `VerifyResult` | [`VerifyResult`] |
+//! | `KdTree` | [`KdTree`] |
+//!
+//! デコード・プロトコルのエラーは `ValueError` として送出する。
+
+use std::fmt::Display;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::batch::{encode_event_batch, try_decode_event_batch};
+use crate::event::{CrossingRecord, PresenceEvent};
+use crate::protocol::{try_execute_presence_protocol, PartyInfo, PresenceConfig};
+use crate::serialize::{serialize_crossing, try_deserialize_crossing};
+use crate::spatial::{KdTree, SpatialEntry};
+use crate::verification::{verify_record, VerifyResult};
+use crate::vivaldi::VivaldiCoord;
+
+fn value_error(e: impl Display) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+// ============================================================================
+// VivaldiCoord / Party
+// ============================================================================
+
+/// Vivaldi 座標 (2D + height)。
+#[pyclass(
+    name = "VivaldiCoord",
+    module = "alice_presence",
+    frozen,
+    skip_from_py_object
+)]
+#[derive(Debug, Clone, Copy)]
+pub struct PyVivaldiCoord(VivaldiCoord);
+
+#[pymethods]
+impl PyVivaldiCoord {
+    #[new]
+    #[pyo3(signature = (x, y, height = 0.0))]
+    fn new(x: f64, y: f64, height: f64) -> Self {
+        Self(VivaldiCoord::with_height(x, y, height))
+    }
+
+    #[getter]
+    fn x(&self) -> f64 {
+        self.0.x
+    }
+
+    #[getter]
+    fn y(&self) -> f64 {
+        self.0.y
+    }
+
+    #[getter]
+    fn height(&self) -> f64 {
+        self.0.height
+    }
+
+    /// 他の座標との Vivaldi 距離。
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.distance(&other.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "VivaldiCoord(x={}, y={}, height={})",
+            self.0.x, self.0.y, self.0.height
+        )
+    }
+}
+
+/// プロトコル参加者 (座標・秘密・ID)。
+#[pyclass(name = "Party", module = "alice_presence", frozen, skip_from_py_object)]
+#[derive(Debug, Clone, Copy)]
+pub struct PyParty(PartyInfo);
+
+#[pymethods]
+impl PyParty {
+    #[new]
+    #[pyo3(signature = (coord, secret, id, coord_error = 0.0))]
+    fn new(coord: &PyVivaldiCoord, secret: u64, id: u32, coord_error: f64) -> Self {
+        Self(PartyInfo::new(coord.0, secret, id).with_coord_error(coord_error))
+    }
+
+    #[getter]
+    fn id(&self) -> u32 {
+        self.0.id
+    }
+
+    #[getter]
+    fn coord(&self) -> PyVivaldiCoord {
+        PyVivaldiCoord(self.0.coord)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Party(id={})", self.0.id)
+    }
+}
+
+// ============================================================================
+// PresenceEvent
+// ============================================================================
+
+/// 18 バイトのプレゼンスイベント。
+#[pyclass(
+    name = "PresenceEvent",
+    module = "alice_presence",
+    frozen,
+    skip_from_py_object
+)]
+#[derive(Debug, Clone)]
+pub struct PyPresenceEvent(PresenceEvent);
+
+#[pymethods]
+impl PyPresenceEvent {
+    #[new]
+    fn new(party_a_id: u32, party_b_id: u32, timestamp_ns: u64) -> Self {
+        Self(PresenceEvent::new(party_a_id, party_b_id, timestamp_ns))
+    }
+
+    /// 18 バイトから復元 (type / flags を検証)。
+    ///
+    /// # Errors
+    ///
+    /// 長さが 18 でない、または未登録の type / 許可されない flags なら `ValueError`。
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        let bytes: &[u8; 18] = data
+            .try_into()
+            .map_err(|_| value_error(format!("expected 18 bytes, got {}", data.len())))?;
+        PresenceEvent::try_from_bytes(bytes)
+            .map(Self)
+            .map_err(value_error)
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.to_bytes())
+    }
+
+    #[getter]
+    fn event_type(&self) -> u8 {
+        self.0.event_type
+    }
+
+    #[getter]
+    fn flags(&self) -> u8 {
+        self.0.flags
+    }
+
+    #[getter]
+    fn party_a_id(&self) -> u32 {
+        self.0.party_a_id
+    }
+
+    #[getter]
+    fn party_b_id(&self) -> u32 {
+        self.0.party_b_id
+    }
+
+    #[getter]
+    fn timestamp_ns(&self) -> u64 {
+        self.0.timestamp_ns
+    }
+
+    #[getter]
+    fn is_mutual(&self) -> bool {
+        self.0.is_mutual()
+    }
+
+    #[getter]
+    fn is_verified(&self) -> bool {
+        self.0.is_verified()
+    }
+
+    #[getter]
+    fn is_proximate(&self) -> bool {
+        self.0.is_proximate()
+    }
+
+    #[getter]
+    fn has_dwell(&self) -> bool {
+        self.0.has_dwell()
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0.to_bytes() == other.0.to_bytes()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PresenceEvent(type=0x{:02x}, flags=0x{:02x}, a={}, b={}, ts={})",
+            self.0.event_type,
+            self.0.flags,
+            self.0.party_a_id,
+            self.0.party_b_id,
+            self.0.timestamp_ns
+        )
+    }
+}
+
+// ============================================================================
+// CrossingRecord / VerifyResult
+// ============================================================================
+
+/// [`VerifyResult`] の Python 表現。
+#[pyclass(
+    name = "VerifyResult",
+    module = "alice_presence",
+    eq,
+    eq_int,
+    frozen,
+    skip_from_py_object
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PyVerifyResult {
+    /// content hash が一致。
+    Valid,
+    /// content hash 不一致 (改ざん疑い)。
+    HashMismatch,
+    /// ZKP 未検証。
+    ZkpNotVerified,
+    /// 近接未確認。
+    NotProximate,
+    /// セッション束縛の不一致。
+    SessionMismatch,
+}
+
+impl From<VerifyResult> for PyVerifyResult {
+    fn from(r: VerifyResult) -> Self {
+        match r {
+            VerifyResult::Valid => Self::Valid,
+            VerifyResult::HashMismatch => Self::HashMismatch,
+            VerifyResult::ZkpNotVerified => Self::ZkpNotVerified,
+            VerifyResult::NotProximate => Self::NotProximate,
+            VerifyResult::SessionMismatch => Self::SessionMismatch,
+        }
+    }
+}
+
+/// 近接エンカウンタの記録。
+#[pyclass(
+    name = "CrossingRecord",
+    module = "alice_presence",
+    frozen,
+    skip_from_py_object
+)]
+#[derive(Debug, Clone)]
+pub struct PyCrossingRecord(CrossingRecord);
+
+#[pymethods]
+impl PyCrossingRecord {
+    /// シリアライズ済みバイト列から復元。
+    ///
+    /// # Errors
+    ///
+    /// バイト列が不正なら `ValueError`。
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        deserialize_crossing(data)
+    }
+
+    fn to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &serialize_crossing(&self.0))
+    }
+
+    /// content hash と ZKP / 近接フラグを検証。
+    fn verify(&self) -> PyVerifyResult {
+        verify_record(&self.0).into()
+    }
+
+    #[getter]
+    fn event(&self) -> PyPresenceEvent {
+        PyPresenceEvent(self.0.event)
+    }
+
+    /// 近接証明の距離。単位は `distance_unit`。
+    #[getter]
+    fn distance(&self) -> f64 {
+        self.0.proximity.distance
+    }
+
+    /// `distance` / `threshold` の単位 (`"network"` または `"metres"`)。
+    #[getter]
+    fn distance_unit(&self) -> &'static str {
+        self.0.proximity.unit.as_str()
+    }
+
+    #[getter]
+    fn threshold(&self) -> f64 {
+        self.0.proximity.threshold
+    }
+
+    #[getter]
+    fn is_proximate(&self) -> bool {
+        self.0.proximity.is_proximate
+    }
+
+    /// 近接判定の確率 P(真の距離 <= threshold)。確率的判定でなければ `None`。
+    #[getter]
+    fn confidence(&self) -> Option<f64> {
+        self.0.proximity.confidence
+    }
+
+    /// 地理的近接証明の距離 (メートル)。なければ `None`。
+    #[getter]
+    fn geo_distance_m(&self) -> Option<f64> {
+        self.0.geo_proximity.map(|p| p.distance)
+    }
+
+    /// 滞在区間 `(start_ns, end_ns)`。なければ `None`。
+    #[getter]
+    fn dwell(&self) -> Option<(u64, u64)> {
+        self.0.dwell.map(|d| (d.start_ns, d.end_ns))
+    }
+
+    #[getter]
+    fn content_hash(&self) -> u64 {
+        self.0.content_hash
+    }
+
+    #[getter]
+    fn is_fully_verified(&self) -> bool {
+        self.0.is_fully_verified()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "CrossingRecord(a={}, b={}, ts={}, distance={}, content_hash=0x{:016x})",
+            self.0.event.party_a_id,
+            self.0.event.party_b_id,
+            self.0.event.timestamp_ns,
+            self.0.proximity.distance,
+            self.0.content_hash
+        )
+    }
+}
+
+// ============================================================================
+// KdTree
+// ============================================================================
+
+/// Vivaldi 座標の k-d tree。
+#[pyclass(
+    name = "KdTree",
+    module = "alice_presence",
+    frozen,
+    skip_from_py_object
+)]
+#[derive(Debug)]
+pub struct PyKdTree {
+    tree: KdTree,
+    len: usize,
+}
+
+#[pymethods]
+impl PyKdTree {
+    /// `(id, VivaldiCoord)` の列から構築。
+    #[new]
+    fn new(entries: Vec<(u32, PyRef<'_, PyVivaldiCoord>)>) -> Self {
+        let entries: Vec<SpatialEntry> = entries
+            .into_iter()
+            .map(|(id, coord)| SpatialEntry { id, coord: coord.0 })
+            .collect();
+        Self {
+            tree: KdTree::build(&entries),
+            len: entries.len(),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.len
+    }
+
+    /// 最近傍の `(id, distance)`。空なら `None`。
+    fn nearest(&self, query: &PyVivaldiCoord) -> Option<(u32, f64)> {
+        self.tree.nearest(&query.0)
+    }
+
+    /// 半径 `radius` 以内の `(id, distance)`。
+    fn range_query(&self, query: &PyVivaldiCoord, radius: f64) -> Vec<(u32, f64)> {
+        self.tree.range_query(&query.0, radius)
+    }
+
+    /// 近い順に最大 `k` 件の `(id, distance)`。
+    fn k_nearest(&self, query: &PyVivaldiCoord, k: usize) -> Vec<(u32, f64)> {
+        self.tree.k_nearest(&query.0, k)
+    }
+}
+
+// ============================================================================
+// Functions
+// ============================================================================
+
+/// プレゼンスプロトコルを実行。
+///
+/// # Errors
+///
+/// 近接条件を満たさなければ `ValueError`。
+#[pyfunction]
+#[pyo3(signature = (a, b, timestamp_ns, proximity_threshold = 10.0))]
+fn execute_presence_protocol(
+    a: &PyParty,
+    b: &PyParty,
+    timestamp_ns: u64,
+    proximity_threshold: f64,
+) -> PyResult<PyCrossingRecord> {
+    let config = PresenceConfig {
+        proximity_threshold,
+        ..PresenceConfig::default()
+    };
+    try_execute_presence_protocol(&a.0, &b.0, timestamp_ns, &config)
+        .map(PyCrossingRecord)
+        .map_err(value_error)
+}
+
+/// `CrossingRecord` を検証。
+#[pyfunction(name = "verify_record")]
+fn py_verify_record(record: &PyCrossingRecord) -> PyVerifyResult {
+    record.verify()
+}
+
+/// `CrossingRecord` をバイト列にシリアライズ。
+#[pyfunction(name = "serialize_crossing")]
+fn py_serialize_crossing<'py>(py: Python<'py>, record: &PyCrossingRecord) -> Bound<'py, PyBytes> {
+    record.to_bytes(py)
+}
+
+/// バイト列から `CrossingRecord` を復元。
+///
+/// # Errors
+///
+/// バイト列が不正なら `ValueError`。
+#[pyfunction]
+fn deserialize_crossing(data: &[u8]) -> PyResult<PyCrossingRecord> {
+    try_deserialize_crossing(data)
+        .map(PyCrossingRecord)
+        .map_err(value_error)
+}
+
+/// イベント列を列指向バッチにエンコード。
+#[pyfunction(name = "encode_event_batch")]
+fn py_encode_event_batch<'py>(
+    py: Python<'py>,
+    events: Vec<PyRef<'_, PyPresenceEvent>>,
+) -> Bound<'py, PyBytes> {
+    let events: Vec<PresenceEvent> = events.into_iter().map(|e| e.0).collect();
+    PyBytes::new(py, &encode_event_batch(&events))
+}
+
+/// 列指向バッチからイベント列を復元し、各イベントを検証する。
+///
+/// # Errors
+///
+/// バイト列が不正なら `ValueError`。
+#[pyfunction]
+fn decode_event_batch(data: &[u8]) -> PyResult<Vec<PyPresenceEvent>> {
+    try_decode_event_batch(data)
+        .map(|events| events.into_iter().map(PyPresenceEvent).collect())
+        .map_err(value_error)
+}
+
+/// `alice_presence` 拡張モジュール。
+#[pymodule]
+fn alice_presence(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyVivaldiCoord>()?;
+    m.add_class::<PyParty>()?;
+    m.add_class::<PyPresenceEvent>()?;
+    m.add_class::<PyCrossingRecord>()?;
+    m.add_class::<PyVerifyResult>()?;
+    m.add_class::<PyKdTree>()?;
+    m.add_function(wrap_pyfunction!(execute_presence_protocol, m)?)?;
+    m.add_function(wrap_pyfunction!(py_verify_record, m)?)?;
+    m.add_function(wrap_pyfunction!(py_serialize_crossing, m)?)?;
+    m.add_function(wrap_pyfunction!(deserialize_crossing, m)?)?;
+    m.add_function(wrap_pyfunction!(py_encode_event_batch, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_event_batch, m)?)?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
@@ -0,0 +1,704 @@
+//! QR 向けテキスト符号化 — 画面を読み取り合うだけでプロトコルを実行する。
+//!
+//! 無線リンクがない対面交換のため、オファー / レスポンス / 完成した
+//! `CrossingRecord` を QR の英数字モードに収まる Base45 (RFC 9285) 文字列に
+//! 符号化する。
+//!
+//! Text layout:
+//!
+//! ```text
+//! "AP1:" + Base45([kind: u8][body][crc32: u32 LE])
+//! ```
+//!
+//! CRC32 は `kind` と `body` を対象とする。解析は厳格で、プレフィックス・
+//! Base45 文字・グループ値・チェックサム・本体長のいずれかが不正なら失敗する。
+//!
+//! 交換の流れ (Network モードのみ):
+//!
+//! 1. A が [`EncounterOffer::new`] を表示し、B が読み取る。
+//! 2. B が [`EncounterResponse::answer`] で近接を確認し、自分の ZKP を付けて表示。
+//! 3. A が [`EncounterOffer::complete`] で `CrossingRecord` を組み立て、
+//!    [`crossing_to_text`] で表示して B に渡す。
+//!
+//! 秘密鍵は一切符号化されない。`PartyInfo::with_session` で両者がセッション
+//! ID を持つ場合、オファー / レスポンスの末尾にそれぞれの ID (8 bytes) が付き、
+//! ZKP チャレンジとレコードは直接実行と同じく両 ID に束縛される
+//! (`CrossingRecord::session`)。片側だけが束縛されていれば
+//! `ProtocolError::MissingSessionId`。
+
+use crate::error::{DecodeError, EncodeError, ProtocolError};
+use crate::event::CrossingRecord;
+use crate::fnv1a;
+use crate::gossip::{CoordAnnouncement, COORD_ANNOUNCEMENT_SIZE};
+use crate::identity::ZkProof;
+use crate::protocol::{
+    assemble_record, exchange_challenges, network_proof, party_commitment, party_proof,
+    session_binding, PartyInfo, PresenceConfig, ProximityMode,
+};
+use crate::serialize::{
+    deserialize_zkproof, le_u64, serialize_crossing, serialize_zkproof, try_deserialize_crossing,
+    ZKPROOF_SIZE,
+};
+use crate::stream::crc32;
+use crate::vivaldi::VivaldiNode;
+
+/// テキスト表現のプレフィックス (バージョン 1)。
+pub const TEXT_PREFIX: &str = "AP1:";
+
+/// 種別タグ: オファー。
+const KIND_OFFER: u8 = b'O';
+/// 種別タグ: レスポンス。
+const KIND_RESPONSE: u8 = b'R';
+/// 種別タグ: `CrossingRecord`。
+const KIND_CROSSING: u8 = b'C';
+
+/// オファー本体: `party_id(4) + commitment(8) + announcement(41)`。
+/// セッションに束縛されていれば `session_id(8)` が続く。
+pub const OFFER_SIZE: usize = 4 + 8 + COORD_ANNOUNCEMENT_SIZE;
+
+/// レスポンス本体: `party_id(4) + offer_digest(8) + announcement(41) + proof(25)`。
+/// セッションに束縛されていれば `session_id(8)` が続く。
+pub const RESPONSE_SIZE: usize = 4 + 8 + COORD_ANNOUNCEMENT_SIZE + ZKPROOF_SIZE;
+
+/// オファー / レスポンス末尾のセッション ID の長さ。
+pub const SESSION_ID_SIZE: usize = 8;
+
+// ============================================================================
+// Base45 (RFC 9285)
+// ============================================================================
+
+/// Base45 の文字集合 (QR 英数字モードと同一)。
+const BASE45_CHARSET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// バイト列を Base45 文字列に符号化する。
+#[must_use]
+pub fn base45_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(2) * 3);
+    for chunk in data.chunks(2) {
+        let (mut n, digits) = match *chunk {
+            [a, b] => (usize::from(a) * 256 + usize::from(b), 3),
+            [a] => (usize::from(a), 2),
+            _ => unreachable!("chunks(2) yields 1 or 2 bytes"),
+        };
+        for _ in 0..digits {
+            out.push(char::from(BASE45_CHARSET[n % 45]));
+            n /= 45;
+        }
+    }
+    out
+}
+
+/// Base45 文字列を復号する。
+///
+/// # Errors
+///
+/// 文字集合外の文字、長さが 3n+1、またはグループ値がバイト範囲を超える場合
+/// `DecodeError::InvalidField("base45")`。
+pub fn base45_decode(text: &str) -> Result<Vec<u8>, DecodeError> {
+    const INVALID: DecodeError = DecodeError::InvalidField("base45");
+    let digits: Vec<usize> = text
+        .bytes()
+        .map(|c| BASE45_CHARSET.iter().position(|&d| d == c).ok_or(INVALID))
+        .collect::<Result<_, _>>()?;
+    let mut out = Vec::with_capacity(digits.len() / 3 * 2 + 1);
+    for group in digits.chunks(3) {
+        match *group {
+            [c, d, e] => {
+                let n = c + d * 45 + e * 45 * 45;
+                let v = u16::try_from(n).map_err(|_| INVALID)?;
+                out.extend_from_slice(&v.to_be_bytes());
+            }
+            [c, d] => out.push(u8::try_from(c + d * 45).map_err(|_| INVALID)?),
+            _ => return Err(INVALID),
+        }
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// Envelope
+// ============================================================================
+
+/// `kind` と本体をチェックサム付きテキストにする。
+fn to_text(kind: u8, body: &[u8]) -> String {
+    let mut buf = Vec::with_capacity(1 + body.len() + 4);
+    buf.push(kind);
+    buf.extend_from_slice(body);
+    let crc = crc32(&buf);
+    buf.extend_from_slice(&crc.to_le_bytes());
+    let mut text = String::from(TEXT_PREFIX);
+    text.push_str(&base45_encode(&buf));
+    text
+}
+
+/// テキストを検証し、`kind` が一致すれば本体を返す。
+fn from_text(text: &str, kind: u8) -> Result<Vec<u8>, DecodeError> {
+    let payload = text
+        .strip_prefix(TEXT_PREFIX)
+        .ok_or(DecodeError::BadMagic)?;
+    let mut buf = base45_decode(payload)?;
+    DecodeError::check_len(&buf, 1 + 4)?;
+    let crc_off = buf.len() - 4;
+    let expected = u32::from_le_bytes([
+        buf[crc_off],
+        buf[crc_off + 1],
+        buf[crc_off + 2],
+        buf[crc_off + 3],
+    ]);
+    if crc32(&buf[..crc_off]) != expected {
+        return Err(DecodeError::Checksum);
+    }
+    if buf[0] != kind {
+        return Err(DecodeError::UnknownType(buf[0]));
+    }
+    buf.truncate(crc_off);
+    buf.remove(0);
+    Ok(buf)
+}
+
+/// 固定部 `size` bytes と省略可能なセッション ID からなる本体の長さを検証し、
+/// セッション ID を返す。
+fn read_session_id(body: &[u8], size: usize) -> Result<Option<u64>, DecodeError> {
+    DecodeError::check_len(body, size)?;
+    match body.len() - size {
+        0 => Ok(None),
+        SESSION_ID_SIZE => Ok(Some(le_u64(body, size))),
+        extra if extra < SESSION_ID_SIZE => Err(DecodeError::TrailingBytes { extra }),
+        extra => Err(DecodeError::TrailingBytes {
+            extra: extra - SESSION_ID_SIZE,
+        }),
+    }
+}
+
+/// 束縛されていればセッション ID を本体の末尾に書く。
+fn write_session_id(buf: &mut Vec<u8>, session_id: Option<u64>) {
+    if let Some(id) = session_id {
+        buf.extend_from_slice(&id.to_le_bytes());
+    }
+}
+
+// ============================================================================
+// EncounterOffer
+// ============================================================================
+
+/// 交換を始める側 (A) が表示するオファー。
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncounterOffer {
+    /// A のパーティ ID。
+    pub party_id: u32,
+    /// A の identity commitment (`H(secret || nonce)`)。
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+    pub commitment: u64,
+    /// A の座標と誤差。`timestamp_ns` が交換時刻になる。
+    pub announcement: CoordAnnouncement,
+    /// A の `Session::session_id` (束縛する場合)。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub session_id: Option<u64>,
+}
+
+impl EncounterOffer {
+    /// `party` の座標と commitment から時刻 `timestamp_ns` のオファーを作る。
+    ///
+    /// `party.session_id` があればオファーに載せる。
+    #[must_use]
+    pub fn new(party: &PartyInfo, timestamp_ns: u64) -> Self {
+        Self {
+            party_id: party.id,
+            commitment: party_commitment(party, timestamp_ns).commitment_hash,
+            announcement: CoordAnnouncement {
+                node: party.node(),
+                timestamp_ns,
+            },
+            session_id: party.session_id,
+        }
+    }
+
+    /// 交換時刻。
+    #[must_use]
+    pub const fn timestamp_ns(&self) -> u64 {
+        self.announcement.timestamp_ns
+    }
+
+    /// レスポンスがこのオファーに結び付いていることを示すダイジェスト。
+    ///
+    /// 本体バイト列のハッシュ。座標が不正でも計算できる。
+    #[must_use]
+    pub fn digest(&self) -> u64 {
+        fnv1a(&self.body(&self.announcement.encode_unchecked()))
+    }
+
+    /// 本体 (`OFFER_SIZE` bytes、束縛時は + `SESSION_ID_SIZE`) にエンコード。
+    ///
+    /// # Errors
+    ///
+    /// 座標や誤差が不正なら [`CoordAnnouncement::to_bytes`] と同じ。
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        Ok(self.body(&self.announcement.to_bytes()?))
+    }
+
+    fn body(&self, announcement: &[u8; COORD_ANNOUNCEMENT_SIZE]) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(OFFER_SIZE + SESSION_ID_SIZE);
+        buf.extend_from_slice(&self.party_id.to_le_bytes());
+        buf.extend_from_slice(&self.commitment.to_le_bytes());
+        buf.extend_from_slice(announcement);
+        write_session_id(&mut buf, self.session_id);
+        buf
+    }
+
+    /// 本体からデコード。
+    ///
+    /// # Errors
+    ///
+    /// 長さが `OFFER_SIZE` でも `OFFER_SIZE + SESSION_ID_SIZE` でもなければ
+    /// `TooShort` / `TrailingBytes`、座標不正は
+    /// [`CoordAnnouncement::from_bytes`] と同じ。
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let session_id = read_session_id(data, OFFER_SIZE)?;
+        Ok(Self {
+            party_id: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            commitment: le_u64(data, 4),
+            announcement: CoordAnnouncement::from_bytes(&data[12..OFFER_SIZE])?,
+            session_id,
+        })
+    }
+
+    /// QR 用テキストにエンコード。
+    ///
+    /// # Errors
+    ///
+    /// [`to_bytes`](Self::to_bytes) と同じ。
+    pub fn to_text(&self) -> Result<String, EncodeError> {
+        Ok(to_text(KIND_OFFER, &self.to_bytes()?))
+    }
+
+    /// QR 用テキストからデコード。
+    ///
+    /// # Errors
+    ///
+    /// プレフィックス不一致は `BadMagic`、Base45 不正は `InvalidField("base45")`、
+    /// チェックサム不一致は `Checksum`、別種別のテキストは `UnknownType`、
+    /// 本体の不正は [`EncounterOffer::from_bytes`] と同じ。
+    pub fn from_text(text: &str) -> Result<Self, DecodeError> {
+        Self::from_bytes(&from_text(text, KIND_OFFER)?)
+    }
+
+    /// B のレスポンスを受けて `CrossingRecord` を完成させる (A 側)。
+    ///
+    /// `party` はこのオファーを作った本人 (同じ ID・セッション) でなければ
+    /// ならない。[`EncounterResponse::answer`] と同じく Network モード専用。
+    /// 両者がセッション ID を持てばレコードは両 ID に束縛される。
+    ///
+    /// # Errors
+    ///
+    /// `config.mode` が Network 以外なら `ProtocolError::UnsupportedMode`、
+    /// レスポンスが別のオファーへの応答なら `ProtocolError::ResponseMismatch`、
+    /// 片側だけがセッション ID を持てば `ProtocolError::MissingSessionId`、
+    /// 近接判定の失敗は `try_execute_presence_protocol` と同じ。
+    pub fn complete(
+        &self,
+        party: &PartyInfo,
+        response: &EncounterResponse,
+        config: &PresenceConfig,
+    ) -> Result<CrossingRecord, ProtocolError> {
+        require_network(config)?;
+        if response.offer_digest != self.digest()
+            || party.id != self.party_id
+            || party.session_id != self.session_id
+        {
+            return Err(ProtocolError::ResponseMismatch);
+        }
+        let session = session_binding(self.session_id, response.session_id)?;
+        let timestamp_ns = self.timestamp_ns();
+        let remote = remote_party(response.party_id, &response.announcement.node);
+        let proximity = network_proof(party, &remote, config)?;
+        let (challenge_a, challenge_b) = exchange_challenges(timestamp_ns, session.as_ref());
+        if response.proof.challenge != challenge_b {
+            return Err(ProtocolError::ResponseMismatch);
+        }
+        let proof_a = party_proof(party, timestamp_ns, challenge_a);
+        let record = assemble_record(
+            self.party_id,
+            response.party_id,
+            timestamp_ns,
+            config,
+            proximity,
+            proof_a,
+            response.proof,
+        );
+        Ok(match session {
+            Some(session) => record.with_session(session),
+            None => record,
+        })
+    }
+}
+
+/// オファー / レスポンスは GPS fix を運ばないため Network モードのみ許可する。
+fn require_network(config: &PresenceConfig) -> Result<(), ProtocolError> {
+    if config.mode == ProximityMode::Network {
+        Ok(())
+    } else {
+        Err(ProtocolError::UnsupportedMode(config.mode))
+    }
+}
+
+/// 秘密を持たないリモートパーティ (近接判定専用)。
+fn remote_party(id: u32, node: &VivaldiNode) -> PartyInfo {
+    PartyInfo::new(node.coord, 0, id).with_coord_error(node.error)
+}
+
+// ============================================================================
+// EncounterResponse
+// ============================================================================
+
+/// オファーを読み取った側 (B) が表示するレスポンス。
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncounterResponse {
+    /// B のパーティ ID。
+    pub party_id: u32,
+    /// 応答したオファーの [`EncounterOffer::digest`]。
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+    pub offer_digest: u64,
+    /// B の座標と誤差 (タイムスタンプはオファーと同じ)。
+    pub announcement: CoordAnnouncement,
+    /// B の ZKP。
+    pub proof: ZkProof,
+    /// B の `Session::session_id` (束縛する場合)。
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub session_id: Option<u64>,
+}
+
+impl EncounterResponse {
+    /// オファーに応答する (B 側)。近接していなければ応答しない。
+    ///
+    /// オファーは GPS fix を運ばないため Network モード専用。オファーと
+    /// `party` の両方がセッション ID を持てば、B の ZKP チャレンジは両 ID に
+    /// 束縛され、レスポンスに B のセッション ID が載る。
+    ///
+    /// # Errors
+    ///
+    /// `config.mode` が Network 以外なら `ProtocolError::UnsupportedMode`、
+    /// 片側だけがセッション ID を持てば `ProtocolError::MissingSessionId`、
+    /// 近接判定の失敗は `try_execute_presence_protocol` と同じ。
+    pub fn answer(
+        offer: &EncounterOffer,
+        party: &PartyInfo,
+        config: &PresenceConfig,
+    ) -> Result<Self, ProtocolError> {
+        require_network(config)?;
+        let session = session_binding(offer.session_id, party.session_id)?;
+        let timestamp_ns = offer.timestamp_ns();
+        let remote = remote_party(offer.party_id, &offer.announcement.node);
+        network_proof(&remote, party, config)?;
+        let (_, challenge_b) = exchange_challenges(timestamp_ns, session.as_ref());
+        Ok(Self {
+            party_id: party.id,
+            offer_digest: offer.digest(),
+            announcement: CoordAnnouncement {
+                node: party.node(),
+                timestamp_ns,
+            },
+            proof: party_proof(party, timestamp_ns, challenge_b),
+            session_id: party.session_id,
+        })
+    }
+
+    /// 本体 (`RESPONSE_SIZE` bytes、束縛時は + `SESSION_ID_SIZE`) にエンコード。
+    ///
+    /// # Errors
+    ///
+    /// 座標や誤差が不正なら [`CoordAnnouncement::to_bytes`] と同じ。
+    pub fn to_bytes(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::with_capacity(RESPONSE_SIZE + SESSION_ID_SIZE);
+        buf.extend_from_slice(&self.party_id.to_le_bytes());
+        buf.extend_from_slice(&self.offer_digest.to_le_bytes());
+        buf.extend_from_slice(&self.announcement.to_bytes()?);
+        serialize_zkproof(&self.proof, &mut buf);
+        write_session_id(&mut buf, self.session_id);
+        Ok(buf)
+    }
+
+    /// 本体からデコード。
+    ///
+    /// # Errors
+    ///
+    /// 長さが `RESPONSE_SIZE` でも `RESPONSE_SIZE + SESSION_ID_SIZE` でも
+    /// なければ `TooShort` / `TrailingBytes`、座標不正は
+    /// [`CoordAnnouncement::from_bytes`] と同じ。
+    pub fn from_bytes(data: &[u8]) -> Result<Self, DecodeError> {
+        let session_id = read_session_id(data, RESPONSE_SIZE)?;
+        let ann_end = 12 + COORD_ANNOUNCEMENT_SIZE;
+        Ok(Self {
+            party_id: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            offer_digest: le_u64(data, 4),
+            announcement: CoordAnnouncement::from_bytes(&data[12..ann_end])?,
+            proof: deserialize_zkproof(&data[ann_end..RESPONSE_SIZE]),
+            session_id,
+        })
+    }
+
+    /// QR 用テキストにエンコード。
+    ///
+    /// # Errors
+    ///
+    /// [`to_bytes`](Self::to_bytes) と同じ。
+    pub fn to_text(&self) -> Result<String, EncodeError> {
+        Ok(to_text(KIND_RESPONSE, &self.to_bytes()?))
+    }
+
+    /// QR 用テキストからデコード。
+    ///
+    /// # Errors
+    ///
+    /// [`EncounterOffer::from_text`] と同じ。
+    pub fn from_text(text: &str) -> Result<Self, DecodeError> {
+        Self::from_bytes(&from_text(text, KIND_RESPONSE)?)
+    }
+}
+
+// ============================================================================
+// CrossingRecord
+// ============================================================================
+
+/// 完成した `CrossingRecord` を QR 用テキストにエンコード。
+#[must_use]
+pub fn crossing_to_text(record: &CrossingRecord) -> String {
+    to_text(KIND_CROSSING, &serialize_crossing(record))
+}
+
+/// QR 用テキストから `CrossingRecord` をデコード。
+///
+/// # Errors
+///
+/// エンベロープの不正は [`EncounterOffer::from_text`] と同じ、本体の不正は
+/// [`try_deserialize_crossing`] と同じ。
+pub fn crossing_from_text(text: &str) -> Result<CrossingRecord, DecodeError> {
+    try_deserialize_crossing(&from_text(text, KIND_CROSSING)?)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::SessionBinding;
+    use crate::protocol::try_execute_presence_protocol;
+    use crate::verification::{verify_record, verify_record_session, VerifyResult};
+    use crate::vivaldi::VivaldiCoord;
+
+    fn alice() -> PartyInfo {
+        PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1)
+    }
+
+    fn bob() -> PartyInfo {
+        PartyInfo::new(VivaldiCoord::new(3.0, 4.0), 99, 2).with_coord_error(0.2)
+    }
+
+    #[test]
+    fn base45_rfc_vectors() {
+        assert_eq!(base45_encode(b"AB"), "BB8");
+        assert_eq!(base45_encode(b"Hello!!"), "%69 VD92EX0");
+        assert_eq!(base45_encode(b"base-45"), "UJCLQE7W581");
+        assert_eq!(base45_decode("QED8WEX0").unwrap(), b"ietf!");
+        assert_eq!(base45_decode("").unwrap(), b"");
+    }
+
+    #[test]
+    fn base45_rejects_invalid() {
+        for bad in ["GGW", "a00", "0", "0000", "BB8#"] {
+            assert_eq!(
+                base45_decode(bad),
+                Err(DecodeError::InvalidField("base45")),
+                "{bad}"
+            );
+        }
+        // 2 文字グループが 255 を超える
+        assert!(base45_decode(":6").is_err());
+    }
+
+    #[test]
+    fn qr_exchange_matches_direct_protocol() {
+        let cfg = PresenceConfig::default();
+        let (a, b) = (alice(), bob());
+
+        let offer_text = EncounterOffer::new(&a, 5_000).to_text().unwrap();
+        assert!(offer_text.starts_with(TEXT_PREFIX));
+        assert!(offer_text[TEXT_PREFIX.len()..]
+            .bytes()
+            .all(|c| BASE45_CHARSET.contains(&c)));
+
+        // B が読み取って応答
+        let offer = EncounterOffer::from_text(&offer_text).unwrap();
+        let response_text = EncounterResponse::answer(&offer, &b, &cfg)
+            .unwrap()
+            .to_text()
+            .unwrap();
+
+        // A が読み取って完成
+        let response = EncounterResponse::from_text(&response_text).unwrap();
+        let record = offer.complete(&a, &response, &cfg).unwrap();
+        assert_eq!(verify_record(&record), VerifyResult::Valid);
+
+        let direct = try_execute_presence_protocol(&a, &b, 5_000, &cfg).unwrap();
+        assert_eq!(record.content_hash, direct.content_hash);
+
+        let record_text = crossing_to_text(&record);
+        let back = crossing_from_text(&record_text).unwrap();
+        assert_eq!(back.content_hash, record.content_hash);
+    }
+
+    #[test]
+    fn qr_exchange_refuses_distant_or_foreign() {
+        let cfg = PresenceConfig::default();
+        let far = PartyInfo::new(VivaldiCoord::new(500.0, 0.0), 7, 3);
+        let offer = EncounterOffer::new(&alice(), 1);
+        assert!(matches!(
+            EncounterResponse::answer(&offer, &far, &cfg),
+            Err(ProtocolError::NotProximate { .. })
+        ));
+
+        // 別のオファーへのレスポンス
+        let other = EncounterOffer::new(&alice(), 2);
+        let response = EncounterResponse::answer(&other, &bob(), &cfg).unwrap();
+        assert!(matches!(
+            offer.complete(&alice(), &response, &cfg),
+            Err(ProtocolError::ResponseMismatch)
+        ));
+
+        let hybrid = PresenceConfig {
+            mode: ProximityMode::Hybrid,
+            ..Default::default()
+        };
+        assert!(matches!(
+            EncounterResponse::answer(&offer, &bob(), &hybrid),
+            Err(ProtocolError::UnsupportedMode(ProximityMode::Hybrid))
+        ));
+
+        // A 側も Network 以外のモードでは完成させない
+        let response = EncounterResponse::answer(&offer, &bob(), &cfg).unwrap();
+        assert!(matches!(
+            offer.complete(&alice(), &response, &hybrid),
+            Err(ProtocolError::UnsupportedMode(ProximityMode::Hybrid))
+        ));
+        let geographic = PresenceConfig {
+            mode: ProximityMode::Geographic,
+            ..Default::default()
+        };
+        assert_eq!(
+            offer
+                .complete(&alice(), &response, &geographic)
+                .unwrap_err(),
+            ProtocolError::UnsupportedMode(ProximityMode::Geographic)
+        );
+    }
+
+    #[test]
+    fn offer_with_invalid_coordinates_is_not_encoded() {
+        // 読み取り側が拒否するオファーは表示しない
+        let nan = PartyInfo::new(VivaldiCoord::new(f64::NAN, 0.0), 42, 1);
+        let offer = EncounterOffer::new(&nan, 5_000);
+        assert_eq!(offer.to_text(), Err(EncodeError::InvalidField("coord")));
+        assert_eq!(offer.to_bytes(), Err(EncodeError::InvalidField("coord")));
+    }
+
+    #[test]
+    fn qr_exchange_carries_session_ids() {
+        let cfg = PresenceConfig::default();
+        let (a, b) = (alice().with_session(10), bob().with_session(20));
+
+        let offer = EncounterOffer::new(&a, 5_000);
+        assert_eq!(
+            offer.to_bytes().unwrap().len(),
+            OFFER_SIZE + SESSION_ID_SIZE
+        );
+        let offer = EncounterOffer::from_text(&offer.to_text().unwrap()).unwrap();
+        assert_eq!(offer.session_id, Some(10));
+
+        let response = EncounterResponse::answer(&offer, &b, &cfg).unwrap();
+        assert_eq!(
+            response.to_bytes().unwrap().len(),
+            RESPONSE_SIZE + SESSION_ID_SIZE
+        );
+        let response = EncounterResponse::from_text(&response.to_text().unwrap()).unwrap();
+        assert_eq!(response.session_id, Some(20));
+
+        let record = offer.complete(&a, &response, &cfg).unwrap();
+        assert_eq!(record.session, Some(SessionBinding::new(10, 20)));
+        assert_eq!(verify_record(&record), VerifyResult::Valid);
+        assert_eq!(verify_record_session(&record, 20), VerifyResult::Valid);
+        let direct = try_execute_presence_protocol(&a, &b, 5_000, &cfg).unwrap();
+        assert_eq!(record.content_hash, direct.content_hash);
+
+        // 片側だけの束縛は拒否
+        assert_eq!(
+            EncounterResponse::answer(&offer, &bob(), &cfg).unwrap_err(),
+            ProtocolError::MissingSessionId
+        );
+        let unbound_offer = EncounterOffer::new(&alice(), 5_000);
+        assert_eq!(
+            EncounterResponse::answer(&unbound_offer, &b, &cfg).unwrap_err(),
+            ProtocolError::MissingSessionId
+        );
+        let mut half = response;
+        half.session_id = None;
+        assert_eq!(
+            offer.complete(&a, &half, &cfg).unwrap_err(),
+            ProtocolError::MissingSessionId
+        );
+        // 別セッションの A は完成させられない
+        assert_eq!(
+            offer
+                .complete(&alice().with_session(11), &response, &cfg)
+                .unwrap_err(),
+            ProtocolError::ResponseMismatch
+        );
+
+        // セッション ID は 8 bytes ちょうど
+        let bytes = offer.to_bytes().unwrap();
+        assert_eq!(
+            EncounterOffer::from_bytes(&bytes[..OFFER_SIZE + 3]),
+            Err(DecodeError::TrailingBytes { extra: 3 })
+        );
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(
+            EncounterOffer::from_bytes(&long),
+            Err(DecodeError::TrailingBytes { extra: 1 })
+        );
+    }
+
+    #[test]
+    fn text_parsing_is_strict() {
+        let offer = EncounterOffer::new(&alice(), 77);
+        let text = offer.to_text().unwrap();
+
+        assert_eq!(
+            EncounterOffer::from_text(&text[1..]),
+            Err(DecodeError::BadMagic)
+        );
+        assert_eq!(
+            EncounterOffer::from_text(&text.to_lowercase()),
+            Err(DecodeError::BadMagic)
+        );
+        assert!(matches!(
+            EncounterResponse::from_text(&text),
+            Err(DecodeError::UnknownType(KIND_OFFER))
+        ));
+
+        // 1 文字の置換はチェックサム (または Base45) で検出される
+        let body_start = TEXT_PREFIX.len();
+        for i in body_start..text.len() {
+            let mut chars: Vec<u8> = text.bytes().collect();
+            chars[i] = if chars[i] == b'0' { b'1' } else { b'0' };
+            let tampered = String::from_utf8(chars).unwrap();
+            assert!(EncounterOffer::from_text(&tampered).is_err(), "{i}");
+        }
+
+        let mut truncated = text.clone();
+        truncated.truncate(text.len() - 3);
+        assert!(EncounterOffer::from_text(&truncated).is_err());
+    }
+}
@@ -0,0 +1,580 @@
+//! Radio ranging evidence
+//!
+//! Decides proximity from what devices actually measure: BLE RSSI via a
+//! log-distance path-loss model, or UWB time-of-flight ranges. Both are
+//! smoothed with a 1D Kalman filter and yield a `ProximityVerdict`
+//! (distance, P(distance <= threshold)) that can be turned into a
+//! `ProximityProof` and embedded in a `CrossingRecord`.
+//!
+//! Author: Moroya Sakamoto
+
+use crate::event::ProximityProof;
+use crate::{fnv1a, normal_cdf};
+
+// ── Samples ────────────────────────────────────────────────────────────
+
+/// One BLE advertisement RSSI reading.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RssiSample {
+    /// Receive timestamp (ns).
+    pub timestamp_ns: u64,
+    /// Received signal strength (dBm).
+    pub rssi_dbm: f64,
+}
+
+/// One UWB (or other time-of-flight) range measurement.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangingSample {
+    /// Measurement timestamp (ns).
+    pub timestamp_ns: u64,
+    /// Measured range (metres).
+    pub range_m: f64,
+    /// Reported 1σ measurement error (metres).
+    pub std_dev_m: f64,
+}
+
+// ── Kalman filter ──────────────────────────────────────────────────────
+
+/// Scalar random-walk Kalman filter.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KalmanFilter1D {
+    /// Current state estimate.
+    pub estimate: f64,
+    /// Current estimate variance (`f64::INFINITY` before the first sample).
+    pub variance: f64,
+    /// Process noise variance per second.
+    pub process_noise: f64,
+    last_ns: Option<u64>,
+}
+
+impl KalmanFilter1D {
+    /// Create an uninitialised filter.
+    #[must_use]
+    pub const fn new(process_noise: f64) -> Self {
+        Self {
+            estimate: 0.0,
+            variance: f64::INFINITY,
+            process_noise,
+            last_ns: None,
+        }
+    }
+
+    /// Has at least one measurement been absorbed?
+    #[must_use]
+    pub const fn is_initialized(&self) -> bool {
+        self.last_ns.is_some()
+    }
+
+    /// Predict to `timestamp_ns`, then correct with `measurement`
+    /// of variance `measurement_var`.
+    pub fn update(&mut self, timestamp_ns: u64, measurement: f64, measurement_var: f64) {
+        let Some(last) = self.last_ns else {
+            self.estimate = measurement;
+            self.variance = measurement_var;
+            self.last_ns = Some(timestamp_ns);
+            return;
+        };
+        let dt_s = timestamp_ns.saturating_sub(last) as f64 * 1e-9;
+        self.variance += self.process_noise * dt_s;
+        let gain = self.variance / (self.variance + measurement_var);
+        self.estimate += gain * (measurement - self.estimate);
+        self.variance *= 1.0 - gain;
+        self.last_ns = Some(timestamp_ns.max(last));
+    }
+}
+
+// ── Evidence abstraction ───────────────────────────────────────────────
+
+/// Proximity decision from a ranging source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProximityVerdict {
+    /// Point estimate of the distance (metres).
+    pub distance_m: f64,
+    /// Threshold the verdict was taken against (metres).
+    pub threshold_m: f64,
+    /// P(true distance <= threshold) under the estimator's error model.
+    pub confidence: f64,
+    /// `distance_m <= threshold_m` and `confidence >= min_confidence`.
+    pub is_proximate: bool,
+}
+
+impl ProximityVerdict {
+    /// Convert to a `ProximityProof` for a `CrossingRecord`.
+    ///
+    /// `coord_hash_a` commits to the raw sample trace, `coord_hash_b` to
+    /// the source kind, and the confidence is carried in
+    /// `ProximityProof::confidence`; all three are covered by
+    /// `content_hash`.
+    #[must_use]
+    pub fn to_proof(&self, kind: EvidenceKind, trace_hash: u64) -> ProximityProof {
+        ProximityProof::from_parts(
+            self.distance_m,
+            self.threshold_m,
+            self.is_proximate,
+            trace_hash,
+            fnv1a(&[kind as u8]),
+        )
+        .with_confidence(self.confidence)
+    }
+}
+
+/// Radio technology that produced the evidence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum EvidenceKind {
+    /// BLE RSSI with a path-loss model.
+    BleRssi = 1,
+    /// UWB / time-of-flight ranging.
+    Ranging = 2,
+}
+
+/// A source of distance evidence between two devices.
+pub trait ProximityEvidence {
+    /// Technology of this source.
+    fn kind(&self) -> EvidenceKind;
+
+    /// Smoothed distance estimate (metres), `None` before any sample.
+    fn distance_m(&self) -> Option<f64>;
+
+    /// P(true distance <= `threshold_m`), `None` before any sample.
+    fn probability_within(&self, threshold_m: f64) -> Option<f64>;
+
+    /// Commitment over every sample absorbed so far.
+    fn trace_hash(&self) -> u64;
+
+    /// Decide proximity: the distance estimate must be within
+    /// `threshold_m` and the confidence at least `min_confidence`.
+    fn verdict(&self, threshold_m: f64, min_confidence: f64) -> Option<ProximityVerdict> {
+        let distance_m = self.distance_m()?;
+        let confidence = self.probability_within(threshold_m)?;
+        Some(ProximityVerdict {
+            distance_m,
+            threshold_m,
+            confidence,
+            is_proximate: distance_m <= threshold_m && confidence >= min_confidence,
+        })
+    }
+
+    /// Decide proximity and convert straight to a `ProximityProof`.
+    fn prove(&self, threshold_m: f64, min_confidence: f64) -> Option<ProximityProof> {
+        let v = self.verdict(threshold_m, min_confidence)?;
+        Some(v.to_proof(self.kind(), self.trace_hash()))
+    }
+}
+
+/// Chain `bytes` onto a running trace commitment.
+fn chain_hash(prev: u64, bytes: &[u8]) -> u64 {
+    let mut buf = Vec::with_capacity(8 + bytes.len());
+    buf.extend_from_slice(&prev.to_le_bytes());
+    buf.extend_from_slice(bytes);
+    fnv1a(&buf)
+}
+
+/// Probability mass of a normal `N(mean, var)` at or below `x`.
+fn prob_below(x: f64, mean: f64, var: f64) -> f64 {
+    if var <= 0.0 {
+        return if mean <= x { 1.0 } else { 0.0 };
+    }
+    normal_cdf((x - mean) / var.sqrt())
+}
+
+// ── BLE RSSI ───────────────────────────────────────────────────────────
+
+/// Log-distance path-loss model: `rssi = tx_power - 10 n log10(d)`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathLossModel {
+    /// Expected RSSI at 1 m (dBm).
+    pub tx_power_dbm: f64,
+    /// Path-loss exponent (2.0 free space, 2.5–4 indoors).
+    pub exponent: f64,
+}
+
+impl Default for PathLossModel {
+    fn default() -> Self {
+        Self {
+            tx_power_dbm: -59.0,
+            exponent: 2.0,
+        }
+    }
+}
+
+impl PathLossModel {
+    /// Calibrate `tx_power_dbm` from readings taken at a known distance.
+    /// Returns `None` if `samples` is empty or `known_distance_m <= 0`.
+    #[must_use]
+    pub fn calibrate(samples: &[RssiSample], known_distance_m: f64, exponent: f64) -> Option<Self> {
+        if samples.is_empty() || known_distance_m <= 0.0 {
+            return None;
+        }
+        let mean = samples.iter().map(|s| s.rssi_dbm).sum::<f64>() / samples.len() as f64;
+        Some(Self {
+            tx_power_dbm: (10.0 * exponent).mul_add(known_distance_m.log10(), mean),
+            exponent,
+        })
+    }
+
+    /// Distance (metres) implied by an RSSI value.
+    #[must_use]
+    pub fn distance_m(&self, rssi_dbm: f64) -> f64 {
+        10f64.powf((self.tx_power_dbm - rssi_dbm) / (10.0 * self.exponent))
+    }
+}
+
+/// RSSI estimator: Kalman-smoothed RSSI mapped through a path-loss model.
+///
+/// Because distance is exponential in RSSI, the distance error is
+/// log-normal; confidence is computed in the log domain.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RssiEstimator {
+    /// Path-loss model.
+    pub model: PathLossModel,
+    /// Per-reading RSSI noise σ (dB), typically 4–8.
+    pub rssi_std_db: f64,
+    filter: KalmanFilter1D,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+    trace_hash: u64,
+    samples: u32,
+}
+
+impl RssiEstimator {
+    /// Create an estimator. `process_noise` is RSSI drift (dB²/s).
+    #[must_use]
+    pub const fn new(model: PathLossModel, rssi_std_db: f64, process_noise: f64) -> Self {
+        Self {
+            model,
+            rssi_std_db,
+            filter: KalmanFilter1D::new(process_noise),
+            trace_hash: 0,
+            samples: 0,
+        }
+    }
+
+    /// Absorb one reading.
+    pub fn push(&mut self, sample: RssiSample) {
+        self.filter.update(
+            sample.timestamp_ns,
+            sample.rssi_dbm,
+            self.rssi_std_db * self.rssi_std_db,
+        );
+        let mut b = [0u8; 16];
+        b[..8].copy_from_slice(&sample.timestamp_ns.to_le_bytes());
+        b[8..].copy_from_slice(&sample.rssi_dbm.to_le_bytes());
+        self.trace_hash = chain_hash(self.trace_hash, &b);
+        self.samples += 1;
+    }
+
+    /// Number of readings absorbed.
+    #[must_use]
+    pub const fn sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    /// Smoothed RSSI (dBm).
+    #[must_use]
+    pub fn smoothed_rssi(&self) -> Option<f64> {
+        self.filter.is_initialized().then_some(self.filter.estimate)
+    }
+}
+
+impl ProximityEvidence for RssiEstimator {
+    fn kind(&self) -> EvidenceKind {
+        EvidenceKind::BleRssi
+    }
+
+    fn distance_m(&self) -> Option<f64> {
+        Some(self.model.distance_m(self.smoothed_rssi()?))
+    }
+
+    fn probability_within(&self, threshold_m: f64) -> Option<f64> {
+        let d = self.distance_m()?;
+        if threshold_m <= 0.0 {
+            return Some(0.0);
+        }
+        // ln d = (tx - rssi) ln10 / (10 n) → σ_ln = σ_rssi ln10 / (10 n)
+        let k = core::f64::consts::LN_10 / (10.0 * self.model.exponent);
+        let var_ln = self.filter.variance * k * k;
+        Some(prob_below(threshold_m.ln(), d.ln(), var_ln))
+    }
+
+    fn trace_hash(&self) -> u64 {
+        self.trace_hash
+    }
+}
+
+// ── UWB / time-of-flight ───────────────────────────────────────────────
+
+/// Range estimator: Kalman filter directly on measured ranges.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangingEstimator {
+    filter: KalmanFilter1D,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+    trace_hash: u64,
+    samples: u32,
+}
+
+impl RangingEstimator {
+    /// Create an estimator. `process_noise` is motion variance (m²/s).
+    #[must_use]
+    pub const fn new(process_noise: f64) -> Self {
+        Self {
+            filter: KalmanFilter1D::new(process_noise),
+            trace_hash: 0,
+            samples: 0,
+        }
+    }
+
+    /// Absorb one range measurement. Negative ranges (`ToF` multipath
+    /// artefacts) are clamped to 0.
+    pub fn push(&mut self, sample: RangingSample) {
+        let range = sample.range_m.max(0.0);
+        let std = sample.std_dev_m.max(1e-3);
+        self.filter.update(sample.timestamp_ns, range, std * std);
+        let mut b = [0u8; 24];
+        b[..8].copy_from_slice(&sample.timestamp_ns.to_le_bytes());
+        b[8..16].copy_from_slice(&sample.range_m.to_le_bytes());
+        b[16..].copy_from_slice(&sample.std_dev_m.to_le_bytes());
+        self.trace_hash = chain_hash(self.trace_hash, &b);
+        self.samples += 1;
+    }
+
+    /// Number of measurements absorbed.
+    #[must_use]
+    pub const fn sample_count(&self) -> u32 {
+        self.samples
+    }
+}
+
+impl ProximityEvidence for RangingEstimator {
+    fn kind(&self) -> EvidenceKind {
+        EvidenceKind::Ranging
+    }
+
+    fn distance_m(&self) -> Option<f64> {
+        self.filter
+            .is_initialized()
+            .then_some(self.filter.estimate.max(0.0))
+    }
+
+    fn probability_within(&self, threshold_m: f64) -> Option<f64> {
+        self.filter
+            .is_initialized()
+            .then(|| prob_below(threshold_m, self.filter.estimate, self.filter.variance))
+    }
+
+    fn trace_hash(&self) -> u64 {
+        self.trace_hash
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic zero-mean noise in [-1, 1) (LCG).
+    fn noise(state: &mut u64) -> f64 {
+        *state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((*state >> 11) as f64 / (1u64 << 53) as f64).mul_add(2.0, -1.0)
+    }
+
+    fn rssi_trace(model: &PathLossModel, distance_m: f64, n: usize) -> Vec<RssiSample> {
+        let mut st = 7;
+        let ideal = (-10.0 * model.exponent).mul_add(distance_m.log10(), model.tx_power_dbm);
+        (0..n)
+            .map(|i| RssiSample {
+                timestamp_ns: i as u64 * 100_000_000,
+                rssi_dbm: noise(&mut st).mul_add(6.0, ideal),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn path_loss_at_one_metre() {
+        let m = PathLossModel::default();
+        assert!((m.distance_m(m.tx_power_dbm) - 1.0).abs() < 1e-12);
+        // 20 dB weaker with n = 2 → 10 m
+        assert!((m.distance_m(m.tx_power_dbm - 20.0) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn calibrate_from_known_distance() {
+        let truth = PathLossModel {
+            tx_power_dbm: -65.0,
+            exponent: 2.0,
+        };
+        let trace = rssi_trace(&truth, 2.0, 200);
+        let m = PathLossModel::calibrate(&trace, 2.0, 2.0).unwrap();
+        assert!((m.tx_power_dbm - truth.tx_power_dbm).abs() < 1.0);
+        assert!(PathLossModel::calibrate(&[], 1.0, 2.0).is_none());
+    }
+
+    #[test]
+    fn kalman_first_sample_initialises() {
+        let mut k = KalmanFilter1D::new(0.1);
+        assert!(!k.is_initialized());
+        k.update(0, 5.0, 1.0);
+        assert!(k.is_initialized());
+        assert_eq!(k.estimate, 5.0);
+        assert_eq!(k.variance, 1.0);
+    }
+
+    #[test]
+    fn kalman_variance_shrinks() {
+        let mut k = KalmanFilter1D::new(0.0);
+        for i in 0..10 {
+            k.update(i * 1_000_000, 3.0, 4.0);
+        }
+        assert!((k.estimate - 3.0).abs() < 1e-12);
+        assert!((k.variance - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rssi_close_trace_is_proximate() {
+        let model = PathLossModel::default();
+        let mut est = RssiEstimator::new(model, 6.0, 0.5);
+        for s in rssi_trace(&model, 1.5, 50) {
+            est.push(s);
+        }
+        let d = est.distance_m().unwrap();
+        assert!(d > 1.0 && d < 2.5, "estimate {d}");
+        let v = est.verdict(3.0, 0.9).unwrap();
+        assert!(v.is_proximate, "confidence {}", v.confidence);
+        assert!(!est.verdict(0.5, 0.9).unwrap().is_proximate);
+    }
+
+    #[test]
+    fn rssi_far_trace_is_not_proximate() {
+        let model = PathLossModel::default();
+        let mut est = RssiEstimator::new(model, 6.0, 0.5);
+        for s in rssi_trace(&model, 20.0, 50) {
+            est.push(s);
+        }
+        let v = est.verdict(3.0, 0.9).unwrap();
+        assert!(!v.is_proximate);
+        assert!(v.confidence < 0.01);
+    }
+
+    #[test]
+    fn distance_beyond_threshold_is_never_proximate() {
+        let mut est = RangingEstimator::new(0.01);
+        est.push(RangingSample {
+            timestamp_ns: 0,
+            range_m: 1.2,
+            std_dev_m: 0.5,
+        });
+        let v = est.verdict(1.0, 0.0).unwrap();
+        assert!(v.confidence > 0.0);
+        assert!(!v.is_proximate);
+        let proof = est.prove(1.0, 0.0).unwrap();
+        assert!(!proof.is_proximate);
+    }
+
+    #[test]
+    fn single_reading_is_low_confidence() {
+        let model = PathLossModel::default();
+        let mut est = RssiEstimator::new(model, 6.0, 0.5);
+        est.push(RssiSample {
+            timestamp_ns: 0,
+            rssi_dbm: -62.0,
+        });
+        let v = est.verdict(2.0, 0.9).unwrap();
+        assert!(v.confidence > 0.5 && v.confidence < 0.9);
+    }
+
+    #[test]
+    fn no_samples_no_verdict() {
+        let est = RssiEstimator::new(PathLossModel::default(), 6.0, 0.5);
+        assert!(est.verdict(3.0, 0.9).is_none());
+        assert!(RangingEstimator::new(0.1).verdict(1.0, 0.9).is_none());
+    }
+
+    #[test]
+    fn uwb_trace() {
+        let mut st = 3;
+        let mut est = RangingEstimator::new(0.01);
+        for i in 0..30 {
+            est.push(RangingSample {
+                timestamp_ns: i * 50_000_000,
+                range_m: noise(&mut st).mul_add(0.1, 0.8),
+                std_dev_m: 0.1,
+            });
+        }
+        assert_eq!(est.sample_count(), 30);
+        assert!((est.distance_m().unwrap() - 0.8).abs() < 0.05);
+        assert!(est.verdict(1.0, 0.99).unwrap().is_proximate);
+        assert!(!est.verdict(0.6, 0.5).unwrap().is_proximate);
+    }
+
+    #[test]
+    fn trace_hash_binds_samples() {
+        let mut a = RangingEstimator::new(0.01);
+        let mut b = RangingEstimator::new(0.01);
+        let s = RangingSample {
+            timestamp_ns: 0,
+            range_m: 1.0,
+            std_dev_m: 0.1,
+        };
+        a.push(s);
+        b.push(RangingSample { range_m: 1.1, ..s });
+        assert_ne!(a.trace_hash(), b.trace_hash());
+    }
+
+    #[test]
+    fn evidence_proof_in_crossing_record() {
+        use crate::event::{CrossingRecord, PresenceEvent};
+        use crate::identity::{IdentityCommitment, ZkProof};
+        use crate::verification::{verify_record, VerifyResult};
+
+        let mut est = RangingEstimator::new(0.01);
+        for i in 0..10 {
+            est.push(RangingSample {
+                timestamp_ns: i * 10_000_000,
+                range_m: 0.5,
+                std_dev_m: 0.1,
+            });
+        }
+        let prox = est.prove(1.0, 0.95).unwrap();
+        assert!(prox.is_proximate);
+        assert_eq!(prox.coord_hash_a, est.trace_hash());
+
+        let (challenge_a, challenge_b) = crate::protocol::exchange_challenges(0, None);
+        let pa = ZkProof::prove(42, &IdentityCommitment::new(42, 1, 0), challenge_a);
+        let pb = ZkProof::prove(99, &IdentityCommitment::new(99, 2, 0), challenge_b);
+        let record = CrossingRecord::new(PresenceEvent::new(1, 2, 0), pa, pb, prox);
+        assert_eq!(verify_record(&record), VerifyResult::Valid);
+    }
+
+    #[test]
+    fn confidence_bound_into_proof() {
+        let v = ProximityVerdict {
+            distance_m: 1.0,
+            threshold_m: 2.0,
+            confidence: 0.9,
+            is_proximate: true,
+        };
+        let p1 = v.to_proof(EvidenceKind::Ranging, 1);
+        assert_eq!(p1.confidence, Some(0.9));
+        assert!(crate::verification::verify_proximity(&p1));
+        let p2 = ProximityVerdict {
+            confidence: 0.99,
+            ..v
+        }
+        .to_proof(EvidenceKind::Ranging, 1);
+        assert_ne!(p1.content_hash, p2.content_hash);
+        assert_ne!(
+            p1.content_hash,
+            v.to_proof(EvidenceKind::BleRssi, 1).content_hash
+        );
+    }
+}
@@ -7,6 +7,7 @@ use crate::fnv1a;
 
 /// リプレイガード設定。
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReplayGuardConfig {
     /// タイムスタンプ有効ウィンドウ (ナノ秒)。デフォルト: 5分。
     pub window_ns: u64,
@@ -25,6 +26,7 @@ impl Default for ReplayGuardConfig {
 
 /// リプレイガード — タイムスタンプとnonce による二重防御。
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReplayGuard {
     /// 設定。
     config: ReplayGuardConfig,
@@ -34,6 +36,7 @@ pub struct ReplayGuard {
 
 /// タイムスタンプ検証結果。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TimestampResult {
     /// 有効期間内。
     Valid,
@@ -45,6 +48,7 @@ pub enum TimestampResult {
 
 /// リプレイ検証結果。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ReplayResult {
     /// 新規（リプレイではない）。
     Fresh,
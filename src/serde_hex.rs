@@ -0,0 +1,82 @@
+//! `u64` ハッシュの serde 表現。
+//!
+//! `#[serde(with = "crate::serde_hex")]` で使う。JSON などの人間可読
+//! フォーマットでは 16 桁の小文字 16 進文字列、バイナリフォーマットでは
+//! そのまま `u64` として書き出す。
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+/// 人間可読なら `"00000000deadbeef"`、それ以外は `u64`。
+///
+/// # Errors
+///
+/// シリアライザのエラーをそのまま返す。
+// serde の `with` が `&T` を要求するため参照渡し
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&format!("{value:016x}"))
+    } else {
+        serializer.serialize_u64(*value)
+    }
+}
+
+/// [`serialize`] の逆。16 進文字列は 1〜16 桁を受け付ける。
+///
+/// # Errors
+///
+/// 16 進として不正な文字列、または桁数超過。
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    if deserializer.is_human_readable() {
+        let s = String::deserialize(deserializer)?;
+        if s.is_empty() || s.len() > 16 {
+            return Err(D::Error::invalid_length(s.len(), &"1 to 16 hex digits"));
+        }
+        u64::from_str_radix(&s, 16).map_err(D::Error::custom)
+    } else {
+        u64::deserialize(deserializer)
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Wrapper {
+        #[serde(with = "super")]
+        hash: u64,
+    }
+
+    #[test]
+    fn json_is_hex_string() {
+        let w = Wrapper { hash: 0xDEAD_BEEF };
+        let json = serde_json::to_string(&w).unwrap();
+        assert_eq!(json, r#"{"hash":"00000000deadbeef"}"#);
+        assert_eq!(serde_json::from_str::<Wrapper>(&json).unwrap(), w);
+    }
+
+    #[test]
+    fn binary_is_raw_u64() {
+        let w = Wrapper { hash: u64::MAX };
+        let bytes = bincode::serialize(&w).unwrap();
+        assert_eq!(bytes, u64::MAX.to_le_bytes());
+        assert_eq!(bincode::deserialize::<Wrapper>(&bytes).unwrap(), w);
+    }
+
+    #[test]
+    fn rejects_bad_hex() {
+        for bad in [
+            r#"{"hash":"xyz"}"#,
+            r#"{"hash":""}"#,
+            r#"{"hash":"00000000000000000"}"#,
+            r#"{"hash":12}"#,
+        ] {
+            assert!(serde_json::from_str::<Wrapper>(bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -1,163 +1,1017 @@
-//! バイナリシリアライズ — `CrossingRecord` / `ProximityProof` の保存/復元。
+//! バイナリシリアライズ — `CrossingRecord` / `ProximityProof` /
+//! `GroupProximityProof` / `PresenceGroup` / `Session` の保存/復元。
 //!
-//! 外部依存ゼロの固定フォーマット。マジックナンバーで識別。
+//! 外部依存ゼロのバイナリフォーマット。マジックナンバーで識別。
+//! `CrossingRecord` はバージョンバイト + TLV 拡張付きのコンテナで保存し、
+//! 旧 121 バイト形式 (v0) も読み込める。
 
-use crate::event::{CrossingRecord, PresenceEvent, ProximityProof};
+use crate::error::{DecodeError, EncodeError};
+use crate::event::{
+    CrossingRecord, DistanceUnit, DwellInterval, EventType, PresenceEvent, ProximityProof,
+    SessionBinding,
+};
+use crate::group::{GroupConfig, GroupMember, GroupProximityProof, PresenceGroup, MAX_GROUP_SIZE};
 use crate::identity::ZkProof;
+use crate::session::{BackoffPolicy, CloseReason, Session, SessionConfig, SessionState};
+use crate::space::CoordinateSpace;
+use crate::stream::crc32;
 
 /// `ProximityProof` マジック。
 const MAGIC_PROX: [u8; 4] = *b"APRX";
-/// `CrossingRecord` マジック。
-const MAGIC_CROSS: [u8; 4] = *b"ACRS";
+/// 旧 (v0, バージョンなし) `CrossingRecord` マジック。
+const MAGIC_CROSS_V0: [u8; 4] = *b"ACRS";
+/// バージョン付き `CrossingRecord` マジック。
+const MAGIC_CROSS: [u8; 4] = *b"ACRV";
 
-// ── ProximityProof: 4 + 8*5 + 1 = 45 bytes ──
+// ── ProximityProof: 4 + 8*5 + 1 = 45 bytes (+ confidence 8 bytes) ──
+//
+// byte 20 はフラグ: bit0 = is_proximate、bit1 = 末尾に confidence (f64) あり、
+// bit2 = 単位がメートル (`DistanceUnit::Metres`)。
+
+/// confidence なし `ProximityProof` のバイトサイズ。
+pub const PROXIMITY_SIZE: usize = 45;
+
+/// フラグ: 近接。
+const PROOF_PROXIMATE: u8 = 0b01;
+/// フラグ: 末尾に confidence が続く。
+const PROOF_HAS_CONFIDENCE: u8 = 0b10;
+/// フラグ: 距離・閾値の単位がメートル。
+const PROOF_METRES: u8 = 0b100;
 
 /// `ProximityProof` をバイト列にシリアライズ。
 #[must_use]
 pub fn serialize_proximity(proof: &ProximityProof) -> Vec<u8> {
-    let mut buf = Vec::with_capacity(45);
+    let mut buf = Vec::with_capacity(PROXIMITY_SIZE + 8);
     buf.extend_from_slice(&MAGIC_PROX);
     buf.extend_from_slice(&proof.distance.to_le_bytes());
     buf.extend_from_slice(&proof.threshold.to_le_bytes());
-    buf.push(u8::from(proof.is_proximate));
+    let mut flags = u8::from(proof.is_proximate);
+    if proof.confidence.is_some() {
+        flags |= PROOF_HAS_CONFIDENCE;
+    }
+    if proof.unit == DistanceUnit::Metres {
+        flags |= PROOF_METRES;
+    }
+    buf.push(flags);
     buf.extend_from_slice(&proof.coord_hash_a.to_le_bytes());
     buf.extend_from_slice(&proof.coord_hash_b.to_le_bytes());
     buf.extend_from_slice(&proof.content_hash.to_le_bytes());
+    if let Some(c) = proof.confidence {
+        buf.extend_from_slice(&c.to_le_bytes());
+    }
     buf
 }
 
 /// バイト列から `ProximityProof` を復元。
+///
+/// # Errors
+///
+/// 長さ不足なら `DecodeError::TooShort`、マジック不一致なら `DecodeError::BadMagic`、
+/// 未定義のフラグビットなら `DecodeError::InvalidField`。
+pub fn try_deserialize_proximity(data: &[u8]) -> Result<ProximityProof, DecodeError> {
+    DecodeError::check_len(data, PROXIMITY_SIZE)?;
+    if data[..4] != MAGIC_PROX {
+        return Err(DecodeError::BadMagic);
+    }
+    let flags = data[20];
+    if flags & !(PROOF_PROXIMATE | PROOF_HAS_CONFIDENCE | PROOF_METRES) != 0 {
+        return Err(DecodeError::InvalidField("is_proximate"));
+    }
+    let confidence = if flags & PROOF_HAS_CONFIDENCE == 0 {
+        None
+    } else {
+        DecodeError::check_len(data, PROXIMITY_SIZE + 8)?;
+        Some(le_f64(data, PROXIMITY_SIZE))
+    };
+    Ok(ProximityProof {
+        distance: le_f64(data, 4),
+        threshold: le_f64(data, 12),
+        is_proximate: flags & PROOF_PROXIMATE != 0,
+        coord_hash_a: le_u64(data, 21),
+        coord_hash_b: le_u64(data, 29),
+        confidence,
+        unit: if flags & PROOF_METRES == 0 {
+            DistanceUnit::Network
+        } else {
+            DistanceUnit::Metres
+        },
+        content_hash: le_u64(data, 37),
+    })
+}
+
+/// バイト列から `ProximityProof` を復元 (失敗理由を捨てる旧 API)。
+#[deprecated(since = "0.2.0", note = "use `try_deserialize_proximity`")]
 #[must_use]
 pub fn deserialize_proximity(data: &[u8]) -> Option<ProximityProof> {
-    if data.len() < 45 || data[..4] != MAGIC_PROX {
-        return None;
-    }
-    let distance = f64::from_le_bytes(data[4..12].try_into().ok()?);
-    let threshold = f64::from_le_bytes(data[12..20].try_into().ok()?);
-    let is_proximate = data[20] != 0;
-    let coord_hash_a = u64::from_le_bytes(data[21..29].try_into().ok()?);
-    let coord_hash_b = u64::from_le_bytes(data[29..37].try_into().ok()?);
-    let content_hash = u64::from_le_bytes(data[37..45].try_into().ok()?);
-
-    Some(ProximityProof {
-        distance,
-        threshold,
-        is_proximate,
-        coord_hash_a,
-        coord_hash_b,
-        content_hash,
-    })
+    try_deserialize_proximity(data).ok()
+}
+
+/// `data[off..off + 8]` を LE `u64` として読む (長さは呼び出し側で検証済み)。
+pub(crate) fn le_u64(data: &[u8], off: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&data[off..off + 8]);
+    u64::from_le_bytes(b)
+}
+
+/// `data[off..off + 4]` を LE `u32` として読む (長さは呼び出し側で検証済み)。
+pub(crate) fn le_u32(data: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]])
+}
+
+/// `data[off..off + 8]` を LE `f64` として読む。
+pub(crate) fn le_f64(data: &[u8], off: usize) -> f64 {
+    f64::from_bits(le_u64(data, off))
 }
 
 // ── ZkProof helper: 8 + 8 + 8 + 1 = 25 bytes ──
 
-const ZKPROOF_SIZE: usize = 25;
+pub(crate) const ZKPROOF_SIZE: usize = 25;
 
-fn serialize_zkproof(proof: &ZkProof, buf: &mut Vec<u8>) {
+pub(crate) fn serialize_zkproof(proof: &ZkProof, buf: &mut Vec<u8>) {
     buf.extend_from_slice(&proof.challenge.to_le_bytes());
     buf.extend_from_slice(&proof.response.to_le_bytes());
     buf.extend_from_slice(&proof.commitment.to_le_bytes());
     buf.push(u8::from(proof.verified));
 }
 
-fn deserialize_zkproof(data: &[u8]) -> Option<ZkProof> {
-    if data.len() < ZKPROOF_SIZE {
-        return None;
+pub(crate) fn deserialize_zkproof(data: &[u8]) -> ZkProof {
+    ZkProof {
+        challenge: le_u64(data, 0),
+        response: le_u64(data, 8),
+        commitment: le_u64(data, 16),
+        verified: data[24] != 0,
     }
-    let challenge = u64::from_le_bytes(data[..8].try_into().ok()?);
-    let response = u64::from_le_bytes(data[8..16].try_into().ok()?);
-    let commitment = u64::from_le_bytes(data[16..24].try_into().ok()?);
-    let verified = data[24] != 0;
-    Some(ZkProof {
-        challenge,
-        response,
-        commitment,
-        verified,
-    })
 }
 
-// ── CrossingRecord: 4 + 18 + 25*2 + 41 + 8 = 121 bytes ──
-// proximity は magic なしで inline 埋め込み (41 bytes)
+// ── CrossingRecord ──
+//
+// v0 (旧形式, 121 bytes): [magic "ACRS"][core 117 bytes]
+// v1 (現行):              [magic "ACRV"][version: u8][core 117 bytes]
+//                         [ext_len: u16 LE][TLV 拡張 ...]
+//
+// core = event(18) + ZkProof A(25) + ZkProof B(25) + proximity inline(41) + content_hash(8)
+// TLV  = [type: u8][len: u16 LE][value: len bytes]
+// 未知の拡張 type は読み飛ばす。
 
-/// `CrossingRecord` の固定バイトサイズ。
-pub const CROSSING_RECORD_SIZE: usize = 4 + 18 + ZKPROOF_SIZE * 2 + 41 + 8;
+/// v0 (バージョンなし) `CrossingRecord` の固定バイトサイズ。
+pub const CROSSING_RECORD_SIZE: usize = 4 + CROSSING_CORE_SIZE;
 
-/// `CrossingRecord` をバイト列にシリアライズ。
-#[must_use]
-pub fn serialize_crossing(record: &CrossingRecord) -> Vec<u8> {
-    let mut buf = Vec::with_capacity(CROSSING_RECORD_SIZE);
-    buf.extend_from_slice(&MAGIC_CROSS);
+/// magic / version を除いたレコード本体のサイズ。
+const CROSSING_CORE_SIZE: usize = 18 + ZKPROOF_SIZE * 2 + PROXIMITY_INLINE_SIZE + 8;
+
+/// 拡張なし v1 レコードのサイズ: `magic(4) + version(1) + core(117) + ext_len(2)`。
+pub const CROSSING_RECORD_V1_MIN_SIZE: usize = 4 + 1 + CROSSING_CORE_SIZE + 2;
+
+/// 現行の `CrossingRecord` フォーマットバージョン。
+pub const CROSSING_FORMAT_VERSION: u8 = 1;
+
+/// inline `ProximityProof` (magic なし) のサイズ。
+pub(crate) const PROXIMITY_INLINE_SIZE: usize = 41;
+
+/// 拡張: 地理 proof (hybrid モード, 41 bytes)。
+pub const EXT_GEO_PROXIMITY: u8 = 0x01;
+/// 拡張: dwell 区間 (start/end 各 u64, 16 bytes)。
+pub const EXT_DWELL: u8 = 0x02;
+/// 拡張: セッション束縛 (A/B の session ID 各 u64, 16 bytes)。
+pub const EXT_SESSION: u8 = 0x03;
+/// 拡張: ネットワーク proof の confidence (f64, 8 bytes)。
+pub const EXT_CONFIDENCE: u8 = 0x04;
+/// 拡張: 地理 proof の confidence (f64, 8 bytes)。
+pub const EXT_GEO_CONFIDENCE: u8 = 0x05;
+/// 拡張: `proximity` の単位 (`DistanceUnit` タグ, 1 byte; `Network` なら省略)。
+pub const EXT_UNIT: u8 = 0x06;
+/// 拡張: 地理 proof の単位 (`DistanceUnit` タグ, 1 byte; `Network` なら省略)。
+pub const EXT_GEO_UNIT: u8 = 0x07;
+
+/// TLV ヘッダサイズ: `type(1) + len(2)`。
+const TLV_HEADER_SIZE: usize = 3;
+
+fn serialize_proximity_inline(proof: &ProximityProof) -> [u8; PROXIMITY_INLINE_SIZE] {
+    let mut v = [0u8; PROXIMITY_INLINE_SIZE];
+    v[..8].copy_from_slice(&proof.distance.to_le_bytes());
+    v[8..16].copy_from_slice(&proof.threshold.to_le_bytes());
+    v[16] = u8::from(proof.is_proximate);
+    v[17..25].copy_from_slice(&proof.coord_hash_a.to_le_bytes());
+    v[25..33].copy_from_slice(&proof.coord_hash_b.to_le_bytes());
+    v[33..].copy_from_slice(&proof.content_hash.to_le_bytes());
+    v
+}
+
+pub(crate) fn deserialize_proximity_inline(data: &[u8]) -> ProximityProof {
+    ProximityProof {
+        distance: le_f64(data, 0),
+        threshold: le_f64(data, 8),
+        is_proximate: data[16] != 0,
+        coord_hash_a: le_u64(data, 17),
+        coord_hash_b: le_u64(data, 25),
+        confidence: None,
+        unit: DistanceUnit::Network,
+        content_hash: le_u64(data, 33),
+    }
+}
+
+/// core 117 バイトを書き込む。
+fn write_core(record: &CrossingRecord, buf: &mut Vec<u8>) {
     // PresenceEvent (18 bytes)
     buf.extend_from_slice(&record.event.to_bytes());
-    // ZkProof A (17 bytes)
-    serialize_zkproof(&record.proof_a, &mut buf);
-    // ZkProof B (17 bytes)
-    serialize_zkproof(&record.proof_b, &mut buf);
+    // ZkProof A / B (25 bytes each)
+    serialize_zkproof(&record.proof_a, buf);
+    serialize_zkproof(&record.proof_b, buf);
     // ProximityProof inline (41 bytes, magic なし)
-    buf.extend_from_slice(&record.proximity.distance.to_le_bytes());
-    buf.extend_from_slice(&record.proximity.threshold.to_le_bytes());
-    buf.push(u8::from(record.proximity.is_proximate));
-    buf.extend_from_slice(&record.proximity.coord_hash_a.to_le_bytes());
-    buf.extend_from_slice(&record.proximity.coord_hash_b.to_le_bytes());
-    buf.extend_from_slice(&record.proximity.content_hash.to_le_bytes());
+    buf.extend_from_slice(&serialize_proximity_inline(&record.proximity));
     // content_hash (8 bytes)
     buf.extend_from_slice(&record.content_hash.to_le_bytes());
+}
+
+/// core 117 バイトから拡張なしのレコードを復元 (長さは検証済み)。
+pub(crate) fn read_core(data: &[u8]) -> CrossingRecord {
+    let mut ev_bytes = [0u8; 18];
+    ev_bytes.copy_from_slice(&data[..18]);
+    let mut off = 18;
+    let proof_a = deserialize_zkproof(&data[off..off + ZKPROOF_SIZE]);
+    off += ZKPROOF_SIZE;
+    let proof_b = deserialize_zkproof(&data[off..off + ZKPROOF_SIZE]);
+    off += ZKPROOF_SIZE;
+    let proximity = deserialize_proximity_inline(&data[off..off + PROXIMITY_INLINE_SIZE]);
+    off += PROXIMITY_INLINE_SIZE;
+    CrossingRecord {
+        event: PresenceEvent::from_bytes(&ev_bytes),
+        proof_a,
+        proof_b,
+        proximity,
+        geo_proximity: None,
+        dwell: None,
+        session: None,
+        content_hash: le_u64(data, off),
+    }
+}
+
+/// TLV を 1 つ書き込む。値は固定長配列に限り、長さが `u16` に
+/// 収まることをコンパイル時に検証する。
+fn write_tlv<const N: usize>(buf: &mut Vec<u8>, ty: u8, value: &[u8; N]) {
+    const { assert!(N <= u16::MAX as usize) };
+    buf.push(ty);
+    buf.extend_from_slice(&(N as u16).to_le_bytes());
+    buf.extend_from_slice(value);
+}
+
+/// `serialize_crossing` が書き出す拡張セクションの最大長
+/// (既知の拡張をすべて 1 つずつ持つ場合)。
+const MAX_EXT_SIZE: usize = TLV_HEADER_SIZE * 7 + PROXIMITY_INLINE_SIZE + 16 + 16 + 8 + 8 + 1 + 1;
+const _: () = assert!(MAX_EXT_SIZE <= u16::MAX as usize);
+
+/// `CrossingRecord` を現行 (v1) フォーマットでシリアライズ。
+///
+/// 地理 proof・dwell 区間・セッション束縛は TLV 拡張として書き込む。
+#[must_use]
+pub fn serialize_crossing(record: &CrossingRecord) -> Vec<u8> {
+    let mut ext = Vec::with_capacity(MAX_EXT_SIZE);
+    if let Some(geo) = &record.geo_proximity {
+        write_tlv(
+            &mut ext,
+            EXT_GEO_PROXIMITY,
+            &serialize_proximity_inline(geo),
+        );
+    }
+    if let Some(dwell) = &record.dwell {
+        let mut v = [0u8; 16];
+        v[..8].copy_from_slice(&dwell.start_ns.to_le_bytes());
+        v[8..].copy_from_slice(&dwell.end_ns.to_le_bytes());
+        write_tlv(&mut ext, EXT_DWELL, &v);
+    }
+    if let Some(session) = &record.session {
+        let mut v = [0u8; 16];
+        v[..8].copy_from_slice(&session.session_a.to_le_bytes());
+        v[8..].copy_from_slice(&session.session_b.to_le_bytes());
+        write_tlv(&mut ext, EXT_SESSION, &v);
+    }
+    if let Some(c) = record.proximity.confidence {
+        write_tlv(&mut ext, EXT_CONFIDENCE, &c.to_le_bytes());
+    }
+    if let Some(c) = record.geo_proximity.and_then(|g| g.confidence) {
+        write_tlv(&mut ext, EXT_GEO_CONFIDENCE, &c.to_le_bytes());
+    }
+    if record.proximity.unit != DistanceUnit::Network {
+        write_tlv(&mut ext, EXT_UNIT, &[record.proximity.unit as u8]);
+    }
+    if let Some(geo) = record
+        .geo_proximity
+        .filter(|g| g.unit != DistanceUnit::Network)
+    {
+        write_tlv(&mut ext, EXT_GEO_UNIT, &[geo.unit as u8]);
+    }
+
+    let mut buf = Vec::with_capacity(CROSSING_RECORD_V1_MIN_SIZE + ext.len());
+    buf.extend_from_slice(&MAGIC_CROSS);
+    buf.push(CROSSING_FORMAT_VERSION);
+    write_core(record, &mut buf);
+    buf.extend_from_slice(&(ext.len() as u16).to_le_bytes());
+    buf.extend_from_slice(&ext);
     buf
 }
 
+/// TLV 拡張セクションのイテレータ。
+///
+/// 途中切れの TLV を見つけたら `Err` を 1 回返して終了する。
+pub(crate) struct TlvIter<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> TlvIter<'a> {
+    pub(crate) const fn new(ext: &'a [u8]) -> Self {
+        Self { rest: ext }
+    }
+}
+
+impl<'a> Iterator for TlvIter<'a> {
+    type Item = Result<(u8, &'a [u8]), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let ext = self.rest;
+        let header = DecodeError::check_len(ext, TLV_HEADER_SIZE).map(|()| {
+            let len = u16::from_le_bytes([ext[1], ext[2]]) as usize;
+            (ext[0], len)
+        });
+        let item = header.and_then(|(ty, len)| {
+            DecodeError::check_len(ext, TLV_HEADER_SIZE + len)?;
+            Ok((ty, &ext[TLV_HEADER_SIZE..TLV_HEADER_SIZE + len]))
+        });
+        self.rest = match item {
+            Ok((_, value)) => &ext[TLV_HEADER_SIZE + value.len()..],
+            Err(_) => &[],
+        };
+        Some(item)
+    }
+}
+
+/// 既知の拡張の長さを検証する。未知の拡張は常に `Ok`。
+pub(crate) const fn check_extension(ty: u8, len: usize) -> Result<(), DecodeError> {
+    match ty {
+        EXT_GEO_PROXIMITY if len != PROXIMITY_INLINE_SIZE => {
+            Err(DecodeError::InvalidField("geo_proximity"))
+        }
+        EXT_DWELL if len != 16 => Err(DecodeError::InvalidField("dwell")),
+        EXT_SESSION if len != 16 => Err(DecodeError::InvalidField("session")),
+        EXT_CONFIDENCE | EXT_GEO_CONFIDENCE if len != 8 => {
+            Err(DecodeError::InvalidField("confidence"))
+        }
+        EXT_UNIT | EXT_GEO_UNIT if len != 1 => Err(DecodeError::InvalidField("unit")),
+        _ => Ok(()),
+    }
+}
+
+/// 長さ検証済みの dwell 拡張を読む。
+pub(crate) fn read_dwell(value: &[u8]) -> DwellInterval {
+    DwellInterval {
+        start_ns: le_u64(value, 0),
+        end_ns: le_u64(value, 8),
+    }
+}
+
+/// 長さ検証済みのセッション束縛拡張を読む。
+pub(crate) fn read_session(value: &[u8]) -> SessionBinding {
+    SessionBinding::new(le_u64(value, 0), le_u64(value, 8))
+}
+
+/// TLV 拡張セクション全体を検証する。
+///
+/// 既知の拡張の長さに加え、既知の type の重複 (同じバイト列が複数の
+/// 解釈を持たないよう)、地理 proof なしの地理 confidence / 単位、
+/// 未知または省略すべき (`Network`) 単位を拒否する。
+pub(crate) fn check_extensions(ext: &[u8]) -> Result<(), DecodeError> {
+    let mut seen = 0u8;
+    for item in TlvIter::new(ext) {
+        let (ty, value) = item?;
+        check_extension(ty, value.len())?;
+        let field = match ty {
+            EXT_GEO_PROXIMITY => "geo_proximity",
+            EXT_DWELL => "dwell",
+            EXT_SESSION => "session",
+            EXT_CONFIDENCE | EXT_GEO_CONFIDENCE => "confidence",
+            EXT_UNIT | EXT_GEO_UNIT => {
+                if read_unit(value) == DistanceUnit::Network {
+                    return Err(DecodeError::InvalidField("unit"));
+                }
+                "unit"
+            }
+            _ => continue,
+        };
+        if seen & (1 << ty) != 0 {
+            return Err(DecodeError::InvalidField(field));
+        }
+        seen |= 1 << ty;
+    }
+    let has_geo = seen & (1 << EXT_GEO_PROXIMITY) != 0;
+    let has_geo_confidence = seen & (1 << EXT_GEO_CONFIDENCE) != 0;
+    if has_geo_confidence && !has_geo {
+        return Err(DecodeError::InvalidField("confidence"));
+    }
+    if seen & (1 << EXT_GEO_UNIT) != 0 && !has_geo {
+        return Err(DecodeError::InvalidField("unit"));
+    }
+    Ok(())
+}
+
+/// 長さ検証済みの単位拡張を読む。未知のタグは `Network` として扱い、
+/// `check_extensions` で拒否する。
+pub(crate) fn read_unit(value: &[u8]) -> DistanceUnit {
+    DistanceUnit::from_u8(value[0]).unwrap_or(DistanceUnit::Network)
+}
+
+/// TLV 拡張セクションを読み、既知の拡張を `record` に反映する。
+fn read_extensions(record: &mut CrossingRecord, ext: &[u8]) -> Result<(), DecodeError> {
+    check_extensions(ext)?;
+    let mut geo_confidence = None;
+    let mut geo_unit = DistanceUnit::Network;
+    for (ty, value) in TlvIter::new(ext).map_while(Result::ok) {
+        match ty {
+            EXT_GEO_PROXIMITY => record.geo_proximity = Some(deserialize_proximity_inline(value)),
+            EXT_DWELL => record.dwell = Some(read_dwell(value)),
+            EXT_SESSION => record.session = Some(read_session(value)),
+            EXT_CONFIDENCE => record.proximity.confidence = Some(le_f64(value, 0)),
+            EXT_GEO_CONFIDENCE => geo_confidence = Some(le_f64(value, 0)),
+            EXT_UNIT => record.proximity.unit = read_unit(value),
+            EXT_GEO_UNIT => geo_unit = read_unit(value),
+            // 未知の拡張は読み飛ばす (前方互換)
+            _ => {}
+        }
+    }
+    if let Some(geo) = &mut record.geo_proximity {
+        geo.confidence = geo_confidence;
+        geo.unit = geo_unit;
+    }
+    Ok(())
+}
+
+/// 先頭の `CrossingRecord` 1 件を区切った結果。
+pub(crate) struct CrossingParts<'a> {
+    /// フォーマットバージョン (v0 は 0)。
+    pub(crate) version: u8,
+    /// core 117 バイト。
+    pub(crate) core: &'a [u8],
+    /// TLV 拡張セクション (v0 は空)。
+    pub(crate) ext: &'a [u8],
+    /// レコード全体のバイト数。
+    pub(crate) len: usize,
+}
+
+/// core 先頭の event の type / flags を検証する (長さは検証済み)。
+///
+/// crossing record の event は `Presence` でなければならない。
+fn check_event(core: &[u8]) -> Result<(), DecodeError> {
+    match core.first_chunk::<18>() {
+        Some(ev) => check_crossing_event(&PresenceEvent::try_from_bytes(ev)?),
+        None => Err(DecodeError::TooShort {
+            expected: 18,
+            actual: core.len(),
+        }),
+    }
+}
+
+/// crossing record に載せられる event か (`Presence` 型) を検証する。
+pub(crate) fn check_crossing_event(event: &PresenceEvent) -> Result<(), DecodeError> {
+    match event.kind()? {
+        EventType::Presence => Ok(()),
+        _ => Err(DecodeError::InvalidField("event_type")),
+    }
+}
+
+/// `data` 先頭の `CrossingRecord` 1 件の magic / version / event / 長さを検証して区切る。
+///
+/// 後続バイトは検査しない。
+pub(crate) fn split_crossing(data: &[u8]) -> Result<CrossingParts<'_>, DecodeError> {
+    DecodeError::check_len(data, 4)?;
+    if data[..4] == MAGIC_CROSS_V0 {
+        DecodeError::check_len(data, CROSSING_RECORD_SIZE)?;
+        check_event(&data[4..])?;
+        return Ok(CrossingParts {
+            version: 0,
+            core: &data[4..CROSSING_RECORD_SIZE],
+            ext: &[],
+            len: CROSSING_RECORD_SIZE,
+        });
+    }
+    if data[..4] != MAGIC_CROSS {
+        return Err(DecodeError::BadMagic);
+    }
+    DecodeError::check_len(data, 5)?;
+    if data[4] != CROSSING_FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(data[4]));
+    }
+    DecodeError::check_len(data, CROSSING_RECORD_V1_MIN_SIZE)?;
+    check_event(&data[5..])?;
+    let off = 5 + CROSSING_CORE_SIZE;
+    let ext_len = u16::from_le_bytes([data[off], data[off + 1]]) as usize;
+    let ext_start = off + 2;
+    DecodeError::check_len(data, ext_start + ext_len)?;
+    Ok(CrossingParts {
+        version: CROSSING_FORMAT_VERSION,
+        core: &data[5..off],
+        ext: &data[ext_start..ext_start + ext_len],
+        len: ext_start + ext_len,
+    })
+}
+
 /// バイト列から `CrossingRecord` を復元。
+///
+/// v1 (`ACRV`) と旧 v0 (`ACRS`, 121 bytes) の両方を受け付ける。
+/// v1 の未知の TLV 拡張は読み飛ばす。
+///
+/// # Errors
+///
+/// 長さ不足・TLV の途中切れは `DecodeError::TooShort`、
+/// 拡張セクションの後ろに余分なバイトがあれば `TrailingBytes`、
+/// マジック不一致は `BadMagic`、未対応バージョンは `UnsupportedVersion`、
+/// 未登録の event type は `UnknownType`、`Presence` 以外の event type・
+/// event の flags 不正・既知拡張の長さ不正は `InvalidField`。
+pub fn try_deserialize_crossing(data: &[u8]) -> Result<CrossingRecord, DecodeError> {
+    let parts = split_crossing(data)?;
+    if data.len() > parts.len {
+        return Err(DecodeError::TrailingBytes {
+            extra: data.len() - parts.len,
+        });
+    }
+    let mut record = read_core(parts.core);
+    read_extensions(&mut record, parts.ext)?;
+    Ok(record)
+}
+
+/// 旧 v0 (121 bytes, `ACRS`) のバイト列を現行フォーマットへ変換する。
+///
+/// content hash は core 部分のみで計算されているため変換後もそのまま検証できる。
+///
+/// # Errors
+///
+/// v0 として読めない場合 (`BadMagic` / `TooShort` / `TrailingBytes`)、
+/// および event が `try_deserialize_crossing` の検証に通らない場合
+/// (`UnknownType` / `InvalidField`)。
+pub fn migrate_crossing_v0(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    DecodeError::check_len(data, CROSSING_RECORD_SIZE)?;
+    if data[..4] != MAGIC_CROSS_V0 {
+        return Err(DecodeError::BadMagic);
+    }
+    if data.len() > CROSSING_RECORD_SIZE {
+        return Err(DecodeError::TrailingBytes {
+            extra: data.len() - CROSSING_RECORD_SIZE,
+        });
+    }
+    check_event(&data[4..])?;
+    Ok(serialize_crossing(&read_core(&data[4..])))
+}
+
+/// バイト列から `CrossingRecord` を復元 (失敗理由を捨てる旧 API)。
+#[deprecated(since = "0.2.0", note = "use `try_deserialize_crossing`")]
 #[must_use]
 pub fn deserialize_crossing(data: &[u8]) -> Option<CrossingRecord> {
-    if data.len() < CROSSING_RECORD_SIZE || data[..4] != MAGIC_CROSS {
-        return None;
+    try_deserialize_crossing(data).ok()
+}
+
+// ── GroupProximityProof ──
+//
+// [magic "AGRP"][version: u8][group_id: u64][max_distance: f64]
+// [threshold: f64][all_proximate: u8][member_count: u16 LE]
+// [member_id: u32 LE × member_count][content_hash: u64]
+//
+// メンバー数はメンバー ID リストの長さそのもの。構造体の `member_count`
+// は書き出さず、読み込み時に `member_ids.len()` から復元する。
+
+/// `GroupProximityProof` マジック。
+const MAGIC_GROUP: [u8; 4] = *b"AGRP";
+
+/// 現行の `GroupProximityProof` フォーマットバージョン。
+pub const GROUP_PROOF_FORMAT_VERSION: u8 = 1;
+
+/// メンバー ID リスト直前までのヘッダサイズ。
+const GROUP_HEADER_SIZE: usize = 4 + 1 + 8 + 8 + 8 + 1 + 2;
+
+/// メンバー 0 人の `GroupProximityProof` のサイズ (ヘッダ + content hash)。
+pub const GROUP_PROOF_MIN_SIZE: usize = GROUP_HEADER_SIZE + 8;
+
+/// `GroupProximityProof` をバイト列にシリアライズ。
+///
+/// # Errors
+///
+/// `member_ids` が `MAX_GROUP_SIZE` を超える場合 `EncodeError::TooManyMembers`
+/// (切り詰めると content hash と一致しなくなるため)。
+pub fn serialize_group_proof(proof: &GroupProximityProof) -> Result<Vec<u8>, EncodeError> {
+    let ids = &proof.member_ids;
+    if ids.len() > MAX_GROUP_SIZE {
+        return Err(EncodeError::TooManyMembers {
+            count: ids.len(),
+            max: MAX_GROUP_SIZE,
+        });
     }
-    let mut off = 4;
+    let mut buf = Vec::with_capacity(GROUP_PROOF_MIN_SIZE + ids.len() * 4);
+    buf.extend_from_slice(&MAGIC_GROUP);
+    buf.push(GROUP_PROOF_FORMAT_VERSION);
+    buf.extend_from_slice(&proof.group_id.to_le_bytes());
+    buf.extend_from_slice(&proof.max_distance.to_le_bytes());
+    buf.extend_from_slice(&proof.threshold.to_le_bytes());
+    buf.push(u8::from(proof.all_proximate));
+    buf.extend_from_slice(&(ids.len() as u16).to_le_bytes());
+    for id in ids {
+        buf.extend_from_slice(&id.to_le_bytes());
+    }
+    buf.extend_from_slice(&proof.content_hash.to_le_bytes());
+    Ok(buf)
+}
 
-    // PresenceEvent (18 bytes)
-    let ev_bytes: &[u8; 18] = data[off..off + 18].try_into().ok()?;
-    let event = PresenceEvent::from_bytes(ev_bytes);
-    off += 18;
+/// バイト列から `GroupProximityProof` を復元。
+///
+/// 構造の検証のみ行う。`group_id` / `content_hash` の整合性は
+/// [`crate::verification::verify_group_proof`] で確認すること。
+///
+/// # Errors
+///
+/// 長さ不足は `DecodeError::TooShort`、余分なバイトは `TrailingBytes`、
+/// マジック不一致は `BadMagic`、未対応バージョンは `UnsupportedVersion`、
+/// メンバー数が `MAX_GROUP_SIZE` 超過なら `InvalidField("member_count")`、
+/// メンバー ID が昇順でない (重複含む) なら `InvalidField("member_ids")`。
+pub fn try_deserialize_group_proof(data: &[u8]) -> Result<GroupProximityProof, DecodeError> {
+    DecodeError::check_len(data, 5)?;
+    if data[..4] != MAGIC_GROUP {
+        return Err(DecodeError::BadMagic);
+    }
+    if data[4] != GROUP_PROOF_FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(data[4]));
+    }
+    DecodeError::check_len(data, GROUP_PROOF_MIN_SIZE)?;
+    let count =
+        u16::from_le_bytes([data[GROUP_HEADER_SIZE - 2], data[GROUP_HEADER_SIZE - 1]]) as usize;
+    if count > MAX_GROUP_SIZE {
+        return Err(DecodeError::InvalidField("member_count"));
+    }
+    let total = GROUP_PROOF_MIN_SIZE + count * 4;
+    DecodeError::check_len(data, total)?;
+    if data.len() > total {
+        return Err(DecodeError::TrailingBytes {
+            extra: data.len() - total,
+        });
+    }
 
-    // ZkProof A
-    let proof_a = deserialize_zkproof(&data[off..off + ZKPROOF_SIZE])?;
-    off += ZKPROOF_SIZE;
+    let member_ids: Vec<u32> = data[GROUP_HEADER_SIZE..GROUP_HEADER_SIZE + count * 4]
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+    // group_id はソート済み ID から計算されるため、昇順以外は不正
+    if member_ids.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(DecodeError::InvalidField("member_ids"));
+    }
 
-    // ZkProof B
-    let proof_b = deserialize_zkproof(&data[off..off + ZKPROOF_SIZE])?;
-    off += ZKPROOF_SIZE;
+    Ok(GroupProximityProof {
+        group_id: le_u64(data, 5),
+        member_count: count,
+        member_ids,
+        max_distance: le_f64(data, 13),
+        threshold: le_f64(data, 21),
+        all_proximate: data[29] != 0,
+        content_hash: le_u64(data, total - 8),
+    })
+}
 
-    // ProximityProof inline (41 bytes)
-    let distance = f64::from_le_bytes(data[off..off + 8].try_into().ok()?);
-    off += 8;
-    let threshold = f64::from_le_bytes(data[off..off + 8].try_into().ok()?);
-    off += 8;
-    let is_proximate = data[off] != 0;
-    off += 1;
-    let coord_hash_a = u64::from_le_bytes(data[off..off + 8].try_into().ok()?);
-    off += 8;
-    let coord_hash_b = u64::from_le_bytes(data[off..off + 8].try_into().ok()?);
-    off += 8;
-    let prox_content_hash = u64::from_le_bytes(data[off..off + 8].try_into().ok()?);
-    off += 8;
-
-    let proximity = ProximityProof {
-        distance,
-        threshold,
-        is_proximate,
-        coord_hash_a,
-        coord_hash_b,
-        content_hash: prox_content_hash,
+// ── PresenceGroup ──
+//
+// [magic "AGRM"][version: u8][proximity_threshold: f64][min_members: u64]
+// [coord_len: u16 LE][member_count: u16 LE]
+// [member_id: u32 | joined_ns: u64 | coord: coord_len bytes] × member_count
+//
+// `coord_len` は `C::ENCODED_LEN`。異なる座標空間で書かれたデータを
+// 長さの不一致として検出する (同じ長さの空間同士は区別できない)。
+
+/// `PresenceGroup` マジック。
+const MAGIC_GROUP_STATE: [u8; 4] = *b"AGRM";
+
+/// 現行の `PresenceGroup` フォーマットバージョン。
+pub const GROUP_FORMAT_VERSION: u8 = 1;
+
+/// メンバー列直前までのヘッダサイズ。
+const GROUP_STATE_HEADER_SIZE: usize = 4 + 1 + 8 + 8 + 2 + 2;
+
+/// `PresenceGroup` をバイト列にシリアライズ。
+///
+/// メンバー数は `PresenceGroup` の不変条件により `MAX_GROUP_SIZE` 以下。
+#[must_use]
+pub fn serialize_group<C: CoordinateSpace>(group: &PresenceGroup<C>) -> Vec<u8> {
+    let members = group.members();
+    let config = group.config();
+    let mut buf =
+        Vec::with_capacity(GROUP_STATE_HEADER_SIZE + members.len() * (12 + C::ENCODED_LEN));
+    buf.extend_from_slice(&MAGIC_GROUP_STATE);
+    buf.push(GROUP_FORMAT_VERSION);
+    buf.extend_from_slice(&config.proximity_threshold.to_le_bytes());
+    buf.extend_from_slice(&(config.min_members as u64).to_le_bytes());
+    buf.extend_from_slice(&(C::ENCODED_LEN as u16).to_le_bytes());
+    buf.extend_from_slice(&(members.len() as u16).to_le_bytes());
+    for m in members {
+        buf.extend_from_slice(&m.id.to_le_bytes());
+        buf.extend_from_slice(&m.joined_ns.to_le_bytes());
+        m.coord.encode(&mut buf);
+    }
+    buf
+}
+
+/// バイト列から `PresenceGroup` を復元。
+///
+/// メンバーは [`PresenceGroup::try_with_members`] で追加し直すため、
+/// 上限と重複の検査は `try_add_member` と同じ。
+///
+/// # Errors
+///
+/// 長さ不足は `DecodeError::TooShort`、余分なバイトは `TrailingBytes`、
+/// マジック不一致は `BadMagic`、未対応バージョンは `UnsupportedVersion`、
+/// 閾値が有限でなければ `InvalidField("proximity_threshold")`、
+/// 座標長が `C::ENCODED_LEN` と異なる・座標が不正なら `InvalidField("coord")`、
+/// メンバー数が `MAX_GROUP_SIZE` 超過なら `InvalidField("member_count")`、
+/// ID の重複は `InvalidField("member_ids")`。
+pub fn try_deserialize_group<C: CoordinateSpace>(
+    data: &[u8],
+) -> Result<PresenceGroup<C>, DecodeError> {
+    DecodeError::check_len(data, 5)?;
+    if data[..4] != MAGIC_GROUP_STATE {
+        return Err(DecodeError::BadMagic);
+    }
+    if data[4] != GROUP_FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(data[4]));
+    }
+    DecodeError::check_len(data, GROUP_STATE_HEADER_SIZE)?;
+    let proximity_threshold = le_f64(data, 5);
+    if !proximity_threshold.is_finite() {
+        return Err(DecodeError::InvalidField("proximity_threshold"));
+    }
+    let min_members =
+        usize::try_from(le_u64(data, 13)).map_err(|_| DecodeError::InvalidField("min_members"))?;
+    let coord_len = u16::from_le_bytes([data[21], data[22]]) as usize;
+    if coord_len != C::ENCODED_LEN {
+        return Err(DecodeError::InvalidField("coord"));
+    }
+    let count = u16::from_le_bytes([data[23], data[24]]) as usize;
+    if count > MAX_GROUP_SIZE {
+        return Err(DecodeError::InvalidField("member_count"));
+    }
+    let stride = 12 + coord_len;
+    let total = GROUP_STATE_HEADER_SIZE + count * stride;
+    DecodeError::check_len(data, total)?;
+    if data.len() > total {
+        return Err(DecodeError::TrailingBytes {
+            extra: data.len() - total,
+        });
+    }
+
+    let members = data[GROUP_STATE_HEADER_SIZE..]
+        .chunks_exact(stride)
+        .map(|m| {
+            Ok(GroupMember {
+                id: le_u32(m, 0),
+                joined_ns: le_u64(m, 4),
+                coord: C::decode(&m[12..]).ok_or(DecodeError::InvalidField("coord"))?,
+            })
+        })
+        .collect::<Result<Vec<_>, DecodeError>>()?;
+    let config = GroupConfig {
+        proximity_threshold,
+        min_members,
     };
+    PresenceGroup::try_with_members(config, members)
+        .map_err(|_| DecodeError::InvalidField("member_ids"))
+}
 
-    let content_hash = u64::from_le_bytes(data[off..off + 8].try_into().ok()?);
+// ── Session ──
+//
+// [magic "ASES"][version: u8][session_id: u64][state: u8][local_id: u32]
+// [has_remote: u8][remote_id: u32][state_entered_ns: u64][created_ns: u64]
+// [carried_state_ns: u64][carried_total_ns: u64][retries: u32][close_reason: u8]
+// [discovery_timeout_ns: u64][exchange_timeout_ns: u64][max_retries: u32]
+// [backoff tag: u8][backoff params: u64 × 3][content_hash: u64]
+// [saved_ns: u64][crc32: u32 LE]
+//
+// close_reason は 0 = なし、1.. = `CloseReason` の順序 + 1。backoff は
+// tag 0 = Fixed (`delay_ns`, 0, 0)、tag 1 = Exponential (`initial_ns`,
+// `max_ns`, jitter の f64 ビット列)。遷移履歴 (`history`) は保存しない。
 
-    Some(CrossingRecord {
-        event,
-        proof_a,
-        proof_b,
-        proximity,
-        content_hash,
-    })
+/// `Session` スナップショットのマジック。
+const MAGIC_SESSION: [u8; 4] = *b"ASES";
+
+/// 現行の `Session` スナップショットのフォーマットバージョン。
+pub const SESSION_FORMAT_VERSION: u8 = 1;
+
+/// `Session` スナップショットのサイズ。
+pub const SESSION_SNAPSHOT_SIZE: usize =
+    4 + 1 + 8 + 1 + 4 + 1 + 4 + 8 + 8 + 8 + 8 + 4 + 1 + 8 + 8 + 4 + 1 + 8 * 3 + 8 + 8 + 4;
+
+const CLOSE_REASONS: [CloseReason; 5] = [
+    CloseReason::Success,
+    CloseReason::Timeout,
+    CloseReason::Cancelled,
+    CloseReason::ProximityFailed,
+    CloseReason::VerificationFailed,
+];
+
+const SESSION_STATES: [SessionState; 5] = [
+    SessionState::Idle,
+    SessionState::Discovering,
+    SessionState::Exchanging,
+    SessionState::Verified,
+    SessionState::Closed,
+];
+
+/// スナップショット保存時と復元時の時計の関係。
+///
+/// 呼び出し側が決める。時刻の大小から推測はしない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// 保存時と同じ時計 (`now_ns >= saved_ns`)。タイムスタンプはそのまま
+    /// 残し、停止中の経過時間もタイムアウトに数える。
+    SameClock,
+    /// 時計がリセットされた (再起動後の単調時計など)。停止時間は不明と
+    /// して数えず、保存時点の経過時間を `carried_state_ns` /
+    /// `carried_total_ns` に持ち越して `now_ns` から再開する。
+    ClockReset,
+}
+
+/// `Session` を時刻 `saved_ns` のスナップショットとしてシリアライズ。
+///
+/// プロセスが終了しても交換を続けられるよう、状態・リモート ID・
+/// タイムスタンプ・持ち越し経過時間・リトライ回数・close 理由・設定・
+/// content hash を CRC32 付きで保存する。`saved_ns` はセッションの
+/// タイムスタンプと同じ時計で測った現在時刻。遷移履歴は保存しない。
+#[must_use]
+pub fn serialize_session(session: &Session, saved_ns: u64) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(SESSION_SNAPSHOT_SIZE);
+    buf.extend_from_slice(&MAGIC_SESSION);
+    buf.push(SESSION_FORMAT_VERSION);
+    buf.extend_from_slice(&session.session_id.to_le_bytes());
+    buf.push(session.state as u8);
+    buf.extend_from_slice(&session.local_id.to_le_bytes());
+    buf.push(u8::from(session.remote_id.is_some()));
+    buf.extend_from_slice(&session.remote_id.unwrap_or(0).to_le_bytes());
+    buf.extend_from_slice(&session.state_entered_ns.to_le_bytes());
+    buf.extend_from_slice(&session.created_ns.to_le_bytes());
+    buf.extend_from_slice(&session.carried_state_ns.to_le_bytes());
+    buf.extend_from_slice(&session.carried_total_ns.to_le_bytes());
+    buf.extend_from_slice(&session.retries.to_le_bytes());
+    buf.push(session.close_reason.map_or(0, |r| {
+        CLOSE_REASONS.iter().position(|&c| c == r).unwrap_or(0) as u8 + 1
+    }));
+    let config = &session.config;
+    buf.extend_from_slice(&config.discovery_timeout_ns.to_le_bytes());
+    buf.extend_from_slice(&config.exchange_timeout_ns.to_le_bytes());
+    buf.extend_from_slice(&config.max_retries.to_le_bytes());
+    let (tag, params) = match config.backoff {
+        BackoffPolicy::Fixed { delay_ns } => (0u8, [delay_ns, 0, 0]),
+        BackoffPolicy::Exponential {
+            initial_ns,
+            max_ns,
+            jitter,
+        } => (1, [initial_ns, max_ns, jitter.to_bits()]),
+    };
+    buf.push(tag);
+    for p in params {
+        buf.extend_from_slice(&p.to_le_bytes());
+    }
+    buf.extend_from_slice(&session.content_hash.to_le_bytes());
+    buf.extend_from_slice(&saved_ns.to_le_bytes());
+    let crc = crc32(&buf);
+    buf.extend_from_slice(&crc.to_le_bytes());
+    buf
+}
+
+/// スナップショットから `Session` を復元し、時刻 `now_ns` で再開する。
+///
+/// `Resume::SameClock` ではタイムスタンプをそのまま残すので、停止中の
+/// 経過時間もタイムアウトに数え、期限を過ぎていれば `is_timed_out` が
+/// すぐ真になる。`Resume::ClockReset` では `state_entered_ns` と
+/// `created_ns` を `now_ns` に置き、保存時点の経過時間を
+/// `carried_state_ns` / `carried_total_ns` として持ち越して content hash を
+/// 再計算する (`now_ns` が経過時間より小さくても失われない)。
+/// `session_id` は変わらない。遷移履歴は保存されないため、復元した
+/// セッションの `history` は `None`。
+///
+/// # Errors
+///
+/// 長さ不正は `TooShort` / `TrailingBytes`、マジック不一致は `BadMagic`、
+/// 未対応バージョンは `UnsupportedVersion`、CRC 不一致は `Checksum`。
+/// 状態・close 理由・backoff の値が不正 (Fixed の未使用パラメータが
+/// 0 でない場合を含む)、状態と remote ID / close 理由の有無が食い違う、
+/// リトライ回数が上限を超える、タイムスタンプが
+/// `created_ns <= state_entered_ns <= saved_ns` を満たさない、content hash が
+/// 再計算値と一致しない、または `Resume::SameClock` で `now_ns` が
+/// `saved_ns` より前の場合は `InvalidField`。
+pub fn try_deserialize_session(
+    data: &[u8],
+    now_ns: u64,
+    resume: Resume,
+) -> Result<Session, DecodeError> {
+    DecodeError::check_len(data, 5)?;
+    if data[..4] != MAGIC_SESSION {
+        return Err(DecodeError::BadMagic);
+    }
+    if data[4] != SESSION_FORMAT_VERSION {
+        return Err(DecodeError::UnsupportedVersion(data[4]));
+    }
+    DecodeError::check_len(data, SESSION_SNAPSHOT_SIZE)?;
+    if data.len() > SESSION_SNAPSHOT_SIZE {
+        return Err(DecodeError::TrailingBytes {
+            extra: data.len() - SESSION_SNAPSHOT_SIZE,
+        });
+    }
+    let crc_off = SESSION_SNAPSHOT_SIZE - 4;
+    if crc32(&data[..crc_off]) != le_u32(data, crc_off) {
+        return Err(DecodeError::Checksum);
+    }
+
+    let state = *SESSION_STATES
+        .get(usize::from(data[13]))
+        .ok_or(DecodeError::InvalidField("state"))?;
+    let remote_id = match data[18] {
+        0 => None,
+        1 => Some(le_u32(data, 19)),
+        _ => return Err(DecodeError::InvalidField("remote_id")),
+    };
+    let close_reason = match data[59] {
+        0 => None,
+        n => Some(
+            *CLOSE_REASONS
+                .get(usize::from(n) - 1)
+                .ok_or(DecodeError::InvalidField("close_reason"))?,
+        ),
+    };
+    let backoff = match data[80] {
+        0 if le_u64(data, 89) == 0 && le_u64(data, 97) == 0 => BackoffPolicy::Fixed {
+            delay_ns: le_u64(data, 81),
+        },
+        1 if le_f64(data, 97).is_finite() => BackoffPolicy::Exponential {
+            initial_ns: le_u64(data, 81),
+            max_ns: le_u64(data, 89),
+            jitter: le_f64(data, 97),
+        },
+        _ => return Err(DecodeError::InvalidField("backoff")),
+    };
+    let config = SessionConfig {
+        discovery_timeout_ns: le_u64(data, 60),
+        exchange_timeout_ns: le_u64(data, 68),
+        max_retries: le_u32(data, 76),
+        backoff,
+    };
+    let mut session = Session {
+        session_id: le_u64(data, 5),
+        state,
+        local_id: le_u32(data, 14),
+        remote_id,
+        state_entered_ns: le_u64(data, 23),
+        created_ns: le_u64(data, 31),
+        carried_state_ns: le_u64(data, 39),
+        carried_total_ns: le_u64(data, 47),
+        retries: le_u32(data, 55),
+        close_reason,
+        config,
+        content_hash: le_u64(data, 105),
+        history: None,
+    };
+    let saved_ns = le_u64(data, 113);
+
+    // Discovering 以降は remote ID を持ち、Idle は持たない
+    // (Idle から Closed になった場合は持たない)
+    let needs_remote = matches!(
+        state,
+        SessionState::Discovering | SessionState::Exchanging | SessionState::Verified
+    );
+    if (needs_remote && remote_id.is_none()) || (state == SessionState::Idle && remote_id.is_some())
+    {
+        return Err(DecodeError::InvalidField("remote_id"));
+    }
+    if (state == SessionState::Closed) != close_reason.is_some() {
+        return Err(DecodeError::InvalidField("close_reason"));
+    }
+    if session.retries > config.max_retries {
+        return Err(DecodeError::InvalidField("retries"));
+    }
+    if session.created_ns > session.state_entered_ns {
+        return Err(DecodeError::InvalidField("created_ns"));
+    }
+    if session.state_entered_ns > saved_ns {
+        return Err(DecodeError::InvalidField("saved_ns"));
+    }
+    if session.compute_hash() != session.content_hash {
+        return Err(DecodeError::InvalidField("content_hash"));
+    }
+
+    match resume {
+        Resume::SameClock if now_ns < saved_ns => {
+            return Err(DecodeError::InvalidField("saved_ns"));
+        }
+        Resume::SameClock => {}
+        Resume::ClockReset => {
+            session.carried_state_ns = session.state_duration_ns(saved_ns);
+            session.carried_total_ns = session.total_duration_ns(saved_ns);
+            session.state_entered_ns = now_ns;
+            session.created_ns = now_ns;
+            session.content_hash = session.compute_hash();
+        }
+    }
+    Ok(session)
 }
 
 // ============================================================================
@@ -165,9 +1019,11 @@ pub fn deserialize_crossing(data: &[u8]) -> Option<CrossingRecord> {
 // ============================================================================
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
     use crate::identity::IdentityCommitment;
+    use crate::protocol::exchange_challenges;
     use crate::vivaldi::VivaldiCoord;
 
     fn make_record() -> CrossingRecord {
@@ -176,8 +1032,9 @@ mod tests {
         let prox = ProximityProof::prove(&a, &b, 10.0);
         let ca = IdentityCommitment::new(42, 1, 100);
         let cb = IdentityCommitment::new(99, 2, 100);
-        let pa = ZkProof::prove(42, &ca, 0xAA);
-        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let (challenge_a, challenge_b) = exchange_challenges(100, None);
+        let pa = ZkProof::prove(42, &ca, challenge_a);
+        let pb = ZkProof::prove(99, &cb, challenge_b);
         let mut event = PresenceEvent::new(1, 2, 100);
         event.set_mutual();
         event.set_verified();
@@ -220,7 +1077,7 @@ mod tests {
     fn crossing_roundtrip() {
         let record = make_record();
         let bytes = serialize_crossing(&record);
-        assert_eq!(bytes.len(), CROSSING_RECORD_SIZE);
+        assert_eq!(bytes.len(), CROSSING_RECORD_V1_MIN_SIZE);
         let restored = deserialize_crossing(&bytes).unwrap();
         assert_eq!(restored.event.party_a_id, record.event.party_a_id);
         assert_eq!(restored.event.party_b_id, record.event.party_b_id);
@@ -274,4 +1131,791 @@ mod tests {
         let restored = deserialize_proximity(&bytes).unwrap();
         assert!(!restored.is_proximate);
     }
+
+    #[test]
+    fn crossing_geo_roundtrip() {
+        use crate::geo::GeoCoord;
+        let geo = ProximityProof::prove(
+            &GeoCoord::new(35.6812, 139.7671),
+            &GeoCoord::new(35.6813, 139.7672),
+            50.0,
+        );
+        let record = make_record().with_geo_proximity(geo);
+        let bytes = serialize_crossing(&record);
+        assert_eq!(bytes.len(), CROSSING_RECORD_V1_MIN_SIZE + 3 + 41 + 3 + 1);
+        let restored = deserialize_crossing(&bytes).unwrap();
+        let g = restored.geo_proximity.unwrap();
+        assert_eq!(g.unit, DistanceUnit::Metres);
+        assert_eq!(g.content_hash, geo.content_hash);
+        assert_eq!(restored.content_hash, record.content_hash);
+        assert_eq!(
+            crate::verification::verify_record(&restored),
+            crate::verification::VerifyResult::Valid
+        );
+    }
+
+    #[test]
+    fn crossing_dwell_roundtrip() {
+        let dwell = DwellInterval {
+            start_ns: 1_000,
+            end_ns: 301_000,
+        };
+        let record = make_record().with_dwell(dwell);
+        let bytes = serialize_crossing(&record);
+        assert_eq!(bytes.len(), CROSSING_RECORD_V1_MIN_SIZE + 3 + 16);
+        let restored = deserialize_crossing(&bytes).unwrap();
+        assert_eq!(restored.dwell, Some(dwell));
+        assert!(restored.geo_proximity.is_none());
+        assert_eq!(
+            crate::verification::verify_record(&restored),
+            crate::verification::VerifyResult::Valid
+        );
+
+        // dwell 拡張の途中で切れている
+        assert!(deserialize_crossing(&bytes[..bytes.len() - 8]).is_none());
+    }
+
+    #[test]
+    fn crossing_session_roundtrip() {
+        let session = SessionBinding::new(0x1111, 0x2222);
+        let record = make_record().with_session(session);
+        let bytes = serialize_crossing(&record);
+        assert_eq!(bytes.len(), CROSSING_RECORD_V1_MIN_SIZE + 3 + 16);
+        let restored = try_deserialize_crossing(&bytes).unwrap();
+        assert_eq!(restored.session, Some(session));
+        assert_eq!(restored.content_hash, record.content_hash);
+        assert!(crate::verification::verify_record_hash(&restored));
+
+        // 長さ不正のセッション拡張
+        let mut bad = serialize_crossing(&make_record());
+        let n = bad.len();
+        bad[n - 2..].copy_from_slice(&5u16.to_le_bytes());
+        bad.extend_from_slice(&[EXT_SESSION, 2, 0, 0, 0]);
+        assert_eq!(
+            try_deserialize_crossing(&bad).unwrap_err(),
+            DecodeError::InvalidField("session")
+        );
+    }
+
+    #[test]
+    fn proximity_confidence_roundtrip() {
+        let proof = ProximityProof::prove(
+            &VivaldiCoord::new(0.0, 0.0),
+            &VivaldiCoord::new(1.0, 0.0),
+            10.0,
+        )
+        .with_confidence(0.97);
+        let bytes = serialize_proximity(&proof);
+        assert_eq!(bytes.len(), PROXIMITY_SIZE + 8);
+        let restored = try_deserialize_proximity(&bytes).unwrap();
+        assert_eq!(restored.confidence, Some(0.97));
+        assert!(crate::verification::verify_proximity(&restored));
+
+        // confidence フラグがあるのに末尾が欠けている
+        assert!(matches!(
+            try_deserialize_proximity(&bytes[..PROXIMITY_SIZE]),
+            Err(DecodeError::TooShort { .. })
+        ));
+        // 未定義のフラグビット
+        let mut bad = bytes;
+        bad[20] |= 0x80;
+        assert_eq!(
+            try_deserialize_proximity(&bad).unwrap_err(),
+            DecodeError::InvalidField("is_proximate")
+        );
+    }
+
+    #[test]
+    fn crossing_confidence_roundtrip() {
+        use crate::geo::GeoCoord;
+        let geo = ProximityProof::prove(
+            &GeoCoord::new(35.6812, 139.7671),
+            &GeoCoord::new(35.6813, 139.7672),
+            50.0,
+        )
+        .with_confidence(0.8);
+        let mut record = make_record();
+        record.proximity = record.proximity.with_confidence(0.9);
+        let record = CrossingRecord::new(
+            record.event,
+            record.proof_a,
+            record.proof_b,
+            record.proximity,
+        )
+        .with_geo_proximity(geo);
+        let bytes = serialize_crossing(&record);
+        assert_eq!(
+            bytes.len(),
+            CROSSING_RECORD_V1_MIN_SIZE + 3 + 41 + 3 + 8 + 3 + 8 + 3 + 1
+        );
+        let restored = try_deserialize_crossing(&bytes).unwrap();
+        assert_eq!(restored.proximity.confidence, Some(0.9));
+        assert_eq!(restored.geo_proximity.unwrap().confidence, Some(0.8));
+        assert!(crate::verification::verify_record_hash(&restored));
+        assert!(crate::verification::verify_proximity(&restored.proximity));
+
+        // 地理 proof なしの地理 confidence
+        let mut bad = serialize_crossing(&make_record());
+        let n = bad.len();
+        bad[n - 2..].copy_from_slice(&11u16.to_le_bytes());
+        bad.extend_from_slice(&[EXT_GEO_CONFIDENCE, 8, 0]);
+        bad.extend_from_slice(&0.5f64.to_le_bytes());
+        assert_eq!(
+            try_deserialize_crossing(&bad).unwrap_err(),
+            DecodeError::InvalidField("confidence")
+        );
+    }
+
+    #[test]
+    fn crossing_unit_roundtrip() {
+        use crate::geo::GeoCoord;
+        use crate::protocol::{try_execute_presence_protocol, PartyInfo, PresenceConfig};
+        let party = |id, lat| {
+            PartyInfo::new(VivaldiCoord::new(0.0, 0.0), u64::from(id), id)
+                .with_geo(GeoCoord::new(lat, 139.7671))
+        };
+        let config = PresenceConfig {
+            mode: crate::protocol::ProximityMode::Geographic,
+            ..PresenceConfig::default()
+        };
+        let record =
+            try_execute_presence_protocol(&party(1, 35.6812), &party(2, 35.6813), 1_000, &config)
+                .unwrap();
+        assert_eq!(record.proximity.unit, DistanceUnit::Metres);
+        let bytes = serialize_crossing(&record);
+        let restored = try_deserialize_crossing(&bytes).unwrap();
+        assert_eq!(restored.proximity.unit, DistanceUnit::Metres);
+        assert_eq!(restored.content_hash, record.content_hash);
+        assert!(crate::verification::verify_proximity(&restored.proximity));
+
+        // 単位を落とすと proof の hash が合わない
+        let mut network = restored.proximity;
+        network.unit = DistanceUnit::Network;
+        assert!(!crate::verification::verify_proximity(&network));
+
+        // 既定値 (Network) と未定義の単位は拒否
+        let plain = serialize_crossing(&make_record());
+        let n = plain.len();
+        for (ty, value) in [(EXT_UNIT, 0), (EXT_UNIT, 9), (EXT_GEO_UNIT, 1)] {
+            let mut bad = plain.clone();
+            bad[n - 2..].copy_from_slice(&4u16.to_le_bytes());
+            bad.extend_from_slice(&[ty, 1, 0, value]);
+            assert_eq!(
+                try_deserialize_crossing(&bad).unwrap_err(),
+                DecodeError::InvalidField("unit"),
+                "{ty} {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn crossing_geo_and_dwell_roundtrip() {
+        use crate::geo::GeoCoord;
+        let geo = ProximityProof::prove(
+            &GeoCoord::new(35.6812, 139.7671),
+            &GeoCoord::new(35.6813, 139.7672),
+            50.0,
+        );
+        let dwell = DwellInterval {
+            start_ns: 5,
+            end_ns: 10,
+        };
+        let record = make_record().with_geo_proximity(geo).with_dwell(dwell);
+        let bytes = serialize_crossing(&record);
+        assert_eq!(
+            bytes.len(),
+            CROSSING_RECORD_V1_MIN_SIZE + 3 + 41 + 3 + 16 + 3 + 1
+        );
+        let restored = deserialize_crossing(&bytes).unwrap();
+        assert_eq!(restored.dwell, Some(dwell));
+        assert_eq!(
+            restored.geo_proximity.unwrap().content_hash,
+            geo.content_hash
+        );
+        assert_eq!(restored.content_hash, record.content_hash);
+    }
+
+    #[test]
+    fn try_deserialize_reports_reason() {
+        assert_eq!(
+            try_deserialize_proximity(&[0; 10]).unwrap_err(),
+            DecodeError::TooShort {
+                expected: 45,
+                actual: 10
+            }
+        );
+        let mut bytes = serialize_crossing(&make_record());
+        assert!(try_deserialize_crossing(&bytes).is_ok());
+        bytes[0] = b'X';
+        assert_eq!(
+            try_deserialize_crossing(&bytes).unwrap_err(),
+            DecodeError::BadMagic
+        );
+    }
+
+    /// v1 バイト列から同じ core を持つ v0 (121 bytes) を作る。
+    fn to_v0(v1: &[u8]) -> Vec<u8> {
+        let mut v0 = MAGIC_CROSS_V0.to_vec();
+        v0.extend_from_slice(&v1[5..5 + CROSSING_CORE_SIZE]);
+        v0
+    }
+
+    #[test]
+    fn crossing_header_has_version() {
+        let bytes = serialize_crossing(&make_record());
+        assert_eq!(&bytes[..4], b"ACRV");
+        assert_eq!(bytes[4], CROSSING_FORMAT_VERSION);
+        assert_eq!(CROSSING_RECORD_V1_MIN_SIZE, 124);
+    }
+
+    #[test]
+    fn crossing_reads_v0_layout() {
+        let record = make_record();
+        let v0 = to_v0(&serialize_crossing(&record));
+        assert_eq!(v0.len(), CROSSING_RECORD_SIZE);
+        let restored = try_deserialize_crossing(&v0).unwrap();
+        assert_eq!(restored.content_hash, record.content_hash);
+        assert_eq!(
+            crate::verification::verify_record(&restored),
+            crate::verification::VerifyResult::Valid
+        );
+
+        // v0 も v1 と同様に後続バイトを拒否する
+        let mut long = v0;
+        long.push(0);
+        assert_eq!(
+            try_deserialize_crossing(&long).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+        assert_eq!(
+            migrate_crossing_v0(&long).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+    }
+
+    #[test]
+    fn crossing_migrates_v0() {
+        let record = make_record();
+        let v1 = serialize_crossing(&record);
+        let migrated = migrate_crossing_v0(&to_v0(&v1)).unwrap();
+        assert_eq!(migrated, v1);
+        assert_eq!(migrate_crossing_v0(&v1), Err(DecodeError::BadMagic));
+        assert!(matches!(
+            migrate_crossing_v0(&to_v0(&v1)[..100]),
+            Err(DecodeError::TooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn crossing_migration_validates_event() {
+        // 検証付きリーダーが拒否する v0 は変換もしない
+        let v0 = to_v0(&serialize_crossing(&make_record()));
+        let mut heartbeat = v0.clone();
+        heartbeat[4] = EventType::Heartbeat as u8;
+        heartbeat[5] = 0;
+        let mut unknown = v0.clone();
+        unknown[4] = 0x00;
+        let mut flags = v0;
+        flags[5] = 0xFF;
+        for bad in [heartbeat, unknown, flags] {
+            let err = try_deserialize_crossing(&bad).unwrap_err();
+            assert_eq!(migrate_crossing_v0(&bad), Err(err));
+        }
+    }
+
+    #[test]
+    fn crossing_skips_unknown_extensions() {
+        let dwell = DwellInterval {
+            start_ns: 7,
+            end_ns: 9,
+        };
+        let record = make_record().with_dwell(dwell);
+        let bytes = serialize_crossing(&record);
+
+        // 未知の拡張 (type 0xEE, 5 bytes) を先頭に差し込む
+        let ext_off = 5 + CROSSING_CORE_SIZE;
+        let ext_len = u16::from_le_bytes([bytes[ext_off], bytes[ext_off + 1]]);
+        let mut patched = bytes[..ext_off].to_vec();
+        patched.extend_from_slice(&(ext_len + 8).to_le_bytes());
+        patched.extend_from_slice(&[0xEE, 5, 0, 1, 2, 3, 4, 5]);
+        patched.extend_from_slice(&bytes[ext_off + 2..]);
+
+        let restored = try_deserialize_crossing(&patched).unwrap();
+        assert_eq!(restored.dwell, Some(dwell));
+        assert_eq!(restored.content_hash, record.content_hash);
+    }
+
+    #[test]
+    fn crossing_rejects_duplicate_known_extensions() {
+        let record = make_record().with_dwell(DwellInterval {
+            start_ns: 7,
+            end_ns: 9,
+        });
+        let bytes = serialize_crossing(&record);
+
+        // 同じ dwell 拡張 (type + u16 長 + 16 bytes) を末尾に重ねる
+        let ext_off = 5 + CROSSING_CORE_SIZE;
+        let ext_len = u16::from_le_bytes([bytes[ext_off], bytes[ext_off + 1]]);
+        let mut dup = bytes[..ext_off].to_vec();
+        dup.extend_from_slice(&(ext_len + 19).to_le_bytes());
+        dup.extend_from_slice(&bytes[ext_off + 2..]);
+        dup.extend_from_slice(&[EXT_DWELL, 16, 0]);
+        dup.extend_from_slice(&[0; 16]);
+        assert_eq!(
+            try_deserialize_crossing(&dup).unwrap_err(),
+            DecodeError::InvalidField("dwell")
+        );
+        assert_eq!(
+            crate::view::CrossingRecordRef::new(&dup).unwrap_err(),
+            DecodeError::InvalidField("dwell")
+        );
+
+        // 未知の type の重複は読み飛ばす
+        let mut unknown = bytes[..ext_off].to_vec();
+        unknown.extend_from_slice(&(ext_len + 8).to_le_bytes());
+        unknown.extend_from_slice(&bytes[ext_off + 2..]);
+        unknown.extend_from_slice(&[0xEE, 1, 0, 1, 0xEE, 1, 0, 2]);
+        assert!(try_deserialize_crossing(&unknown).is_ok());
+    }
+
+    #[test]
+    fn crossing_rejects_bad_containers() {
+        let bytes = serialize_crossing(&make_record());
+        let mut future = bytes.clone();
+        future[4] = 2;
+        assert_eq!(
+            try_deserialize_crossing(&future).unwrap_err(),
+            DecodeError::UnsupportedVersion(2)
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            try_deserialize_crossing(&trailing).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+
+        // 既知の拡張で長さが不正
+        let ext_off = 5 + CROSSING_CORE_SIZE;
+        let mut bad = bytes[..ext_off].to_vec();
+        bad.extend_from_slice(&4u16.to_le_bytes());
+        bad.extend_from_slice(&[EXT_DWELL, 1, 0, 0]);
+        assert_eq!(
+            try_deserialize_crossing(&bad).unwrap_err(),
+            DecodeError::InvalidField("dwell")
+        );
+
+        for len in 0..bytes.len() {
+            assert!(try_deserialize_crossing(&bytes[..len]).is_err(), "{len}");
+        }
+    }
+
+    fn make_group_proof() -> GroupProximityProof {
+        use crate::group::{GroupConfig, PresenceGroup};
+        let mut g = PresenceGroup::new(GroupConfig::default());
+        for (id, x) in [(7, 0.0), (3, 1.0), (70_000, 2.0)] {
+            g.try_add_member(id, VivaldiCoord::new(x, 0.0), 0).unwrap();
+        }
+        g.prove_proximity().unwrap()
+    }
+
+    #[test]
+    fn group_proof_roundtrip() {
+        let proof = make_group_proof();
+        let bytes = serialize_group_proof(&proof).unwrap();
+        assert_eq!(bytes.len(), GROUP_PROOF_MIN_SIZE + 3 * 4);
+        assert_eq!(&bytes[..4], b"AGRP");
+
+        let restored = try_deserialize_group_proof(&bytes).unwrap();
+        assert_eq!(restored.group_id, proof.group_id);
+        assert_eq!(restored.member_ids, vec![3, 7, 70_000]);
+        assert_eq!(restored.member_count, 3);
+        assert!((restored.max_distance - proof.max_distance).abs() < 1e-12);
+        assert!((restored.threshold - proof.threshold).abs() < 1e-12);
+        assert_eq!(restored.all_proximate, proof.all_proximate);
+        assert_eq!(restored.content_hash, proof.content_hash);
+    }
+
+    #[test]
+    fn group_proof_rejects_malformed() {
+        let bytes = serialize_group_proof(&make_group_proof()).unwrap();
+        for len in 0..bytes.len() {
+            assert!(try_deserialize_group_proof(&bytes[..len]).is_err(), "{len}");
+        }
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            try_deserialize_group_proof(&trailing).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+
+        let mut future = bytes.clone();
+        future[4] = 9;
+        assert_eq!(
+            try_deserialize_group_proof(&future).unwrap_err(),
+            DecodeError::UnsupportedVersion(9)
+        );
+
+        // メンバー数が上限超過
+        let mut huge = bytes.clone();
+        huge[GROUP_HEADER_SIZE - 2..GROUP_HEADER_SIZE]
+            .copy_from_slice(&((MAX_GROUP_SIZE + 1) as u16).to_le_bytes());
+        assert_eq!(
+            try_deserialize_group_proof(&huge).unwrap_err(),
+            DecodeError::InvalidField("member_count")
+        );
+
+        // ID の順序入れ替え
+        let mut unsorted = bytes.clone();
+        unsorted.swap(GROUP_HEADER_SIZE, GROUP_HEADER_SIZE + 4);
+        assert_eq!(
+            try_deserialize_group_proof(&unsorted).unwrap_err(),
+            DecodeError::InvalidField("member_ids")
+        );
+
+        assert_eq!(
+            try_deserialize_group_proof(&serialize_crossing(&make_record())).unwrap_err(),
+            DecodeError::BadMagic
+        );
+    }
+
+    #[test]
+    fn group_proof_rejects_oversized_member_list() {
+        let mut proof = make_group_proof();
+        proof.member_ids = (0..=MAX_GROUP_SIZE as u32).collect();
+        proof.member_count = proof.member_ids.len();
+        assert_eq!(
+            serialize_group_proof(&proof).unwrap_err(),
+            EncodeError::TooManyMembers {
+                count: MAX_GROUP_SIZE + 1,
+                max: MAX_GROUP_SIZE
+            }
+        );
+    }
+
+    #[test]
+    fn group_roundtrip() {
+        use crate::hyperbolic::HyperbolicCoord;
+        let mut g = PresenceGroup::new(GroupConfig {
+            proximity_threshold: 4.5,
+            min_members: 3,
+        });
+        for (id, x) in [(7, 0.0), (3, 1.0), (70_000, 2.0)] {
+            g.try_add_member(
+                id,
+                VivaldiCoord::with_height(x, -x, 0.5),
+                100 + u64::from(id),
+            )
+            .unwrap();
+        }
+        let bytes = serialize_group(&g);
+        assert_eq!(bytes.len(), GROUP_STATE_HEADER_SIZE + 3 * (12 + 24));
+        assert_eq!(&bytes[..4], b"AGRM");
+        let back: PresenceGroup = try_deserialize_group(&bytes).unwrap();
+        assert_eq!(back.members().len(), 3);
+        for (m, o) in back.members().iter().zip(g.members()) {
+            assert_eq!((m.id, m.joined_ns), (o.id, o.joined_ns));
+            assert_eq!(m.coord.commit(), o.coord.commit());
+        }
+        assert_eq!(back.config().min_members, 3);
+        assert_eq!(
+            back.prove_proximity().unwrap().content_hash,
+            g.prove_proximity().unwrap().content_hash
+        );
+
+        // 座標空間の不一致 (Vivaldi 24 bytes / 双曲 16 bytes)
+        assert_eq!(
+            try_deserialize_group::<HyperbolicCoord>(&bytes).unwrap_err(),
+            DecodeError::InvalidField("coord")
+        );
+    }
+
+    #[test]
+    fn group_rejects_malformed() {
+        let mut g = PresenceGroup::new(GroupConfig::default());
+        for id in [1, 2] {
+            g.try_add_member(id, VivaldiCoord::new(0.0, 0.0), 0)
+                .unwrap();
+        }
+        let bytes = serialize_group(&g);
+        for len in 0..bytes.len() {
+            assert!(
+                try_deserialize_group::<VivaldiCoord>(&bytes[..len]).is_err(),
+                "{len}"
+            );
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            try_deserialize_group::<VivaldiCoord>(&trailing).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+
+        // 重複 ID (2 人目の ID を 1 に書き換える)
+        let mut dup = bytes.clone();
+        let second = GROUP_STATE_HEADER_SIZE + 12 + 24;
+        dup[second..second + 4].copy_from_slice(&1u32.to_le_bytes());
+        assert_eq!(
+            try_deserialize_group::<VivaldiCoord>(&dup).unwrap_err(),
+            DecodeError::InvalidField("member_ids")
+        );
+
+        // メンバー数が上限超過
+        let mut huge = bytes.clone();
+        huge[23..25].copy_from_slice(&((MAX_GROUP_SIZE + 1) as u16).to_le_bytes());
+        assert_eq!(
+            try_deserialize_group::<VivaldiCoord>(&huge).unwrap_err(),
+            DecodeError::InvalidField("member_count")
+        );
+
+        // 非有限な閾値
+        let mut nan = bytes;
+        nan[5..13].copy_from_slice(&f64::NAN.to_le_bytes());
+        assert_eq!(
+            try_deserialize_group::<VivaldiCoord>(&nan).unwrap_err(),
+            DecodeError::InvalidField("proximity_threshold")
+        );
+    }
+
+    #[test]
+    fn crossing_rejects_unknown_event_type() {
+        let mut bytes = serialize_crossing(&make_record());
+        bytes[5] = 0x00;
+        assert_eq!(
+            try_deserialize_crossing(&bytes).unwrap_err(),
+            DecodeError::UnknownType(0x00)
+        );
+    }
+
+    #[test]
+    fn crossing_requires_presence_event() {
+        // 登録済みでも Presence 以外の type は crossing record に載らない
+        let mut bytes = serialize_crossing(&make_record());
+        bytes[5] = EventType::Heartbeat as u8;
+        bytes[6] = 0;
+        assert_eq!(
+            try_deserialize_crossing(&bytes).unwrap_err(),
+            DecodeError::InvalidField("event_type")
+        );
+        assert_eq!(
+            crate::view::CrossingRecordRef::new(&bytes).unwrap_err(),
+            DecodeError::InvalidField("event_type")
+        );
+    }
+
+    fn exchanging_session() -> Session {
+        let config = SessionConfig {
+            discovery_timeout_ns: 1_000_000_000,
+            exchange_timeout_ns: 2_000_000_000,
+            max_retries: 2,
+            backoff: BackoffPolicy::Exponential {
+                initial_ns: 100_000_000,
+                max_ns: 800_000_000,
+                jitter: 0.25,
+            },
+        };
+        let mut s = Session::new(1, 10_000_000_000, config);
+        s.try_discover(2, 10_100_000_000).unwrap();
+        s.try_begin_exchange(10_200_000_000).unwrap();
+        s.try_retry_at(10_300_000_000).unwrap();
+        s
+    }
+
+    /// CRC を付け直す (CRC 以外の検証を通すため)。
+    fn reseal(bytes: &mut [u8]) {
+        let n = bytes.len() - 4;
+        let crc = crc32(&bytes[..n]);
+        bytes[n..].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    #[test]
+    fn session_roundtrip() {
+        let s = exchanging_session();
+        let bytes = serialize_session(&s, 10_500_000_000);
+        assert_eq!(bytes.len(), SESSION_SNAPSHOT_SIZE);
+        let back = try_deserialize_session(&bytes, 10_600_000_000, Resume::SameClock).unwrap();
+        assert_eq!(back.session_id, s.session_id);
+        assert_eq!(back.state, SessionState::Exchanging);
+        assert_eq!(back.remote_id, Some(2));
+        assert_eq!(back.state_entered_ns, s.state_entered_ns);
+        assert_eq!(back.created_ns, s.created_ns);
+        assert_eq!((back.carried_state_ns, back.carried_total_ns), (0, 0));
+        assert_eq!(back.retries, 1);
+        assert_eq!(back.close_reason, None);
+        assert_eq!(back.config.max_retries, 2);
+        assert_eq!(back.config.backoff, s.config.backoff);
+        assert_eq!(back.content_hash, s.content_hash);
+        assert!(back.history.is_none());
+
+        let mut closed = Session::new(5, 0, SessionConfig::default());
+        closed.try_close(CloseReason::Cancelled, 10).unwrap();
+        let bytes = serialize_session(&closed, 20);
+        let back = try_deserialize_session(&bytes, 30, Resume::SameClock).unwrap();
+        assert_eq!(back.close_reason, Some(CloseReason::Cancelled));
+        assert_eq!(back.remote_id, None);
+        assert_eq!(back.config.backoff, BackoffPolicy::Fixed { delay_ns: 0 });
+    }
+
+    #[test]
+    fn session_history_is_not_saved() {
+        let mut s = Session::new(1, 0, SessionConfig::default()).with_history(4);
+        s.try_discover(2, 10).unwrap();
+        let bytes = serialize_session(&s, 20);
+        let back = try_deserialize_session(&bytes, 20, Resume::SameClock).unwrap();
+        assert!(back.history.is_none());
+        assert_eq!(back.content_hash, s.content_hash);
+    }
+
+    #[test]
+    fn session_resume_counts_downtime_on_same_clock() {
+        let s = exchanging_session();
+        let bytes = serialize_session(&s, 10_500_000_000);
+        // 期限 (12.3 s) 前に再開: そのまま続行できる
+        let back = try_deserialize_session(&bytes, 11_000_000_000, Resume::SameClock).unwrap();
+        assert!(!back.is_timed_out(11_000_000_000));
+        // 期限後に再開: すぐにタイムアウトし、backoff 後に再試行できる
+        let late = 13_000_000_000;
+        let back = try_deserialize_session(&bytes, late, Resume::SameClock).unwrap();
+        assert!(back.is_timed_out(late));
+        let retry_at = back.next_retry_at(late).unwrap();
+        assert_eq!(
+            retry_at,
+            back.deadline_ns().unwrap() + back.config.backoff.delay_ns(2, back.session_id)
+        );
+        // 同じ時計で保存時刻より前に戻ることはない
+        assert_eq!(
+            try_deserialize_session(&bytes, 10_400_000_000, Resume::SameClock).unwrap_err(),
+            DecodeError::InvalidField("saved_ns")
+        );
+    }
+
+    #[test]
+    fn session_resume_after_clock_reset_keeps_elapsed_phase_time() {
+        let s = exchanging_session();
+        let saved = 10_500_000_000;
+        let bytes = serialize_session(&s, saved);
+        // 再起動後の時計がまだ保存時刻より先にある場合も、停止時間は数えない
+        for now in [3_000_000_000, 20_000_000_000] {
+            let back = try_deserialize_session(&bytes, now, Resume::ClockReset).unwrap();
+            assert_eq!(back.session_id, s.session_id);
+            assert_eq!(back.state_duration_ns(now), s.state_duration_ns(saved));
+            assert_eq!(back.total_duration_ns(now), s.total_duration_ns(saved));
+            assert_eq!(back.content_hash, back.compute_hash());
+            assert_ne!(back.content_hash, s.content_hash);
+            assert!(!back.is_timed_out(now));
+            assert!(back.is_timed_out(now + 1_800_000_001));
+            // 再保存したスナップショットも同じ経過時間で復元できる
+            let again = serialize_session(&back, now + 100);
+            let back2 = try_deserialize_session(&again, 50, Resume::ClockReset).unwrap();
+            assert_eq!(
+                back2.state_duration_ns(50),
+                s.state_duration_ns(saved) + 100
+            );
+            assert_eq!(
+                back2.total_duration_ns(50),
+                s.total_duration_ns(saved) + 100
+            );
+        }
+    }
+
+    #[test]
+    fn session_resume_after_clock_reset_to_before_elapsed_time() {
+        // 保存 10.5 s、状態遷移 10.3 s、再起動後の現在時刻 0.1 s
+        let s = exchanging_session();
+        let bytes = serialize_session(&s, 10_500_000_000);
+        let now = 100_000_000;
+        let back = try_deserialize_session(&bytes, now, Resume::ClockReset).unwrap();
+        assert_eq!(back.state_duration_ns(now), 200_000_000);
+        assert_eq!(back.total_duration_ns(now), 500_000_000);
+        assert_eq!(back.deadline_ns(), Some(1_900_000_000));
+        assert!(!back.is_timed_out(1_900_000_000));
+        assert!(back.is_timed_out(1_900_000_001));
+    }
+
+    #[test]
+    fn session_integrity_checks() {
+        let bytes = serialize_session(&exchanging_session(), 10_500_000_000);
+        let now = 10_500_000_000;
+        let restore = |b: &[u8]| try_deserialize_session(b, now, Resume::SameClock).unwrap_err();
+
+        let mut flipped = bytes.clone();
+        flipped[23] ^= 1;
+        assert_eq!(restore(&flipped), DecodeError::Checksum);
+
+        // CRC を付け直しても content hash で検出する
+        reseal(&mut flipped);
+        assert_eq!(restore(&flipped), DecodeError::InvalidField("content_hash"));
+        let mut carried = bytes.clone();
+        carried[39] ^= 1;
+        reseal(&mut carried);
+        assert_eq!(restore(&carried), DecodeError::InvalidField("content_hash"));
+
+        let mut bad_state = bytes.clone();
+        bad_state[13] = 9;
+        reseal(&mut bad_state);
+        assert_eq!(restore(&bad_state), DecodeError::InvalidField("state"));
+
+        let mut no_remote = bytes.clone();
+        no_remote[18] = 0;
+        reseal(&mut no_remote);
+        assert_eq!(restore(&no_remote), DecodeError::InvalidField("remote_id"));
+
+        let mut reason = bytes.clone();
+        reason[59] = 2;
+        reseal(&mut reason);
+        assert_eq!(restore(&reason), DecodeError::InvalidField("close_reason"));
+
+        let mut backoff = bytes.clone();
+        backoff[80] = 7;
+        reseal(&mut backoff);
+        assert_eq!(restore(&backoff), DecodeError::InvalidField("backoff"));
+
+        assert_eq!(
+            restore(&bytes[..40]),
+            DecodeError::TooShort {
+                expected: SESSION_SNAPSHOT_SIZE,
+                actual: 40
+            }
+        );
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(restore(&long), DecodeError::TrailingBytes { extra: 1 });
+        let mut version = bytes;
+        version[4] = 2;
+        assert_eq!(restore(&version), DecodeError::UnsupportedVersion(2));
+    }
+
+    #[test]
+    fn session_consistency_checks() {
+        let restore = |b: &[u8]| try_deserialize_session(b, 100, Resume::SameClock).unwrap_err();
+
+        // Idle は remote ID を持たない
+        let mut idle = serialize_session(&Session::new(1, 0, SessionConfig::default()), 50);
+        idle[18] = 1;
+        reseal(&mut idle);
+        assert_eq!(restore(&idle), DecodeError::InvalidField("remote_id"));
+
+        // Fixed の未使用パラメータは 0
+        for off in [89, 97] {
+            let mut fixed = serialize_session(&Session::new(1, 0, SessionConfig::default()), 50);
+            fixed[off] = 1;
+            reseal(&mut fixed);
+            assert_eq!(restore(&fixed), DecodeError::InvalidField("backoff"));
+        }
+
+        // 状態に入る前の時刻で保存されたスナップショット
+        let mut s = Session::new(1, 0, SessionConfig::default());
+        s.try_discover(2, 60).unwrap();
+        assert_eq!(
+            restore(&serialize_session(&s, 50)),
+            DecodeError::InvalidField("saved_ns")
+        );
+
+        // 作成時刻が状態遷移の時刻より後
+        let mut created = serialize_session(&s, 70);
+        created[31..39].copy_from_slice(&61u64.to_le_bytes());
+        reseal(&mut created);
+        assert_eq!(restore(&created), DecodeError::InvalidField("created_ns"));
+    }
 }
@@ -2,14 +2,22 @@
 //!
 //! Models the lifecycle of a presence exchange session through
 //! five states: Idle → Discovering → Exchanging → Verified → Closed.
-//! Enforces valid transitions and tracks timeout.
+//! Valid transitions are an explicit table (state × input → state); a
+//! session can optionally keep a bounded, timestamped transition history
+//! for auditing. Tracks timeout.
 //!
 //! Author: Moroya Sakamoto
 
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use crate::error::DecodeError;
+use crate::error::TransitionError;
 use crate::fnv1a;
 
 /// Session state in the presence protocol FSM.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum SessionState {
     /// No active session; waiting for discovery.
@@ -24,8 +32,189 @@ pub enum SessionState {
     Closed = 4,
 }
 
+impl SessionState {
+    /// State reached by applying `input`, or `None` if the table rejects it.
+    #[must_use]
+    pub const fn next(self, input: SessionInput) -> Option<Self> {
+        TRANSITIONS[self as usize][input.index()]
+    }
+}
+
+/// An input driving the session FSM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionInput {
+    /// Peer found.
+    Discover { remote_id: u32 },
+    /// Proximity check passed.
+    BeginExchange,
+    /// ZKP exchange verified.
+    Verify,
+    /// Close the session.
+    Close(CloseReason),
+    /// Retry the timed-out phase; stays in the current state.
+    Retry,
+}
+
+impl SessionInput {
+    /// Column of this input in the transition table.
+    const fn index(self) -> usize {
+        match self {
+            Self::Discover { .. } => 0,
+            Self::BeginExchange => 1,
+            Self::Verify => 2,
+            Self::Close(_) => 3,
+            Self::Retry => 4,
+        }
+    }
+
+    /// The state this input leads to when accepted, or `None` for `Retry`,
+    /// which stays in the current state.
+    #[must_use]
+    pub const fn target(self) -> Option<SessionState> {
+        match self {
+            Self::Discover { .. } => Some(SessionState::Discovering),
+            Self::BeginExchange => Some(SessionState::Exchanging),
+            Self::Verify => Some(SessionState::Verified),
+            Self::Close(_) => Some(SessionState::Closed),
+            Self::Retry => None,
+        }
+    }
+}
+
+/// Transition table indexed by `[state][input]`.
+///
+/// Columns: Discover, `BeginExchange`, Verify, Close, Retry.
+const TRANSITIONS: [[Option<SessionState>; 5]; 5] = {
+    const DISCOVERING: Option<SessionState> = Some(SessionState::Discovering);
+    const EXCHANGING: Option<SessionState> = Some(SessionState::Exchanging);
+    const VERIFIED: Option<SessionState> = Some(SessionState::Verified);
+    const CLOSED: Option<SessionState> = Some(SessionState::Closed);
+    [
+        /* Idle        */ [DISCOVERING, None, None, CLOSED, None],
+        /* Discovering */ [None, EXCHANGING, None, CLOSED, DISCOVERING],
+        /* Exchanging  */ [None, None, VERIFIED, CLOSED, EXCHANGING],
+        /* Verified    */ [None, None, None, CLOSED, None],
+        /* Closed      */ [None, None, None, None, None],
+    ]
+};
+
+/// One accepted transition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransitionRecord {
+    pub from: SessionState,
+    pub to: SessionState,
+    pub input: SessionInput,
+    pub timestamp_ns: u64,
+}
+
+/// Records reserved up front by `TransitionHistory::new`; beyond this the
+/// log grows on demand, so a huge `capacity` costs nothing until used.
+const HISTORY_PREALLOC: usize = 16;
+
+/// Bounded transition log; the oldest records are evicted first.
+///
+/// Deserialization rejects more records than `capacity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TransitionHistoryRepr"))]
+pub struct TransitionHistory {
+    records: VecDeque<TransitionRecord>,
+    capacity: usize,
+    dropped: u64,
+}
+
+/// Unvalidated serde form of `TransitionHistory`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TransitionHistoryRepr {
+    records: VecDeque<TransitionRecord>,
+    capacity: usize,
+    dropped: u64,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TransitionHistoryRepr> for TransitionHistory {
+    type Error = DecodeError;
+
+    fn try_from(r: TransitionHistoryRepr) -> Result<Self, DecodeError> {
+        if r.records.len() > r.capacity {
+            return Err(DecodeError::InvalidField("history"));
+        }
+        Ok(Self {
+            records: r.records,
+            capacity: r.capacity,
+            dropped: r.dropped,
+        })
+    }
+}
+
+impl TransitionHistory {
+    /// Empty history keeping at most `capacity` records.
+    ///
+    /// Only a few slots are allocated up front, so `usize::MAX` is a valid
+    /// "unbounded" capacity.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity.min(HISTORY_PREALLOC)),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, record: TransitionRecord) {
+        if self.capacity == 0 {
+            self.dropped += 1;
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+            self.dropped += 1;
+        }
+        self.records.push_back(record);
+    }
+
+    /// Retained records, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TransitionRecord> {
+        self.records.iter()
+    }
+
+    /// Most recent record.
+    #[must_use]
+    pub fn last(&self) -> Option<&TransitionRecord> {
+        self.records.back()
+    }
+
+    /// Number of retained records.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// `true` if no records are retained.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Maximum number of retained records.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of records evicted (or never stored) because of the bound.
+    #[must_use]
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
 /// Reason a session was closed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CloseReason {
     /// Successfully completed (reached Verified, then closed).
     Success,
@@ -39,8 +228,63 @@ pub enum CloseReason {
     VerificationFailed,
 }
 
+/// Delay between a phase timing out and its retry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BackoffPolicy {
+    /// The same delay before every retry.
+    Fixed { delay_ns: u64 },
+    /// `initial_ns` doubling with each attempt, capped at `max_ns`.
+    ///
+    /// `jitter` (clamped to 0..=1) shortens each delay by up to that
+    /// fraction. The jitter is derived from the session ID and attempt
+    /// number, so it is deterministic per session yet spreads retries of
+    /// different sessions apart.
+    Exponential {
+        initial_ns: u64,
+        max_ns: u64,
+        jitter: f64,
+    },
+}
+
+impl Default for BackoffPolicy {
+    /// Retry as soon as the phase times out.
+    fn default() -> Self {
+        Self::Fixed { delay_ns: 0 }
+    }
+}
+
+impl BackoffPolicy {
+    /// Delay (ns) before retry number `attempt` (1-based) of the session
+    /// with `session_id`.
+    #[must_use]
+    pub fn delay_ns(&self, attempt: u32, session_id: u64) -> u64 {
+        match *self {
+            Self::Fixed { delay_ns } => delay_ns,
+            Self::Exponential {
+                initial_ns,
+                max_ns,
+                jitter,
+            } => {
+                let factor = 1u64
+                    .checked_shl(attempt.saturating_sub(1))
+                    .unwrap_or(u64::MAX);
+                let delay = initial_ns.saturating_mul(factor).min(max_ns);
+                let mut buf = [0u8; 12];
+                buf[..8].copy_from_slice(&session_id.to_le_bytes());
+                buf[8..].copy_from_slice(&attempt.to_le_bytes());
+                // Top 53 bits → uniform in [0, 1)
+                let unit = (fnv1a(&buf) >> 11) as f64 / (1u64 << 53) as f64;
+                let cut = delay as f64 * jitter.clamp(0.0, 1.0) * unit;
+                delay - (cut as u64).min(delay)
+            }
+        }
+    }
+}
+
 /// Session configuration.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SessionConfig {
     /// Maximum time (ns) to stay in Discovering before timeout.
     pub discovery_timeout_ns: u64,
@@ -48,6 +292,9 @@ pub struct SessionConfig {
     pub exchange_timeout_ns: u64,
     /// Maximum number of retry attempts per phase.
     pub max_retries: u32,
+    /// Delay between a timeout and the next retry.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub backoff: BackoffPolicy,
 }
 
 impl Default for SessionConfig {
@@ -56,14 +303,17 @@ impl Default for SessionConfig {
             discovery_timeout_ns: 5_000_000_000, // 5 seconds
             exchange_timeout_ns: 10_000_000_000, // 10 seconds
             max_retries: 3,
+            backoff: BackoffPolicy::default(),
         }
     }
 }
 
 /// A presence session tracking the FSM state, timestamps, and retries.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Session {
     /// Unique session identifier.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub session_id: u64,
     /// Current FSM state.
     pub state: SessionState,
@@ -75,6 +325,15 @@ pub struct Session {
     pub state_entered_ns: u64,
     /// Timestamp (ns) when session was created.
     pub created_ns: u64,
+    /// Time (ns) already spent in the current state before
+    /// `state_entered_ns`, carried over when a snapshot is restored after a
+    /// clock reset. Cleared by every transition and retry.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub carried_state_ns: u64,
+    /// Session lifetime (ns) before `created_ns`, carried over when a
+    /// snapshot is restored after a clock reset.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub carried_total_ns: u64,
     /// Number of retries used in current phase.
     pub retries: u32,
     /// Close reason (set when state == Closed).
@@ -82,7 +341,11 @@ pub struct Session {
     /// Configuration for this session.
     pub config: SessionConfig,
     /// Content hash of session state for integrity.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
     pub content_hash: u64,
+    /// Accepted transitions, if enabled with `with_history`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub history: Option<TransitionHistory>,
 }
 
 impl Session {
@@ -101,97 +364,240 @@ impl Session {
             remote_id: None,
             state_entered_ns: timestamp_ns,
             created_ns: timestamp_ns,
+            carried_state_ns: 0,
+            carried_total_ns: 0,
             retries: 0,
             close_reason: None,
             config,
             content_hash: 0,
+            history: None,
         };
         s.update_hash();
         s
     }
 
-    /// Transition: Idle → Discovering (peer found).
-    pub fn discover(&mut self, remote_id: u32, timestamp_ns: u64) -> bool {
-        if self.state != SessionState::Idle {
-            return false;
+    /// Keep the last `capacity` accepted transitions in `history`.
+    ///
+    /// The history is in-memory only: `serialize_session` does not store
+    /// it, and a restored session starts without one.
+    #[must_use]
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        self.history = Some(TransitionHistory::new(capacity));
+        self
+    }
+
+    /// Apply `input` according to the transition table.
+    ///
+    /// Entering Discovering or Exchanging resets the retry counter; Retry
+    /// increments it; Close records its reason. Returns the new state.
+    ///
+    /// # Errors
+    ///
+    /// `TransitionError::InvalidState` if the table has no entry for the
+    /// current state and `input`, `TransitionError::RetriesExhausted` for
+    /// `Retry` once `config.max_retries` is reached; in both cases the
+    /// session is left unchanged.
+    pub fn try_apply(
+        &mut self,
+        input: SessionInput,
+        timestamp_ns: u64,
+    ) -> Result<SessionState, TransitionError> {
+        let from = self.state;
+        let to = from
+            .next(input)
+            .ok_or(TransitionError::InvalidState { from, input })?;
+        match input {
+            SessionInput::Discover { remote_id } => {
+                self.remote_id = Some(remote_id);
+                self.retries = 0;
+            }
+            SessionInput::BeginExchange => self.retries = 0,
+            SessionInput::Verify => {}
+            SessionInput::Close(reason) => self.close_reason = Some(reason),
+            SessionInput::Retry => self.bump_retries()?,
         }
-        self.remote_id = Some(remote_id);
-        self.state = SessionState::Discovering;
+        self.state = to;
         self.state_entered_ns = timestamp_ns;
-        self.retries = 0;
+        self.carried_state_ns = 0;
+        if let Some(history) = &mut self.history {
+            history.push(TransitionRecord {
+                from,
+                to,
+                input,
+                timestamp_ns,
+            });
+        }
         self.update_hash();
-        true
+        Ok(to)
+    }
+
+    /// Transition: Idle → Discovering (peer found).
+    ///
+    /// # Errors
+    ///
+    /// `TransitionError::InvalidState` unless the session is Idle.
+    pub fn try_discover(
+        &mut self,
+        remote_id: u32,
+        timestamp_ns: u64,
+    ) -> Result<(), TransitionError> {
+        self.try_apply(SessionInput::Discover { remote_id }, timestamp_ns)
+            .map(drop)
+    }
+
+    /// Transition: Discovering → Exchanging (proximity OK).
+    ///
+    /// # Errors
+    ///
+    /// `TransitionError::InvalidState` unless the session is Discovering.
+    pub fn try_begin_exchange(&mut self, timestamp_ns: u64) -> Result<(), TransitionError> {
+        self.try_apply(SessionInput::BeginExchange, timestamp_ns)
+            .map(drop)
+    }
+
+    /// Transition: Exchanging → Verified (ZKP OK).
+    ///
+    /// # Errors
+    ///
+    /// `TransitionError::InvalidState` unless the session is Exchanging.
+    pub fn try_verify(&mut self, timestamp_ns: u64) -> Result<(), TransitionError> {
+        self.try_apply(SessionInput::Verify, timestamp_ns).map(drop)
+    }
+
+    /// Transition: any → Closed.
+    ///
+    /// # Errors
+    ///
+    /// `TransitionError::InvalidState` if the session is already Closed.
+    pub fn try_close(
+        &mut self,
+        reason: CloseReason,
+        timestamp_ns: u64,
+    ) -> Result<(), TransitionError> {
+        self.try_apply(SessionInput::Close(reason), timestamp_ns)
+            .map(drop)
+    }
+
+    /// Transition: Idle → Discovering (peer found).
+    #[deprecated(since = "0.2.0", note = "use `try_discover`")]
+    pub fn discover(&mut self, remote_id: u32, timestamp_ns: u64) -> bool {
+        self.try_discover(remote_id, timestamp_ns).is_ok()
     }
 
     /// Transition: Discovering → Exchanging (proximity OK).
+    #[deprecated(since = "0.2.0", note = "use `try_begin_exchange`")]
     pub fn begin_exchange(&mut self, timestamp_ns: u64) -> bool {
-        if self.state != SessionState::Discovering {
-            return false;
-        }
-        self.state = SessionState::Exchanging;
-        self.state_entered_ns = timestamp_ns;
-        self.retries = 0;
-        self.update_hash();
-        true
+        self.try_begin_exchange(timestamp_ns).is_ok()
     }
 
     /// Transition: Exchanging → Verified (ZKP OK).
+    #[deprecated(since = "0.2.0", note = "use `try_verify`")]
     pub fn verify(&mut self, timestamp_ns: u64) -> bool {
-        if self.state != SessionState::Exchanging {
-            return false;
-        }
-        self.state = SessionState::Verified;
-        self.state_entered_ns = timestamp_ns;
-        self.update_hash();
-        true
+        self.try_verify(timestamp_ns).is_ok()
     }
 
     /// Transition: any → Closed.
+    #[deprecated(since = "0.2.0", note = "use `try_close`")]
     pub fn close(&mut self, reason: CloseReason, timestamp_ns: u64) -> bool {
-        if self.state == SessionState::Closed {
-            return false;
-        }
-        self.state = SessionState::Closed;
-        self.state_entered_ns = timestamp_ns;
-        self.close_reason = Some(reason);
-        self.update_hash();
-        true
+        self.try_close(reason, timestamp_ns).is_ok()
+    }
+
+    /// Timestamp (ns) after which the current phase times out, or `None`
+    /// in states without a timeout (Idle, Verified, Closed).
+    /// `carried_state_ns` counts toward the timeout.
+    #[must_use]
+    pub const fn deadline_ns(&self) -> Option<u64> {
+        let timeout = match self.state {
+            SessionState::Discovering => self.config.discovery_timeout_ns,
+            SessionState::Exchanging => self.config.exchange_timeout_ns,
+            _ => return None,
+        };
+        Some(
+            self.state_entered_ns
+                .saturating_add(timeout)
+                .saturating_sub(self.carried_state_ns),
+        )
     }
 
     /// Check if the current phase has timed out.
+    ///
+    /// A retry (`try_retry_at`) restarts the phase, so this measures from
+    /// the latest retry.
     #[must_use]
     pub fn is_timed_out(&self, current_ns: u64) -> bool {
-        if self.state == SessionState::Idle || self.state == SessionState::Closed {
-            return false;
-        }
-        let elapsed = current_ns.saturating_sub(self.state_entered_ns);
-        match self.state {
-            SessionState::Discovering => elapsed > self.config.discovery_timeout_ns,
-            SessionState::Exchanging => elapsed > self.config.exchange_timeout_ns,
-            _ => false,
+        self.deadline_ns()
+            .is_some_and(|deadline| current_ns > deadline)
+    }
+
+    /// When the timed-out phase should be retried under `config.backoff`:
+    /// the deadline plus the backoff delay for the next attempt.
+    ///
+    /// `None` if the phase has not timed out at `current_ns` or the retry
+    /// budget is exhausted (the session should then be closed with
+    /// `CloseReason::Timeout`). The retry is due once `current_ns` reaches
+    /// the returned timestamp.
+    #[must_use]
+    pub fn next_retry_at(&self, current_ns: u64) -> Option<u64> {
+        if !self.is_timed_out(current_ns) || self.retries >= self.config.max_retries {
+            return None;
         }
+        let deadline = self.deadline_ns()?;
+        let delay = self
+            .config
+            .backoff
+            .delay_ns(self.retries + 1, self.session_id);
+        Some(deadline.saturating_add(delay))
     }
 
-    /// Increment retry counter. Returns false if max retries exceeded.
-    pub fn retry(&mut self) -> bool {
+    fn bump_retries(&mut self) -> Result<(), TransitionError> {
         if self.retries >= self.config.max_retries {
-            return false;
+            return Err(TransitionError::RetriesExhausted {
+                max_retries: self.config.max_retries,
+            });
         }
         self.retries += 1;
+        Ok(())
+    }
+
+    /// Retry the current phase at `timestamp_ns`: increment the retry
+    /// counter and restart the phase clock.
+    ///
+    /// # Errors
+    ///
+    /// `TransitionError::InvalidState` unless the session is Discovering or
+    /// Exchanging; `TransitionError::RetriesExhausted` once
+    /// `config.max_retries` is reached.
+    pub fn try_retry_at(&mut self, timestamp_ns: u64) -> Result<(), TransitionError> {
+        self.try_apply(SessionInput::Retry, timestamp_ns).map(drop)
+    }
+
+    /// Increment retry counter. Returns false if max retries exceeded.
+    ///
+    /// Unlike `try_retry_at`, this only counts the retry, in any state; it
+    /// does not restart the phase clock, so the session stays timed out.
+    #[deprecated(since = "0.2.0", note = "use `try_retry_at`")]
+    pub fn retry(&mut self) -> bool {
+        let ok = self.bump_retries().is_ok();
         self.update_hash();
-        true
+        ok
     }
 
-    /// Duration (ns) spent in the current state.
+    /// Duration (ns) spent in the current state, including
+    /// `carried_state_ns`.
     #[must_use]
     pub const fn state_duration_ns(&self, current_ns: u64) -> u64 {
-        current_ns.saturating_sub(self.state_entered_ns)
+        current_ns
+            .saturating_sub(self.state_entered_ns)
+            .saturating_add(self.carried_state_ns)
     }
 
-    /// Total session duration (ns) from creation.
+    /// Total session duration (ns) from creation, including
+    /// `carried_total_ns`.
     #[must_use]
     pub const fn total_duration_ns(&self, current_ns: u64) -> u64 {
-        current_ns.saturating_sub(self.created_ns)
+        current_ns
+            .saturating_sub(self.created_ns)
+            .saturating_add(self.carried_total_ns)
     }
 
     /// Is this session still active (not Closed)?
@@ -200,20 +606,28 @@ impl Session {
         self.state != SessionState::Closed
     }
 
-    fn update_hash(&mut self) {
-        let mut buf = [0u8; 25];
+    /// Hash over ID, state, local ID, state entry time, carried state time
+    /// and retries.
+    pub(crate) fn compute_hash(&self) -> u64 {
+        let mut buf = [0u8; 33];
         buf[..8].copy_from_slice(&self.session_id.to_le_bytes());
         buf[8] = self.state as u8;
         buf[9..13].copy_from_slice(&self.local_id.to_le_bytes());
         buf[13..21].copy_from_slice(&self.state_entered_ns.to_le_bytes());
-        buf[21..25].copy_from_slice(&self.retries.to_le_bytes());
-        self.content_hash = fnv1a(&buf);
+        buf[21..29].copy_from_slice(&self.carried_state_ns.to_le_bytes());
+        buf[29..33].copy_from_slice(&self.retries.to_le_bytes());
+        fnv1a(&buf)
+    }
+
+    fn update_hash(&mut self) {
+        self.content_hash = self.compute_hash();
     }
 }
 
 // ── Tests ──────────────────────────────────────────────────────────────
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
 
@@ -425,4 +839,249 @@ mod tests {
         s3.verify(300);
         assert!(s3.close(CloseReason::Success, 400));
     }
+
+    #[test]
+    fn try_transitions_report_state() {
+        let mut s = Session::new(1, 0, SessionConfig::default());
+        assert_eq!(
+            s.try_verify(10),
+            Err(TransitionError::InvalidState {
+                from: SessionState::Idle,
+                input: SessionInput::Verify,
+            })
+        );
+        assert_eq!(s.try_discover(2, 10), Ok(()));
+        assert_eq!(s.try_begin_exchange(20), Ok(()));
+        assert_eq!(s.try_verify(30), Ok(()));
+        assert_eq!(s.try_close(CloseReason::Success, 40), Ok(()));
+        assert_eq!(
+            s.try_close(CloseReason::Success, 50),
+            Err(TransitionError::InvalidState {
+                from: SessionState::Closed,
+                input: SessionInput::Close(CloseReason::Success),
+            })
+        );
+    }
+
+    #[test]
+    fn table_matches_try_methods() {
+        let states = [
+            SessionState::Idle,
+            SessionState::Discovering,
+            SessionState::Exchanging,
+            SessionState::Verified,
+            SessionState::Closed,
+        ];
+        let inputs = [
+            SessionInput::Discover { remote_id: 2 },
+            SessionInput::BeginExchange,
+            SessionInput::Verify,
+            SessionInput::Close(CloseReason::Cancelled),
+            SessionInput::Retry,
+        ];
+        for &from in &states {
+            for &input in &inputs {
+                let mut s = Session::new(1, 0, SessionConfig::default());
+                s.state = from;
+                let before = s.content_hash;
+                let result = s.try_apply(input, 10);
+                if let Some(to) = from.next(input) {
+                    assert_eq!(result, Ok(to));
+                    assert_eq!(to, input.target().unwrap_or(from));
+                    assert_eq!(s.state, to);
+                } else {
+                    assert_eq!(result, Err(TransitionError::InvalidState { from, input }));
+                    assert_eq!(s.state, from);
+                    assert_eq!(s.content_hash, before);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn history_records_accepted_transitions() {
+        let mut s = Session::new(1, 0, SessionConfig::default()).with_history(8);
+        s.try_discover(2, 10).unwrap();
+        assert!(s.try_verify(15).is_err());
+        s.try_begin_exchange(20).unwrap();
+        s.try_close(CloseReason::Cancelled, 30).unwrap();
+
+        let h = s.history.as_ref().unwrap();
+        let path: Vec<_> = h.iter().map(|r| (r.from, r.to, r.timestamp_ns)).collect();
+        assert_eq!(
+            path,
+            vec![
+                (SessionState::Idle, SessionState::Discovering, 10),
+                (SessionState::Discovering, SessionState::Exchanging, 20),
+                (SessionState::Exchanging, SessionState::Closed, 30),
+            ]
+        );
+        assert_eq!(
+            h.last().unwrap().input,
+            SessionInput::Close(CloseReason::Cancelled)
+        );
+        assert_eq!(h.dropped(), 0);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut s = Session::new(1, 0, SessionConfig::default()).with_history(2);
+        s.try_discover(2, 10).unwrap();
+        s.try_begin_exchange(20).unwrap();
+        s.try_verify(30).unwrap();
+        let h = s.history.as_ref().unwrap();
+        assert_eq!(h.len(), 2);
+        assert_eq!(h.capacity(), 2);
+        assert_eq!(h.dropped(), 1);
+        assert_eq!(h.iter().next().unwrap().to, SessionState::Exchanging);
+
+        let mut off = Session::new(1, 0, SessionConfig::default()).with_history(0);
+        off.try_discover(2, 10).unwrap();
+        let h = off.history.as_ref().unwrap();
+        assert!(h.is_empty());
+        assert_eq!(h.dropped(), 1);
+        assert!(Session::new(1, 0, SessionConfig::default())
+            .history
+            .is_none());
+
+        // A huge bound does not allocate up front.
+        let mut big = Session::new(1, 0, SessionConfig::default()).with_history(usize::MAX);
+        big.try_discover(2, 10).unwrap();
+        let h = big.history.as_ref().unwrap();
+        assert_eq!((h.len(), h.capacity()), (1, usize::MAX));
+    }
+
+    #[test]
+    fn retry_at_restarts_phase() {
+        let cfg = SessionConfig {
+            discovery_timeout_ns: 1000,
+            max_retries: 1,
+            ..SessionConfig::default()
+        };
+        let mut s = Session::new(1, 0, cfg);
+        s.try_discover(2, 0).unwrap();
+        assert_eq!(s.deadline_ns(), Some(1000));
+        assert!(s.is_timed_out(1001));
+        let before = s.content_hash;
+
+        assert_eq!(s.try_retry_at(1001), Ok(()));
+        assert_eq!(s.retries, 1);
+        assert_eq!(s.state_entered_ns, 1001);
+        assert_ne!(s.content_hash, before);
+        assert!(!s.is_timed_out(2001));
+        assert!(s.is_timed_out(2002));
+        assert_eq!(
+            s.try_retry_at(2002),
+            Err(TransitionError::RetriesExhausted { max_retries: 1 })
+        );
+        assert_eq!(s.state_entered_ns, 1001);
+    }
+
+    #[test]
+    fn retry_rejected_outside_timed_phases() {
+        let idle = Session::new(1, 0, SessionConfig::default());
+        let mut verified = idle.clone();
+        verified.try_discover(2, 10).unwrap();
+        verified.try_begin_exchange(20).unwrap();
+        verified.try_verify(30).unwrap();
+        let mut closed = idle.clone();
+        closed.try_close(CloseReason::Cancelled, 40).unwrap();
+
+        for mut s in [idle, verified, closed] {
+            let (state, entered, before) = (s.state, s.state_entered_ns, s.content_hash);
+            assert_eq!(
+                s.try_retry_at(1000),
+                Err(TransitionError::InvalidState {
+                    from: state,
+                    input: SessionInput::Retry,
+                })
+            );
+            assert_eq!((s.retries, s.state_entered_ns), (0, entered));
+            assert_eq!(s.content_hash, before);
+        }
+    }
+
+    #[test]
+    fn carried_time_counts_until_next_transition() {
+        let cfg = SessionConfig {
+            discovery_timeout_ns: 1000,
+            ..SessionConfig::default()
+        };
+        let mut s = Session::new(1, 0, cfg);
+        s.try_discover(2, 100).unwrap();
+        s.carried_state_ns = 300;
+        s.carried_total_ns = 500;
+        assert_eq!(s.state_duration_ns(150), 350);
+        assert_eq!(s.total_duration_ns(150), 650);
+        assert_eq!(s.deadline_ns(), Some(800));
+        assert!(s.is_timed_out(801));
+
+        s.try_begin_exchange(200).unwrap();
+        assert_eq!(s.carried_state_ns, 0);
+        assert_eq!(s.state_duration_ns(250), 50);
+        assert_eq!(s.total_duration_ns(250), 750);
+    }
+
+    #[test]
+    fn next_retry_at_follows_backoff() {
+        let cfg = SessionConfig {
+            discovery_timeout_ns: 1000,
+            max_retries: 2,
+            backoff: BackoffPolicy::Fixed { delay_ns: 300 },
+            ..SessionConfig::default()
+        };
+        let mut s = Session::new(1, 0, cfg);
+        assert_eq!(s.next_retry_at(5000), None); // Idle never times out
+        s.try_discover(2, 0).unwrap();
+        assert_eq!(s.next_retry_at(1000), None); // not timed out yet
+        assert_eq!(s.next_retry_at(1001), Some(1300));
+        s.try_retry_at(1300).unwrap();
+        assert_eq!(s.next_retry_at(2301), Some(2600));
+        s.try_retry_at(2600).unwrap();
+        assert_eq!(s.next_retry_at(4000), None); // budget exhausted
+        assert!(s.is_timed_out(4000));
+    }
+
+    #[test]
+    fn exponential_backoff_doubles_and_caps() {
+        let policy = BackoffPolicy::Exponential {
+            initial_ns: 100,
+            max_ns: 1000,
+            jitter: 0.0,
+        };
+        let delays: Vec<u64> = (1..=6).map(|n| policy.delay_ns(n, 42)).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(policy.delay_ns(200, 42), 1000);
+        assert_eq!(BackoffPolicy::default().delay_ns(3, 42), 0);
+    }
+
+    #[test]
+    fn exponential_jitter_is_deterministic_and_bounded() {
+        let policy = BackoffPolicy::Exponential {
+            initial_ns: 1_000_000,
+            max_ns: 1_000_000_000,
+            jitter: 0.5,
+        };
+        for attempt in 1..=8 {
+            let full = 1_000_000u64 << (attempt - 1);
+            for session_id in [1u64, 2, 0xDEAD_BEEF] {
+                let d = policy.delay_ns(attempt, session_id);
+                assert_eq!(d, policy.delay_ns(attempt, session_id));
+                assert!(
+                    d <= full && d >= full / 2,
+                    "{d} outside [{}, {full}]",
+                    full / 2
+                );
+            }
+        }
+        // Different sessions are spread apart.
+        assert_ne!(policy.delay_ns(1, 1), policy.delay_ns(1, 2));
+        // Jitter above 1 is clamped: the delay never underflows.
+        let wild = BackoffPolicy::Exponential {
+            initial_ns: 10,
+            max_ns: 10,
+            jitter: 5.0,
+        };
+        assert!(wild.delay_ns(1, 7) <= 10);
+    }
 }
@@ -0,0 +1,560 @@
+//! Multi-session manager
+//!
+//! A `Session` is a single FSM whose owner must poll `is_timed_out`.
+//! `SessionManager` owns many sessions keyed by `session_id` (with a
+//! secondary index by remote party ID), drives their transitions, and on
+//! each `tick` either retries a timed-out phase once its backoff delay has
+//! elapsed (restarting its clock while `SessionConfig::max_retries` allows)
+//! or closes the session with `CloseReason::Timeout`. Every transition it
+//! performs is reported as a `SessionEvent`.
+//!
+//! Author: Moroya Sakamoto
+
+use std::collections::BTreeMap;
+
+use crate::error::{SessionManagerError, TransitionError};
+use crate::session::{CloseReason, Session, SessionConfig, SessionState};
+
+/// A transition performed by the manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionEvent {
+    /// The session moved from `from` to `to` (not to Closed).
+    Transitioned {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+        session_id: u64,
+        from: SessionState,
+        to: SessionState,
+        timestamp_ns: u64,
+    },
+    /// The current phase timed out and was restarted; `attempt` is the
+    /// retry count after this retry.
+    Retried {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+        session_id: u64,
+        state: SessionState,
+        attempt: u32,
+        timestamp_ns: u64,
+    },
+    /// The session moved from `from` to Closed.
+    Closed {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_hex"))]
+        session_id: u64,
+        from: SessionState,
+        reason: CloseReason,
+        timestamp_ns: u64,
+    },
+}
+
+impl SessionEvent {
+    /// ID of the session this event belongs to.
+    #[must_use]
+    pub const fn session_id(&self) -> u64 {
+        match *self {
+            Self::Transitioned { session_id, .. }
+            | Self::Retried { session_id, .. }
+            | Self::Closed { session_id, .. } => session_id,
+        }
+    }
+}
+
+/// Owns many sessions, indexed by session ID and by remote party ID.
+#[derive(Debug, Clone, Default)]
+pub struct SessionManager {
+    sessions: BTreeMap<u64, Session>,
+    by_remote: BTreeMap<u32, u64>,
+}
+
+impl SessionManager {
+    /// Create an empty manager.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new Idle session and return its ID.
+    ///
+    /// # Errors
+    ///
+    /// `DuplicateSession` if a session with the same ID (same `local_id`
+    /// and timestamp) is already managed, Closed or not; the existing
+    /// session is left untouched. Use `remove` or `insert` to replace it.
+    pub fn open(
+        &mut self,
+        local_id: u32,
+        timestamp_ns: u64,
+        config: SessionConfig,
+    ) -> Result<u64, SessionManagerError> {
+        let session = Session::new(local_id, timestamp_ns, config);
+        let session_id = session.session_id;
+        if self.sessions.contains_key(&session_id) {
+            return Err(SessionManagerError::DuplicateSession { session_id });
+        }
+        self.insert(session)?;
+        Ok(session_id)
+    }
+
+    /// Take ownership of an existing session, returning any session it
+    /// replaces.
+    ///
+    /// An active session with a remote ID claims the remote index entry.
+    ///
+    /// # Errors
+    ///
+    /// `RemoteInUse` if the session is active and another active session
+    /// is bound to its remote ID, as `discover` would report; nothing is
+    /// inserted.
+    pub fn insert(&mut self, session: Session) -> Result<Option<Session>, SessionManagerError> {
+        let id = session.session_id;
+        let remote = session.remote_id.filter(|_| session.is_active());
+        if let Some(remote_id) = remote {
+            self.check_remote(remote_id, id)?;
+        }
+        let old = self.sessions.insert(id, session);
+        if let Some(old_remote) = old.as_ref().and_then(|s| s.remote_id) {
+            self.unindex(old_remote, id);
+        }
+        if let Some(remote_id) = remote {
+            self.by_remote.insert(remote_id, id);
+        }
+        Ok(old)
+    }
+
+    /// Remove a session.
+    pub fn remove(&mut self, session_id: u64) -> Option<Session> {
+        let session = self.sessions.remove(&session_id)?;
+        if let Some(remote_id) = session.remote_id {
+            self.unindex(remote_id, session_id);
+        }
+        Some(session)
+    }
+
+    /// Remove all Closed sessions, returning how many were dropped.
+    pub fn remove_closed(&mut self) -> usize {
+        let before = self.sessions.len();
+        self.sessions.retain(|_, s| s.is_active());
+        let sessions = &self.sessions;
+        self.by_remote.retain(|_, id| sessions.contains_key(id));
+        before - self.sessions.len()
+    }
+
+    /// Session by ID.
+    #[must_use]
+    pub fn get(&self, session_id: u64) -> Option<&Session> {
+        self.sessions.get(&session_id)
+    }
+
+    /// Session most recently bound to `remote_id`.
+    #[must_use]
+    pub fn get_by_remote(&self, remote_id: u32) -> Option<&Session> {
+        self.by_remote
+            .get(&remote_id)
+            .and_then(|id| self.sessions.get(id))
+    }
+
+    /// Number of managed sessions (including Closed ones).
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// `true` if no sessions are managed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Number of sessions that are not Closed.
+    #[must_use]
+    pub fn active_count(&self) -> usize {
+        self.sessions.values().filter(|s| s.is_active()).count()
+    }
+
+    /// All sessions in ascending `session_id` order.
+    pub fn iter(&self) -> impl Iterator<Item = &Session> {
+        self.sessions.values()
+    }
+
+    /// Idle → Discovering, binding `remote_id` to the session.
+    ///
+    /// # Errors
+    ///
+    /// `UnknownSession`, `RemoteInUse` if another active session is bound
+    /// to `remote_id`, or the session's own `InvalidState` wrapped in
+    /// `Transition`.
+    pub fn discover(
+        &mut self,
+        session_id: u64,
+        remote_id: u32,
+        timestamp_ns: u64,
+    ) -> Result<SessionEvent, SessionManagerError> {
+        self.check_remote(remote_id, session_id)?;
+        let event = self.transition(session_id, timestamp_ns, |s| {
+            s.try_discover(remote_id, timestamp_ns)
+        })?;
+        self.by_remote.insert(remote_id, session_id);
+        Ok(event)
+    }
+
+    /// Discovering → Exchanging.
+    ///
+    /// # Errors
+    ///
+    /// `UnknownSession` or the session's own `InvalidState` wrapped in
+    /// `Transition`.
+    pub fn begin_exchange(
+        &mut self,
+        session_id: u64,
+        timestamp_ns: u64,
+    ) -> Result<SessionEvent, SessionManagerError> {
+        self.transition(session_id, timestamp_ns, |s| {
+            s.try_begin_exchange(timestamp_ns)
+        })
+    }
+
+    /// Exchanging → Verified.
+    ///
+    /// # Errors
+    ///
+    /// `UnknownSession` or the session's own `InvalidState` wrapped in
+    /// `Transition`.
+    pub fn verify(
+        &mut self,
+        session_id: u64,
+        timestamp_ns: u64,
+    ) -> Result<SessionEvent, SessionManagerError> {
+        self.transition(session_id, timestamp_ns, |s| s.try_verify(timestamp_ns))
+    }
+
+    /// Any active state → Closed.
+    ///
+    /// # Errors
+    ///
+    /// `UnknownSession`, or `Transition(InvalidState)` if already Closed.
+    pub fn close(
+        &mut self,
+        session_id: u64,
+        reason: CloseReason,
+        timestamp_ns: u64,
+    ) -> Result<SessionEvent, SessionManagerError> {
+        self.transition(session_id, timestamp_ns, |s| {
+            s.try_close(reason, timestamp_ns)
+        })
+    }
+
+    /// Handle timeouts at `now_ns`.
+    ///
+    /// Each timed-out session is retried once `Session::next_retry_at` is
+    /// reached (its phase clock restarts at `now_ns`) while its retry budget
+    /// lasts, and otherwise closed with `CloseReason::Timeout`. Sessions
+    /// still waiting out their backoff are left alone. Events are returned
+    /// in ascending `session_id` order.
+    pub fn tick(&mut self, now_ns: u64) -> Vec<SessionEvent> {
+        let mut events = Vec::new();
+        for s in self.sessions.values_mut() {
+            if !s.is_timed_out(now_ns) {
+                continue;
+            }
+            let state = s.state;
+            if let Some(retry_at) = s.next_retry_at(now_ns) {
+                if now_ns < retry_at || s.try_retry_at(now_ns).is_err() {
+                    continue;
+                }
+                events.push(SessionEvent::Retried {
+                    session_id: s.session_id,
+                    state,
+                    attempt: s.retries,
+                    timestamp_ns: now_ns,
+                });
+            } else if s.try_close(CloseReason::Timeout, now_ns).is_ok() {
+                events.push(SessionEvent::Closed {
+                    session_id: s.session_id,
+                    from: state,
+                    reason: CloseReason::Timeout,
+                    timestamp_ns: now_ns,
+                });
+            }
+        }
+        events
+    }
+
+    fn transition(
+        &mut self,
+        session_id: u64,
+        timestamp_ns: u64,
+        step: impl FnOnce(&mut Session) -> Result<(), TransitionError>,
+    ) -> Result<SessionEvent, SessionManagerError> {
+        let s = self
+            .sessions
+            .get_mut(&session_id)
+            .ok_or(SessionManagerError::UnknownSession { session_id })?;
+        let from = s.state;
+        step(s)?;
+        Ok(match s.close_reason {
+            Some(reason) if s.state == SessionState::Closed => SessionEvent::Closed {
+                session_id,
+                from,
+                reason,
+                timestamp_ns,
+            },
+            _ => SessionEvent::Transitioned {
+                session_id,
+                from,
+                to: s.state,
+                timestamp_ns,
+            },
+        })
+    }
+
+    /// `RemoteInUse` if an active session other than `session_id` is bound
+    /// to `remote_id`.
+    fn check_remote(&self, remote_id: u32, session_id: u64) -> Result<(), SessionManagerError> {
+        match self.by_remote.get(&remote_id) {
+            Some(&other)
+                if other != session_id
+                    && self.sessions.get(&other).is_some_and(Session::is_active) =>
+            {
+                Err(SessionManagerError::RemoteInUse {
+                    remote_id,
+                    session_id: other,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn unindex(&mut self, remote_id: u32, session_id: u64) {
+        if self.by_remote.get(&remote_id) == Some(&session_id) {
+            self.by_remote.remove(&remote_id);
+        }
+    }
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(max_retries: u32) -> SessionConfig {
+        SessionConfig {
+            discovery_timeout_ns: 1000,
+            exchange_timeout_ns: 2000,
+            max_retries,
+            ..SessionConfig::default()
+        }
+    }
+
+    #[test]
+    fn happy_path_reports_each_transition() {
+        let mut m = SessionManager::new();
+        let id = m.open(1, 0, cfg(0)).unwrap();
+        assert_eq!(
+            m.discover(id, 7, 10),
+            Ok(SessionEvent::Transitioned {
+                session_id: id,
+                from: SessionState::Idle,
+                to: SessionState::Discovering,
+                timestamp_ns: 10,
+            })
+        );
+        assert!(m.begin_exchange(id, 20).is_ok());
+        assert!(m.verify(id, 30).is_ok());
+        assert_eq!(
+            m.close(id, CloseReason::Success, 40),
+            Ok(SessionEvent::Closed {
+                session_id: id,
+                from: SessionState::Verified,
+                reason: CloseReason::Success,
+                timestamp_ns: 40,
+            })
+        );
+        assert_eq!(m.get_by_remote(7).map(|s| s.session_id), Some(id));
+        assert_eq!(m.active_count(), 0);
+    }
+
+    #[test]
+    fn unknown_session_and_invalid_state() {
+        let mut m = SessionManager::new();
+        assert_eq!(
+            m.verify(42, 0),
+            Err(SessionManagerError::UnknownSession { session_id: 42 })
+        );
+        let id = m.open(1, 0, cfg(0)).unwrap();
+        assert!(matches!(
+            m.verify(id, 0),
+            Err(SessionManagerError::Transition(
+                TransitionError::InvalidState { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn remote_bound_to_one_active_session() {
+        let mut m = SessionManager::new();
+        let a = m.open(1, 0, cfg(0)).unwrap();
+        let b = m.open(1, 1, cfg(0)).unwrap();
+        m.discover(a, 7, 10).unwrap();
+        assert_eq!(
+            m.discover(b, 7, 10),
+            Err(SessionManagerError::RemoteInUse {
+                remote_id: 7,
+                session_id: a,
+            })
+        );
+        m.close(a, CloseReason::Cancelled, 20).unwrap();
+        m.discover(b, 7, 30).unwrap();
+        assert_eq!(m.get_by_remote(7).map(|s| s.session_id), Some(b));
+    }
+
+    #[test]
+    fn open_rejects_duplicate_session_id() {
+        let mut m = SessionManager::new();
+        let id = m.open(1, 0, cfg(0)).unwrap();
+        m.discover(id, 7, 10).unwrap();
+        assert_eq!(
+            m.open(1, 0, cfg(3)),
+            Err(SessionManagerError::DuplicateSession { session_id: id })
+        );
+        // The live session and its remote binding are untouched.
+        assert_eq!(m.get(id).unwrap().state, SessionState::Discovering);
+        assert_eq!(m.get_by_remote(7).map(|s| s.session_id), Some(id));
+
+        m.close(id, CloseReason::Cancelled, 20).unwrap();
+        assert!(m.open(1, 0, cfg(0)).is_err());
+        m.remove(id);
+        assert_eq!(m.open(1, 0, cfg(0)), Ok(id));
+    }
+
+    #[test]
+    fn tick_retries_then_times_out() {
+        let mut m = SessionManager::new();
+        let id = m.open(1, 0, cfg(1)).unwrap();
+        m.discover(id, 7, 0).unwrap();
+        assert!(m.tick(1000).is_empty());
+
+        assert_eq!(
+            m.tick(1001),
+            vec![SessionEvent::Retried {
+                session_id: id,
+                state: SessionState::Discovering,
+                attempt: 1,
+                timestamp_ns: 1001,
+            }]
+        );
+        // The retry restarted the phase clock.
+        assert!(m.tick(2001).is_empty());
+
+        assert_eq!(
+            m.tick(2002),
+            vec![SessionEvent::Closed {
+                session_id: id,
+                from: SessionState::Discovering,
+                reason: CloseReason::Timeout,
+                timestamp_ns: 2002,
+            }]
+        );
+        assert_eq!(m.get(id).unwrap().close_reason, Some(CloseReason::Timeout));
+        assert!(m.tick(1_000_000).is_empty());
+    }
+
+    #[test]
+    fn tick_only_touches_timed_out_sessions() {
+        let mut m = SessionManager::new();
+        let idle = m.open(1, 0, cfg(0)).unwrap();
+        let fast = m.open(2, 0, cfg(0)).unwrap();
+        let slow = m.open(3, 0, cfg(0)).unwrap();
+        m.discover(fast, 10, 0).unwrap();
+        m.discover(slow, 11, 0).unwrap();
+        m.begin_exchange(slow, 0).unwrap();
+
+        let events = m.tick(1500);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].session_id(), fast);
+        assert_eq!(m.get(idle).unwrap().state, SessionState::Idle);
+        assert_eq!(m.get(slow).unwrap().state, SessionState::Exchanging);
+    }
+
+    #[test]
+    fn remove_and_remove_closed_clear_remote_index() {
+        let mut m = SessionManager::new();
+        let a = m.open(1, 0, cfg(0)).unwrap();
+        let b = m.open(2, 0, cfg(0)).unwrap();
+        m.discover(a, 7, 0).unwrap();
+        m.discover(b, 8, 0).unwrap();
+        m.close(a, CloseReason::Cancelled, 1).unwrap();
+
+        assert_eq!(m.remove_closed(), 1);
+        assert!(m.get_by_remote(7).is_none());
+        assert_eq!(m.len(), 1);
+
+        assert!(m.remove(b).is_some());
+        assert!(m.get_by_remote(8).is_none());
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn insert_indexes_existing_session() {
+        let mut s = Session::new(1, 0, cfg(0));
+        s.try_discover(9, 5).unwrap();
+        let id = s.session_id;
+        let mut m = SessionManager::new();
+        assert!(m.insert(s).unwrap().is_none());
+        assert_eq!(m.get_by_remote(9).map(|s| s.session_id), Some(id));
+        assert_eq!(m.iter().count(), 1);
+    }
+
+    #[test]
+    fn insert_rejects_remote_in_use() {
+        let mut m = SessionManager::new();
+        let a = m.open(1, 0, cfg(0)).unwrap();
+        m.discover(a, 9, 5).unwrap();
+
+        let mut s = Session::new(2, 0, cfg(0));
+        s.try_discover(9, 6).unwrap();
+        let b = s.session_id;
+        assert_eq!(
+            m.insert(s.clone()).unwrap_err(),
+            SessionManagerError::RemoteInUse {
+                remote_id: 9,
+                session_id: a,
+            }
+        );
+        assert!(m.get(b).is_none());
+        assert_eq!(m.get_by_remote(9).map(|s| s.session_id), Some(a));
+
+        // Once the holder is closed the remote can be claimed.
+        m.close(a, CloseReason::Cancelled, 7).unwrap();
+        assert!(m.insert(s).unwrap().is_none());
+        assert_eq!(m.get_by_remote(9).map(|s| s.session_id), Some(b));
+    }
+
+    #[test]
+    fn tick_waits_out_backoff() {
+        use crate::session::BackoffPolicy;
+        let mut m = SessionManager::new();
+        let config = SessionConfig {
+            backoff: BackoffPolicy::Fixed { delay_ns: 500 },
+            ..cfg(1)
+        };
+        let id = m.open(1, 0, config).unwrap();
+        m.discover(id, 7, 0).unwrap();
+        // Timed out after 1000 ns; retry due at 1500 ns.
+        assert_eq!(m.get(id).unwrap().next_retry_at(1001), Some(1500));
+        assert!(m.tick(1001).is_empty());
+        assert!(m.tick(1499).is_empty());
+        assert!(matches!(
+            m.tick(1500).as_slice(),
+            [SessionEvent::Retried { attempt: 1, .. }]
+        ));
+        assert_eq!(m.get(id).unwrap().state_entered_ns, 1500);
+        // Budget exhausted: the next timeout closes immediately.
+        assert!(matches!(
+            m.tick(2501).as_slice(),
+            [SessionEvent::Closed {
+                reason: CloseReason::Timeout,
+                ..
+            }]
+        ));
+    }
+}
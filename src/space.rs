@@ -0,0 +1,125 @@
+//! Coordinate-space abstraction
+//!
+//! Proximity proofs, presence groups and the k-d tree only need a
+//! handful of operations from a network embedding: distance, spring
+//! update, a privacy-preserving commitment and a fixed-size encoding.
+//! `CoordinateSpace` captures exactly those so the embedding can be
+//! chosen per deployment (Euclidean Vivaldi, hyperbolic, ...).
+//!
+//! Author: Moroya Sakamoto
+
+use core::fmt::Debug;
+
+use crate::event::DistanceUnit;
+
+/// A metric embedding usable for presence proximity.
+///
+/// Implementations must keep `distance` symmetric and non-negative, and
+/// `axis` must be chosen so that `axis_lower_bound` never exceeds the
+/// true distance — the k-d tree relies on it to prune subtrees.
+pub trait CoordinateSpace: Copy + Debug {
+    /// Number of axes the k-d tree may split on.
+    const AXES: usize;
+    /// Exact byte length produced by [`encode`](Self::encode).
+    const ENCODED_LEN: usize;
+    /// Unit of [`distance`](Self::distance), recorded in proximity proofs.
+    const UNIT: DistanceUnit = DistanceUnit::Network;
+
+    /// Estimated network distance between two coordinates.
+    fn distance(&self, other: &Self) -> f64;
+
+    /// Move this coordinate toward a measured `rtt` against `other`.
+    ///
+    /// `cc` is the adaptive timestep (typically 0.01..0.25).
+    fn update(&mut self, other: &Self, rtt: f64, cc: f64);
+
+    /// Commitment hash of the coordinate (used in proofs instead of the
+    /// raw position).
+    fn commit(&self) -> u64;
+
+    /// Append the fixed-size little-endian encoding to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decode from the first [`ENCODED_LEN`](Self::ENCODED_LEN) bytes.
+    /// Returns `None` if `data` is too short or the values are invalid.
+    fn decode(data: &[u8]) -> Option<Self>;
+
+    /// Projection onto split axis `axis` (`0..AXES`).
+    fn axis(&self, axis: usize) -> f64;
+
+    /// Lower bound on `distance(self, x)` for any `x` on the far side of
+    /// the split plane through `split` on `axis`.
+    fn axis_lower_bound(&self, split: &Self, axis: usize) -> f64 {
+        (self.axis(axis) - split.axis(axis)).abs()
+    }
+}
+
+/// Read a little-endian `f64` at `off`, rejecting non-finite values.
+pub(crate) fn read_finite_f64(data: &[u8], off: usize) -> Option<f64> {
+    let v = f64::from_le_bytes(data.get(off..off + 8)?.try_into().ok()?);
+    v.is_finite().then_some(v)
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperbolic::HyperbolicCoord;
+    use crate::vivaldi::VivaldiCoord;
+
+    fn roundtrip<C: CoordinateSpace>(c: &C) -> C {
+        let mut buf = Vec::new();
+        c.encode(&mut buf);
+        assert_eq!(buf.len(), C::ENCODED_LEN);
+        C::decode(&buf).unwrap()
+    }
+
+    #[test]
+    fn vivaldi_encode_roundtrip() {
+        let c = VivaldiCoord::with_height(1.5, -2.5, 0.25);
+        let r = roundtrip(&c);
+        assert_eq!(r.commit(), c.commit());
+    }
+
+    #[test]
+    fn hyperbolic_encode_roundtrip() {
+        let c = HyperbolicCoord::with_height(0.3, -0.7, 0.1);
+        let r = roundtrip(&c);
+        assert_eq!(r.commit(), c.commit());
+    }
+
+    #[test]
+    fn decode_too_short() {
+        assert!(VivaldiCoord::decode(&[0; 10]).is_none());
+        assert!(HyperbolicCoord::decode(&[0; 10]).is_none());
+    }
+
+    #[test]
+    fn decode_rejects_nan() {
+        let mut buf = Vec::new();
+        VivaldiCoord::new(1.0, 2.0).encode(&mut buf);
+        buf[..8].copy_from_slice(&f64::NAN.to_le_bytes());
+        assert!(VivaldiCoord::decode(&buf).is_none());
+    }
+
+    #[test]
+    fn vivaldi_commit_matches_hash() {
+        let c = VivaldiCoord::with_height(3.0, 4.0, 1.0);
+        assert_eq!(c.commit(), c.hash());
+    }
+
+    #[test]
+    fn axis_lower_bound_is_admissible() {
+        let a = HyperbolicCoord::new(0.5, 2.0);
+        let b = HyperbolicCoord::new(-1.5, 0.25);
+        for axis in 0..HyperbolicCoord::AXES {
+            assert!(a.axis_lower_bound(&b, axis) <= a.distance(&b) + 1e-12);
+        }
+        let c = VivaldiCoord::new(0.5, 2.0);
+        let d = VivaldiCoord::new(-1.5, 0.25);
+        for axis in 0..VivaldiCoord::AXES {
+            assert!(c.axis_lower_bound(&d, axis) <= c.distance(&d) + 1e-12);
+        }
+    }
+}
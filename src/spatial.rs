@@ -1,41 +1,44 @@
 //! k-d tree spatial index for proximity queries
 //!
-//! 2D k-d tree over Vivaldi coordinates for O(log N) nearest-neighbor
-//! and range queries. Used for efficient batch proximity checks.
+//! k-d tree over any `CoordinateSpace` (Vivaldi by default) for
+//! O(log N) nearest-neighbor and range queries. Used for efficient
+//! batch proximity checks.
 //!
 //! Author: Moroya Sakamoto
 
+use crate::space::CoordinateSpace;
 use crate::vivaldi::VivaldiCoord;
 
 /// An entry in the spatial index: party ID + coordinate.
 #[derive(Debug, Clone, Copy)]
-pub struct SpatialEntry {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpatialEntry<C = VivaldiCoord> {
     pub id: u32,
-    pub coord: VivaldiCoord,
+    pub coord: C,
 }
 
 /// k-d tree node (stored in a flat Vec for cache-friendly traversal).
 #[derive(Debug, Clone)]
-struct KdNode {
-    entry: SpatialEntry,
-    /// 0 = split on x, 1 = split on y
+struct KdNode<C> {
+    entry: SpatialEntry<C>,
+    /// Index into `CoordinateSpace::axis` (0 = x, 1 = y for Vivaldi)
     split_axis: u8,
     left: Option<usize>,
     right: Option<usize>,
 }
 
-/// 2D k-d tree over Vivaldi coordinates.
+/// k-d tree over coordinates of space `C`.
 #[derive(Debug, Clone)]
-pub struct KdTree {
-    nodes: Vec<KdNode>,
+pub struct KdTree<C = VivaldiCoord> {
+    nodes: Vec<KdNode<C>>,
     root: Option<usize>,
 }
 
-impl KdTree {
+impl<C: CoordinateSpace> KdTree<C> {
     /// Build a k-d tree from a list of entries.
     /// O(N log N) construction.
     #[must_use]
-    pub fn build(entries: &[SpatialEntry]) -> Self {
+    pub fn build(entries: &[SpatialEntry<C>]) -> Self {
         if entries.is_empty() {
             return Self {
                 nodes: Vec::new(),
@@ -43,7 +46,7 @@ impl KdTree {
             };
         }
 
-        let mut sorted: Vec<SpatialEntry> = entries.to_vec();
+        let mut sorted: Vec<SpatialEntry<C>> = entries.to_vec();
         let mut nodes = Vec::with_capacity(entries.len());
         let root = Self::build_recursive(&mut sorted, 0, entries.len(), 0, &mut nodes);
 
@@ -54,22 +57,23 @@ impl KdTree {
     }
 
     fn build_recursive(
-        entries: &mut [SpatialEntry],
+        entries: &mut [SpatialEntry<C>],
         start: usize,
         end: usize,
         depth: usize,
-        nodes: &mut Vec<KdNode>,
+        nodes: &mut Vec<KdNode<C>>,
     ) -> usize {
         let len = end - start;
-        let axis = (depth % 2) as u8;
+        let axis = (depth % C::AXES) as u8;
 
         // Sort by current axis
         let slice = &mut entries[start..end];
-        if axis == 0 {
-            slice.sort_unstable_by(|a, b| a.coord.x.partial_cmp(&b.coord.x).unwrap());
-        } else {
-            slice.sort_unstable_by(|a, b| a.coord.y.partial_cmp(&b.coord.y).unwrap());
-        }
+        slice.sort_unstable_by(|a, b| {
+            a.coord
+                .axis(axis as usize)
+                .partial_cmp(&b.coord.axis(axis as usize))
+                .unwrap()
+        });
 
         let mid = start + len / 2;
         let entry = entries[mid];
@@ -110,10 +114,10 @@ impl KdTree {
     /// Find the nearest neighbor to a query point.
     /// Returns `(id, distance)` or `None` if tree is empty.
     #[must_use]
-    pub fn nearest(&self, query: &VivaldiCoord) -> Option<(u32, f64)> {
+    pub fn nearest(&self, query: &C) -> Option<(u32, f64)> {
         let root = self.root?;
         let mut best_id = self.nodes[root].entry.id;
-        let mut best_dist = Self::vivaldi_dist(query, &self.nodes[root].entry.coord);
+        let mut best_dist = query.distance(&self.nodes[root].entry.coord);
         self.nearest_recursive(root, query, &mut best_id, &mut best_dist);
         Some((best_id, best_dist))
     }
@@ -121,25 +125,20 @@ impl KdTree {
     fn nearest_recursive(
         &self,
         node_idx: usize,
-        query: &VivaldiCoord,
+        query: &C,
         best_id: &mut u32,
         best_dist: &mut f64,
     ) {
         let node = &self.nodes[node_idx];
-        let d = Self::vivaldi_dist(query, &node.entry.coord);
+        let d = query.distance(&node.entry.coord);
         if d < *best_dist {
             *best_dist = d;
             *best_id = node.entry.id;
         }
 
         // Determine which side of the split plane the query falls on
-        let (query_val, split_val) = if node.split_axis == 0 {
-            (query.x, node.entry.coord.x)
-        } else {
-            (query.y, node.entry.coord.y)
-        };
-
-        let diff = query_val - split_val;
+        let axis = node.split_axis as usize;
+        let diff = query.axis(axis) - node.entry.coord.axis(axis);
         let (first, second) = if diff < 0.0 {
             (node.left, node.right)
         } else {
@@ -153,17 +152,17 @@ impl KdTree {
 
         // Check if we need to search the other side
         // The split plane distance is a lower bound (ignoring height)
-        if diff.abs() < *best_dist {
+        if query.axis_lower_bound(&node.entry.coord, axis) < *best_dist {
             if let Some(second_idx) = second {
                 self.nearest_recursive(second_idx, query, best_id, best_dist);
             }
         }
     }
 
-    /// Find all entries within `radius` Vivaldi distance of the query point.
+    /// Find all entries within `radius` distance of the query point.
     /// Returns a Vec of `(id, distance)`.
     #[must_use]
-    pub fn range_query(&self, query: &VivaldiCoord, radius: f64) -> Vec<(u32, f64)> {
+    pub fn range_query(&self, query: &C, radius: f64) -> Vec<(u32, f64)> {
         let mut results = Vec::new();
         if let Some(root) = self.root {
             self.range_recursive(root, query, radius, &mut results);
@@ -174,23 +173,18 @@ impl KdTree {
     fn range_recursive(
         &self,
         node_idx: usize,
-        query: &VivaldiCoord,
+        query: &C,
         radius: f64,
         results: &mut Vec<(u32, f64)>,
     ) {
         let node = &self.nodes[node_idx];
-        let d = Self::vivaldi_dist(query, &node.entry.coord);
+        let d = query.distance(&node.entry.coord);
         if d <= radius {
             results.push((node.entry.id, d));
         }
 
-        let (query_val, split_val) = if node.split_axis == 0 {
-            (query.x, node.entry.coord.x)
-        } else {
-            (query.y, node.entry.coord.y)
-        };
-
-        let diff = query_val - split_val;
+        let axis = node.split_axis as usize;
+        let diff = query.axis(axis) - node.entry.coord.axis(axis);
 
         // Always check the side the query is on
         let (near, far) = if diff < 0.0 {
@@ -204,7 +198,7 @@ impl KdTree {
         }
 
         // Check the far side only if the split plane is within radius
-        if diff.abs() <= radius {
+        if query.axis_lower_bound(&node.entry.coord, axis) <= radius {
             if let Some(far_idx) = far {
                 self.range_recursive(far_idx, query, radius, results);
             }
@@ -217,7 +211,7 @@ impl KdTree {
     ///
     /// Panics if distance comparison yields `NaN`.
     #[must_use]
-    pub fn k_nearest(&self, query: &VivaldiCoord, k: usize) -> Vec<(u32, f64)> {
+    pub fn k_nearest(&self, query: &C, k: usize) -> Vec<(u32, f64)> {
         if k == 0 || self.is_empty() {
             return Vec::new();
         }
@@ -228,17 +222,12 @@ impl KdTree {
         let mut all: Vec<(u32, f64)> = self
             .nodes
             .iter()
-            .map(|n| (n.entry.id, Self::vivaldi_dist(query, &n.entry.coord)))
+            .map(|n| (n.entry.id, query.distance(&n.entry.coord)))
             .collect();
         all.sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
         all.truncate(k);
         all
     }
-
-    /// Vivaldi distance (includes height terms).
-    fn vivaldi_dist(a: &VivaldiCoord, b: &VivaldiCoord) -> f64 {
-        a.distance(b)
-    }
 }
 
 // ── Tests ──────────────────────────────────────────────────────────────
@@ -246,6 +235,7 @@ impl KdTree {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hyperbolic::HyperbolicCoord;
 
     fn entry(id: u32, x: f64, y: f64) -> SpatialEntry {
         SpatialEntry {
@@ -416,4 +406,35 @@ mod tests {
         assert!(ids.contains(&1), "entry at exact boundary must be included");
         assert!(!ids.contains(&2), "entry beyond boundary must be excluded");
     }
+
+    #[test]
+    fn hyperbolic_range_matches_brute_force() {
+        let mut entries = Vec::new();
+        for i in 0..8 {
+            for j in 0..8 {
+                entries.push(SpatialEntry {
+                    id: (i * 8 + j) as u32,
+                    coord: HyperbolicCoord::new(i as f64 - 4.0, j as f64 - 4.0),
+                });
+            }
+        }
+        let tree = KdTree::build(&entries);
+        let q = HyperbolicCoord::new(0.5, -0.5);
+        let mut got: Vec<u32> = tree.range_query(&q, 1.5).iter().map(|r| r.0).collect();
+        got.sort_unstable();
+        let expected: Vec<u32> = entries
+            .iter()
+            .filter(|e| q.distance(&e.coord) <= 1.5)
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(got, expected);
+
+        let (id, dist) = tree.nearest(&q).unwrap();
+        let best = entries
+            .iter()
+            .map(|e| q.distance(&e.coord))
+            .fold(f64::INFINITY, f64::min);
+        assert!((dist - best).abs() < 1e-12);
+        assert!((q.distance(&entries[id as usize].coord) - best).abs() < 1e-12);
+    }
 }
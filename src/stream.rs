@@ -0,0 +1,704 @@
+//! ストリームフレーミング — ソケット/ファイル向けの自己同期フレーム形式。
+//!
+//! `PresenceEvent` やシリアライズ済みレコードを連続したバイト列に載せるための
+//! フレーム形式と、`std::io::Read` / `Write` アダプタ、バイト単位で
+//! 投入できるインクリメンタルデコーダを提供する。
+//!
+//! Frame layout (リトルエンディアン):
+//!
+//! ```text
+//! [sync: 0xA1 0x1C][type: u8][len: u16][payload: len bytes][crc32: u32]
+//! ```
+//!
+//! CRC32 (IEEE) は `type` から `payload` 末尾までを対象とする。
+//! 同期マーカー不一致・未知の type・長さ超過・CRC 不一致のいずれかを検出した
+//! 場合、デコーダは 1 バイト進めて次の同期マーカーを探し直す (再同期)。
+//!
+//! 外部依存ゼロ。
+
+use std::io::{self, Read, Write};
+
+use crate::error::{DecodeError, EncodeError};
+use crate::event::{CrossingRecord, PresenceEvent, ProximityProof};
+use crate::gossip::GossipMessage;
+use crate::group::GroupProximityProof;
+use crate::serialize::{
+    serialize_crossing, serialize_group_proof, serialize_proximity, try_deserialize_crossing,
+    try_deserialize_group_proof, try_deserialize_proximity,
+};
+
+/// フレーム先頭の同期マーカー。
+pub const FRAME_SYNC: [u8; 2] = [0xA1, 0x1C];
+
+/// フレームヘッダサイズ: `sync(2) + type(1) + len(2)`。
+pub const FRAME_HEADER_SIZE: usize = 2 + 1 + 2;
+
+/// フレーム末尾の CRC32 サイズ。
+pub const FRAME_TRAILER_SIZE: usize = 4;
+
+/// ペイロードの最大長。
+///
+/// 破損した長さフィールドで大量のバイトを待ち続けないよう、既知の
+/// レコードが収まる範囲に制限する。
+pub const MAX_FRAME_PAYLOAD: usize = 1024;
+
+// ============================================================================
+// CRC32
+// ============================================================================
+
+/// CRC32 (IEEE 802.3, 反転多項式 0xEDB88320) のテーブル。
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+/// CRC32 (IEEE) を計算する。
+#[must_use]
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc = CRC32_TABLE[((crc ^ u32::from(b)) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+// ============================================================================
+// Frame
+// ============================================================================
+
+/// フレームのレコード種別タグ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum FrameType {
+    /// 18 バイトの `PresenceEvent`。
+    Event = 0x01,
+    /// `serialize_crossing` 形式の `CrossingRecord`。
+    Crossing = 0x02,
+    /// `serialize_proximity` 形式の `ProximityProof`。
+    Proximity = 0x03,
+    /// `serialize_group_proof` 形式の `GroupProximityProof`。
+    GroupProof = 0x04,
+    /// `GossipMessage::encode` 形式のゴシップメッセージ。
+    Gossip = 0x05,
+}
+
+impl FrameType {
+    /// タグバイトから種別を復元。
+    ///
+    /// # Errors
+    ///
+    /// 未知のタグなら `DecodeError::UnknownType`。
+    pub const fn from_u8(tag: u8) -> Result<Self, DecodeError> {
+        match tag {
+            0x01 => Ok(Self::Event),
+            0x02 => Ok(Self::Crossing),
+            0x03 => Ok(Self::Proximity),
+            0x04 => Ok(Self::GroupProof),
+            0x05 => Ok(Self::Gossip),
+            t => Err(DecodeError::UnknownType(t)),
+        }
+    }
+}
+
+/// デコード済みフレーム (CRC 検証済み)。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// レコード種別。
+    pub frame_type: FrameType,
+    /// ペイロード。
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    /// フレームを `buf` の末尾にエンコードする。
+    ///
+    /// # Errors
+    ///
+    /// ペイロードが `MAX_FRAME_PAYLOAD` を超える場合 `EncodeError::PayloadTooLarge`。
+    pub fn encode_into(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        encode_frame(self.frame_type, &self.payload, buf)
+    }
+
+    /// フレームをバイト列にエンコードする。
+    ///
+    /// # Errors
+    ///
+    /// [`Frame::encode_into`] と同じ。
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::with_capacity(frame_len(self.payload.len()));
+        self.encode_into(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// ペイロード長 `len` のフレーム全体のバイト数。
+#[must_use]
+pub const fn frame_len(len: usize) -> usize {
+    FRAME_HEADER_SIZE + len + FRAME_TRAILER_SIZE
+}
+
+/// 1 フレームを `buf` の末尾にエンコードする。
+///
+/// # Errors
+///
+/// ペイロードが `MAX_FRAME_PAYLOAD` を超える場合 `EncodeError::PayloadTooLarge`。
+pub fn encode_frame(
+    frame_type: FrameType,
+    payload: &[u8],
+    buf: &mut Vec<u8>,
+) -> Result<(), EncodeError> {
+    if payload.len() > MAX_FRAME_PAYLOAD {
+        return Err(EncodeError::PayloadTooLarge {
+            len: payload.len(),
+            max: MAX_FRAME_PAYLOAD,
+        });
+    }
+    buf.reserve(frame_len(payload.len()));
+    buf.extend_from_slice(&FRAME_SYNC);
+    let body_start = buf.len();
+    buf.push(frame_type as u8);
+    buf.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    buf.extend_from_slice(payload);
+    let crc = crc32(&buf[body_start..]);
+    buf.extend_from_slice(&crc.to_le_bytes());
+    Ok(())
+}
+
+// ============================================================================
+// StreamRecord
+// ============================================================================
+
+/// フレームに載せる型付きレコード。
+#[derive(Debug, Clone)]
+pub enum StreamRecord {
+    /// Presence event。
+    Event(PresenceEvent),
+    /// Crossing record。
+    Crossing(CrossingRecord),
+    /// 近接 proof。
+    Proximity(ProximityProof),
+    /// グループ近接 proof。
+    GroupProof(GroupProximityProof),
+    /// ゴシップメッセージ。
+    Gossip(GossipMessage),
+}
+
+impl StreamRecord {
+    /// 対応するフレーム種別。
+    #[must_use]
+    pub const fn frame_type(&self) -> FrameType {
+        match self {
+            Self::Event(_) => FrameType::Event,
+            Self::Crossing(_) => FrameType::Crossing,
+            Self::Proximity(_) => FrameType::Proximity,
+            Self::GroupProof(_) => FrameType::GroupProof,
+            Self::Gossip(_) => FrameType::Gossip,
+        }
+    }
+
+    /// フレームへ変換する。
+    ///
+    /// # Errors
+    ///
+    /// グループ proof のメンバー数超過は `EncodeError::TooManyMembers`、
+    /// 不正な座標アナウンスを含むゴシップメッセージは `EncodeError::InvalidField`。
+    pub fn to_frame(&self) -> Result<Frame, EncodeError> {
+        let payload = match self {
+            Self::Event(ev) => ev.to_bytes().to_vec(),
+            Self::Crossing(r) => serialize_crossing(r),
+            Self::Proximity(p) => serialize_proximity(p),
+            Self::GroupProof(g) => serialize_group_proof(g)?,
+            Self::Gossip(m) => m.encode()?,
+        };
+        Ok(Frame {
+            frame_type: self.frame_type(),
+            payload,
+        })
+    }
+
+    /// フレームのペイロードをレコードとしてデコードする。
+    ///
+    /// # Errors
+    ///
+    /// 各レコード形式のデコードエラー。`Event` はちょうど 18 バイトでなければ
+    /// `TooShort` / `TrailingBytes`、未登録の type は `UnknownType`。
+    pub fn from_frame(frame: &Frame) -> Result<Self, DecodeError> {
+        let p = frame.payload.as_slice();
+        Ok(match frame.frame_type {
+            FrameType::Event => {
+                let size = PresenceEvent::byte_size();
+                DecodeError::check_len(p, size)?;
+                if p.len() > size {
+                    return Err(DecodeError::TrailingBytes {
+                        extra: p.len() - size,
+                    });
+                }
+                let mut b = [0u8; 18];
+                b.copy_from_slice(p);
+                Self::Event(PresenceEvent::try_from_bytes(&b)?)
+            }
+            FrameType::Crossing => Self::Crossing(try_deserialize_crossing(p)?),
+            FrameType::Proximity => Self::Proximity(try_deserialize_proximity(p)?),
+            FrameType::GroupProof => Self::GroupProof(try_deserialize_group_proof(p)?),
+            FrameType::Gossip => Self::Gossip(GossipMessage::decode(p)?),
+        })
+    }
+}
+
+// ============================================================================
+// FrameDecoder
+// ============================================================================
+
+/// インクリメンタルデコーダ。
+///
+/// 任意の区切りで [`push`](Self::push) したバイト列から、完全かつ CRC が
+/// 一致するフレームだけを [`next_frame`](Self::next_frame) で取り出す。
+/// 破損部分は読み飛ばし、その量を統計として保持する。
+#[derive(Debug, Clone, Default)]
+pub struct FrameDecoder {
+    buf: Vec<u8>,
+    /// `buf` の先頭から処理済みのバイト数。
+    pos: usize,
+    skipped_bytes: u64,
+    corrupt_frames: u64,
+}
+
+impl FrameDecoder {
+    /// 空のデコーダを作成。
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            pos: 0,
+            skipped_bytes: 0,
+            corrupt_frames: 0,
+        }
+    }
+
+    /// 受信バイトを追加する。
+    ///
+    /// 処理済み部分が未処理部分以上になったときだけ詰め直すので、
+    /// 大きなチャンクを一度に渡してもデコードは入力長に対して線形。
+    pub fn push(&mut self, data: &[u8]) {
+        if self.pos > 0 && self.pos >= self.buf.len() - self.pos {
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// 次の完全なフレームを取り出す。データ不足なら `None`。
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        loop {
+            self.seek_sync();
+            let buf = &self.buf[self.pos..];
+            if buf.len() < FRAME_HEADER_SIZE {
+                return None;
+            }
+            let len = u16::from_le_bytes([buf[3], buf[4]]) as usize;
+            let Ok(frame_type) = FrameType::from_u8(buf[2]) else {
+                self.reject();
+                continue;
+            };
+            if len > MAX_FRAME_PAYLOAD {
+                self.reject();
+                continue;
+            }
+            let total = frame_len(len);
+            if buf.len() < total {
+                return None;
+            }
+            let crc_off = FRAME_HEADER_SIZE + len;
+            let expected = u32::from_le_bytes([
+                buf[crc_off],
+                buf[crc_off + 1],
+                buf[crc_off + 2],
+                buf[crc_off + 3],
+            ]);
+            if crc32(&buf[2..crc_off]) != expected {
+                self.reject();
+                continue;
+            }
+            let payload = buf[FRAME_HEADER_SIZE..crc_off].to_vec();
+            self.pos += total;
+            return Some(Frame {
+                frame_type,
+                payload,
+            });
+        }
+    }
+
+    /// 未処理のまま保持しているバイト数。
+    #[must_use]
+    pub fn pending_bytes(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    /// 再同期で読み飛ばしたバイト数の累計。
+    #[must_use]
+    pub const fn skipped_bytes(&self) -> u64 {
+        self.skipped_bytes
+    }
+
+    /// 破損と判定したフレーム候補の数の累計。
+    #[must_use]
+    pub const fn corrupt_frames(&self) -> u64 {
+        self.corrupt_frames
+    }
+
+    /// 同期マーカーが先頭に来るまでバイトを捨てる。
+    ///
+    /// 末尾 1 バイトがマーカーの 1 バイト目なら、続きを待つため残す。
+    fn seek_sync(&mut self) {
+        let buf = &self.buf[self.pos..];
+        let drop = buf
+            .windows(2)
+            .position(|w| w == FRAME_SYNC)
+            .unwrap_or_else(|| {
+                let keep = usize::from(buf.last() == Some(&FRAME_SYNC[0]));
+                buf.len() - keep
+            });
+        self.pos += drop;
+        self.skipped_bytes += drop as u64;
+    }
+
+    /// 先頭の同期マーカーを破損扱いで 1 バイト捨てる。
+    fn reject(&mut self) {
+        self.pos += 1;
+        self.skipped_bytes += 1;
+        self.corrupt_frames += 1;
+    }
+}
+
+// ============================================================================
+// I/O adapters
+// ============================================================================
+
+/// `Write` にフレームを書き出すアダプタ。
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// `inner` をラップする。
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            buf: Vec::new(),
+        }
+    }
+
+    /// 1 フレームを書き出す。
+    ///
+    /// # Errors
+    ///
+    /// ペイロード長超過は `io::ErrorKind::InvalidInput`、その他は `inner` の I/O エラー。
+    pub fn write_frame(&mut self, frame_type: FrameType, payload: &[u8]) -> io::Result<()> {
+        self.buf.clear();
+        encode_frame(frame_type, payload, &mut self.buf)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.inner.write_all(&self.buf)
+    }
+
+    /// 型付きレコードを 1 フレームとして書き出す。
+    ///
+    /// # Errors
+    ///
+    /// [`write_frame`](Self::write_frame) と同じ。レコードがエンコード
+    /// できない場合も `io::ErrorKind::InvalidInput`。
+    pub fn write_record(&mut self, record: &StreamRecord) -> io::Result<()> {
+        let frame = record
+            .to_frame()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.write_frame(frame.frame_type, &frame.payload)
+    }
+
+    /// `inner` をフラッシュする。
+    ///
+    /// # Errors
+    ///
+    /// `inner` の I/O エラー。
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// ラップしている writer を返す。
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// `Read` からフレームを読み出すアダプタ。
+///
+/// 破損部分は [`FrameDecoder`] と同様に読み飛ばす。EOF 時に残った不完全な
+/// フレームは [`decoder`](Self::decoder) の `pending_bytes` で確認できる。
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    inner: R,
+    decoder: FrameDecoder,
+    chunk: Box<[u8]>,
+}
+
+impl<R: Read> FrameReader<R> {
+    /// 読み込みチャンクサイズ。
+    const CHUNK_SIZE: usize = 4096;
+
+    /// `inner` をラップする。
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            decoder: FrameDecoder::new(),
+            chunk: vec![0u8; Self::CHUNK_SIZE].into_boxed_slice(),
+        }
+    }
+
+    /// 次のフレームを読む。EOF なら `Ok(None)`。
+    ///
+    /// # Errors
+    ///
+    /// `inner` の I/O エラー (`Interrupted` は再試行する)。
+    pub fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        loop {
+            if let Some(frame) = self.decoder.next_frame() {
+                return Ok(Some(frame));
+            }
+            match self.inner.read(&mut self.chunk) {
+                Ok(0) => return Ok(None),
+                Ok(n) => self.decoder.push(&self.chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 次のフレームを型付きレコードとして読む。
+    ///
+    /// # Errors
+    ///
+    /// I/O エラー、またはペイロードのデコード失敗 (`io::ErrorKind::InvalidData`)。
+    pub fn read_record(&mut self) -> io::Result<Option<StreamRecord>> {
+        match self.read_frame()? {
+            Some(frame) => StreamRecord::from_frame(&frame)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            None => Ok(None),
+        }
+    }
+
+    /// 内部デコーダ (統計・残りバイト数の参照用)。
+    pub const fn decoder(&self) -> &FrameDecoder {
+        &self.decoder
+    }
+
+    /// ラップしている reader を返す。
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Iterator for FrameReader<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vivaldi::VivaldiCoord;
+
+    fn event(i: u32) -> PresenceEvent {
+        PresenceEvent::new(i, i + 1, u64::from(i) * 1000)
+    }
+
+    fn stream_of(n: u32) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for i in 0..n {
+            encode_frame(FrameType::Event, &event(i).to_bytes(), &mut buf).unwrap();
+        }
+        buf
+    }
+
+    fn decode_all(dec: &mut FrameDecoder) -> Vec<Frame> {
+        std::iter::from_fn(|| dec.next_frame()).collect()
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn event_frame_layout() {
+        let frame = StreamRecord::Event(event(1)).to_frame().unwrap();
+        let bytes = frame.encode().unwrap();
+        assert_eq!(bytes.len(), frame_len(18));
+        assert_eq!(bytes[..2], FRAME_SYNC);
+        assert_eq!(bytes[2], FrameType::Event as u8);
+        assert_eq!(u16::from_le_bytes([bytes[3], bytes[4]]), 18);
+    }
+
+    #[test]
+    fn decoder_handles_byte_at_a_time() {
+        let bytes = stream_of(5);
+        let mut dec = FrameDecoder::new();
+        let mut frames = Vec::new();
+        for b in &bytes {
+            dec.push(std::slice::from_ref(b));
+            frames.extend(decode_all(&mut dec));
+        }
+        assert_eq!(frames.len(), 5);
+        assert_eq!(dec.pending_bytes(), 0);
+        assert_eq!(dec.skipped_bytes(), 0);
+        let rec = StreamRecord::from_frame(&frames[3]).unwrap();
+        assert!(matches!(rec, StreamRecord::Event(e) if e.party_a_id == 3));
+    }
+
+    #[test]
+    fn resync_after_corruption() {
+        let mut bytes = b"garbage".to_vec();
+        bytes.extend(stream_of(4));
+        // 2 フレーム目のペイロードを破損
+        let second = 7 + frame_len(18) + FRAME_HEADER_SIZE + 4;
+        bytes[second] ^= 0xFF;
+
+        let mut dec = FrameDecoder::new();
+        dec.push(&bytes);
+        let ids: Vec<u32> = decode_all(&mut dec)
+            .iter()
+            .map(|f| match StreamRecord::from_frame(f).unwrap() {
+                StreamRecord::Event(e) => e.party_a_id,
+                other => panic!("{other:?}"),
+            })
+            .collect();
+        assert_eq!(ids, vec![0, 2, 3]);
+        assert_eq!(dec.corrupt_frames(), 1);
+        assert_eq!(dec.skipped_bytes(), 7 + frame_len(18) as u64);
+    }
+
+    #[test]
+    fn decodes_large_chunk_of_many_frames() {
+        let n = 50_000;
+        let mut bytes = stream_of(n);
+        // 先頭付近を破損させ、大きなバッファ上の再同期も通す
+        bytes[FRAME_HEADER_SIZE] ^= 0xFF;
+        let mut dec = FrameDecoder::new();
+        dec.push(&bytes);
+        let frames = decode_all(&mut dec);
+        assert_eq!(frames.len(), n as usize - 1);
+        assert_eq!(dec.corrupt_frames(), 1);
+        assert_eq!(dec.pending_bytes(), 0);
+
+        // 消費後の push で詰め直しても続きを正しく読める
+        dec.push(&stream_of(3));
+        assert_eq!(decode_all(&mut dec).len(), 3);
+        assert_eq!(dec.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn resync_after_truncated_frame() {
+        let full = stream_of(2);
+        let mut bytes = full[..10].to_vec(); // 途中で切れたフレーム
+        bytes.extend(&full);
+        let mut dec = FrameDecoder::new();
+        dec.push(&bytes);
+        assert_eq!(decode_all(&mut dec).len(), 2);
+    }
+
+    #[test]
+    fn rejects_unknown_type_and_oversized_length() {
+        let mut bytes = vec![0xA1, 0x1C, 0x7F, 0, 0];
+        bytes.extend_from_slice(&[0xA1, 0x1C, 0x01, 0xFF, 0xFF]);
+        bytes.extend(stream_of(1));
+        let mut dec = FrameDecoder::new();
+        dec.push(&bytes);
+        assert_eq!(decode_all(&mut dec).len(), 1);
+        assert_eq!(dec.corrupt_frames(), 2);
+    }
+
+    #[test]
+    fn oversized_payload_is_refused() {
+        let mut buf = Vec::new();
+        assert_eq!(
+            encode_frame(FrameType::Crossing, &[0; MAX_FRAME_PAYLOAD + 1], &mut buf),
+            Err(EncodeError::PayloadTooLarge {
+                len: MAX_FRAME_PAYLOAD + 1,
+                max: MAX_FRAME_PAYLOAD
+            })
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn reader_writer_roundtrip() {
+        let a = VivaldiCoord::new(0.0, 0.0);
+        let b = VivaldiCoord::new(1.0, 0.0);
+        let prox = ProximityProof::prove(&a, &b, 10.0);
+
+        let mut w = FrameWriter::new(Vec::new());
+        w.write_record(&StreamRecord::Event(event(9))).unwrap();
+        w.write_record(&StreamRecord::Proximity(prox)).unwrap();
+        w.write_record(&StreamRecord::Gossip(GossipMessage::Probe {
+            sender_id: 4,
+            nonce: 5,
+        }))
+        .unwrap();
+        w.flush().unwrap();
+        let bytes = w.into_inner();
+
+        let mut r = FrameReader::new(bytes.as_slice());
+        assert!(matches!(
+            r.read_record().unwrap(),
+            Some(StreamRecord::Event(e)) if e.party_a_id == 9
+        ));
+        assert!(matches!(
+            r.read_record().unwrap(),
+            Some(StreamRecord::Proximity(p)) if p.content_hash == prox.content_hash
+        ));
+        assert!(matches!(
+            r.read_record().unwrap(),
+            Some(StreamRecord::Gossip(GossipMessage::Probe { nonce: 5, .. }))
+        ));
+        assert!(r.read_record().unwrap().is_none());
+
+        let frames: Vec<Frame> = FrameReader::new(bytes.as_slice())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+    }
+
+    #[test]
+    fn reader_reports_bad_payload() {
+        let mut bytes = Vec::new();
+        encode_frame(FrameType::Event, &[0; 5], &mut bytes).unwrap();
+        let err = FrameReader::new(bytes.as_slice())
+            .read_record()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
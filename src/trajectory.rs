@@ -0,0 +1,489 @@
+//! Coordinate trajectories and dwell-time encounters
+//!
+//! A real encounter is "we were close for at least N minutes". A
+//! `Trajectory` is a timestamped series of Vivaldi samples for one party;
+//! positions between samples are linearly interpolated. Between two
+//! trajectories the distance on each common time segment is convex, so the
+//! exact entry/exit times of the proximity region can be found by search
+//! and stitched into continuous `ProximityInterval`s.
+//!
+//! Author: Moroya Sakamoto
+
+#[cfg(feature = "serde")]
+use crate::error::DecodeError;
+use crate::vivaldi::VivaldiCoord;
+
+/// One timestamped coordinate sample.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrajectorySample {
+    pub timestamp_ns: u64,
+    pub coord: VivaldiCoord,
+}
+
+/// Time-ordered coordinate samples for one party.
+///
+/// Deserialization rejects timestamps that are not strictly increasing.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TrajectoryRepr"))]
+pub struct Trajectory {
+    samples: Vec<TrajectorySample>,
+}
+
+/// Unvalidated serde form of `Trajectory`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct TrajectoryRepr {
+    samples: Vec<TrajectorySample>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TrajectoryRepr> for Trajectory {
+    type Error = DecodeError;
+
+    fn try_from(r: TrajectoryRepr) -> Result<Self, DecodeError> {
+        if r.samples
+            .windows(2)
+            .any(|w| w[0].timestamp_ns >= w[1].timestamp_ns)
+        {
+            return Err(DecodeError::InvalidField("samples"));
+        }
+        Ok(Self { samples: r.samples })
+    }
+}
+
+impl Trajectory {
+    /// Create an empty trajectory.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            samples: Vec::new(),
+        }
+    }
+
+    /// Append a sample. Returns false if `timestamp_ns` is not strictly
+    /// after the last sample.
+    pub fn push(&mut self, timestamp_ns: u64, coord: VivaldiCoord) -> bool {
+        if self
+            .samples
+            .last()
+            .is_some_and(|s| s.timestamp_ns >= timestamp_ns)
+        {
+            return false;
+        }
+        self.samples.push(TrajectorySample {
+            timestamp_ns,
+            coord,
+        });
+        true
+    }
+
+    /// Number of samples.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Is the trajectory empty?
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// All samples, oldest first.
+    #[must_use]
+    pub fn samples(&self) -> &[TrajectorySample] {
+        &self.samples
+    }
+
+    /// `(first, last)` sample timestamps.
+    #[must_use]
+    pub fn time_span(&self) -> Option<(u64, u64)> {
+        Some((
+            self.samples.first()?.timestamp_ns,
+            self.samples.last()?.timestamp_ns,
+        ))
+    }
+
+    /// Index of the segment `[i, i+1]` containing `t` (or the last sample).
+    fn segment_at(&self, t: u64) -> Option<usize> {
+        let (start, end) = self.time_span()?;
+        if t < start || t > end {
+            return None;
+        }
+        let i = self.samples.partition_point(|s| s.timestamp_ns <= t);
+        Some(i.saturating_sub(1))
+    }
+
+    /// Linearly interpolated position at `t`; `None` outside the span.
+    #[must_use]
+    pub fn position_at(&self, t: u64) -> Option<VivaldiCoord> {
+        let i = self.segment_at(t)?;
+        let s0 = &self.samples[i];
+        let Some(s1) = self.samples.get(i + 1) else {
+            return Some(s0.coord);
+        };
+        let f = (t - s0.timestamp_ns) as f64 / (s1.timestamp_ns - s0.timestamp_ns) as f64;
+        let lerp = |a: f64, b: f64| (b - a).mul_add(f, a);
+        Some(VivaldiCoord::with_height(
+            lerp(s0.coord.x, s1.coord.x),
+            lerp(s0.coord.y, s1.coord.y),
+            lerp(s0.coord.height, s1.coord.height),
+        ))
+    }
+
+    /// Length (ns) of the sampling gap around `t`; 0 at the last sample.
+    fn gap_at(&self, t: u64) -> u64 {
+        self.segment_at(t)
+            .and_then(|i| {
+                let next = self.samples.get(i + 1)?;
+                Some(next.timestamp_ns - self.samples[i].timestamp_ns)
+            })
+            .unwrap_or(0)
+    }
+}
+
+// ── Encounter detection ────────────────────────────────────────────────
+
+/// Dwell-time encounter configuration.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DwellConfig {
+    /// Minimum continuous proximity for an encounter (default 5 minutes).
+    pub min_dwell_ns: u64,
+    /// Sampling gaps longer than this break continuity; positions are not
+    /// interpolated across them (default 60 seconds).
+    pub max_gap_ns: u64,
+}
+
+impl Default for DwellConfig {
+    fn default() -> Self {
+        Self {
+            min_dwell_ns: 300_000_000_000, // 5 minutes
+            max_gap_ns: 60_000_000_000,    // 60 seconds
+        }
+    }
+}
+
+/// A continuous time interval during which two parties were proximate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProximityInterval {
+    /// First proximate instant (ns).
+    pub start_ns: u64,
+    /// Last proximate instant (ns).
+    pub end_ns: u64,
+    /// Closest approach within the interval.
+    pub min_distance: f64,
+    /// Instant (ns) of the closest approach.
+    pub closest_ns: u64,
+}
+
+impl ProximityInterval {
+    /// Dwell time (ns).
+    #[must_use]
+    pub const fn dwell_ns(&self) -> u64 {
+        self.end_ns - self.start_ns
+    }
+}
+
+/// Distance between two trajectories at `t` (both must cover `t`).
+fn distance_at(a: &Trajectory, b: &Trajectory, t: u64) -> f64 {
+    match (a.position_at(t), b.position_at(t)) {
+        (Some(pa), Some(pb)) => pa.distance(&pb),
+        _ => f64::INFINITY,
+    }
+}
+
+/// Minimum of a convex function on `[lo, hi]` (integer ternary search).
+fn argmin_convex(mut lo: u64, mut hi: u64, f: impl Fn(u64) -> f64) -> u64 {
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        let m1 = lo + third;
+        let m2 = hi - third;
+        if f(m1) <= f(m2) {
+            hi = m2;
+        } else {
+            lo = m1;
+        }
+    }
+    (lo..=hi)
+        .min_by(|&x, &y| f(x).total_cmp(&f(y)))
+        .unwrap_or(lo)
+}
+
+/// First `t` in `[lo, hi]` with `pred(t)`, given `pred` is monotone
+/// false→true and `pred(hi)` holds.
+fn first_true(mut lo: u64, mut hi: u64, pred: impl Fn(u64) -> bool) -> u64 {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    hi
+}
+
+/// Compute every continuous proximity interval between two trajectories.
+///
+/// Only the time range covered by both trajectories is considered.
+/// Boundaries are exact to the nanosecond.
+#[must_use]
+pub fn proximity_intervals(
+    a: &Trajectory,
+    b: &Trajectory,
+    threshold: f64,
+    max_gap_ns: u64,
+) -> Vec<ProximityInterval> {
+    let mut out = Vec::new();
+    let (Some((a0, a1)), Some((b0, b1))) = (a.time_span(), b.time_span()) else {
+        return out;
+    };
+    let (start, end) = (a0.max(b0), a1.min(b1));
+    if start > end {
+        return out;
+    }
+
+    // 両軌跡のサンプル時刻を合わせた区分点
+    let mut knots: Vec<u64> = a
+        .samples
+        .iter()
+        .chain(&b.samples)
+        .map(|s| s.timestamp_ns)
+        .filter(|&t| t >= start && t <= end)
+        .chain([start, end])
+        .collect();
+    knots.sort_unstable();
+    knots.dedup();
+
+    let d = |t: u64| distance_at(a, b, t);
+    let mut open: Option<ProximityInterval> = None;
+    let close = |open: &mut Option<ProximityInterval>, out: &mut Vec<ProximityInterval>| {
+        if let Some(iv) = open.take() {
+            out.push(iv);
+        }
+    };
+
+    if knots.len() == 1 {
+        let t = knots[0];
+        if d(t) <= threshold {
+            out.push(ProximityInterval {
+                start_ns: t,
+                end_ns: t,
+                min_distance: d(t),
+                closest_ns: t,
+            });
+        }
+        return out;
+    }
+
+    for w in knots.windows(2) {
+        let (t0, t1) = (w[0], w[1]);
+        let gap = a.gap_at(t0).max(b.gap_at(t0));
+        if gap > max_gap_ns {
+            // 補間できない空白 → 連続性を切る
+            close(&mut open, &mut out);
+            continue;
+        }
+        let tmin = argmin_convex(t0, t1, d);
+        let dmin = d(tmin);
+        if dmin > threshold {
+            close(&mut open, &mut out);
+            continue;
+        }
+        let entry = if d(t0) <= threshold {
+            t0
+        } else {
+            first_true(t0, tmin, |t| d(t) <= threshold)
+        };
+        let exit = if d(t1) <= threshold {
+            t1
+        } else {
+            // 最後に閾値以下となる時刻 = 閾値超えの最初の時刻 - 1
+            first_true(tmin, t1, |t| d(t) > threshold) - 1
+        };
+        match open.as_mut() {
+            Some(iv) if iv.end_ns == entry => {
+                iv.end_ns = exit;
+                if dmin < iv.min_distance {
+                    iv.min_distance = dmin;
+                    iv.closest_ns = tmin;
+                }
+            }
+            _ => {
+                close(&mut open, &mut out);
+                open = Some(ProximityInterval {
+                    start_ns: entry,
+                    end_ns: exit,
+                    min_distance: dmin,
+                    closest_ns: tmin,
+                });
+            }
+        }
+        if exit < t1 {
+            close(&mut open, &mut out);
+        }
+    }
+    close(&mut open, &mut out);
+    out
+}
+
+/// Longest proximity interval (distance <= `threshold`) lasting at least
+/// `config.min_dwell_ns`.
+#[must_use]
+pub fn detect_encounter(
+    a: &Trajectory,
+    b: &Trajectory,
+    threshold: f64,
+    config: &DwellConfig,
+) -> Option<ProximityInterval> {
+    proximity_intervals(a, b, threshold, config.max_gap_ns)
+        .into_iter()
+        .filter(|iv| iv.dwell_ns() >= config.min_dwell_ns)
+        .max_by_key(ProximityInterval::dwell_ns)
+}
+
+// ── Tests ──────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+    const MIN: u64 = 60 * SEC;
+
+    fn traj(points: &[(u64, f64, f64)]) -> Trajectory {
+        let mut t = Trajectory::new();
+        for &(ts, x, y) in points {
+            assert!(t.push(ts, VivaldiCoord::new(x, y)));
+        }
+        t
+    }
+
+    /// Walk along x from `x0` to `x1` over `[t0, t1]`, sampled every 10 s.
+    fn walk(t0: u64, t1: u64, x0: f64, x1: f64) -> Trajectory {
+        let mut t = Trajectory::new();
+        let mut ts = t0;
+        while ts <= t1 {
+            let f = (ts - t0) as f64 / (t1 - t0) as f64;
+            t.push(ts, VivaldiCoord::new((x1 - x0).mul_add(f, x0), 0.0));
+            ts += 10 * SEC;
+        }
+        t
+    }
+
+    #[test]
+    fn push_rejects_out_of_order() {
+        let mut t = Trajectory::new();
+        assert!(t.push(10, VivaldiCoord::new(0.0, 0.0)));
+        assert!(!t.push(10, VivaldiCoord::new(1.0, 0.0)));
+        assert!(!t.push(5, VivaldiCoord::new(1.0, 0.0)));
+        assert_eq!(t.len(), 1);
+    }
+
+    #[test]
+    fn position_interpolates() {
+        let t = traj(&[(0, 0.0, 0.0), (100, 10.0, 20.0)]);
+        let p = t.position_at(25).unwrap();
+        assert!((p.x - 2.5).abs() < 1e-12);
+        assert!((p.y - 5.0).abs() < 1e-12);
+        assert!(t.position_at(101).is_none());
+        assert!((t.position_at(100).unwrap().x - 10.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn stationary_pair_one_interval() {
+        let a = traj(&[(0, 0.0, 0.0), (10 * MIN, 0.0, 0.0)]);
+        let b = traj(&[(0, 3.0, 0.0), (10 * MIN, 3.0, 0.0)]);
+        let ivs = proximity_intervals(&a, &b, 5.0, 20 * MIN);
+        assert_eq!(ivs.len(), 1);
+        assert_eq!(ivs[0].start_ns, 0);
+        assert_eq!(ivs[0].end_ns, 10 * MIN);
+        assert!((ivs[0].min_distance - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn exact_entry_and_exit() {
+        // b が x=-20 → +20 を 40 秒で通過、a は原点 → |x| <= 5 の 10 秒間
+        let a = traj(&[(0, 0.0, 0.0), (40 * SEC, 0.0, 0.0)]);
+        let b = traj(&[(0, -20.0, 0.0), (40 * SEC, 20.0, 0.0)]);
+        let ivs = proximity_intervals(&a, &b, 5.0, MIN);
+        assert_eq!(ivs.len(), 1);
+        assert_eq!(ivs[0].start_ns, 15 * SEC);
+        assert_eq!(ivs[0].end_ns, 25 * SEC);
+        assert_eq!(ivs[0].closest_ns, 20 * SEC);
+        assert!(ivs[0].min_distance < 1e-6);
+    }
+
+    #[test]
+    fn meet_dwell_depart() {
+        // a は静止、b は 0-5 分で接近、5-15 分滞在、15-20 分で離脱
+        let a = walk(0, 20 * MIN, 0.0, 0.0);
+        let mut b = walk(0, 5 * MIN, 100.0, 2.0);
+        for s in walk(5 * MIN + 10 * SEC, 15 * MIN, 2.0, 2.0).samples() {
+            b.push(s.timestamp_ns, s.coord);
+        }
+        for s in walk(15 * MIN + 10 * SEC, 20 * MIN, 2.0, 100.0).samples() {
+            b.push(s.timestamp_ns, s.coord);
+        }
+        let enc = detect_encounter(&a, &b, 10.0, &DwellConfig::default()).unwrap();
+        assert!(enc.dwell_ns() >= 10 * MIN);
+        assert!(enc.start_ns < 5 * MIN && enc.end_ns > 15 * MIN);
+
+        let strict = DwellConfig {
+            min_dwell_ns: 15 * MIN,
+            ..Default::default()
+        };
+        assert!(detect_encounter(&a, &b, 10.0, &strict).is_none());
+    }
+
+    #[test]
+    fn two_separate_intervals() {
+        let a = traj(&[(0, 0.0, 0.0), (30 * SEC, 0.0, 0.0)]);
+        let b = traj(&[
+            (0, 0.0, 0.0),
+            (10 * SEC, 50.0, 0.0),
+            (20 * SEC, 50.0, 0.0),
+            (30 * SEC, 0.0, 0.0),
+        ]);
+        let ivs = proximity_intervals(&a, &b, 10.0, MIN);
+        assert_eq!(ivs.len(), 2);
+        assert_eq!(ivs[0].start_ns, 0);
+        assert_eq!(ivs[1].end_ns, 30 * SEC);
+    }
+
+    #[test]
+    fn sampling_gap_breaks_continuity() {
+        let a = traj(&[(0, 0.0, 0.0), (MIN, 0.0, 0.0), (10 * MIN, 0.0, 0.0)]);
+        let b = traj(&[(0, 1.0, 0.0), (MIN, 1.0, 0.0), (10 * MIN, 1.0, 0.0)]);
+        let cfg = DwellConfig {
+            min_dwell_ns: 5 * MIN,
+            max_gap_ns: 2 * MIN,
+        };
+        // 9 分の空白は補間しない → 1 分の区間のみ
+        let ivs = proximity_intervals(&a, &b, 10.0, cfg.max_gap_ns);
+        assert_eq!(ivs.len(), 1);
+        assert_eq!(ivs[0].end_ns, MIN);
+        assert!(detect_encounter(&a, &b, 10.0, &cfg).is_none());
+    }
+
+    #[test]
+    fn no_overlap_no_intervals() {
+        let a = traj(&[(0, 0.0, 0.0), (10, 0.0, 0.0)]);
+        let b = traj(&[(20, 0.0, 0.0), (30, 0.0, 0.0)]);
+        assert!(proximity_intervals(&a, &b, 10.0, 100).is_empty());
+        assert!(proximity_intervals(&Trajectory::new(), &b, 10.0, 100).is_empty());
+    }
+
+    #[test]
+    fn height_counts_toward_distance() {
+        let mut a = Trajectory::new();
+        a.push(0, VivaldiCoord::with_height(0.0, 0.0, 6.0));
+        a.push(MIN, VivaldiCoord::with_height(0.0, 0.0, 6.0));
+        let b = traj(&[(0, 0.0, 0.0), (MIN, 0.0, 0.0)]);
+        assert!(proximity_intervals(&a, &b, 5.0, MIN).is_empty());
+    }
+}
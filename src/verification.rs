@@ -1,13 +1,16 @@
 //! Crossing record 完全性検証。
 //!
 //! `CrossingRecord` の content hash を再計算し、改ざんの有無を検出する。
-//! また `ProximityProof` の content hash も独立検証可能。
+//! また `ProximityProof` / `GroupProximityProof` の content hash も独立検証可能。
 
 use crate::event::{CrossingRecord, ProximityProof};
 use crate::fnv1a;
+use crate::group::{group_id_of, GroupProximityProof};
+use crate::protocol::exchange_challenges;
 
 /// 検証結果。
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VerifyResult {
     /// content hash が一致。
     Valid,
@@ -17,58 +20,124 @@ pub enum VerifyResult {
     ZkpNotVerified,
     /// 近接未確認。
     NotProximate,
+    /// セッション束縛の不一致 (ZKP チャレンジが束縛から導出されていない、
+    /// または指定セッションのレコードではない)。
+    SessionMismatch,
 }
 
 /// `ProximityProof` の content hash を再計算し検証。
 #[must_use]
 pub fn verify_proximity(proof: &ProximityProof) -> bool {
-    let mut buf = [0u8; 40];
-    buf[..8].copy_from_slice(&proof.distance.to_le_bytes());
-    buf[8..16].copy_from_slice(&proof.threshold.to_le_bytes());
-    buf[16..24].copy_from_slice(&proof.coord_hash_a.to_le_bytes());
-    buf[24..32].copy_from_slice(&proof.coord_hash_b.to_le_bytes());
-    buf[32..40].copy_from_slice(&(proof.is_proximate as u64).to_le_bytes());
-    let expected = fnv1a(&buf);
-    expected == proof.content_hash
+    proof.compute_hash() == proof.content_hash
 }
 
 /// `CrossingRecord` の content hash を再計算し検証。
 #[must_use]
 pub fn verify_record_hash(record: &CrossingRecord) -> bool {
-    let ev_bytes = record.event.to_bytes();
-    let mut buf = Vec::with_capacity(18 + 8 * 4);
-    buf.extend_from_slice(&ev_bytes);
-    buf.extend_from_slice(&record.proof_a.response.to_le_bytes());
-    buf.extend_from_slice(&record.proof_b.response.to_le_bytes());
-    buf.extend_from_slice(&record.proximity.content_hash.to_le_bytes());
-    buf.extend_from_slice(&record.proximity.distance.to_le_bytes());
-    let expected = fnv1a(&buf);
-    expected == record.content_hash
+    record.compute_hash() == record.content_hash
 }
 
 /// `CrossingRecord` の総合検証。
 ///
 /// 1. content hash 一致
 /// 2. 両方の ZKP が verified
-/// 3. proximity が確認済み
+/// 3. proximity が確認済み (hybrid なら地理 proof も)
 /// 4. proximity proof の content hash も検証
+///
+/// dwell フラグと dwell 区間の有無が食い違う、または区間が逆転している
+/// 場合も `HashMismatch` とする。両 ZKP のチャレンジがタイムスタンプと
+/// セッション束縛 (なければ束縛なし) から
+/// [`exchange_challenges`] で導出されたものでなければ
+/// `SessionMismatch` (束縛を取り除いて再ハッシュしたレコードもこれで拒否される)。
 #[must_use]
 pub fn verify_record(record: &CrossingRecord) -> VerifyResult {
     if !verify_record_hash(record) {
         return VerifyResult::HashMismatch;
     }
+    let expected = exchange_challenges(record.event.timestamp_ns, record.session.as_ref());
+    if (record.proof_a.challenge, record.proof_b.challenge) != expected {
+        return VerifyResult::SessionMismatch;
+    }
+    match &record.dwell {
+        Some(dwell) if !record.event.has_dwell() || dwell.end_ns < dwell.start_ns => {
+            return VerifyResult::HashMismatch;
+        }
+        None if record.event.has_dwell() => return VerifyResult::HashMismatch,
+        _ => {}
+    }
     if !record.proof_a.verified || !record.proof_b.verified {
         return VerifyResult::ZkpNotVerified;
     }
     if !record.proximity.is_proximate {
         return VerifyResult::NotProximate;
     }
+    if let Some(geo) = &record.geo_proximity {
+        if !geo.is_proximate {
+            return VerifyResult::NotProximate;
+        }
+        if !verify_proximity(geo) {
+            return VerifyResult::HashMismatch;
+        }
+    }
     if !verify_proximity(&record.proximity) {
         return VerifyResult::HashMismatch;
     }
     VerifyResult::Valid
 }
 
+/// `CrossingRecord` が `session_id` のセッションで作られたかを含めて検証。
+///
+/// 束縛がない、または束縛のどちら側も `session_id` でなければ
+/// `SessionMismatch`。それ以外は [`verify_record`] と同じ。
+#[must_use]
+pub fn verify_record_session(record: &CrossingRecord, session_id: u64) -> VerifyResult {
+    match &record.session {
+        Some(session) if session.contains(session_id) => verify_record(record),
+        _ => VerifyResult::SessionMismatch,
+    }
+}
+
+/// `GroupProximityProof` の `group_id` と content hash を再計算し検証。
+///
+/// `group_id` はソート済み `member_ids` から、content hash は
+/// `member_ids.len()` をメンバー数として再計算する。`member_count` が
+/// ID リストの長さと食い違う場合も `false`。
+#[must_use]
+pub fn verify_group_hash(proof: &GroupProximityProof) -> bool {
+    if proof.member_count != proof.member_ids.len()
+        || proof.member_ids.windows(2).any(|w| w[0] >= w[1])
+        || group_id_of(&proof.member_ids) != proof.group_id
+    {
+        return false;
+    }
+    let mut buf = [0u8; 40];
+    buf[..8].copy_from_slice(&proof.group_id.to_le_bytes());
+    buf[8..16].copy_from_slice(&proof.max_distance.to_le_bytes());
+    buf[16..24].copy_from_slice(&proof.threshold.to_le_bytes());
+    buf[24..32].copy_from_slice(&(proof.member_ids.len() as u64).to_le_bytes());
+    buf[32..40].copy_from_slice(&u64::from(proof.all_proximate).to_le_bytes());
+    fnv1a(&buf) == proof.content_hash
+}
+
+/// `GroupProximityProof` の総合検証。
+///
+/// 1. `group_id` / content hash 一致 ([`verify_group_hash`])
+/// 2. `all_proximate` が `max_distance <= threshold` と整合 (不整合は `HashMismatch`)
+/// 3. 全員が近接している
+#[must_use]
+pub fn verify_group_proof(proof: &GroupProximityProof) -> VerifyResult {
+    if !verify_group_hash(proof) {
+        return VerifyResult::HashMismatch;
+    }
+    if proof.all_proximate != (proof.max_distance <= proof.threshold) {
+        return VerifyResult::HashMismatch;
+    }
+    if !proof.all_proximate {
+        return VerifyResult::NotProximate;
+    }
+    VerifyResult::Valid
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -76,7 +145,7 @@ pub fn verify_record(record: &CrossingRecord) -> VerifyResult {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::event::PresenceEvent;
+    use crate::event::{PresenceEvent, SessionBinding};
     use crate::identity::{IdentityCommitment, ZkProof};
     use crate::vivaldi::VivaldiCoord;
 
@@ -86,8 +155,9 @@ mod tests {
         let prox = ProximityProof::prove(&a, &b, 10.0);
         let ca = IdentityCommitment::new(42, 1, 100);
         let cb = IdentityCommitment::new(99, 2, 100);
-        let pa = ZkProof::prove(42, &ca, 0xAA);
-        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let (challenge_a, challenge_b) = exchange_challenges(100, None);
+        let pa = ZkProof::prove(42, &ca, challenge_a);
+        let pb = ZkProof::prove(99, &cb, challenge_b);
         let mut event = PresenceEvent::new(1, 2, 100);
         event.set_mutual();
         event.set_verified();
@@ -139,8 +209,9 @@ mod tests {
         let ca = IdentityCommitment::new(42, 1, 100);
         let cb = IdentityCommitment::new(99, 2, 100);
         // 不正な秘密鍵で proof_a を作成 → verified = false
-        let pa = ZkProof::prove(999, &ca, 0xAA);
-        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let (challenge_a, challenge_b) = exchange_challenges(100, None);
+        let pa = ZkProof::prove(999, &ca, challenge_a);
+        let pb = ZkProof::prove(99, &cb, challenge_b);
         let mut event = PresenceEvent::new(1, 2, 100);
         event.set_mutual();
         event.set_verified();
@@ -155,8 +226,9 @@ mod tests {
         let prox = ProximityProof::prove(&a, &b, 1.0); // 範囲外
         let ca = IdentityCommitment::new(42, 1, 100);
         let cb = IdentityCommitment::new(99, 2, 100);
-        let pa = ZkProof::prove(42, &ca, 0xAA);
-        let pb = ZkProof::prove(99, &cb, 0xBB);
+        let (challenge_a, challenge_b) = exchange_challenges(100, None);
+        let pa = ZkProof::prove(42, &ca, challenge_a);
+        let pb = ZkProof::prove(99, &cb, challenge_b);
         let mut event = PresenceEvent::new(1, 2, 100);
         event.set_mutual();
         event.set_verified();
@@ -184,4 +256,150 @@ mod tests {
         record.content_hash = 0;
         assert!(!verify_record_hash(&record));
     }
+
+    #[test]
+    fn dwell_flag_mismatch_rejected() {
+        use crate::event::DwellInterval;
+        let mut record = make_valid_record().with_dwell(DwellInterval {
+            start_ns: 10,
+            end_ns: 20,
+        });
+        assert_eq!(verify_record(&record), VerifyResult::Valid);
+        // 区間を消してもフラグが残る → 不整合
+        record.dwell = None;
+        assert_eq!(verify_record(&record), VerifyResult::HashMismatch);
+    }
+
+    fn make_group_proof(far: bool) -> GroupProximityProof {
+        use crate::group::{GroupConfig, PresenceGroup};
+        let mut g = PresenceGroup::new(GroupConfig::default());
+        g.try_add_member(5, VivaldiCoord::new(0.0, 0.0), 0).unwrap();
+        g.try_add_member(1, VivaldiCoord::new(1.0, 0.0), 0).unwrap();
+        let x = if far { 500.0 } else { 2.0 };
+        g.try_add_member(9, VivaldiCoord::new(x, 0.0), 0).unwrap();
+        g.prove_proximity().unwrap()
+    }
+
+    #[test]
+    fn valid_group_proof() {
+        let proof = make_group_proof(false);
+        assert!(verify_group_hash(&proof));
+        assert_eq!(verify_group_proof(&proof), VerifyResult::Valid);
+        assert_eq!(
+            verify_group_proof(&make_group_proof(true)),
+            VerifyResult::NotProximate
+        );
+    }
+
+    #[test]
+    fn tampered_group_proof() {
+        let proof = make_group_proof(false);
+
+        let mut swapped = proof.clone();
+        swapped.member_ids[2] = 10;
+        assert_eq!(verify_group_proof(&swapped), VerifyResult::HashMismatch);
+
+        let mut count = proof.clone();
+        count.member_count = 259; // u8 に切り詰めると 3 になる値
+        assert_eq!(verify_group_proof(&count), VerifyResult::HashMismatch);
+
+        let mut dropped = proof.clone();
+        dropped.member_ids.pop();
+        dropped.member_count = 2;
+        assert_eq!(verify_group_proof(&dropped), VerifyResult::HashMismatch);
+
+        let mut dist = proof.clone();
+        dist.max_distance = 0.5;
+        assert_eq!(verify_group_proof(&dist), VerifyResult::HashMismatch);
+
+        // 遠いグループを「全員近接」と偽る
+        let mut forged = make_group_proof(true);
+        forged.all_proximate = true;
+        assert_eq!(verify_group_proof(&forged), VerifyResult::HashMismatch);
+    }
+
+    #[test]
+    fn group_proof_survives_serialization() {
+        use crate::serialize::{serialize_group_proof, try_deserialize_group_proof};
+        let proof = make_group_proof(false);
+        let restored =
+            try_deserialize_group_proof(&serialize_group_proof(&proof).unwrap()).unwrap();
+        assert_eq!(verify_group_proof(&restored), VerifyResult::Valid);
+    }
+
+    fn bound_record(session_a: u64, session_b: u64) -> CrossingRecord {
+        use crate::protocol::{try_execute_presence_protocol, PartyInfo, PresenceConfig};
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1).with_session(session_a);
+        let b = PartyInfo::new(VivaldiCoord::new(1.0, 0.0), 99, 2).with_session(session_b);
+        try_execute_presence_protocol(&a, &b, 1000, &PresenceConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn session_bound_record_verifies() {
+        let record = bound_record(7, 8);
+        assert_eq!(verify_record(&record), VerifyResult::Valid);
+        assert_eq!(verify_record_session(&record, 7), VerifyResult::Valid);
+        assert_eq!(verify_record_session(&record, 8), VerifyResult::Valid);
+        assert_eq!(
+            verify_record_session(&record, 9),
+            VerifyResult::SessionMismatch
+        );
+        assert_eq!(
+            verify_record_session(&make_valid_record(), 7),
+            VerifyResult::SessionMismatch
+        );
+    }
+
+    #[test]
+    fn proofs_replayed_into_other_session_rejected() {
+        let original = bound_record(7, 8);
+        // 別セッションへの束縛を付け替え、content hash も再計算する
+        let mut replayed = original;
+        replayed.session = None;
+        let replayed = replayed.with_session(SessionBinding::new(7, 9));
+        assert!(verify_record_hash(&replayed));
+        assert_eq!(verify_record(&replayed), VerifyResult::SessionMismatch);
+        assert_eq!(
+            verify_record_session(&replayed, 9),
+            VerifyResult::SessionMismatch
+        );
+        // 別セッションで実行すると ZKP チャレンジ自体が変わる
+        let other = bound_record(7, 9);
+        assert_ne!(other.proof_b.challenge, original.proof_b.challenge);
+        assert_ne!(other.proof_b.response, original.proof_b.response);
+    }
+
+    #[test]
+    fn stripped_session_rejected() {
+        // 束縛を取り除いて再ハッシュしても、チャレンジは束縛なしのものと一致しない
+        let mut stripped = bound_record(7, 8);
+        stripped.session = None;
+        stripped.content_hash = stripped.compute_hash();
+        assert!(verify_record_hash(&stripped));
+        assert_eq!(verify_record(&stripped), VerifyResult::SessionMismatch);
+
+        // 任意のチャレンジで作った束縛なしレコードも同様
+        let mut forged = make_valid_record();
+        let cb = IdentityCommitment::new(99, 2, 100);
+        forged.proof_b = ZkProof::prove(99, &cb, 0xBB);
+        forged.content_hash = forged.compute_hash();
+        assert_eq!(verify_record(&forged), VerifyResult::SessionMismatch);
+    }
+
+    #[test]
+    fn trailers_cannot_be_swapped() {
+        // 地理 proof と同じ 16 バイトのセッション束縛に付け替えても hash は通らない
+        let geo = ProximityProof::prove(
+            &VivaldiCoord::new(0.0, 0.0),
+            &VivaldiCoord::new(3.0, 4.0),
+            10.0,
+        );
+        let mut record = make_valid_record().with_geo_proximity(geo);
+        record.geo_proximity = None;
+        record.session = Some(SessionBinding::new(
+            geo.content_hash,
+            geo.distance.to_bits(),
+        ));
+        assert!(!verify_record_hash(&record));
+    }
 }
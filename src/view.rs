@@ -0,0 +1,551 @@
+//! ゼロコピービュー — シリアライズ済みバイト列を直接読むアクセサ。
+//!
+//! `CrossingRecordRef` / `PresenceEventRef` は `&[u8]` を借用したまま
+//! フィールドを読み出す。長さ・マジック・バージョン・TLV 構造は構築時に
+//! 一度だけ検証するため、以降のアクセサは失敗しない。大量のレコードを
+//! パーティ ID やタイムスタンプで絞り込むときに、必要なレコードだけを
+//! [`CrossingRecordRef::to_record`] で所有型に変換すればよい。
+
+use crate::error::DecodeError;
+use crate::event::{
+    CrossingRecord, DistanceUnit, DwellInterval, EventType, PresenceEvent, ProximityProof,
+    SessionBinding, FLAG_DWELL, FLAG_MUTUAL, FLAG_PROXIMATE, FLAG_VERIFIED,
+};
+use crate::identity::ZkProof;
+use crate::serialize::{
+    check_extensions, deserialize_proximity_inline, deserialize_zkproof, le_f64, le_u64, read_core,
+    read_dwell, read_session, read_unit, split_crossing, TlvIter, EXT_CONFIDENCE, EXT_DWELL,
+    EXT_GEO_CONFIDENCE, EXT_GEO_PROXIMITY, EXT_GEO_UNIT, EXT_SESSION, EXT_UNIT,
+    PROXIMITY_INLINE_SIZE, ZKPROOF_SIZE,
+};
+
+/// core 内の各フィールドのオフセット。
+const OFF_PROOF_A: usize = 18;
+const OFF_PROOF_B: usize = OFF_PROOF_A + ZKPROOF_SIZE;
+const OFF_PROXIMITY: usize = OFF_PROOF_B + ZKPROOF_SIZE;
+const OFF_CONTENT_HASH: usize = OFF_PROXIMITY + PROXIMITY_INLINE_SIZE;
+
+// ============================================================================
+// PresenceEventRef
+// ============================================================================
+
+/// 18 バイトの `PresenceEvent` を借用したまま読むビュー。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PresenceEventRef<'a> {
+    bytes: &'a [u8; 18],
+}
+
+impl<'a> PresenceEventRef<'a> {
+    /// 18 バイト配列から作成 (type / flags は検証しない)。
+    #[must_use]
+    pub const fn from_array(bytes: &'a [u8; 18]) -> Self {
+        Self { bytes }
+    }
+
+    /// スライスから作成する。ちょうど 18 バイトである必要がある。
+    ///
+    /// # Errors
+    ///
+    /// 18 バイト未満は `DecodeError::TooShort`、超過は `TrailingBytes`、
+    /// type / flags 不正は [`PresenceEvent::validate`] と同じ。
+    pub fn new(data: &'a [u8]) -> Result<Self, DecodeError> {
+        let (view, rest) = Self::from_prefix(data)?;
+        if !rest.is_empty() {
+            return Err(DecodeError::TrailingBytes { extra: rest.len() });
+        }
+        Ok(view)
+    }
+
+    /// `data` 先頭の 18 バイトをビューにし、残りを返す。
+    ///
+    /// # Errors
+    ///
+    /// 18 バイト未満は `DecodeError::TooShort`、type / flags 不正は
+    /// [`PresenceEvent::validate`] と同じ。
+    pub fn from_prefix(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        match data.split_first_chunk::<18>() {
+            Some((bytes, rest)) => {
+                PresenceEvent::try_from_bytes(bytes)?;
+                Ok((Self { bytes }, rest))
+            }
+            None => Err(DecodeError::TooShort {
+                expected: 18,
+                actual: data.len(),
+            }),
+        }
+    }
+
+    /// 元のバイト列。
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8; 18] {
+        self.bytes
+    }
+
+    #[must_use]
+    pub const fn event_type(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    /// 登録済みの event type。
+    ///
+    /// # Errors
+    ///
+    /// `from_array` で未検証のまま作った場合に `UnknownType` となり得る。
+    pub const fn kind(&self) -> Result<EventType, DecodeError> {
+        EventType::from_u8(self.event_type())
+    }
+
+    #[must_use]
+    pub const fn flags(&self) -> u8 {
+        self.bytes[1]
+    }
+
+    #[must_use]
+    pub const fn party_a_id(&self) -> u32 {
+        let b = self.bytes;
+        u32::from_le_bytes([b[2], b[3], b[4], b[5]])
+    }
+
+    #[must_use]
+    pub const fn party_b_id(&self) -> u32 {
+        let b = self.bytes;
+        u32::from_le_bytes([b[6], b[7], b[8], b[9]])
+    }
+
+    #[must_use]
+    pub const fn timestamp_ns(&self) -> u64 {
+        let b = self.bytes;
+        u64::from_le_bytes([b[10], b[11], b[12], b[13], b[14], b[15], b[16], b[17]])
+    }
+
+    /// `id` が A / B のどちらかに含まれるか。
+    #[must_use]
+    pub const fn involves(&self, id: u32) -> bool {
+        self.party_a_id() == id || self.party_b_id() == id
+    }
+
+    #[must_use]
+    pub const fn is_mutual(&self) -> bool {
+        self.flags() & FLAG_MUTUAL != 0
+    }
+
+    #[must_use]
+    pub const fn is_verified(&self) -> bool {
+        self.flags() & FLAG_VERIFIED != 0
+    }
+
+    #[must_use]
+    pub const fn is_proximate(&self) -> bool {
+        self.flags() & FLAG_PROXIMATE != 0
+    }
+
+    #[must_use]
+    pub const fn has_dwell(&self) -> bool {
+        self.flags() & FLAG_DWELL != 0
+    }
+
+    /// 所有型へ変換。
+    #[must_use]
+    pub const fn to_event(&self) -> PresenceEvent {
+        PresenceEvent::from_bytes(self.bytes)
+    }
+}
+
+// ============================================================================
+// CrossingRecordRef
+// ============================================================================
+
+/// シリアライズ済み `CrossingRecord` (v0 / v1) を借用したまま読むビュー。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrossingRecordRef<'a> {
+    bytes: &'a [u8],
+    event: PresenceEventRef<'a>,
+    core: &'a [u8],
+    ext: &'a [u8],
+    version: u8,
+}
+
+impl<'a> CrossingRecordRef<'a> {
+    /// レコード 1 件ちょうどのバイト列から作成する。
+    ///
+    /// # Errors
+    ///
+    /// [`crate::serialize::try_deserialize_crossing`] と同じ条件で失敗する。
+    pub fn new(data: &'a [u8]) -> Result<Self, DecodeError> {
+        let (view, rest) = Self::from_prefix(data)?;
+        if !rest.is_empty() {
+            return Err(DecodeError::TrailingBytes { extra: rest.len() });
+        }
+        Ok(view)
+    }
+
+    /// `data` 先頭のレコード 1 件をビューにし、残りを返す。
+    ///
+    /// v1 は拡張セクション長を持つため、連結されたアーカイブを先頭から
+    /// 順に切り出せる。
+    ///
+    /// # Errors
+    ///
+    /// 長さ不足・TLV の途中切れは `TooShort`、マジック不一致は `BadMagic`、
+    /// 未対応バージョンは `UnsupportedVersion`、既知拡張の長さ不正は `InvalidField`。
+    pub fn from_prefix(data: &'a [u8]) -> Result<(Self, &'a [u8]), DecodeError> {
+        let parts = split_crossing(data)?;
+        let (event, _) = PresenceEventRef::from_prefix(parts.core)?;
+        check_extensions(parts.ext)?;
+        let (bytes, rest) = data.split_at(parts.len);
+        Ok((
+            Self {
+                bytes,
+                event,
+                core: parts.core,
+                ext: parts.ext,
+                version: parts.version,
+            },
+            rest,
+        ))
+    }
+
+    /// このレコードのバイト列 (magic から拡張末尾まで)。
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// フォーマットバージョン (v0 は 0)。
+    #[must_use]
+    pub const fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// presence event のビュー。
+    #[must_use]
+    pub const fn event(&self) -> PresenceEventRef<'a> {
+        self.event
+    }
+
+    #[must_use]
+    pub const fn party_a_id(&self) -> u32 {
+        self.event.party_a_id()
+    }
+
+    #[must_use]
+    pub const fn party_b_id(&self) -> u32 {
+        self.event.party_b_id()
+    }
+
+    #[must_use]
+    pub const fn timestamp_ns(&self) -> u64 {
+        self.event.timestamp_ns()
+    }
+
+    #[must_use]
+    pub fn proof_a(&self) -> ZkProof {
+        deserialize_zkproof(&self.core[OFF_PROOF_A..OFF_PROOF_B])
+    }
+
+    #[must_use]
+    pub fn proof_b(&self) -> ZkProof {
+        deserialize_zkproof(&self.core[OFF_PROOF_B..OFF_PROXIMITY])
+    }
+
+    /// 主近接 proof (Geographic モードでは地理 proof)。
+    #[must_use]
+    pub fn proximity(&self) -> ProximityProof {
+        let mut proof = deserialize_proximity_inline(&self.core[OFF_PROXIMITY..OFF_CONTENT_HASH]);
+        proof.confidence = self.extension(EXT_CONFIDENCE).map(|v| le_f64(v, 0));
+        proof.unit = self.distance_unit();
+        proof
+    }
+
+    /// 主近接 proof の距離 (proof 全体を復元せずに読む)。単位は
+    /// [`distance_unit`](Self::distance_unit)。
+    #[must_use]
+    pub fn distance(&self) -> f64 {
+        le_f64(self.core, OFF_PROXIMITY)
+    }
+
+    /// 主近接 proof の距離の単位。
+    #[must_use]
+    pub fn distance_unit(&self) -> DistanceUnit {
+        self.extension(EXT_UNIT)
+            .map_or(DistanceUnit::Network, read_unit)
+    }
+
+    /// 主近接 proof の `is_proximate`。
+    #[must_use]
+    pub fn is_proximate(&self) -> bool {
+        self.core[OFF_PROXIMITY + 16] != 0
+    }
+
+    #[must_use]
+    pub fn content_hash(&self) -> u64 {
+        le_u64(self.core, OFF_CONTENT_HASH)
+    }
+
+    /// 地理 proof 拡張 (hybrid モードのみ)。
+    #[must_use]
+    pub fn geo_proximity(&self) -> Option<ProximityProof> {
+        let mut proof = self
+            .extension(EXT_GEO_PROXIMITY)
+            .map(deserialize_proximity_inline)?;
+        proof.confidence = self.extension(EXT_GEO_CONFIDENCE).map(|v| le_f64(v, 0));
+        proof.unit = self
+            .extension(EXT_GEO_UNIT)
+            .map_or(DistanceUnit::Network, read_unit);
+        Some(proof)
+    }
+
+    /// dwell 区間拡張。
+    #[must_use]
+    pub fn dwell(&self) -> Option<DwellInterval> {
+        self.extension(EXT_DWELL).map(read_dwell)
+    }
+
+    /// セッション束縛拡張。
+    #[must_use]
+    pub fn session(&self) -> Option<SessionBinding> {
+        self.extension(EXT_SESSION).map(read_session)
+    }
+
+    /// 指定 type の TLV 拡張の値。既知の type の重複は構築時に拒否する
+    /// ため一意で、未知の type が複数あれば最後のもの。
+    #[must_use]
+    pub fn extension(&self, ty: u8) -> Option<&'a [u8]> {
+        self.extensions()
+            .filter(|&(t, _)| t == ty)
+            .last()
+            .map(|(_, v)| v)
+    }
+
+    /// すべての TLV 拡張 `(type, value)` (未知の type を含む)。
+    pub fn extensions(&self) -> impl Iterator<Item = (u8, &'a [u8])> {
+        // 構築時に検証済みのため Err は発生しない
+        TlvIter::new(self.ext).map_while(Result::ok)
+    }
+
+    /// 所有型へ変換。
+    #[must_use]
+    pub fn to_record(&self) -> CrossingRecord {
+        let mut record = read_core(self.core);
+        record.proximity = self.proximity();
+        record.geo_proximity = self.geo_proximity();
+        record.dwell = self.dwell();
+        record.session = self.session();
+        record
+    }
+}
+
+/// 連結された `CrossingRecord` アーカイブを先頭から走査するイテレータ。
+///
+/// 不正なレコードに出会うと `Err` を 1 回返して終了する。
+#[derive(Debug, Clone)]
+pub struct CrossingRecordIter<'a> {
+    rest: &'a [u8],
+}
+
+/// 連結された `CrossingRecord` のバイト列をゼロコピーで走査する。
+#[must_use]
+pub const fn iter_crossings(data: &[u8]) -> CrossingRecordIter<'_> {
+    CrossingRecordIter { rest: data }
+}
+
+impl<'a> Iterator for CrossingRecordIter<'a> {
+    type Item = Result<CrossingRecordRef<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        match CrossingRecordRef::from_prefix(self.rest) {
+            Ok((view, rest)) => {
+                self.rest = rest;
+                Some(Ok(view))
+            }
+            Err(e) => {
+                self.rest = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geo::GeoCoord;
+    use crate::protocol::{
+        try_execute_presence_protocol, PartyInfo, PresenceConfig, ProximityMode,
+    };
+    use crate::serialize::{serialize_crossing, try_deserialize_crossing, CROSSING_RECORD_SIZE};
+    use crate::vivaldi::VivaldiCoord;
+
+    fn make_record(a_id: u32, ts: u64) -> CrossingRecord {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, a_id);
+        let b = PartyInfo::new(VivaldiCoord::new(3.0, 4.0), 99, 2);
+        try_execute_presence_protocol(&a, &b, ts, &PresenceConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn event_ref_matches_owned() {
+        let mut ev = PresenceEvent::new(7, 8, 123_456);
+        ev.set_verified();
+        let bytes = ev.to_bytes();
+        let view = PresenceEventRef::new(&bytes).unwrap();
+        assert_eq!(view.party_a_id(), 7);
+        assert_eq!(view.party_b_id(), 8);
+        assert_eq!(view.timestamp_ns(), 123_456);
+        assert_eq!(view.event_type(), ev.event_type);
+        assert!(view.is_verified() && !view.is_mutual());
+        assert!(view.involves(8) && !view.involves(9));
+        assert_eq!(view.to_event().to_bytes(), bytes);
+
+        assert!(PresenceEventRef::new(&bytes[..17]).is_err());
+        let mut long = bytes.to_vec();
+        long.push(0);
+        assert_eq!(
+            PresenceEventRef::new(&long).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+    }
+
+    #[test]
+    fn crossing_ref_matches_owned() {
+        let record = make_record(1, 500).with_dwell(DwellInterval {
+            start_ns: 500,
+            end_ns: 900,
+        });
+        let bytes = serialize_crossing(&record);
+        let view = CrossingRecordRef::new(&bytes).unwrap();
+        assert_eq!(view.version(), 1);
+        assert_eq!(view.party_a_id(), 1);
+        assert_eq!(view.timestamp_ns(), 500);
+        assert_eq!(view.content_hash(), record.content_hash);
+        assert!((view.distance() - 5.0).abs() < 1e-12);
+        assert!(view.is_proximate());
+        assert_eq!(view.proof_a().response, record.proof_a.response);
+        assert_eq!(view.proof_b().commitment, record.proof_b.commitment);
+        assert_eq!(view.dwell(), record.dwell);
+        assert!(view.session().is_none());
+        assert!(view.geo_proximity().is_none());
+
+        let owned = view.to_record();
+        let reference = try_deserialize_crossing(&bytes).unwrap();
+        assert_eq!(owned.content_hash, reference.content_hash);
+        assert_eq!(owned.dwell, reference.dwell);
+        assert_eq!(
+            crate::verification::verify_record(&owned),
+            crate::verification::VerifyResult::Valid
+        );
+    }
+
+    #[test]
+    fn crossing_ref_reads_units() {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1)
+            .with_geo(GeoCoord::new(35.6812, 139.7671));
+        let b = PartyInfo::new(VivaldiCoord::new(1.0, 0.0), 99, 2)
+            .with_geo(GeoCoord::new(35.6813, 139.7671));
+        for (mode, unit) in [
+            (ProximityMode::Geographic, DistanceUnit::Metres),
+            (ProximityMode::Hybrid, DistanceUnit::Network),
+        ] {
+            let config = PresenceConfig {
+                mode,
+                ..PresenceConfig::default()
+            };
+            let record = try_execute_presence_protocol(&a, &b, 500, &config).unwrap();
+            let bytes = serialize_crossing(&record);
+            let view = CrossingRecordRef::new(&bytes).unwrap();
+            assert_eq!(view.distance_unit(), unit);
+            assert_eq!(view.proximity().content_hash, record.proximity.content_hash);
+            assert_eq!(
+                view.geo_proximity().map(|g| g.unit),
+                record.geo_proximity.map(|g| g.unit)
+            );
+            assert_eq!(view.to_record().content_hash, record.content_hash);
+        }
+    }
+
+    #[test]
+    fn crossing_ref_reads_session() {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1).with_session(7);
+        let b = PartyInfo::new(VivaldiCoord::new(1.0, 0.0), 99, 2).with_session(8);
+        let record =
+            try_execute_presence_protocol(&a, &b, 500, &PresenceConfig::default()).unwrap();
+        let bytes = serialize_crossing(&record);
+        let view = CrossingRecordRef::new(&bytes).unwrap();
+        assert_eq!(view.session(), Some(SessionBinding::new(7, 8)));
+        let owned = view.to_record();
+        assert_eq!(owned.session, record.session);
+        assert_eq!(
+            crate::verification::verify_record_session(&owned, 8),
+            crate::verification::VerifyResult::Valid
+        );
+    }
+
+    #[test]
+    fn crossing_ref_reads_v0() {
+        let bytes = serialize_crossing(&make_record(3, 10));
+        let mut v0 = b"ACRS".to_vec();
+        v0.extend_from_slice(&bytes[5..5 + CROSSING_RECORD_SIZE - 4]);
+        let view = CrossingRecordRef::new(&v0).unwrap();
+        assert_eq!(view.version(), 0);
+        assert_eq!(view.party_a_id(), 3);
+        assert_eq!(view.extensions().count(), 0);
+
+        // v0 も後続バイトを拒否する (try_deserialize_crossing と同じ)
+        v0.push(0);
+        assert_eq!(
+            CrossingRecordRef::new(&v0).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+        assert_eq!(
+            crate::serialize::try_deserialize_crossing(&v0).unwrap_err(),
+            DecodeError::TrailingBytes { extra: 1 }
+        );
+    }
+
+    #[test]
+    fn crossing_ref_validates_once() {
+        let bytes = serialize_crossing(&make_record(1, 1));
+        for len in 0..bytes.len() {
+            assert!(CrossingRecordRef::new(&bytes[..len]).is_err(), "{len}");
+        }
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert_eq!(
+            CrossingRecordRef::new(&bad).unwrap_err(),
+            DecodeError::BadMagic
+        );
+    }
+
+    #[test]
+    fn scan_archive_without_copying() {
+        let mut archive = Vec::new();
+        for i in 0..10u32 {
+            let mut r = make_record(i % 3, u64::from(i) * 100);
+            if i % 2 == 0 {
+                r = r.with_dwell(DwellInterval {
+                    start_ns: 0,
+                    end_ns: 1,
+                });
+            }
+            archive.extend(serialize_crossing(&r));
+        }
+        let hits: Vec<u64> = iter_crossings(&archive)
+            .map(Result::unwrap)
+            .filter(|r| r.event().involves(1) && r.timestamp_ns() >= 300)
+            .map(|r| r.timestamp_ns())
+            .collect();
+        assert_eq!(hits, vec![400, 700]);
+
+        // 途中で切れたアーカイブはエラーを 1 回返して終わる
+        let cut = &archive[..archive.len() - 3];
+        let results: Vec<_> = iter_crossings(cut).collect();
+        assert_eq!(results.len(), 10);
+        assert!(results[9].is_err());
+    }
+}
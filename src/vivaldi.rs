@@ -5,13 +5,15 @@
 //!
 //! Author: Moroya Sakamoto
 
-use crate::fnv1a;
+use crate::space::{read_finite_f64, CoordinateSpace};
+use crate::{fnv1a, normal_cdf};
 
 /// Vivaldi network coordinate (2D + height for error estimation).
 ///
 /// Distance model: sqrt((x1-x2)^2 + (y1-y2)^2) + h1 + h2
 /// The height term is always >= 0 and represents estimation error.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VivaldiCoord {
     pub x: f64,
     pub y: f64,
@@ -86,6 +88,128 @@ impl VivaldiCoord {
     }
 }
 
+// ── Node with error estimate ───────────────────────────────────────────
+
+/// Upper bound on the relative error estimate.
+pub const MAX_ERROR: f64 = 1.5;
+
+/// A Vivaldi coordinate together with its local relative error estimate.
+///
+/// `error` approximates `|predicted - rtt| / rtt` averaged over recent
+/// samples (1.0 = no confidence, 0.0 = exact), as in Dabek et al.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VivaldiNode {
+    pub coord: VivaldiCoord,
+    /// Relative error estimate, `[0, MAX_ERROR]`.
+    pub error: f64,
+}
+
+impl VivaldiNode {
+    /// Initial error for a node that has not observed any RTT.
+    pub const INITIAL_ERROR: f64 = 1.0;
+
+    /// Create a fresh node (error = `INITIAL_ERROR`).
+    #[must_use]
+    pub const fn new(coord: VivaldiCoord) -> Self {
+        Self {
+            coord,
+            error: Self::INITIAL_ERROR,
+        }
+    }
+
+    /// Create a node with an explicit error, clamped to `[0, MAX_ERROR]`.
+    #[must_use]
+    pub fn with_error(coord: VivaldiCoord, error: f64) -> Self {
+        Self {
+            coord,
+            error: error.clamp(0.0, MAX_ERROR),
+        }
+    }
+
+    /// Adaptive Vivaldi step: the timestep and error update are weighted
+    /// by `w = e_local / (e_local + e_remote)`, so confident remotes pull
+    /// harder. `cc` and `ce` are the usual tuning constants (~0.25).
+    pub fn observe(&mut self, remote: &Self, rtt: f64, cc: f64, ce: f64) {
+        if !rtt.is_finite() || rtt <= 0.0 {
+            return;
+        }
+        let sum = self.error + remote.error;
+        let w = if sum > 0.0 { self.error / sum } else { 0.5 };
+        let sample_error = (self.coord.distance(&remote.coord) - rtt).abs() / rtt;
+        self.error = (sample_error * ce)
+            .mul_add(w, self.error * ce.mul_add(-w, 1.0))
+            .clamp(0.0, MAX_ERROR);
+        self.coord.update(&remote.coord, rtt, cc * w);
+    }
+
+    /// 1σ of the true distance around the predicted one: the predicted
+    /// distance (height terms included) scaled by the combined relative
+    /// error of both nodes.
+    #[must_use]
+    pub fn distance_stddev(&self, other: &Self) -> f64 {
+        self.coord.distance(&other.coord) * self.error.hypot(other.error)
+    }
+
+    /// P(true distance <= `threshold`).
+    ///
+    /// The true distance is modelled as a normal around the predicted
+    /// distance with [`distance_stddev`](Self::distance_stddev),
+    /// truncated at zero.
+    #[must_use]
+    pub fn proximity_probability(&self, other: &Self, threshold: f64) -> f64 {
+        let mean = self.coord.distance(&other.coord);
+        let sigma = self.distance_stddev(other);
+        if threshold < 0.0 {
+            return 0.0;
+        }
+        if sigma < 1e-12 {
+            return if mean <= threshold { 1.0 } else { 0.0 };
+        }
+        let below_zero = normal_cdf(-mean / sigma);
+        let below_t = normal_cdf((threshold - mean) / sigma);
+        ((below_t - below_zero) / (1.0 - below_zero)).clamp(0.0, 1.0)
+    }
+}
+
+impl CoordinateSpace for VivaldiCoord {
+    const AXES: usize = 2;
+    const ENCODED_LEN: usize = 24;
+
+    fn distance(&self, other: &Self) -> f64 {
+        Self::distance(self, other)
+    }
+
+    fn update(&mut self, other: &Self, rtt: f64, cc: f64) {
+        Self::update(self, other, rtt, cc);
+    }
+
+    fn commit(&self) -> u64 {
+        self.hash()
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.x.to_le_bytes());
+        out.extend_from_slice(&self.y.to_le_bytes());
+        out.extend_from_slice(&self.height.to_le_bytes());
+    }
+
+    fn decode(data: &[u8]) -> Option<Self> {
+        let x = read_finite_f64(data, 0)?;
+        let y = read_finite_f64(data, 8)?;
+        let height = read_finite_f64(data, 16)?;
+        Some(Self::with_height(x, y, height))
+    }
+
+    fn axis(&self, axis: usize) -> f64 {
+        if axis == 0 {
+            self.x
+        } else {
+            self.y
+        }
+    }
+}
+
 // ── Tests ──────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -221,4 +345,70 @@ mod tests {
         assert!(a.x.is_finite());
         assert!(a.y.is_finite());
     }
+
+    #[test]
+    fn node_exact_error_is_hard_decision() {
+        let a = VivaldiNode::with_error(VivaldiCoord::new(0.0, 0.0), 0.0);
+        let b = VivaldiNode::with_error(VivaldiCoord::new(5.0, 0.0), 0.0);
+        assert_eq!(a.proximity_probability(&b, 5.0), 1.0);
+        assert_eq!(a.proximity_probability(&b, 4.9), 0.0);
+    }
+
+    #[test]
+    fn node_probability_at_threshold_near_half() {
+        // Truncation at zero is negligible when mean >> sigma
+        let a = VivaldiNode::with_error(VivaldiCoord::new(0.0, 0.0), 0.1);
+        let b = VivaldiNode::with_error(VivaldiCoord::new(10.0, 0.0), 0.1);
+        assert!((a.proximity_probability(&b, 10.0) - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn node_probability_monotonic_in_threshold() {
+        let a = VivaldiNode::with_error(VivaldiCoord::new(0.0, 0.0), 0.3);
+        let b = VivaldiNode::with_error(VivaldiCoord::new(8.0, 0.0), 0.4);
+        let mut prev = 0.0;
+        for t in [0.0, 2.0, 5.0, 8.0, 12.0, 20.0, 100.0] {
+            let p = a.proximity_probability(&b, t);
+            assert!(p >= prev);
+            prev = p;
+        }
+        assert!(prev > 0.999);
+    }
+
+    #[test]
+    fn node_higher_error_less_certain() {
+        let b_coord = VivaldiCoord::new(5.0, 0.0);
+        let a = VivaldiNode::with_error(VivaldiCoord::new(0.0, 0.0), 0.05);
+        let noisy = VivaldiNode::with_error(VivaldiCoord::new(0.0, 0.0), 0.8);
+        let b = VivaldiNode::with_error(b_coord, 0.05);
+        assert!(a.proximity_probability(&b, 8.0) > noisy.proximity_probability(&b, 8.0));
+    }
+
+    #[test]
+    fn node_error_clamped() {
+        let n = VivaldiNode::with_error(VivaldiCoord::new(0.0, 0.0), 9.0);
+        assert_eq!(n.error, MAX_ERROR);
+        assert_eq!(VivaldiNode::new(n.coord).error, VivaldiNode::INITIAL_ERROR);
+    }
+
+    #[test]
+    fn node_observe_reduces_error() {
+        let mut a = VivaldiNode::new(VivaldiCoord::new(0.0, 0.0));
+        let b = VivaldiNode::with_error(VivaldiCoord::new(10.0, 0.0), 0.05);
+        for _ in 0..200 {
+            a.observe(&b, 10.0, 0.25, 0.25);
+        }
+        assert!(a.error < 0.1, "error {}", a.error);
+        assert!((a.coord.distance(&b.coord) - 10.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn node_observe_ignores_bad_rtt() {
+        let mut a = VivaldiNode::new(VivaldiCoord::new(1.0, 1.0));
+        let b = VivaldiNode::new(VivaldiCoord::new(2.0, 2.0));
+        a.observe(&b, 0.0, 0.25, 0.25);
+        a.observe(&b, f64::NAN, 0.25, 0.25);
+        assert_eq!(a.coord.x, 1.0);
+        assert_eq!(a.error, VivaldiNode::INITIAL_ERROR);
+    }
 }
@@ -0,0 +1,442 @@
+//! WebAssembly バインディング — ブラウザ内検証 (`wasm` feature)。
+//!
+//! サーバなしでウェブページ上からレコードを検証するための `wasm-bindgen`
+//! エクスポート。`wasm32-unknown-unknown` 向けにビルドする:
+//!
+//! ```text
+//! cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/alice_presence.wasm
+//! cargo test --target wasm32-unknown-unknown --features wasm   # Node (headless)
+//! ```
+//!
+//! | JavaScript | Rust |
+//! |------------|------|
+//! | `CrossingRecord.fromBytes` / `verify` | [`try_deserialize_crossing`] / [`verify_record`] |
+//! | `ProximityProof.fromBytes` / `verify` | [`try_deserialize_proximity`] / [`verify_proximity`] |
+//! | `computeGroupProof` / `GroupProof.fromBytes` / `verify` | [`PresenceGroup::prove_proximity`] / [`verify_group_proof`] |
+//!
+//! `u64` (タイムスタンプ・ハッシュ) は JS の `BigInt` になる。デコード失敗などは
+//! `Error` として送出する。
+
+use wasm_bindgen::prelude::*;
+
+use crate::event::{CrossingRecord, ProximityProof};
+use crate::group::{GroupConfig, GroupProximityProof, PresenceGroup};
+use crate::serialize::{
+    serialize_crossing, serialize_group_proof, serialize_proximity, try_deserialize_crossing,
+    try_deserialize_group_proof, try_deserialize_proximity,
+};
+use crate::verification::{verify_group_proof, verify_proximity, verify_record, VerifyResult};
+use crate::vivaldi::VivaldiCoord;
+
+fn js_error(e: impl core::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// [`VerifyResult`] の JS 表現。
+#[wasm_bindgen(js_name = VerifyStatus)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WasmVerifyResult {
+    /// content hash が一致。
+    Valid = 0,
+    /// content hash 不一致 (改ざん疑い)。
+    HashMismatch = 1,
+    /// ZKP 未検証。
+    ZkpNotVerified = 2,
+    /// 近接未確認。
+    NotProximate = 3,
+    /// セッション束縛の不一致。
+    SessionMismatch = 4,
+}
+
+impl From<VerifyResult> for WasmVerifyResult {
+    fn from(r: VerifyResult) -> Self {
+        match r {
+            VerifyResult::Valid => Self::Valid,
+            VerifyResult::HashMismatch => Self::HashMismatch,
+            VerifyResult::ZkpNotVerified => Self::ZkpNotVerified,
+            VerifyResult::NotProximate => Self::NotProximate,
+            VerifyResult::SessionMismatch => Self::SessionMismatch,
+        }
+    }
+}
+
+// ============================================================================
+// CrossingRecord
+// ============================================================================
+
+/// デコード済み `CrossingRecord`。
+#[wasm_bindgen(js_name = CrossingRecord)]
+#[derive(Debug, Clone)]
+pub struct WasmCrossingRecord(CrossingRecord);
+
+#[wasm_bindgen(js_class = CrossingRecord)]
+impl WasmCrossingRecord {
+    /// シリアライズ済みバイト列からデコード。
+    ///
+    /// # Errors
+    ///
+    /// バイト列が不正なら `Error`。
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, JsError> {
+        try_deserialize_crossing(bytes).map(Self).map_err(js_error)
+    }
+
+    /// バイト列にシリアライズ。
+    #[must_use]
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize_crossing(&self.0)
+    }
+
+    /// content hash と ZKP / 近接フラグを検証。
+    #[must_use]
+    pub fn verify(&self) -> WasmVerifyResult {
+        verify_record(&self.0).into()
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = partyA)]
+    pub fn party_a(&self) -> u32 {
+        self.0.event.party_a_id
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = partyB)]
+    pub fn party_b(&self) -> u32 {
+        self.0.event.party_b_id
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = timestampNs)]
+    pub fn timestamp_ns(&self) -> u64 {
+        self.0.event.timestamp_ns
+    }
+
+    /// 18 バイトのプレゼンスイベント。
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn event(&self) -> Vec<u8> {
+        self.0.event.to_bytes().to_vec()
+    }
+
+    /// 近接証明。
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn proximity(&self) -> WasmProximityProof {
+        WasmProximityProof(self.0.proximity)
+    }
+
+    /// 地理的近接証明 (なければ `undefined`)。
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = geoProximity)]
+    pub fn geo_proximity(&self) -> Option<WasmProximityProof> {
+        self.0.geo_proximity.map(WasmProximityProof)
+    }
+
+    /// 滞在区間の開始 (ns, なければ `undefined`)。
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = dwellStartNs)]
+    pub fn dwell_start_ns(&self) -> Option<u64> {
+        self.0.dwell.map(|d| d.start_ns)
+    }
+
+    /// 滞在区間の終了 (ns, なければ `undefined`)。
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = dwellEndNs)]
+    pub fn dwell_end_ns(&self) -> Option<u64> {
+        self.0.dwell.map(|d| d.end_ns)
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = contentHash)]
+    pub fn content_hash(&self) -> u64 {
+        self.0.content_hash
+    }
+}
+
+/// バイト列をデコードして検証 (`CrossingRecord.fromBytes(b).verify()` の短縮形)。
+///
+/// # Errors
+///
+/// バイト列が不正なら `Error`。
+#[wasm_bindgen(js_name = verifyCrossingRecord)]
+pub fn verify_crossing_record(bytes: &[u8]) -> Result<WasmVerifyResult, JsError> {
+    WasmCrossingRecord::from_bytes(bytes).map(|r| r.verify())
+}
+
+// ============================================================================
+// ProximityProof
+// ============================================================================
+
+/// デコード済み `ProximityProof`。
+#[wasm_bindgen(js_name = ProximityProof)]
+#[derive(Debug, Clone, Copy)]
+pub struct WasmProximityProof(ProximityProof);
+
+#[wasm_bindgen(js_class = ProximityProof)]
+impl WasmProximityProof {
+    /// シリアライズ済みバイト列 (`APRX`) からデコード。
+    ///
+    /// # Errors
+    ///
+    /// バイト列が不正なら `Error`。
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, JsError> {
+        try_deserialize_proximity(bytes).map(Self).map_err(js_error)
+    }
+
+    /// バイト列にシリアライズ。
+    #[must_use]
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize_proximity(&self.0)
+    }
+
+    /// content hash を再計算して検証。
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        verify_proximity(&self.0)
+    }
+
+    /// 距離。単位は [`distance_unit`](Self::distance_unit)。
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn distance(&self) -> f64 {
+        self.0.distance
+    }
+
+    /// `distance` / `threshold` の単位 (`"network"` または `"metres"`)。
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = distanceUnit)]
+    pub fn distance_unit(&self) -> String {
+        self.0.unit.as_str().to_owned()
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn threshold(&self) -> f64 {
+        self.0.threshold
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = isProximate)]
+    pub fn is_proximate(&self) -> bool {
+        self.0.is_proximate
+    }
+
+    /// P(真の距離 <= threshold)。確率的判定でなければ `undefined`。
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn confidence(&self) -> Option<f64> {
+        self.0.confidence
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = contentHash)]
+    pub fn content_hash(&self) -> u64 {
+        self.0.content_hash
+    }
+}
+
+// ============================================================================
+// Group proofs
+// ============================================================================
+
+/// `GroupProximityProof`。
+#[wasm_bindgen(js_name = GroupProof)]
+#[derive(Debug, Clone)]
+pub struct WasmGroupProof(GroupProximityProof);
+
+#[wasm_bindgen(js_class = GroupProof)]
+impl WasmGroupProof {
+    /// シリアライズ済みバイト列 (`AGRP`) からデコード。
+    ///
+    /// # Errors
+    ///
+    /// バイト列が不正なら `Error`。
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, JsError> {
+        try_deserialize_group_proof(bytes)
+            .map(Self)
+            .map_err(js_error)
+    }
+
+    /// バイト列にシリアライズ。
+    ///
+    /// # Errors
+    ///
+    /// メンバー数が上限を超えていれば `Error`。
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        serialize_group_proof(&self.0).map_err(js_error)
+    }
+
+    /// `group_id` と content hash を再計算し、近接を検証。
+    #[must_use]
+    pub fn verify(&self) -> WasmVerifyResult {
+        verify_group_proof(&self.0).into()
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = groupId)]
+    pub fn group_id(&self) -> u64 {
+        self.0.group_id
+    }
+
+    /// 昇順のメンバー ID。
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = memberIds)]
+    pub fn member_ids(&self) -> Vec<u32> {
+        self.0.member_ids.clone()
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = maxDistance)]
+    pub fn max_distance(&self) -> f64 {
+        self.0.max_distance
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter)]
+    pub fn threshold(&self) -> f64 {
+        self.0.threshold
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = allProximate)]
+    pub fn all_proximate(&self) -> bool {
+        self.0.all_proximate
+    }
+
+    #[must_use]
+    #[wasm_bindgen(getter, js_name = contentHash)]
+    pub fn content_hash(&self) -> u64 {
+        self.0.content_hash
+    }
+}
+
+/// `ids[i]` と `coords[3i..3i + 3]` (x, y, height) からグループ証明を計算。
+fn group_proof(ids: &[u32], coords: &[f64], threshold: f64) -> Result<GroupProximityProof, String> {
+    if coords.len() != ids.len() * 3 {
+        return Err(format!(
+            "expected {} coordinates (x, y, height per member), got {}",
+            ids.len() * 3,
+            coords.len()
+        ));
+    }
+    let config = GroupConfig {
+        proximity_threshold: threshold,
+        ..GroupConfig::default()
+    };
+    let mut group = PresenceGroup::new(config);
+    for (&id, c) in ids.iter().zip(coords.chunks_exact(3)) {
+        group
+            .try_add_member(id, VivaldiCoord::with_height(c[0], c[1], c[2]), 0)
+            .map_err(|e| e.to_string())?;
+    }
+    group
+        .prove_proximity()
+        .ok_or_else(|| format!("need at least {} members", config.min_members))
+}
+
+/// メンバーの Vivaldi 座標からグループ近接証明を計算。
+///
+/// `coords` はメンバーごとに `x, y, height` を並べた `Float64Array`。
+///
+/// # Errors
+///
+/// 座標数の不一致、ID の重複、メンバー数の過不足なら `Error`。
+#[wasm_bindgen(js_name = computeGroupProof)]
+pub fn compute_group_proof(
+    ids: &[u32],
+    coords: &[f64],
+    threshold: f64,
+) -> Result<WasmGroupProof, JsError> {
+    group_proof(ids, coords, threshold)
+        .map(WasmGroupProof)
+        .map_err(|e| JsError::new(&e))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+// JsError の生成は wasm32 以外では panic するため、ネイティブでは成功パスと
+// 内部ヘルパーのみ検査する。wasm32 上のテストは tests/wasm.rs。
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{try_execute_presence_protocol, PartyInfo, PresenceConfig};
+
+    fn record_bytes() -> Vec<u8> {
+        let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+        let b = PartyInfo::new(VivaldiCoord::new(3.0, 4.0), 99, 2);
+        let rec = try_execute_presence_protocol(&a, &b, 1000, &PresenceConfig::default()).unwrap();
+        serialize_crossing(&rec)
+    }
+
+    #[test]
+    fn crossing_record_roundtrip() {
+        let bytes = record_bytes();
+        let rec = WasmCrossingRecord::from_bytes(&bytes).unwrap();
+        assert_eq!(rec.verify(), WasmVerifyResult::Valid);
+        assert_eq!((rec.party_a(), rec.party_b()), (1, 2));
+        assert_eq!(rec.timestamp_ns(), 1000);
+        assert_eq!(rec.event().len(), 18);
+        assert!(rec.geo_proximity().is_none() && rec.dwell_start_ns().is_none());
+        assert_eq!(rec.to_bytes(), bytes);
+        assert_eq!(
+            verify_crossing_record(&bytes).unwrap(),
+            WasmVerifyResult::Valid
+        );
+
+        let prox = rec.proximity();
+        assert!(prox.verify() && prox.is_proximate());
+        let back = WasmProximityProof::from_bytes(&prox.to_bytes()).unwrap();
+        assert_eq!(back.content_hash(), prox.content_hash());
+        assert!((back.distance() - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tampered_record_is_rejected() {
+        let mut bytes = record_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        // デコード失敗 (JsError) はネイティブでは生成できないので、成功時のみ見る
+        if let Ok(rec) = try_deserialize_crossing(&bytes) {
+            assert_eq!(
+                WasmCrossingRecord(rec).verify(),
+                WasmVerifyResult::HashMismatch
+            );
+        }
+    }
+
+    #[test]
+    fn group_proof_compute_and_decode() {
+        let coords = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 0.0];
+        let proof = compute_group_proof(&[30, 10, 20], &coords, 10.0).unwrap();
+        assert_eq!(proof.member_ids(), vec![10, 20, 30]);
+        assert!(proof.all_proximate());
+        assert_eq!(proof.verify(), WasmVerifyResult::Valid);
+
+        let back = WasmGroupProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(back.group_id(), proof.group_id());
+        assert_eq!(back.content_hash(), proof.content_hash());
+
+        let far = compute_group_proof(&[1, 2], &[0.0, 0.0, 0.0, 50.0, 0.0, 0.0], 10.0).unwrap();
+        assert!(!far.all_proximate());
+        assert_eq!(far.verify(), WasmVerifyResult::NotProximate);
+    }
+
+    #[test]
+    fn group_proof_input_errors() {
+        assert!(group_proof(&[1, 2], &[0.0; 5], 10.0)
+            .unwrap_err()
+            .contains("expected 6"));
+        assert!(group_proof(&[1, 1], &[0.0; 6], 10.0).is_err());
+        assert!(group_proof(&[1], &[0.0; 3], 10.0)
+            .unwrap_err()
+            .contains("at least 2"));
+    }
+}
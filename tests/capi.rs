@@ -0,0 +1,79 @@
+//! C ABI の結合テスト — ヘッダの生成結果と C テストプログラム
+//!
+//! 実行: `cargo test --features capi --test capi`
+
+#![cfg(feature = "capi")]
+
+#[cfg(unix)]
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+fn manifest_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn generate_header() -> String {
+    let dir = manifest_dir();
+    let config = cbindgen::Config::from_file(dir.join("cbindgen.toml")).unwrap();
+    let mut out = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(dir.join("src/capi.rs"))
+        .generate()
+        .unwrap()
+        .write(&mut out);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let path = manifest_dir().join("include/alice_presence.h");
+    let generated = generate_header();
+    if std::env::var_os("ALICE_UPDATE_HEADER").is_some() {
+        std::fs::write(&path, &generated).unwrap();
+    }
+    let committed = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "include/alice_presence.h is stale; regenerate with \
+         ALICE_UPDATE_HEADER=1 cargo test --features capi --test capi"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn c_test_program() {
+    let dir = manifest_dir();
+    let lib = common::build_lib("capi", "staticlib", "libalice_presence.a");
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi_test");
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    let mut cmd = Command::new(&cc);
+    cmd.arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(dir.join("include"))
+        .arg(dir.join("tests/capi/capi_test.c"))
+        .arg(&lib)
+        .arg("-o")
+        .arg(&out)
+        .args(["-lpthread", "-lm"]);
+    if cfg!(target_os = "linux") {
+        cmd.arg("-ldl");
+    }
+    let Ok(status) = cmd.status() else {
+        eprintln!("skipping C test program: `{cc}` not found");
+        return;
+    };
+    assert!(status.success(), "compiling tests/capi/capi_test.c failed");
+
+    let output = Command::new(&out).output().unwrap();
+    print!("{}", String::from_utf8_lossy(&output.stdout));
+    assert!(
+        output.status.success(),
+        "capi_test failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
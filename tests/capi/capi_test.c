@@ -0,0 +1,109 @@
+/* ALICE-Presence C API test program, built and run by tests/capi.rs. */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "alice_presence.h"
+
+static int failures = 0;
+
+#define CHECK(cond)                                                          \
+    do {                                                                     \
+        if (!(cond)) {                                                       \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #cond);                                                  \
+            failures++;                                                      \
+        }                                                                    \
+    } while (0)
+
+static void test_roundtrip(void) {
+    ApParty *alice = ap_party_new(0.0, 0.0, 0.0, 0xA11CE, 1);
+    ApParty *bob = ap_party_new(3.0, 4.0, 0.0, 0xB0B, 2);
+    ApRecord *rec = NULL;
+
+    CHECK(ap_execute_protocol(alice, bob, 1000, NULL, &rec) == AP_STATUS_OK);
+    CHECK(rec != NULL);
+    CHECK(ap_record_party_a_id(rec) == 1);
+    CHECK(ap_record_party_b_id(rec) == 2);
+    CHECK(ap_record_timestamp_ns(rec) == 1000);
+    CHECK(fabs(ap_record_distance(rec) - 5.0) < 1e-9);
+    CHECK(ap_record_distance_unit(rec) == AP_DISTANCE_UNIT_NETWORK);
+
+    size_t len = 0;
+    CHECK(ap_record_serialize(rec, NULL, 0, &len) == AP_STATUS_BUFFER_TOO_SMALL);
+    CHECK(len > 0);
+    uint8_t *buf = malloc(len);
+    CHECK(ap_record_serialize(rec, buf, len, &len) == AP_STATUS_OK);
+
+    ApRecord *back = NULL;
+    CHECK(ap_record_deserialize(buf, len, &back) == AP_STATUS_OK);
+    CHECK(ap_record_content_hash(back) == ap_record_content_hash(rec));
+
+    ApVerifyResult verdict = AP_VERIFY_RESULT_HASH_MISMATCH;
+    CHECK(ap_record_verify(back, &verdict) == AP_STATUS_OK);
+    CHECK(verdict == AP_VERIFY_RESULT_VALID);
+
+    uint8_t event[18];
+    CHECK(ap_record_event_bytes(back, event) == AP_STATUS_OK);
+    CHECK(event[0] == 'P');
+
+    /* A flipped payload byte is caught by verification or decoding. */
+    buf[len - 1] ^= 0xFF;
+    ApRecord *tampered = NULL;
+    ApStatus st = ap_record_deserialize(buf, len, &tampered);
+    if (st == AP_STATUS_OK) {
+        CHECK(ap_record_verify(tampered, &verdict) == AP_STATUS_OK);
+        CHECK(verdict == AP_VERIFY_RESULT_HASH_MISMATCH);
+    } else {
+        CHECK(st == AP_STATUS_DECODE_ERROR);
+        CHECK(tampered == NULL);
+    }
+
+    ap_record_free(tampered);
+    ap_record_free(back);
+    ap_record_free(rec);
+    free(buf);
+    ap_party_free(bob);
+    ap_party_free(alice);
+}
+
+static void test_errors(void) {
+    ApParty *alice = ap_party_new(0.0, 0.0, 0.0, 1, 1);
+    ApParty *far = ap_party_new(80.0, 80.0, 0.0, 2, 2);
+    ApConfig *cfg = ap_config_new();
+    ApRecord *rec = NULL;
+
+    CHECK(ap_execute_protocol(alice, far, 0, cfg, &rec) == AP_STATUS_NOT_PROXIMATE);
+    CHECK(rec == NULL);
+    CHECK(ap_config_set_mode(cfg, AP_PROXIMITY_MODE_GEOGRAPHIC) == AP_STATUS_OK);
+    CHECK(ap_execute_protocol(alice, far, 0, cfg, &rec) == AP_STATUS_MISSING_GEO_FIX);
+    /* Out-of-range enum values are rejected, not undefined behaviour. */
+    CHECK(ap_config_set_mode(cfg, 42) == AP_STATUS_INVALID_ARGUMENT);
+    CHECK(ap_config_set_mode(cfg, -1) == AP_STATUS_INVALID_ARGUMENT);
+    CHECK(ap_execute_protocol(alice, far, 0, cfg, &rec) == AP_STATUS_MISSING_GEO_FIX);
+    CHECK(ap_execute_protocol(NULL, far, 0, cfg, &rec) == AP_STATUS_NULL_POINTER);
+
+    const uint8_t junk[4] = {0xDE, 0xAD, 0xBE, 0xEF};
+    CHECK(ap_record_deserialize(junk, sizeof junk, &rec) == AP_STATUS_DECODE_ERROR);
+    CHECK(rec == NULL);
+    printf("status: %s\n", ap_status_str(AP_STATUS_DECODE_ERROR));
+    CHECK(strcmp(ap_status_str(AP_STATUS_INVALID_ARGUMENT), "invalid argument") == 0);
+    CHECK(strcmp(ap_status_str(1000), "unknown status") == 0);
+
+    ap_config_free(cfg);
+    ap_party_free(far);
+    ap_party_free(alice);
+}
+
+int main(void) {
+    test_roundtrip();
+    test_errors();
+    if (failures > 0) {
+        fprintf(stderr, "%d check(s) failed\n", failures);
+        return 1;
+    }
+    printf("capi_test: ok\n");
+    return 0;
+}
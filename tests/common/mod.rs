@@ -0,0 +1,24 @@
+//! 結合テスト共通 — C / Python 向けライブラリのビルド
+//!
+//! `Cargo.toml` は rlib しか宣言しないので、`staticlib` / `cdylib` は
+//! 必要な feature だけを有効にして `cargo rustc --crate-type` で個別にビルドする。
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `feature` を有効にして `crate_type` の成果物をビルドし、`file_name` のパスを返す。
+pub fn build_lib(feature: &str, crate_type: &str, file_name: &str) -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{feature}-{crate_type}"));
+    let status = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["rustc", "--lib", "--features", feature])
+        .args(["--crate-type", crate_type, "--target-dir"])
+        .arg(&target_dir)
+        .status()
+        .unwrap();
+    assert!(
+        status.success(),
+        "`cargo rustc --crate-type {crate_type}` failed"
+    );
+    target_dir.join("debug").join(file_name)
+}
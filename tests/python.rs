@@ -0,0 +1,94 @@
+//! Python バインディングの結合テスト — 拡張モジュールをロードして pytest を実行
+//!
+//! 実行: `cargo test --features python --test python`
+//!
+//! pytest がなければ、同じテストファイルを最小限のランナー
+//! (`pytest.approx` / `pytest.raises` 相当) で実行する。
+
+#![cfg(all(feature = "python", unix))]
+
+mod common;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// pytest がない環境向けのランナー。
+const FALLBACK_RUNNER: &str = r#"
+import contextlib, importlib.util, math, re, sys, types
+
+class _Approx:
+    def __init__(self, expected, rel=1e-6, abs=1e-12):
+        self.expected, self.rel, self.abs = expected, rel, abs
+    def __eq__(self, other):
+        return math.isclose(other, self.expected, rel_tol=self.rel, abs_tol=self.abs)
+
+@contextlib.contextmanager
+def _raises(exc, match=None):
+    try:
+        yield
+    except exc as e:
+        if match is not None and not re.search(match, str(e)):
+            raise AssertionError(f"{e!r} does not match {match!r}")
+        return
+    raise AssertionError(f"DID NOT RAISE {exc.__name__}")
+
+sys.modules["pytest"] = types.SimpleNamespace(approx=_Approx, raises=_raises)
+path = sys.argv[1]
+spec = importlib.util.spec_from_file_location("under_test", path)
+mod = importlib.util.module_from_spec(spec)
+spec.loader.exec_module(mod)
+tests = [name for name in dir(mod) if name.startswith("test_")]
+for name in tests:
+    getattr(mod, name)()
+    print(f"{name} PASSED")
+print(f"{len(tests)} passed")
+"#;
+
+#[test]
+fn pytest_suite() {
+    let python = std::env::var("PYTHON").unwrap_or_else(|_| "python3".into());
+    let Ok(probe) = Command::new(&python).arg("--version").output() else {
+        eprintln!("skipping Python tests: `{python}` not found");
+        return;
+    };
+    assert!(probe.status.success());
+
+    // cdylib をビルドし、`alice_presence.so` として import する
+    let lib = common::build_lib(
+        "python",
+        "cdylib",
+        if cfg!(target_os = "macos") {
+            "libalice_presence.dylib"
+        } else {
+            "libalice_presence.so"
+        },
+    );
+    let module_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("python");
+    std::fs::create_dir_all(&module_dir).unwrap();
+    std::fs::copy(&lib, module_dir.join("alice_presence.so")).unwrap();
+
+    let tests = Path::new(env!("CARGO_MANIFEST_DIR")).join("python/tests/test_alice_presence.py");
+    let has_pytest = Command::new(&python)
+        .args(["-c", "import pytest"])
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success());
+    let mut cmd = Command::new(&python);
+    if has_pytest {
+        cmd.args(["-m", "pytest", "-q", "-p", "no:cacheprovider"]);
+    } else {
+        cmd.args(["-c", FALLBACK_RUNNER]);
+    }
+    let output = cmd
+        .arg(&tests)
+        .env("PYTHONPATH", &module_dir)
+        .env("PYTHONDONTWRITEBYTECODE", "1")
+        .output()
+        .unwrap();
+    print!("{}", String::from_utf8_lossy(&output.stdout));
+    assert!(
+        output.status.success(),
+        "Python tests failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
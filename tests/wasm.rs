@@ -0,0 +1,63 @@
+//! WebAssembly バインディングのテスト — Node 上で headless 実行
+//!
+//! 実行: `cargo test --target wasm32-unknown-unknown --features wasm --test wasm`
+//! (`wasm-bindgen-test-runner` が必要。`.cargo/config.toml` 参照)
+
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use alice_presence::serialize::serialize_crossing;
+use alice_presence::wasm::{
+    compute_group_proof, verify_crossing_record, WasmCrossingRecord, WasmGroupProof,
+    WasmProximityProof, WasmVerifyResult,
+};
+use alice_presence::{try_execute_presence_protocol, PartyInfo, PresenceConfig, VivaldiCoord};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn record_bytes() -> Vec<u8> {
+    let a = PartyInfo::new(VivaldiCoord::new(0.0, 0.0), 42, 1);
+    let b = PartyInfo::new(VivaldiCoord::new(3.0, 4.0), 99, 2);
+    let rec = try_execute_presence_protocol(&a, &b, 1000, &PresenceConfig::default()).unwrap();
+    serialize_crossing(&rec)
+}
+
+#[wasm_bindgen_test]
+fn decode_and_verify_record() {
+    let bytes = record_bytes();
+    let rec = WasmCrossingRecord::from_bytes(&bytes).unwrap();
+    assert_eq!(rec.verify(), WasmVerifyResult::Valid);
+    assert_eq!(rec.timestamp_ns(), 1000);
+    assert_eq!(
+        verify_crossing_record(&bytes).unwrap(),
+        WasmVerifyResult::Valid
+    );
+}
+
+#[wasm_bindgen_test]
+fn malformed_input_is_a_js_error() {
+    assert!(WasmCrossingRecord::from_bytes(&[0u8; 8]).is_err());
+    assert!(WasmProximityProof::from_bytes(b"APRX").is_err());
+    assert!(WasmGroupProof::from_bytes(&[]).is_err());
+    assert!(compute_group_proof(&[1, 2], &[0.0; 5], 10.0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn proximity_proof_roundtrip() {
+    let rec = WasmCrossingRecord::from_bytes(&record_bytes()).unwrap();
+    let proof = WasmProximityProof::from_bytes(&rec.proximity().to_bytes()).unwrap();
+    assert!(proof.verify());
+    assert!((proof.distance() - 5.0).abs() < 1e-9);
+    assert_eq!(proof.distance_unit(), "network");
+}
+
+#[wasm_bindgen_test]
+fn group_proof_roundtrip() {
+    let proof = compute_group_proof(
+        &[3, 1, 2],
+        &[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+        10.0,
+    )
+    .unwrap();
+    assert_eq!(proof.member_ids(), vec![1, 2, 3]);
+    let back = WasmGroupProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+    assert_eq!(back.verify(), WasmVerifyResult::Valid);
+}